@@ -0,0 +1,4 @@
+// The control flow demos in main.rs only show what each kind of loop prints. looplang is a tiny
+// interpreted language with the same loop/while/for/label rules, so the examples can be run as
+// scripts and their state can be traced one iteration at a time.
+pub mod looplang;
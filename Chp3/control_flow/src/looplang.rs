@@ -0,0 +1,210 @@
+/* looplang is a miniature, Rust-flavoured scripting language. It only knows about integers and
+ * booleans, but it supports the same control flow as chapter 3: if/else as an expression, 'loop',
+ * 'while', 'for' over a range, labelled break and continue, and breaking out of a loop with a
+ * value.
+ * A script goes through the usual pipeline:
+ *   source text -> lexer (tokens) -> parser (AST) -> evaluator (walks the tree and runs it)
+ * */
+pub mod ast;
+pub mod eval;
+pub mod lexer;
+pub mod parser;
+
+use std::fmt;
+
+pub use eval::Interpreter;
+
+// Every stage reports problems with the same error type, so a caller only has to handle one
+// thing. line and col are 1-based, like rustc's diagnostics.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LangError {
+    pub line: usize,
+    pub col: usize,
+    pub message: String,
+}
+
+impl LangError {
+    pub fn new(pos: Pos, message: impl Into<String>) -> Self {
+        LangError {
+            line: pos.line,
+            col: pos.col,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for LangError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "error at {}:{}: {}", self.line, self.col, self.message)
+    }
+}
+
+impl std::error::Error for LangError {}
+
+// A position in the source text, attached to tokens and AST nodes for error messages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pos {
+    pub line: usize,
+    pub col: usize,
+}
+
+// Parses a whole script into its list of statements without running it.
+pub fn parse(source: &str) -> Result<ast::Block, LangError> {
+    let tokens = lexer::tokenize(source)?;
+    parser::Parser::new(tokens).parse_program()
+}
+
+// Runs a script, writing its println! output to stdout. With trace set, the interpreter also
+// prints the variable table at the start of every loop iteration.
+pub fn run(source: &str, trace: bool) -> Result<eval::Value, LangError> {
+    let program = parse(source)?;
+    let mut interpreter = Interpreter::new(trace);
+    interpreter.run(&program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::eval::Value;
+    use super::*;
+    use crate::looplang::ast::IntType;
+
+    fn value(source: &str) -> Value {
+        run(source, false).unwrap()
+    }
+
+    fn int(n: i128) -> Value {
+        Value::Int(n, Some(IntType::I32))
+    }
+
+    // The error's line, column and message.
+    fn error(source: &str) -> (usize, usize, String) {
+        let err = run(source, false).unwrap_err();
+        (err.line, err.col, err.message)
+    }
+
+    #[test]
+    fn the_example_scripts_run() {
+        for source in [
+            include_str!("../scripts/break_value.loop"),
+            include_str!("../scripts/conditional.loop"),
+            include_str!("../scripts/continue_outer.loop"),
+            include_str!("../scripts/labeled_loop.loop"),
+            include_str!("../scripts/loop_loop.loop"),
+            include_str!("../scripts/while_loop.loop"),
+        ] {
+            assert!(run(source, false).is_ok());
+        }
+    }
+
+    #[test]
+    fn labelled_break() {
+        // breaks out of both loops from the inner one
+        let source = "
+            let mut count = 0;
+            'outer: loop {
+                let mut remaining = 10;
+                loop {
+                    if remaining == 9 { break; }
+                    if count == 2 { break 'outer; }
+                    remaining -= 1;
+                }
+                count += 1;
+            }
+            count";
+        assert_eq!(value(source), int(2));
+    }
+
+    #[test]
+    fn labelled_continue() {
+        // continue 'rows skips the rest of the row, including the code after the inner loop
+        let source = "
+            let mut cells = 0;
+            let mut full_rows = 0;
+            'rows: for row in 1..=4 {
+                for col in 1..=4 {
+                    if col > row { continue 'rows; }
+                    cells += 1;
+                }
+                full_rows += 1;
+            }
+            cells * 10 + full_rows";
+        assert_eq!(value(source), int(101));
+        // an unlabelled continue only goes to the next pass of the innermost loop
+        let source = "
+            let mut total = 0;
+            for i in 0..5 {
+                let mut j = 0;
+                while j < 3 {
+                    j += 1;
+                    if j == 2 { continue; }
+                    total += 1;
+                }
+                if i == 4 { continue; }
+            }
+            total";
+        assert_eq!(value(source), int(10));
+    }
+
+    #[test]
+    fn break_with_a_value() {
+        let source = "
+            let mut candidate = 50;
+            let found = loop {
+                candidate -= 1;
+                if candidate % 28 == 0 { break candidate; }
+            };
+            found";
+        assert_eq!(value(source), int(28));
+        // a labelled break can carry a value out of an outer loop
+        let source = "
+            let result = 'search: loop {
+                for i in 1..10 {
+                    if i * i > 50 { break 'search i; }
+                }
+                break 0;
+            };
+            result";
+        assert_eq!(value(source), int(8));
+        // loops without a break value are ()
+        assert_eq!(value("loop { break; }"), Value::Unit);
+    }
+
+    #[test]
+    fn break_and_continue_errors() {
+        assert_eq!(
+            error("break;"),
+            (1, 1, "'break' outside of a loop".to_string())
+        );
+        assert_eq!(
+            error("loop {\n    continue 'nowhere;\n}"),
+            (2, 5, "use of undeclared label 'nowhere".to_string())
+        );
+        assert_eq!(
+            error("while true {\n    break 5;\n}"),
+            (
+                2,
+                5,
+                "'break' with value from a 'while' loop; only 'loop' can break with a value"
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            error("for i in 0..3 { break 'outer; }").2,
+            "use of undeclared label 'outer"
+        );
+    }
+
+    #[test]
+    fn other_errors() {
+        assert_eq!(
+            error("let x = 1;\nx = 2;").2,
+            "cannot assign twice to immutable variable 'x' (help: declare it with 'let mut x')"
+        );
+        assert_eq!(
+            error("let x = y;"),
+            (1, 9, "cannot find value 'y' in this scope".to_string())
+        );
+        assert_eq!(error("let x = 1 @ 2;").2, "unexpected character '@'");
+        assert_eq!(error("/* never closed").2, "unterminated block comment");
+    }
+}
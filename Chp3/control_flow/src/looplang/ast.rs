@@ -0,0 +1,208 @@
+// The abstract syntax tree: what the parser produces and what the evaluator walks over.
+use super::Pos;
+
+// The integer types a variable can be annotated with. Values are stored as i128 while the program
+// runs, and the declared type is used to check for overflow whenever a variable is written,
+// the same way a debug build of a Rust program panics on overflow.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntType {
+    I8,
+    I16,
+    I32,
+    I64,
+    Isize,
+    U8,
+    U16,
+    U32,
+    U64,
+    Usize,
+}
+
+impl IntType {
+    pub const ALL: [IntType; 10] = [
+        IntType::I8,
+        IntType::I16,
+        IntType::I32,
+        IntType::I64,
+        IntType::Isize,
+        IntType::U8,
+        IntType::U16,
+        IntType::U32,
+        IntType::U64,
+        IntType::Usize,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IntType::I8 => "i8",
+            IntType::I16 => "i16",
+            IntType::I32 => "i32",
+            IntType::I64 => "i64",
+            IntType::Isize => "isize",
+            IntType::U8 => "u8",
+            IntType::U16 => "u16",
+            IntType::U32 => "u32",
+            IntType::U64 => "u64",
+            IntType::Usize => "usize",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntType> {
+        IntType::ALL.into_iter().find(|ty| ty.name() == name)
+    }
+
+    // the smallest and largest values the type can hold
+    pub fn range(self) -> (i128, i128) {
+        match self {
+            IntType::I8 => (i8::MIN as i128, i8::MAX as i128),
+            IntType::I16 => (i16::MIN as i128, i16::MAX as i128),
+            IntType::I32 => (i32::MIN as i128, i32::MAX as i128),
+            IntType::I64 | IntType::Isize => (i64::MIN as i128, i64::MAX as i128),
+            IntType::U8 => (0, u8::MAX as i128),
+            IntType::U16 => (0, u16::MAX as i128),
+            IntType::U32 => (0, u32::MAX as i128),
+            IntType::U64 | IntType::Usize => (0, u64::MAX as i128),
+        }
+    }
+}
+
+// A variable is either an integer of some IntType or a bool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int(IntType),
+    Bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Neg,
+    Not,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Eq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+}
+
+impl BinOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Rem => "%",
+            BinOp::Eq => "==",
+            BinOp::NotEq => "!=",
+            BinOp::Lt => "<",
+            BinOp::LtEq => "<=",
+            BinOp::Gt => ">",
+            BinOp::GtEq => ">=",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+        }
+    }
+}
+
+// A block is a list of statements, optionally followed by a tail expression without a semicolon
+// that becomes the value of the whole block, just like a Rust function body.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    pub tail: Option<Box<Expr>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    Let {
+        name: String,
+        mutable: bool,
+        ty: Option<Type>,
+        value: Expr,
+        pos: Pos,
+    },
+    // 'x = e' and the compound forms 'x += e' etc. op is None for plain assignment
+    Assign {
+        name: String,
+        op: Option<BinOp>,
+        value: Expr,
+        pos: Pos,
+    },
+    Println {
+        format: String,
+        args: Vec<Expr>,
+        pos: Pos,
+    },
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub pos: Pos,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind {
+    Int(i128, Option<IntType>),
+    Bool(bool),
+    Var(String),
+    Unary(UnaryOp, Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    Block(Block),
+    If {
+        cond: Box<Expr>,
+        then_branch: Block,
+        else_branch: Option<Box<Expr>>, // either another If or a Block
+    },
+    Loop {
+        label: Option<String>,
+        body: Block,
+    },
+    While {
+        label: Option<String>,
+        cond: Box<Expr>,
+        body: Block,
+    },
+    For {
+        label: Option<String>,
+        var: String,
+        start: Box<Expr>,
+        end: Box<Expr>,
+        inclusive: bool,
+        body: Block,
+    },
+    Break {
+        label: Option<String>,
+        value: Option<Box<Expr>>,
+    },
+    Continue {
+        label: Option<String>,
+    },
+}
+
+impl ExprKind {
+    // if, loops and blocks end in '}' and don't need a semicolon when used as a statement
+    pub fn is_block_like(&self) -> bool {
+        matches!(
+            self,
+            ExprKind::Block(_)
+                | ExprKind::If { .. }
+                | ExprKind::Loop { .. }
+                | ExprKind::While { .. }
+                | ExprKind::For { .. }
+        )
+    }
+}
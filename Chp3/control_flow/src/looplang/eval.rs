@@ -0,0 +1,746 @@
+/* The evaluator walks the AST and runs it directly (a "tree-walking interpreter").
+ * break and continue are the interesting part: when one runs, it has to unwind through every
+ * enclosing block until it reaches the loop it belongs to. That is done by returning a Flow value
+ * as the Err side of a Result, so the '?' operator carries it outwards for free, and each loop
+ * checks whether the Flow is addressed to it (same label, or no label and it's the innermost loop).
+ * */
+use std::fmt;
+
+use super::ast::{BinOp, Block, Expr, ExprKind, IntType, Stmt, Type, UnaryOp};
+use super::{LangError, Pos};
+
+// The values a script can produce. An integer remembers its type once it has one; an integer
+// literal without a suffix has no type yet and takes on the type of whatever it's combined with,
+// which is a small version of rustc's type inference.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Value {
+    Int(i128, Option<IntType>),
+    Bool(bool),
+    Unit,
+}
+
+impl Value {
+    fn type_name(&self) -> &'static str {
+        match self {
+            Value::Int(_, Some(ty)) => ty.name(),
+            Value::Int(_, None) => "{integer}",
+            Value::Bool(_) => "bool",
+            Value::Unit => "()",
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(n, _) => write!(f, "{n}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Unit => write!(f, "()"),
+        }
+    }
+}
+
+struct Variable {
+    name: String,
+    value: Value,
+    ty: Type,
+    mutable: bool,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum LoopKind {
+    Loop,
+    While,
+    For,
+}
+
+impl LoopKind {
+    fn keyword(self) -> &'static str {
+        match self {
+            LoopKind::Loop => "loop",
+            LoopKind::While => "while",
+            LoopKind::For => "for",
+        }
+    }
+}
+
+// A loop that is currently running, used to resolve labels and for the trace output.
+struct ActiveLoop {
+    label: Option<String>,
+    kind: LoopKind,
+    pos: Pos,
+}
+
+// Why evaluation stopped early: a break or continue looking for its loop, or a real error.
+enum Flow {
+    Break { label: Option<String>, value: Value },
+    Continue { label: Option<String> },
+    Error(LangError),
+}
+
+impl From<LangError> for Flow {
+    fn from(err: LangError) -> Self {
+        Flow::Error(err)
+    }
+}
+
+type Eval<T> = Result<T, Flow>;
+
+pub struct Interpreter {
+    // one Vec of variables per block scope, innermost last. Shadowing just pushes another
+    // variable with the same name, and lookups search from the end.
+    scopes: Vec<Vec<Variable>>,
+    loops: Vec<ActiveLoop>,
+    trace: bool,
+}
+
+impl Interpreter {
+    pub fn new(trace: bool) -> Self {
+        Interpreter {
+            scopes: Vec::new(),
+            loops: Vec::new(),
+            trace,
+        }
+    }
+
+    // Runs a parsed program and returns the value of its tail expression (or () if it has none).
+    pub fn run(&mut self, program: &Block) -> Result<Value, LangError> {
+        match self.eval_block(program) {
+            Ok(value) => Ok(value),
+            Err(Flow::Error(err)) => Err(err),
+            // eval_break and eval_continue refuse to run outside of a matching loop, so these
+            // can't escape the program
+            Err(Flow::Break { .. }) | Err(Flow::Continue { .. }) => {
+                unreachable!("break/continue escaped every loop")
+            }
+        }
+    }
+
+    fn eval_block(&mut self, block: &Block) -> Eval<Value> {
+        self.scopes.push(Vec::new());
+        let result = self.eval_block_inner(block);
+        self.scopes.pop();
+        result
+    }
+
+    fn eval_block_inner(&mut self, block: &Block) -> Eval<Value> {
+        for stmt in &block.stmts {
+            self.eval_stmt(stmt)?;
+        }
+        match &block.tail {
+            Some(expr) => self.eval_expr(expr),
+            None => Ok(Value::Unit),
+        }
+    }
+
+    fn eval_stmt(&mut self, stmt: &Stmt) -> Eval<()> {
+        match stmt {
+            Stmt::Let {
+                name,
+                mutable,
+                ty,
+                value,
+                pos,
+            } => {
+                let value = self.eval_expr(value)?;
+                let ty: Type = match (ty, value) {
+                    (Some(ty), _) => *ty,
+                    (None, Value::Int(_, Some(int_type))) => Type::Int(int_type),
+                    (None, Value::Int(_, None)) => Type::Int(IntType::I32), // rustc's default
+                    (None, Value::Bool(_)) => Type::Bool,
+                    (None, Value::Unit) => {
+                        return Err(LangError::new(
+                            *pos,
+                            format!("'{name}' would hold (), which this language can't store"),
+                        )
+                        .into())
+                    }
+                };
+                let value = coerce(value, ty, *pos)?;
+                self.scopes
+                    .last_mut()
+                    .expect("there is always at least one scope")
+                    .push(Variable {
+                        name: name.clone(),
+                        value,
+                        ty,
+                        mutable: *mutable,
+                    });
+                Ok(())
+            }
+            Stmt::Assign {
+                name,
+                op,
+                value,
+                pos,
+            } => {
+                let rhs = self.eval_expr(value)?;
+                let variable = self.lookup(name, *pos)?;
+                if !variable.mutable {
+                    return Err(LangError::new(
+                        *pos,
+                        format!(
+                            "cannot assign twice to immutable variable '{name}' \
+                             (help: declare it with 'let mut {name}')"
+                        ),
+                    )
+                    .into());
+                }
+                let (current, ty) = (variable.value, variable.ty);
+                let new_value = match op {
+                    Some(op) => binary_op(*op, current, rhs, *pos)?,
+                    None => rhs,
+                };
+                let new_value = coerce(new_value, ty, *pos)?;
+                self.lookup_mut(name).value = new_value;
+                Ok(())
+            }
+            Stmt::Println { format, args, pos } => {
+                let mut values: Vec<Value> = Vec::with_capacity(args.len());
+                for arg in args {
+                    values.push(self.eval_expr(arg)?);
+                }
+                let line = self.format(format, &values, *pos)?;
+                println!("{line}");
+                Ok(())
+            }
+            Stmt::Expr(expr) => {
+                self.eval_expr(expr)?;
+                Ok(())
+            }
+        }
+    }
+
+    fn eval_expr(&mut self, expr: &Expr) -> Eval<Value> {
+        let pos = expr.pos;
+        match &expr.kind {
+            ExprKind::Int(n, suffix) => Ok(Value::Int(*n, *suffix)),
+            ExprKind::Bool(b) => Ok(Value::Bool(*b)),
+            ExprKind::Var(name) => Ok(self.lookup(name, pos)?.value),
+            ExprKind::Unary(op, operand) => {
+                let value = self.eval_expr(operand)?;
+                Ok(unary_op(*op, value, pos)?)
+            }
+            ExprKind::Binary(op, left, right) => {
+                let left = self.eval_expr(left)?;
+                // && and || short-circuit, so the right side may never run
+                match (op, left) {
+                    (BinOp::And, Value::Bool(false)) => return Ok(Value::Bool(false)),
+                    (BinOp::Or, Value::Bool(true)) => return Ok(Value::Bool(true)),
+                    _ => {}
+                }
+                let right = self.eval_expr(right)?;
+                Ok(binary_op(*op, left, right, pos)?)
+            }
+            ExprKind::Block(block) => self.eval_block(block),
+            ExprKind::If {
+                cond,
+                then_branch,
+                else_branch,
+            } => {
+                if self.eval_condition(cond, "if")? {
+                    self.eval_block(then_branch)
+                } else if let Some(else_branch) = else_branch {
+                    self.eval_expr(else_branch)
+                } else {
+                    Ok(Value::Unit)
+                }
+            }
+            ExprKind::Loop { label, body } => {
+                self.enter_loop(label, LoopKind::Loop, pos);
+                let result = self.run_loop(body);
+                self.exit_loop(result)
+            }
+            ExprKind::While { label, cond, body } => {
+                self.enter_loop(label, LoopKind::While, pos);
+                let result = self.run_while(cond, body);
+                self.exit_loop(result)
+            }
+            ExprKind::For {
+                label,
+                var,
+                start,
+                end,
+                inclusive,
+                body,
+            } => {
+                let start = self.eval_expr(start)?;
+                let end = self.eval_expr(end)?;
+                self.enter_loop(label, LoopKind::For, pos);
+                let result = self.run_for(var, start, end, *inclusive, body, pos);
+                self.exit_loop(result)
+            }
+            ExprKind::Break { label, value } => {
+                let value = match value {
+                    Some(value) => self.eval_expr(value)?,
+                    None => Value::Unit,
+                };
+                self.eval_break(label, value, pos)
+            }
+            ExprKind::Continue { label } => self.eval_continue(label, pos),
+        }
+    }
+
+    fn eval_condition(&mut self, cond: &Expr, keyword: &str) -> Eval<bool> {
+        match self.eval_expr(cond)? {
+            Value::Bool(b) => Ok(b),
+            other => Err(LangError::new(
+                cond.pos,
+                format!(
+                    "mismatched types: the condition of '{keyword}' must be a bool, found {}",
+                    other.type_name()
+                ),
+            )
+            .into()),
+        }
+    }
+
+    fn enter_loop(&mut self, label: &Option<String>, kind: LoopKind, pos: Pos) {
+        self.loops.push(ActiveLoop {
+            label: label.clone(),
+            kind,
+            pos,
+        });
+    }
+
+    // Pops the loop and decides what its Flow means. A break or continue with no label, or with
+    // this loop's label, is ours; anything else keeps unwinding to an outer loop.
+    fn exit_loop(&mut self, result: Eval<Value>) -> Eval<Value> {
+        let active = self.loops.pop().expect("exit_loop without enter_loop");
+        let value = match result {
+            Err(Flow::Break { label, value }) if label.is_none() || label == active.label => value,
+            other => return other,
+        };
+        if self.trace {
+            let message = match value {
+                Value::Unit => format!("{} exits", describe(&active)),
+                _ => format!("{} exits with {value}", describe(&active)),
+            };
+            self.trace_line(&message);
+        }
+        Ok(value)
+    }
+
+    // Returns Ok(true) to keep looping. A continue addressed to this loop (the innermost one
+    // when the loop is running its body) just starts the next iteration.
+    fn body_continues(&mut self, result: Eval<Value>) -> Eval<bool> {
+        match result {
+            Ok(_) => Ok(true),
+            Err(Flow::Continue { label }) => {
+                let active = self.loops.last().expect("inside a loop");
+                if label.is_none() || label == active.label {
+                    Ok(true)
+                } else {
+                    Err(Flow::Continue { label })
+                }
+            }
+            Err(other) => Err(other),
+        }
+    }
+
+    fn run_loop(&mut self, body: &Block) -> Eval<Value> {
+        let mut iteration: u64 = 0;
+        loop {
+            iteration += 1;
+            self.trace_iteration(iteration);
+            let result = self.eval_block(body);
+            self.body_continues(result)?;
+        }
+    }
+
+    fn run_while(&mut self, cond: &Expr, body: &Block) -> Eval<Value> {
+        let mut iteration: u64 = 0;
+        while self.eval_condition(cond, "while")? {
+            iteration += 1;
+            self.trace_iteration(iteration);
+            let result = self.eval_block(body);
+            self.body_continues(result)?;
+        }
+        Ok(Value::Unit)
+    }
+
+    fn run_for(
+        &mut self,
+        var: &str,
+        start: Value,
+        end: Value,
+        inclusive: bool,
+        body: &Block,
+        pos: Pos,
+    ) -> Eval<Value> {
+        let (first, last, ty) = match (start, end) {
+            (Value::Int(a, ta), Value::Int(b, tb)) => {
+                let ty = unify(ta, tb, pos)?.unwrap_or(IntType::I32);
+                (a, b, ty)
+            }
+            (a, b) => {
+                return Err(LangError::new(
+                    pos,
+                    format!(
+                        "a for loop range needs integers, found {}..{}",
+                        a.type_name(),
+                        b.type_name()
+                    ),
+                )
+                .into())
+            }
+        };
+        let last = if inclusive { last } else { last - 1 };
+        let mut current = first;
+        let mut iteration: u64 = 0;
+        while current <= last {
+            iteration += 1;
+            // the loop variable lives in its own scope around the body, so it's fresh (and
+            // immutable) on every iteration
+            self.scopes.push(vec![Variable {
+                name: var.to_string(),
+                value: Value::Int(current, Some(ty)),
+                ty: Type::Int(ty),
+                mutable: false,
+            }]);
+            self.trace_iteration(iteration);
+            let result = self.eval_block(body);
+            self.scopes.pop();
+            self.body_continues(result)?;
+            current += 1;
+        }
+        Ok(Value::Unit)
+    }
+
+    fn eval_break(&mut self, label: &Option<String>, value: Value, pos: Pos) -> Eval<Value> {
+        let target = self.find_loop(label, "break", pos)?;
+        if value != Value::Unit && target.kind != LoopKind::Loop {
+            return Err(LangError::new(
+                pos,
+                format!(
+                    "'break' with value from a '{}' loop; only 'loop' can break with a value",
+                    target.kind.keyword()
+                ),
+            )
+            .into());
+        }
+        if self.trace {
+            let message = match value {
+                Value::Unit => format!("break out of {}", describe(target)),
+                _ => format!("break out of {} with {value}", describe(target)),
+            };
+            self.trace_line(&message);
+        }
+        Err(Flow::Break {
+            label: label.clone(),
+            value,
+        })
+    }
+
+    fn eval_continue(&mut self, label: &Option<String>, pos: Pos) -> Eval<Value> {
+        let target = self.find_loop(label, "continue", pos)?;
+        if self.trace {
+            self.trace_line(&format!(
+                "continue with the next iteration of {}",
+                describe(target)
+            ));
+        }
+        Err(Flow::Continue {
+            label: label.clone(),
+        })
+    }
+
+    fn find_loop(&self, label: &Option<String>, keyword: &str, pos: Pos) -> Eval<&ActiveLoop> {
+        let found = match label {
+            None => self.loops.last(),
+            Some(name) => self
+                .loops
+                .iter()
+                .rev()
+                .find(|active| active.label.as_deref() == Some(name.as_str())),
+        };
+        found.ok_or_else(|| {
+            let message = match label {
+                None => format!("'{keyword}' outside of a loop"),
+                Some(name) => format!("use of undeclared label '{name}"),
+            };
+            Flow::Error(LangError::new(pos, message))
+        })
+    }
+
+    fn lookup(&self, name: &str, pos: Pos) -> Result<&Variable, LangError> {
+        self.scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.iter().rev())
+            .find(|variable| variable.name == name)
+            .ok_or_else(|| LangError::new(pos, format!("cannot find value '{name}' in this scope")))
+    }
+
+    fn lookup_mut(&mut self, name: &str) -> &mut Variable {
+        self.scopes
+            .iter_mut()
+            .rev()
+            .flat_map(|scope| scope.iter_mut().rev())
+            .find(|variable| variable.name == name)
+            .expect("lookup succeeded before lookup_mut")
+    }
+
+    // Fills in a println! format string. Supports {} (next argument), {0} (argument by index),
+    // {name} (a variable) and {{ / }} for literal braces.
+    fn format(&self, format: &str, args: &[Value], pos: Pos) -> Result<String, LangError> {
+        let mut out = String::new();
+        let mut next_arg: usize = 0;
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    out.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    out.push('}');
+                }
+                '{' => {
+                    let mut inside = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(other) => inside.push(other),
+                            None => {
+                                return Err(LangError::new(pos, "unclosed '{' in format string"))
+                            }
+                        }
+                    }
+                    let value = if inside.is_empty() {
+                        next_arg += 1;
+                        args.get(next_arg - 1).copied()
+                    } else if let Ok(index) = inside.parse::<usize>() {
+                        args.get(index).copied()
+                    } else {
+                        Some(self.lookup(&inside, pos)?.value)
+                    };
+                    match value {
+                        Some(value) => out.push_str(&value.to_string()),
+                        None => {
+                            return Err(LangError::new(
+                                pos,
+                                "format string refers to an argument that wasn't given",
+                            ))
+                        }
+                    }
+                }
+                '}' => return Err(LangError::new(pos, "unmatched '}' in format string")),
+                other => out.push(other),
+            }
+        }
+        Ok(out)
+    }
+
+    fn trace_iteration(&self, iteration: u64) {
+        if !self.trace {
+            return;
+        }
+        let active = self.loops.last().expect("tracing inside a loop");
+        let message = format!(
+            "{} iteration {iteration}: {}",
+            describe(active),
+            self.variable_table()
+        );
+        self.trace_line(&message);
+    }
+
+    // Every variable that is visible right now, outermost first. Shadowed variables are left out
+    // because the script can't reach them either.
+    fn variable_table(&self) -> String {
+        let mut visible: Vec<&Variable> = Vec::new();
+        for scope in self.scopes.iter().rev() {
+            for variable in scope.iter().rev() {
+                if !visible.iter().any(|seen| seen.name == variable.name) {
+                    visible.push(variable);
+                }
+            }
+        }
+        if visible.is_empty() {
+            return "(no variables)".to_string();
+        }
+        visible
+            .iter()
+            .rev()
+            .map(|variable| {
+                let ty = match variable.ty {
+                    Type::Int(int_type) => int_type.name(),
+                    Type::Bool => "bool",
+                };
+                format!("{}: {ty} = {}", variable.name, variable.value)
+            })
+            .collect::<Vec<String>>()
+            .join(", ")
+    }
+
+    // trace output is indented by loop depth so nested loops line up under their parents
+    fn trace_line(&self, message: &str) {
+        let depth = self.loops.len().saturating_sub(1);
+        println!("\x1b[36m{}[trace] {message}\x1b[0m", "  ".repeat(depth));
+    }
+}
+
+fn describe(active: &ActiveLoop) -> String {
+    match &active.label {
+        Some(label) => format!("'{label}"),
+        None => format!("'{}' at line {}", active.kind.keyword(), active.pos.line),
+    }
+}
+
+// Two integer types have to agree, unless one side is an untyped literal.
+fn unify(a: Option<IntType>, b: Option<IntType>, pos: Pos) -> Result<Option<IntType>, LangError> {
+    match (a, b) {
+        (Some(a), Some(b)) if a != b => Err(LangError::new(
+            pos,
+            format!(
+                "mismatched types: expected {}, found {}",
+                a.name(),
+                b.name()
+            ),
+        )),
+        (Some(ty), _) | (_, Some(ty)) => Ok(Some(ty)),
+        (None, None) => Ok(None),
+    }
+}
+
+fn check_range(value: i128, ty: Option<IntType>, what: &str, pos: Pos) -> Result<(), LangError> {
+    let ty = ty.unwrap_or(IntType::I32);
+    let (min, max) = ty.range();
+    if value < min || value > max {
+        return Err(LangError::new(
+            pos,
+            format!(
+                "attempt to {what} with overflow ({value} doesn't fit in {})",
+                ty.name()
+            ),
+        ));
+    }
+    Ok(())
+}
+
+// Makes sure a value can be stored in a variable of the given type.
+fn coerce(value: Value, ty: Type, pos: Pos) -> Result<Value, LangError> {
+    match (value, ty) {
+        (Value::Int(n, found), Type::Int(expected)) => {
+            unify(Some(expected), found, pos)?;
+            let (min, max) = expected.range();
+            if n < min || n > max {
+                return Err(LangError::new(
+                    pos,
+                    format!("{n} is out of range for {}", expected.name()),
+                ));
+            }
+            Ok(Value::Int(n, Some(expected)))
+        }
+        (Value::Bool(b), Type::Bool) => Ok(Value::Bool(b)),
+        (other, expected) => {
+            let expected = match expected {
+                Type::Int(int_type) => int_type.name(),
+                Type::Bool => "bool",
+            };
+            Err(LangError::new(
+                pos,
+                format!(
+                    "mismatched types: expected {expected}, found {}",
+                    other.type_name()
+                ),
+            ))
+        }
+    }
+}
+
+fn unary_op(op: UnaryOp, value: Value, pos: Pos) -> Result<Value, LangError> {
+    match (op, value) {
+        (UnaryOp::Neg, Value::Int(n, ty)) => {
+            if let Some(int_type) = ty {
+                if int_type.range().0 == 0 {
+                    return Err(LangError::new(
+                        pos,
+                        format!(
+                            "cannot apply unary operator '-' to type {}",
+                            int_type.name()
+                        ),
+                    ));
+                }
+            }
+            check_range(-n, ty, "negate", pos)?;
+            Ok(Value::Int(-n, ty))
+        }
+        (UnaryOp::Not, Value::Bool(b)) => Ok(Value::Bool(!b)),
+        // bitwise not flips every bit of the declared width; for unsigned types that's max - n
+        (UnaryOp::Not, Value::Int(n, ty)) => match ty.map(IntType::range) {
+            Some((0, max)) => Ok(Value::Int(max - n, ty)),
+            _ => Ok(Value::Int(!n, ty)),
+        },
+        (op, other) => Err(LangError::new(
+            pos,
+            format!(
+                "cannot apply unary operator '{}' to type {}",
+                if op == UnaryOp::Neg { "-" } else { "!" },
+                other.type_name()
+            ),
+        )),
+    }
+}
+
+fn binary_op(op: BinOp, left: Value, right: Value, pos: Pos) -> Result<Value, LangError> {
+    match (left, right) {
+        (Value::Int(a, ta), Value::Int(b, tb)) => {
+            let ty = unify(ta, tb, pos)?;
+            // the arithmetic happens in i128, then check_range decides whether the result fits
+            // in the operands' type. u64 * u64 can even overflow i128, hence the checked_ calls
+            let (result, what) = match op {
+                BinOp::Add => (a.checked_add(b), "add"),
+                BinOp::Sub => (a.checked_sub(b), "subtract"),
+                BinOp::Mul => (a.checked_mul(b), "multiply"),
+                BinOp::Div | BinOp::Rem if b == 0 => {
+                    let message = if op == BinOp::Div {
+                        "attempt to divide by zero"
+                    } else {
+                        "attempt to calculate the remainder with a divisor of zero"
+                    };
+                    return Err(LangError::new(pos, message));
+                }
+                // Rust's integer division truncates towards zero, and so does i128's
+                BinOp::Div => (a.checked_div(b), "divide"),
+                BinOp::Rem => (a.checked_rem(b), "calculate the remainder"),
+                BinOp::Eq => return Ok(Value::Bool(a == b)),
+                BinOp::NotEq => return Ok(Value::Bool(a != b)),
+                BinOp::Lt => return Ok(Value::Bool(a < b)),
+                BinOp::LtEq => return Ok(Value::Bool(a <= b)),
+                BinOp::Gt => return Ok(Value::Bool(a > b)),
+                BinOp::GtEq => return Ok(Value::Bool(a >= b)),
+                BinOp::And | BinOp::Or => {
+                    return Err(LangError::new(
+                        pos,
+                        format!("'{}' needs bool operands, found integers", op.symbol()),
+                    ))
+                }
+            };
+            let result = result
+                .ok_or_else(|| LangError::new(pos, format!("attempt to {what} with overflow")))?;
+            check_range(result, ty, what, pos)?;
+            Ok(Value::Int(result, ty))
+        }
+        (Value::Bool(a), Value::Bool(b)) => match op {
+            BinOp::And => Ok(Value::Bool(a && b)),
+            BinOp::Or => Ok(Value::Bool(a || b)),
+            BinOp::Eq => Ok(Value::Bool(a == b)),
+            BinOp::NotEq => Ok(Value::Bool(a != b)),
+            _ => Err(LangError::new(
+                pos,
+                format!("cannot apply '{}' to bool values", op.symbol()),
+            )),
+        },
+        (left, right) => Err(LangError::new(
+            pos,
+            format!(
+                "mismatched types: cannot apply '{}' to {} and {}",
+                op.symbol(),
+                left.type_name(),
+                right.type_name()
+            ),
+        )),
+    }
+}
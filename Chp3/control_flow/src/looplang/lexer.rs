@@ -0,0 +1,269 @@
+// The lexer turns the source text into a flat list of tokens. It doesn't know anything about
+// grammar; it only groups characters into numbers, names, labels, strings and symbols.
+use super::ast::IntType;
+use super::{LangError, Pos};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TokenKind {
+    Int(i128, Option<IntType>),
+    Ident(String),
+    Label(String), // 'counting_up, stored without the leading quote
+    Str(String),
+    // keywords
+    Let,
+    Mut,
+    If,
+    Else,
+    Loop,
+    While,
+    For,
+    In,
+    Break,
+    Continue,
+    True,
+    False,
+    // punctuation and operators
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Semi,
+    Colon,
+    Comma,
+    Bang,
+    Assign,
+    PlusAssign,
+    MinusAssign,
+    StarAssign,
+    SlashAssign,
+    PercentAssign,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Percent,
+    EqEq,
+    NotEq,
+    Lt,
+    LtEq,
+    Gt,
+    GtEq,
+    AndAnd,
+    OrOr,
+    DotDot,
+    DotDotEq,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub pos: Pos,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, LangError> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i: usize = 0;
+    let mut line: usize = 1;
+    let mut col: usize = 1;
+
+    // advances past n characters, keeping line and col up to date
+    let advance = |i: &mut usize, line: &mut usize, col: &mut usize, n: usize| {
+        for _ in 0..n {
+            if chars[*i] == '\n' {
+                *line += 1;
+                *col = 1;
+            } else {
+                *col += 1;
+            }
+            *i += 1;
+        }
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let pos = Pos { line, col };
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            advance(&mut i, &mut line, &mut col, 1);
+            continue;
+        }
+        // line comments
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i] != '\n' {
+                advance(&mut i, &mut line, &mut col, 1);
+            }
+            continue;
+        }
+        // block comments
+        if c == '/' && next == Some('*') {
+            advance(&mut i, &mut line, &mut col, 2);
+            loop {
+                if i + 1 >= chars.len() {
+                    return Err(LangError::new(pos, "unterminated block comment"));
+                }
+                if chars[i] == '*' && chars[i + 1] == '/' {
+                    advance(&mut i, &mut line, &mut col, 2);
+                    break;
+                }
+                advance(&mut i, &mut line, &mut col, 1);
+            }
+            continue;
+        }
+
+        if c.is_ascii_digit() {
+            let start = i;
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '_') {
+                advance(&mut i, &mut line, &mut col, 1);
+            }
+            let text: String = chars[start..i].iter().collect();
+            let (value, suffix) = parse_int(&text, pos)?;
+            tokens.push(Token {
+                kind: TokenKind::Int(value, suffix),
+                pos,
+            });
+            continue;
+        }
+
+        if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                advance(&mut i, &mut line, &mut col, 1);
+            }
+            let word: String = chars[start..i].iter().collect();
+            let kind = match word.as_str() {
+                "let" => TokenKind::Let,
+                "mut" => TokenKind::Mut,
+                "if" => TokenKind::If,
+                "else" => TokenKind::Else,
+                "loop" => TokenKind::Loop,
+                "while" => TokenKind::While,
+                "for" => TokenKind::For,
+                "in" => TokenKind::In,
+                "break" => TokenKind::Break,
+                "continue" => TokenKind::Continue,
+                "true" => TokenKind::True,
+                "false" => TokenKind::False,
+                _ => TokenKind::Ident(word),
+            };
+            tokens.push(Token { kind, pos });
+            continue;
+        }
+
+        // loop labels start with a single quote, just like in Rust
+        if c == '\'' {
+            advance(&mut i, &mut line, &mut col, 1);
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                advance(&mut i, &mut line, &mut col, 1);
+            }
+            if start == i {
+                return Err(LangError::new(pos, "expected a label name after '"));
+            }
+            tokens.push(Token {
+                kind: TokenKind::Label(chars[start..i].iter().collect()),
+                pos,
+            });
+            continue;
+        }
+
+        if c == '"' {
+            advance(&mut i, &mut line, &mut col, 1);
+            let mut text = String::new();
+            loop {
+                match chars.get(i) {
+                    None => return Err(LangError::new(pos, "unterminated string literal")),
+                    Some('"') => break,
+                    Some('\\') => {
+                        let escaped = match chars.get(i + 1) {
+                            Some('n') => '\n',
+                            Some('t') => '\t',
+                            Some('"') => '"',
+                            Some('\\') => '\\',
+                            _ => {
+                                let here = Pos { line, col };
+                                return Err(LangError::new(here, "unknown escape sequence"));
+                            }
+                        };
+                        text.push(escaped);
+                        advance(&mut i, &mut line, &mut col, 2);
+                    }
+                    Some(&other) => {
+                        text.push(other);
+                        advance(&mut i, &mut line, &mut col, 1);
+                    }
+                }
+            }
+            advance(&mut i, &mut line, &mut col, 1);
+            tokens.push(Token {
+                kind: TokenKind::Str(text),
+                pos,
+            });
+            continue;
+        }
+
+        // symbols, longest match first
+        let third = chars.get(i + 2).copied();
+        let (kind, len) = match (c, next, third) {
+            ('.', Some('.'), Some('=')) => (TokenKind::DotDotEq, 3),
+            ('.', Some('.'), _) => (TokenKind::DotDot, 2),
+            ('=', Some('='), _) => (TokenKind::EqEq, 2),
+            ('!', Some('='), _) => (TokenKind::NotEq, 2),
+            ('<', Some('='), _) => (TokenKind::LtEq, 2),
+            ('>', Some('='), _) => (TokenKind::GtEq, 2),
+            ('&', Some('&'), _) => (TokenKind::AndAnd, 2),
+            ('|', Some('|'), _) => (TokenKind::OrOr, 2),
+            ('+', Some('='), _) => (TokenKind::PlusAssign, 2),
+            ('-', Some('='), _) => (TokenKind::MinusAssign, 2),
+            ('*', Some('='), _) => (TokenKind::StarAssign, 2),
+            ('/', Some('='), _) => (TokenKind::SlashAssign, 2),
+            ('%', Some('='), _) => (TokenKind::PercentAssign, 2),
+            ('(', _, _) => (TokenKind::LParen, 1),
+            (')', _, _) => (TokenKind::RParen, 1),
+            ('{', _, _) => (TokenKind::LBrace, 1),
+            ('}', _, _) => (TokenKind::RBrace, 1),
+            (';', _, _) => (TokenKind::Semi, 1),
+            (':', _, _) => (TokenKind::Colon, 1),
+            (',', _, _) => (TokenKind::Comma, 1),
+            ('!', _, _) => (TokenKind::Bang, 1),
+            ('=', _, _) => (TokenKind::Assign, 1),
+            ('+', _, _) => (TokenKind::Plus, 1),
+            ('-', _, _) => (TokenKind::Minus, 1),
+            ('*', _, _) => (TokenKind::Star, 1),
+            ('/', _, _) => (TokenKind::Slash, 1),
+            ('%', _, _) => (TokenKind::Percent, 1),
+            ('<', _, _) => (TokenKind::Lt, 1),
+            ('>', _, _) => (TokenKind::Gt, 1),
+            _ => return Err(LangError::new(pos, format!("unexpected character '{c}'"))),
+        };
+        advance(&mut i, &mut line, &mut col, len);
+        tokens.push(Token { kind, pos });
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        pos: Pos { line, col },
+    });
+    Ok(tokens)
+}
+
+// Integer literals may use '_' as a separator and may carry a type suffix like 8i16 or 3u8, in
+// which case a variable initialised with them gets that type.
+fn parse_int(text: &str, pos: Pos) -> Result<(i128, Option<IntType>), LangError> {
+    let mut digits: &str = text;
+    let mut suffix: Option<IntType> = None;
+    for ty in IntType::ALL {
+        if let Some(stripped) = text.strip_suffix(ty.name()) {
+            digits = stripped;
+            suffix = Some(ty);
+            break;
+        }
+    }
+    let cleaned: String = digits.chars().filter(|&c| c != '_').collect();
+    let value = cleaned
+        .parse::<i128>()
+        .map_err(|_| LangError::new(pos, format!("invalid integer literal '{text}'")))?;
+    Ok((value, suffix))
+}
@@ -0,0 +1,468 @@
+/* A hand-written recursive descent parser. Each grammar rule is one method, and binary operators
+ * are handled with one method per precedence level, from loosest to tightest:
+ *   ||   &&   == != < <= > >=   + -   * / %   unary - !   primary
+ * */
+use super::ast::{BinOp, Block, Expr, ExprKind, IntType, Stmt, Type, UnaryOp};
+use super::lexer::{Token, TokenKind};
+use super::{LangError, Pos};
+
+pub struct Parser {
+    tokens: Vec<Token>,
+    current: usize,
+}
+
+impl Parser {
+    pub fn new(tokens: Vec<Token>) -> Self {
+        Parser { tokens, current: 0 }
+    }
+
+    // A whole program is a block without the surrounding braces.
+    pub fn parse_program(&mut self) -> Result<Block, LangError> {
+        self.parse_block_contents(&TokenKind::Eof)
+    }
+
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.current].kind
+    }
+
+    fn peek_at(&self, offset: usize) -> &TokenKind {
+        let index = (self.current + offset).min(self.tokens.len() - 1);
+        &self.tokens[index].kind
+    }
+
+    fn pos(&self) -> Pos {
+        self.tokens[self.current].pos
+    }
+
+    fn bump(&mut self) -> Token {
+        let token = self.tokens[self.current].clone();
+        if self.current < self.tokens.len() - 1 {
+            self.current += 1;
+        }
+        token
+    }
+
+    fn check(&self, kind: &TokenKind) -> bool {
+        self.peek() == kind
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.check(kind) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, kind: &TokenKind, what: &str) -> Result<Token, LangError> {
+        if self.check(kind) {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(what))
+        }
+    }
+
+    fn expect_ident(&mut self, what: &str) -> Result<String, LangError> {
+        match self.peek().clone() {
+            TokenKind::Ident(name) => {
+                self.bump();
+                Ok(name)
+            }
+            _ => Err(self.unexpected(what)),
+        }
+    }
+
+    fn unexpected(&self, what: &str) -> LangError {
+        let found = match self.peek() {
+            TokenKind::Eof => "end of input".to_string(),
+            other => format!("{other:?}"),
+        };
+        LangError::new(self.pos(), format!("expected {what}, found {found}"))
+    }
+
+    fn parse_block(&mut self) -> Result<Block, LangError> {
+        self.expect(&TokenKind::LBrace, "'{'")?;
+        let block = self.parse_block_contents(&TokenKind::RBrace)?;
+        self.expect(&TokenKind::RBrace, "'}'")?;
+        Ok(block)
+    }
+
+    // Parses statements until 'end' (which is left for the caller to consume). An expression
+    // that is directly followed by 'end' without a semicolon becomes the block's tail value.
+    fn parse_block_contents(&mut self, end: &TokenKind) -> Result<Block, LangError> {
+        let mut stmts: Vec<Stmt> = Vec::new();
+        let mut tail: Option<Box<Expr>> = None;
+        while !self.check(end) {
+            if self.check(&TokenKind::Eof) {
+                return Err(self.unexpected("'}'"));
+            }
+            if self.eat(&TokenKind::Semi) {
+                continue;
+            }
+            match self.peek() {
+                TokenKind::Let => stmts.push(self.parse_let()?),
+                TokenKind::Ident(name) if name == "println" => stmts.push(self.parse_println()?),
+                TokenKind::Ident(_) if assign_op(self.peek_at(1)).is_some() => {
+                    stmts.push(self.parse_assign()?)
+                }
+                _ => {
+                    let expr = self.parse_expr()?;
+                    if self.eat(&TokenKind::Semi) {
+                        stmts.push(Stmt::Expr(expr));
+                    } else if self.check(end) {
+                        tail = Some(Box::new(expr));
+                    } else if expr.kind.is_block_like() {
+                        stmts.push(Stmt::Expr(expr));
+                    } else {
+                        return Err(self.unexpected("';'"));
+                    }
+                }
+            }
+        }
+        Ok(Block { stmts, tail })
+    }
+
+    fn parse_let(&mut self) -> Result<Stmt, LangError> {
+        let pos = self.bump().pos; // 'let'
+        let mutable = self.eat(&TokenKind::Mut);
+        let name = self.expect_ident("a variable name")?;
+        let mut ty: Option<Type> = None;
+        if self.eat(&TokenKind::Colon) {
+            let type_pos = self.pos();
+            let type_name = self.expect_ident("a type")?;
+            ty = Some(match type_name.as_str() {
+                "bool" => Type::Bool,
+                other => match IntType::from_name(other) {
+                    Some(int_type) => Type::Int(int_type),
+                    None => {
+                        return Err(LangError::new(
+                            type_pos,
+                            format!("unknown type '{other}' (only integers and bool exist here)"),
+                        ))
+                    }
+                },
+            });
+        }
+        self.expect(&TokenKind::Assign, "'=' (variables must be initialised)")?;
+        let value = self.parse_expr()?;
+        self.expect(&TokenKind::Semi, "';'")?;
+        Ok(Stmt::Let {
+            name,
+            mutable,
+            ty,
+            value,
+            pos,
+        })
+    }
+
+    fn parse_assign(&mut self) -> Result<Stmt, LangError> {
+        let pos = self.pos();
+        let name = self.expect_ident("a variable name")?;
+        let op = assign_op(self.peek()).expect("caller checked for an assignment operator");
+        self.bump();
+        let value = self.parse_expr()?;
+        self.expect(&TokenKind::Semi, "';'")?;
+        Ok(Stmt::Assign {
+            name,
+            op,
+            value,
+            pos,
+        })
+    }
+
+    // println!("count = {count}") or println!("{} and {}", a, b)
+    fn parse_println(&mut self) -> Result<Stmt, LangError> {
+        let pos = self.bump().pos; // 'println'
+        self.expect(&TokenKind::Bang, "'!' (println is a macro)")?;
+        self.expect(&TokenKind::LParen, "'('")?;
+        let format = match self.peek().clone() {
+            TokenKind::Str(text) => {
+                self.bump();
+                text
+            }
+            TokenKind::RParen => String::new(),
+            _ => return Err(self.unexpected("a format string")),
+        };
+        let mut args: Vec<Expr> = Vec::new();
+        while self.eat(&TokenKind::Comma) {
+            if self.check(&TokenKind::RParen) {
+                break; // trailing comma
+            }
+            args.push(self.parse_expr()?);
+        }
+        self.expect(&TokenKind::RParen, "')'")?;
+        self.expect(&TokenKind::Semi, "';'")?;
+        Ok(Stmt::Println { format, args, pos })
+    }
+
+    pub fn parse_expr(&mut self) -> Result<Expr, LangError> {
+        self.parse_or()
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_and()?;
+        while self.check(&TokenKind::OrOr) {
+            let pos = self.bump().pos;
+            let right = self.parse_and()?;
+            left = binary(BinOp::Or, left, right, pos);
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_comparison()?;
+        while self.check(&TokenKind::AndAnd) {
+            let pos = self.bump().pos;
+            let right = self.parse_comparison()?;
+            left = binary(BinOp::And, left, right, pos);
+        }
+        Ok(left)
+    }
+
+    // comparisons don't chain in Rust ('a < b < c' is an error), so only one is allowed here
+    fn parse_comparison(&mut self) -> Result<Expr, LangError> {
+        let left = self.parse_additive()?;
+        let op = match self.peek() {
+            TokenKind::EqEq => BinOp::Eq,
+            TokenKind::NotEq => BinOp::NotEq,
+            TokenKind::Lt => BinOp::Lt,
+            TokenKind::LtEq => BinOp::LtEq,
+            TokenKind::Gt => BinOp::Gt,
+            TokenKind::GtEq => BinOp::GtEq,
+            _ => return Ok(left),
+        };
+        let pos = self.bump().pos;
+        let right = self.parse_additive()?;
+        if matches!(
+            self.peek(),
+            TokenKind::EqEq
+                | TokenKind::NotEq
+                | TokenKind::Lt
+                | TokenKind::LtEq
+                | TokenKind::Gt
+                | TokenKind::GtEq
+        ) {
+            return Err(LangError::new(
+                self.pos(),
+                "comparison operators cannot be chained; use parentheses",
+            ));
+        }
+        Ok(binary(op, left, right, pos))
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                TokenKind::Plus => BinOp::Add,
+                TokenKind::Minus => BinOp::Sub,
+                _ => return Ok(left),
+            };
+            let pos = self.bump().pos;
+            let right = self.parse_multiplicative()?;
+            left = binary(op, left, right, pos);
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, LangError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            let op = match self.peek() {
+                TokenKind::Star => BinOp::Mul,
+                TokenKind::Slash => BinOp::Div,
+                TokenKind::Percent => BinOp::Rem,
+                _ => return Ok(left),
+            };
+            let pos = self.bump().pos;
+            let right = self.parse_unary()?;
+            left = binary(op, left, right, pos);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, LangError> {
+        let op = match self.peek() {
+            TokenKind::Minus => UnaryOp::Neg,
+            TokenKind::Bang => UnaryOp::Not,
+            _ => return self.parse_primary(),
+        };
+        let pos = self.bump().pos;
+        let operand = self.parse_unary()?;
+        Ok(Expr {
+            kind: ExprKind::Unary(op, Box::new(operand)),
+            pos,
+        })
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, LangError> {
+        let pos = self.pos();
+        let kind = match self.peek().clone() {
+            TokenKind::Int(value, suffix) => {
+                self.bump();
+                ExprKind::Int(value, suffix)
+            }
+            TokenKind::True => {
+                self.bump();
+                ExprKind::Bool(true)
+            }
+            TokenKind::False => {
+                self.bump();
+                ExprKind::Bool(false)
+            }
+            TokenKind::Ident(name) => {
+                self.bump();
+                ExprKind::Var(name)
+            }
+            TokenKind::LParen => {
+                self.bump();
+                let inner = self.parse_expr()?;
+                self.expect(&TokenKind::RParen, "')'")?;
+                return Ok(inner);
+            }
+            TokenKind::LBrace => ExprKind::Block(self.parse_block()?),
+            TokenKind::If => return self.parse_if(),
+            TokenKind::Label(label) => {
+                self.bump();
+                self.expect(&TokenKind::Colon, "':' after a loop label")?;
+                return self.parse_loop_kind(Some(label), pos);
+            }
+            TokenKind::Loop | TokenKind::While | TokenKind::For => {
+                return self.parse_loop_kind(None, pos)
+            }
+            TokenKind::Break => {
+                self.bump();
+                let label = self.parse_optional_label();
+                let value = if self.starts_expr() {
+                    Some(Box::new(self.parse_expr()?))
+                } else {
+                    None
+                };
+                ExprKind::Break { label, value }
+            }
+            TokenKind::Continue => {
+                self.bump();
+                let label = self.parse_optional_label();
+                ExprKind::Continue { label }
+            }
+            _ => return Err(self.unexpected("an expression")),
+        };
+        Ok(Expr { kind, pos })
+    }
+
+    fn parse_optional_label(&mut self) -> Option<String> {
+        match self.peek().clone() {
+            TokenKind::Label(label) => {
+                self.bump();
+                Some(label)
+            }
+            _ => None,
+        }
+    }
+
+    // whether 'break' is followed by a value rather than the end of the statement
+    fn starts_expr(&self) -> bool {
+        !matches!(
+            self.peek(),
+            TokenKind::Semi
+                | TokenKind::RBrace
+                | TokenKind::RParen
+                | TokenKind::Comma
+                | TokenKind::Eof
+        )
+    }
+
+    fn parse_if(&mut self) -> Result<Expr, LangError> {
+        let pos = self.bump().pos; // 'if'
+        let cond = self.parse_expr()?;
+        let then_branch = self.parse_block()?;
+        let else_branch = if self.eat(&TokenKind::Else) {
+            if self.check(&TokenKind::If) {
+                Some(Box::new(self.parse_if()?))
+            } else {
+                let else_pos = self.pos();
+                let block = self.parse_block()?;
+                Some(Box::new(Expr {
+                    kind: ExprKind::Block(block),
+                    pos: else_pos,
+                }))
+            }
+        } else {
+            None
+        };
+        Ok(Expr {
+            kind: ExprKind::If {
+                cond: Box::new(cond),
+                then_branch,
+                else_branch,
+            },
+            pos,
+        })
+    }
+
+    fn parse_loop_kind(&mut self, label: Option<String>, pos: Pos) -> Result<Expr, LangError> {
+        let kind = match self.peek() {
+            TokenKind::Loop => {
+                self.bump();
+                let body = self.parse_block()?;
+                ExprKind::Loop { label, body }
+            }
+            TokenKind::While => {
+                self.bump();
+                let cond = self.parse_expr()?;
+                let body = self.parse_block()?;
+                ExprKind::While {
+                    label,
+                    cond: Box::new(cond),
+                    body,
+                }
+            }
+            TokenKind::For => {
+                self.bump();
+                let var = self.expect_ident("a loop variable")?;
+                self.expect(&TokenKind::In, "'in'")?;
+                let start = self.parse_expr()?;
+                let inclusive = if self.eat(&TokenKind::DotDotEq) {
+                    true
+                } else {
+                    self.expect(
+                        &TokenKind::DotDot,
+                        "'..' or '..=' (for loops run over a range)",
+                    )?;
+                    false
+                };
+                let end = self.parse_expr()?;
+                let body = self.parse_block()?;
+                ExprKind::For {
+                    label,
+                    var,
+                    start: Box::new(start),
+                    end: Box::new(end),
+                    inclusive,
+                    body,
+                }
+            }
+            _ => return Err(self.unexpected("'loop', 'while' or 'for' after a label")),
+        };
+        Ok(Expr { kind, pos })
+    }
+}
+
+fn binary(op: BinOp, left: Expr, right: Expr, pos: Pos) -> Expr {
+    Expr {
+        kind: ExprKind::Binary(op, Box::new(left), Box::new(right)),
+        pos,
+    }
+}
+
+// Some(None) for '=', Some(Some(op)) for the compound assignments, None for anything else
+fn assign_op(kind: &TokenKind) -> Option<Option<BinOp>> {
+    match kind {
+        TokenKind::Assign => Some(None),
+        TokenKind::PlusAssign => Some(Some(BinOp::Add)),
+        TokenKind::MinusAssign => Some(Some(BinOp::Sub)),
+        TokenKind::StarAssign => Some(Some(BinOp::Mul)),
+        TokenKind::SlashAssign => Some(Some(BinOp::Div)),
+        TokenKind::PercentAssign => Some(Some(BinOp::Rem)),
+        _ => None,
+    }
+}
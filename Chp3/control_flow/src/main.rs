@@ -1,4 +1,12 @@
+use control_flow::looplang;
+
 fn main() {
+    // 'cargo run -- <script> [--trace]' runs a looplang script instead of the demos below
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        run_script(&args);
+        return;
+    }
     println!("Hello, world!");
     conditional(8i16);
     conditional(15i16);
@@ -17,6 +25,7 @@ fn main() {
     while_loop(3u8);
     while_loop(10u8);
     for_loop();
+    traced_labeled_loop();
 }
 
 fn conditional(num: i16) {
@@ -117,3 +126,38 @@ fn for_loop() {
         println!("{element}");
     }
 }
+
+/* Tracing the labeled loop */
+// The same labeled_loop() as above, but run by the looplang interpreter (src/looplang.rs) with
+// tracing turned on, so every iteration prints the variables that are in scope at that point.
+// Notice that 'remaining' only shows up in the inner loop's trace; it's declared inside the outer
+// loop's body, so it is dropped and re-created on every pass of 'counting_up.
+fn traced_labeled_loop() {
+    println!("\nlabeled_loop() again, traced by looplang:");
+    let source: &str = include_str!("../scripts/labeled_loop.loop");
+    if let Err(err) = looplang::run(source, true) {
+        println!("{err}");
+    }
+}
+
+// Says what went wrong and exits with status 1, so scripts can tell.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+fn run_script(args: &[String]) {
+    let trace: bool = args.iter().any(|arg| arg == "--trace");
+    let Some(path) = args.iter().find(|arg| !arg.starts_with("--")) else {
+        fail("usage: control_flow <script.loop> [--trace]");
+    };
+    let source = match std::fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(err) => fail(format!("could not read {path}: {err}")),
+    };
+    match looplang::run(&source, trace) {
+        Ok(looplang::eval::Value::Unit) => {}
+        Ok(value) => println!("=> {value}"),
+        Err(err) => fail(format!("{path}: {err}")),
+    }
+}
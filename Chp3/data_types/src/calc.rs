@@ -0,0 +1,1235 @@
+/* Typed calculator */
+// The operations section of main.rs prints things like 5 / 3 and 10.0 / 3.0, where the answer
+// depends entirely on the types rustc picks for the literals. This calculator reads the same kind
+// of expressions and works out the types the way rustc does before evaluating anything:
+//   * literals can have a suffix (5u8, 10.0f32, 1e3f32), be hex, octal or binary (0xff, 0o17,
+//     0b1010) and use '_' separators (1_000)
+//   * a literal without a suffix takes its type from whatever it's combined with, so in
+//     5u8 + 10 the 10 is a u8 too. Anything left undecided becomes i32 or f64, like in rustc
+//   * both sides of an operator have to be the same type, so 2.5f32 + 1.0f64 is a
+//     "mismatched types" error, just like the comment in main.rs says
+//   * 'as' converts between types, and 'let x: u8 = ...' keeps a value around for later lines
+// Evaluation follows Rust's rules too: integer division truncates, and overflow panics in debug
+// mode and wraps in release mode. Dividing by zero panics in both.
+//
+// One difference from real Rust: rustc notices when a constant expression like 255u8 + 1 is
+// going to overflow and refuses to compile it (the arithmetic_overflow lint). The calculator
+// evaluates everything as though the values only showed up at runtime, so you see the panic.
+use crate::int_explorer::{IntKind, Op, Wide};
+use std::collections::HashMap;
+use std::fmt;
+
+/* Types */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Type {
+    Int(IntKind),
+    F32,
+    F64,
+}
+
+impl Type {
+    pub fn by_name(name: &str) -> Option<Type> {
+        match name {
+            "f32" => Some(Type::F32),
+            "f64" => Some(Type::F64),
+            _ => IntKind::by_name(name).map(Type::Int),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Type::Int(kind) => kind.name,
+            Type::F32 => "f32",
+            Type::F64 => "f64",
+        }
+    }
+
+    fn is_float(self) -> bool {
+        matches!(self, Type::F32 | Type::F64)
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    // overflow panics, like 'cargo run'
+    Debug,
+    // overflow wraps around, like 'cargo run --release'
+    Release,
+}
+
+/* Errors */
+// Compile errors and runtime panics both point at a span of the input line, so they can be
+// printed with the ^^^ underline rustc uses.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    // an error code like E0308, if rustc has one for it, and the headline message
+    Error(Option<&'static str>, String),
+    Panic(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalcError {
+    pub problem: Problem,
+    pub start: usize,
+    pub end: usize,
+    pub label: String,
+}
+
+impl CalcError {
+    fn error(code: Option<&'static str>, message: impl Into<String>, span: Span) -> Self {
+        CalcError {
+            problem: Problem::Error(code, message.into()),
+            start: span.0,
+            end: span.1,
+            label: String::new(),
+        }
+    }
+
+    fn panic(message: impl Into<String>, span: Span) -> Self {
+        CalcError {
+            problem: Problem::Panic(message.into()),
+            start: span.0,
+            end: span.1,
+            label: String::new(),
+        }
+    }
+
+    fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+        self
+    }
+
+    // Formats the error the way rustc (or the panic handler) would, underlining the part of
+    // the input it's about.
+    pub fn render(&self, source: &str) -> String {
+        let headline = match &self.problem {
+            Problem::Error(Some(code), message) => format!("error[{code}]: {message}"),
+            Problem::Error(None, message) => format!("error: {message}"),
+            Problem::Panic(message) => format!(
+                "thread 'main' panicked at <input>:1:{}:\n{message}",
+                self.start + 1
+            ),
+        };
+        let width = (self.end.max(self.start + 1)) - self.start;
+        let underline = format!(
+            "{}{} {}",
+            " ".repeat(self.start),
+            "^".repeat(width),
+            self.label
+        );
+        format!(
+            "{headline}\n  |\n  | {source}\n  | {}\n",
+            underline.trim_end()
+        )
+    }
+}
+
+impl fmt::Display for CalcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.problem {
+            Problem::Error(_, message) | Problem::Panic(message) => write!(f, "{message}")?,
+        }
+        if !self.label.is_empty() {
+            write!(f, ": {}", self.label)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for CalcError {}
+
+/* Tokens */
+// start and end character positions in the input
+type Span = (usize, usize);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // the digits with '_' and any 0x/0o/0b prefix removed, the radix, and the suffix
+    Int(String, u32, Option<String>),
+    // the literal text for str::parse, and the suffix
+    Float(String, Option<String>),
+    Ident(String),
+    Let,
+    As,
+    Op(Op),
+    LeftParen,
+    RightParen,
+    Colon,
+    Equals,
+    Semicolon,
+}
+
+fn tokenize(input: &str) -> Result<Vec<(Token, Span)>, CalcError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens: Vec<(Token, Span)> = Vec::new();
+    let mut i: usize = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() {
+            let token = number(&chars, &mut i)?;
+            tokens.push((token, (start, i)));
+            continue;
+        }
+        if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let token = match word.as_str() {
+                "let" => Token::Let,
+                "as" => Token::As,
+                _ => Token::Ident(word),
+            };
+            tokens.push((token, (start, i)));
+            continue;
+        }
+        let token = match c {
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '%' => Token::Op(Op::Rem),
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            ':' => Token::Colon,
+            '=' => Token::Equals,
+            ';' => Token::Semicolon,
+            _ => {
+                return Err(CalcError::error(
+                    None,
+                    format!("unknown start of token: {c}"),
+                    (i, i + 1),
+                ))
+            }
+        };
+        i += 1;
+        tokens.push((token, (start, i)));
+    }
+    Ok(tokens)
+}
+
+// Reads a number literal starting at chars[*i], the way rustc's lexer splits it into digits and
+// a suffix.
+fn number(chars: &[char], i: &mut usize) -> Result<Token, CalcError> {
+    let start = *i;
+    let radix: u32 = match (chars[*i], chars.get(*i + 1)) {
+        ('0', Some('x')) => 16,
+        ('0', Some('o')) => 8,
+        ('0', Some('b')) => 2,
+        _ => 10,
+    };
+    if radix != 10 {
+        *i += 2;
+    }
+    let mut digits = String::new();
+    let mut is_float = false;
+    while *i < chars.len() {
+        let c = chars[*i];
+        if c == '_' {
+            *i += 1;
+        } else if c.is_digit(radix) || (radix != 10 && c.is_ascii_digit()) {
+            digits.push(c);
+            *i += 1;
+        } else if radix == 10 && c == '.' && !is_float {
+            // 1.5 and 1. are floats, but 1..2 is a range and 1.max(2) is a method call
+            match chars.get(*i + 1) {
+                Some(&next) if next.is_ascii_digit() => {}
+                Some(&next) if next == '.' || next == '_' || next.is_alphabetic() => break,
+                _ => {}
+            }
+            digits.push('.');
+            is_float = true;
+            *i += 1;
+        } else if radix == 10 && (c == 'e' || c == 'E') && exponent_follows(chars, *i + 1) {
+            digits.push('e');
+            *i += 1;
+            if matches!(chars.get(*i), Some('+') | Some('-')) {
+                digits.push(chars[*i]);
+                *i += 1;
+            }
+            while *i < chars.len() && (chars[*i].is_ascii_digit() || chars[*i] == '_') {
+                if chars[*i] != '_' {
+                    digits.push(chars[*i]);
+                }
+                *i += 1;
+            }
+            is_float = true;
+            break;
+        } else {
+            break;
+        }
+    }
+    let suffix_start = *i;
+    while *i < chars.len() && (chars[*i].is_alphanumeric() || chars[*i] == '_') {
+        *i += 1;
+    }
+    let suffix: Option<String> =
+        (suffix_start < *i).then(|| chars[suffix_start..*i].iter().collect());
+
+    if digits.is_empty() {
+        return Err(CalcError::error(
+            None,
+            "no valid digits found for number",
+            (start, *i),
+        ));
+    }
+    if let Some(bad) = digits.chars().find(|c| !c.is_digit(radix) && radix != 10) {
+        let name = if radix == 8 { "octal" } else { "binary" };
+        return Err(CalcError::error(
+            None,
+            format!("invalid digit for a base {radix} literal"),
+            (start, *i),
+        )
+        .with_label(format!("'{bad}' is not a valid {name} digit")));
+    }
+    let float_suffix = matches!(suffix.as_deref(), Some("f32") | Some("f64"));
+    match suffix.as_deref() {
+        None => {}
+        Some(s) if float_suffix && radix != 10 => {
+            return Err(CalcError::error(
+                None,
+                format!("{} float literal is not supported", radix_name(radix)),
+                (start, *i),
+            )
+            .with_label(format!("suffix `{s}` only works on decimal literals")));
+        }
+        Some(s) if is_float && !float_suffix => {
+            return Err(CalcError::error(
+                None,
+                format!("invalid suffix `{s}` for float literal"),
+                (suffix_start, *i),
+            )
+            .with_label("valid suffixes are `f32` and `f64`"));
+        }
+        Some(s) if !float_suffix && IntKind::by_name(s).is_none() => {
+            return Err(CalcError::error(
+                None,
+                format!("invalid suffix `{s}` for number literal"),
+                (suffix_start, *i),
+            )
+            .with_label(
+                "the suffix must be one of the numeric types (`u32`, `isize`, `f32`, etc.)",
+            ));
+        }
+        Some(_) => {}
+    }
+    // 5f32 is a float even without a decimal point
+    if is_float || float_suffix {
+        Ok(Token::Float(digits, suffix))
+    } else {
+        Ok(Token::Int(digits, radix, suffix))
+    }
+}
+
+fn exponent_follows(chars: &[char], i: usize) -> bool {
+    match chars.get(i) {
+        Some('+') | Some('-') => chars.get(i + 1).is_some_and(|c| c.is_ascii_digit()),
+        Some(c) => c.is_ascii_digit(),
+        None => false,
+    }
+}
+
+fn radix_name(radix: u32) -> &'static str {
+    match radix {
+        16 => "hexadecimal",
+        8 => "octal",
+        _ => "binary",
+    }
+}
+
+/* Syntax */
+#[derive(Debug, Clone, PartialEq)]
+enum ExprKind {
+    Int(Wide, Option<Type>),
+    Float(String, Option<Type>),
+    Var(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+    Cast(Box<Expr>, Type),
+}
+
+// Every node gets an id, which is where the type checker writes down its type.
+#[derive(Debug, Clone, PartialEq)]
+struct Expr {
+    id: usize,
+    kind: ExprKind,
+    span: Span,
+    // how many levels of the tree this node is on top of, counting itself
+    depth: usize,
+}
+
+// The parser, the checker and eval all recurse once per level of the tree, and so does dropping
+// it, so thousands of brackets (or thousands of terms in a row, since 1 + 1 + 1 leans to the left)
+// would overflow the stack. Each level takes a lot of stack in a debug build, so this stays low
+// enough for a thread with a small stack, like a test's; nobody types 64 levels by hand.
+pub const MAX_DEPTH: usize = 64;
+
+enum Line {
+    Let(String, Option<Type>, Expr),
+    Expr(Expr),
+}
+
+struct Parser {
+    chars: Vec<char>,
+    tokens: Vec<(Token, Span)>,
+    pos: usize,
+    next_id: usize,
+    end: usize,
+    // how many brackets and minus signs the parser is inside right now
+    nesting: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn span(&self) -> Span {
+        match self.tokens.get(self.pos) {
+            Some((_, span)) => *span,
+            None => (self.end, self.end + 1),
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<Span, CalcError> {
+        if self.peek() == Some(&token) {
+            self.pos += 1;
+            return Ok(self.tokens[self.pos - 1].1);
+        }
+        Err(self.unexpected(what))
+    }
+
+    fn unexpected(&self, what: &str) -> CalcError {
+        let found = match self.tokens.get(self.pos) {
+            Some((_, (start, end))) => format!("found `{}`", self.source_text(*start, *end)),
+            None => "found the end of the line".to_string(),
+        };
+        CalcError::error(None, format!("expected {what}, {found}"), self.span())
+    }
+
+    fn source_text(&self, start: usize, end: usize) -> String {
+        self.chars[start..end].iter().collect()
+    }
+
+    fn too_deep(&self, span: Span) -> CalcError {
+        CalcError::error(None, "expression is nested too deeply", span)
+            .with_label(format!("more than {MAX_DEPTH} levels"))
+    }
+
+    // Brackets and minus signs make the parser recurse before there's a node to measure, so they
+    // are counted on the way in.
+    fn enter(&mut self, span: Span) -> Result<(), CalcError> {
+        if self.nesting >= MAX_DEPTH {
+            return Err(self.too_deep(span));
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    fn node(&mut self, kind: ExprKind, span: Span) -> Result<Expr, CalcError> {
+        let below = match &kind {
+            ExprKind::Neg(inner) | ExprKind::Cast(inner, _) => inner.depth,
+            ExprKind::Binary(_, left, right) => left.depth.max(right.depth),
+            _ => 0,
+        };
+        if below >= MAX_DEPTH {
+            return Err(self.too_deep(span));
+        }
+        self.next_id += 1;
+        Ok(Expr {
+            id: self.next_id - 1,
+            kind,
+            span,
+            depth: below + 1,
+        })
+    }
+
+    fn line(&mut self) -> Result<Line, CalcError> {
+        let line = if self.peek() == Some(&Token::Let) {
+            self.pos += 1;
+            let name = match self.tokens.get(self.pos) {
+                Some((Token::Ident(name), _)) => name.clone(),
+                _ => return Err(self.unexpected("a variable name")),
+            };
+            self.pos += 1;
+            let annotation = if self.peek() == Some(&Token::Colon) {
+                self.pos += 1;
+                Some(self.type_name()?.0)
+            } else {
+                None
+            };
+            self.expect(Token::Equals, "`=`")?;
+            Line::Let(name, annotation, self.additive()?)
+        } else {
+            Line::Expr(self.additive()?)
+        };
+        if self.peek() == Some(&Token::Semicolon) {
+            self.pos += 1;
+        }
+        if self.pos < self.tokens.len() {
+            return Err(self.unexpected("an operator"));
+        }
+        Ok(line)
+    }
+
+    fn type_name(&mut self) -> Result<(Type, Span), CalcError> {
+        match self.tokens.get(self.pos) {
+            Some((Token::Ident(name), span)) => {
+                let span = *span;
+                let name = name.clone();
+                self.pos += 1;
+                match Type::by_name(&name) {
+                    Some(ty) => Ok((ty, span)),
+                    None => Err(CalcError::error(
+                        Some("E0412"),
+                        format!("cannot find type `{name}` in this scope"),
+                        span,
+                    )
+                    .with_label("not found in this scope")),
+                }
+            }
+            _ => Err(self.unexpected("a type")),
+        }
+    }
+
+    // + and - bind loosest, then * / %, then 'as', then unary minus
+    fn additive(&mut self) -> Result<Expr, CalcError> {
+        let mut left = self.multiplicative()?;
+        while let Some(Token::Op(op @ (Op::Add | Op::Sub))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.multiplicative()?;
+            let span = (left.span.0, right.span.1);
+            left = self.node(ExprKind::Binary(op, Box::new(left), Box::new(right)), span)?;
+        }
+        Ok(left)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, CalcError> {
+        let mut left = self.cast()?;
+        while let Some(Token::Op(op @ (Op::Mul | Op::Div | Op::Rem))) = self.peek() {
+            let op = *op;
+            self.pos += 1;
+            let right = self.cast()?;
+            let span = (left.span.0, right.span.1);
+            left = self.node(ExprKind::Binary(op, Box::new(left), Box::new(right)), span)?;
+        }
+        Ok(left)
+    }
+
+    fn cast(&mut self) -> Result<Expr, CalcError> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(&Token::As) {
+            self.pos += 1;
+            let (ty, type_span) = self.type_name()?;
+            let span = (expr.span.0, type_span.1);
+            expr = self.node(ExprKind::Cast(Box::new(expr), ty), span)?;
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<Expr, CalcError> {
+        if self.peek() == Some(&Token::Op(Op::Sub)) {
+            let start = self.span().0;
+            self.enter(self.span())?;
+            self.pos += 1;
+            let operand = self.unary()?;
+            self.nesting -= 1;
+            let span = (start, operand.span.1);
+            return self.node(ExprKind::Neg(Box::new(operand)), span);
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, CalcError> {
+        let Some((token, span)) = self.tokens.get(self.pos).cloned() else {
+            return Err(self.unexpected("an expression"));
+        };
+        self.pos += 1;
+        let suffix_type = |suffix: Option<String>| suffix.as_deref().and_then(Type::by_name);
+        match token {
+            Token::Int(digits, radix, suffix) => {
+                let Ok(magnitude) = u128::from_str_radix(&digits, radix) else {
+                    return Err(CalcError::error(None, "integer literal is too large", span)
+                        .with_label("value exceeds limit of `u128::MAX`"));
+                };
+                self.node(
+                    ExprKind::Int(Wide::new(false, magnitude), suffix_type(suffix)),
+                    span,
+                )
+            }
+            Token::Float(text, suffix) => {
+                self.node(ExprKind::Float(text, suffix_type(suffix)), span)
+            }
+            Token::Ident(name) => self.node(ExprKind::Var(name), span),
+            Token::LeftParen => {
+                self.enter(span)?;
+                let inner = self.additive()?;
+                self.nesting -= 1;
+                let close = self.expect(Token::RightParen, "`)`")?;
+                Ok(Expr {
+                    span: (span.0, close.1),
+                    ..inner
+                })
+            }
+            _ => {
+                self.pos -= 1;
+                Err(self.unexpected("an expression"))
+            }
+        }
+    }
+}
+
+/* Type inference */
+// Each expression gets a slot. A slot either holds a known type, or is still open: an integer
+// literal without a suffix is "some integer type", written {integer} in rustc's errors, and a
+// float literal is {float}. Unifying two slots links them, so deciding one decides the other.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Slot {
+    Known(Type),
+    AnyInt,
+    AnyFloat,
+    SameAs(usize),
+}
+
+struct Checker<'a> {
+    slots: Vec<Slot>,
+    // the slot for every expression, by id
+    node_slots: Vec<usize>,
+    variables: &'a HashMap<String, (Type, Value)>,
+}
+
+impl Checker<'_> {
+    fn new_slot(&mut self, slot: Slot) -> usize {
+        self.slots.push(slot);
+        self.slots.len() - 1
+    }
+
+    fn root(&self, mut slot: usize) -> usize {
+        while let Slot::SameAs(next) = self.slots[slot] {
+            slot = next;
+        }
+        slot
+    }
+
+    fn describe(&self, slot: usize) -> String {
+        match self.slots[self.root(slot)] {
+            Slot::Known(ty) => format!("`{ty}`"),
+            Slot::AnyInt => "integer".to_string(),
+            _ => "floating-point number".to_string(),
+        }
+    }
+
+    // Makes two slots the same type, or explains why they can't be.
+    fn unify(&mut self, a: usize, b: usize) -> Result<(), ()> {
+        let (a, b) = (self.root(a), self.root(b));
+        if a == b {
+            return Ok(());
+        }
+        let (keep, link) = match (self.slots[a], self.slots[b]) {
+            (Slot::Known(x), Slot::Known(y)) if x == y => (a, b),
+            (Slot::Known(Type::Int(_)), Slot::AnyInt) => (a, b),
+            (Slot::AnyInt, Slot::Known(Type::Int(_))) => (b, a),
+            (Slot::Known(ty), Slot::AnyFloat) if ty.is_float() => (a, b),
+            (Slot::AnyFloat, Slot::Known(ty)) if ty.is_float() => (b, a),
+            (Slot::AnyInt, Slot::AnyInt) | (Slot::AnyFloat, Slot::AnyFloat) => (a, b),
+            _ => return Err(()),
+        };
+        self.slots[link] = Slot::SameAs(keep);
+        Ok(())
+    }
+
+    fn check(&mut self, expr: &Expr) -> Result<usize, CalcError> {
+        let slot = match &expr.kind {
+            ExprKind::Int(_, suffix) => self.new_slot(suffix.map_or(Slot::AnyInt, Slot::Known)),
+            ExprKind::Float(_, suffix) => self.new_slot(suffix.map_or(Slot::AnyFloat, Slot::Known)),
+            ExprKind::Var(name) => match self.variables.get(name) {
+                Some((ty, _)) => self.new_slot(Slot::Known(*ty)),
+                None => {
+                    return Err(CalcError::error(
+                        Some("E0425"),
+                        format!("cannot find value `{name}` in this scope"),
+                        expr.span,
+                    )
+                    .with_label("not found in this scope"))
+                }
+            },
+            ExprKind::Neg(operand) => self.check(operand)?,
+            ExprKind::Binary(op, left, right) => {
+                let left_slot = self.check(left)?;
+                let right_slot = self.check(right)?;
+                if self.unify(left_slot, right_slot).is_err() {
+                    return Err(self.mismatch(*op, left_slot, right_slot, right.span));
+                }
+                left_slot
+            }
+            ExprKind::Cast(inner, target) => {
+                let inner_slot = self.check(inner)?;
+                // rustc lets the target type decide an undecided literal when it's the same kind
+                // of number, so in 300 as u8 the literal is already a u8 (and out of range)
+                let root = self.root(inner_slot);
+                let compatible = match self.slots[root] {
+                    Slot::AnyInt => matches!(target, Type::Int(_)),
+                    Slot::AnyFloat => target.is_float(),
+                    _ => false,
+                };
+                if compatible {
+                    self.slots[root] = Slot::Known(*target);
+                }
+                self.new_slot(Slot::Known(*target))
+            }
+        };
+        if self.node_slots.len() <= expr.id {
+            self.node_slots.resize(expr.id + 1, 0);
+        }
+        self.node_slots[expr.id] = slot;
+        Ok(slot)
+    }
+
+    fn mismatch(&self, op: Op, left: usize, right: usize, span: Span) -> CalcError {
+        let either_known = [left, right]
+            .iter()
+            .any(|&slot| matches!(self.slots[self.root(slot)], Slot::Known(_)));
+        if either_known {
+            return CalcError::error(Some("E0308"), "mismatched types", span).with_label(format!(
+                "expected {}, found {}",
+                self.describe(left),
+                self.describe(right)
+            ));
+        }
+        // {integer} with {float}: rustc can't pick a type for either, so it complains about the
+        // operator instead
+        let (l, r) = match self.slots[self.root(left)] {
+            Slot::AnyInt => ("`{integer}`", "`{float}`"),
+            _ => ("`{float}`", "`{integer}`"),
+        };
+        let message = match op {
+            Op::Add => format!("cannot add {r} to {l}"),
+            Op::Sub => format!("cannot subtract {r} from {l}"),
+            Op::Mul => format!("cannot multiply {l} by {r}"),
+            Op::Div => format!("cannot divide {l} by {r}"),
+            Op::Rem => format!("cannot calculate the remainder of {l} divided by {r}"),
+        };
+        CalcError::error(Some("E0277"), message, span)
+            .with_label(format!("no implementation for {l} {} {r}", op.symbol()))
+    }
+
+    // Whatever is still undecided gets rustc's fallback: i32 for integers, f64 for floats.
+    fn resolve(&self, slot: usize) -> Type {
+        match self.slots[self.root(slot)] {
+            Slot::Known(ty) => ty,
+            Slot::AnyInt => Type::Int(IntKind::by_name("i32").expect("i32 is a type")),
+            _ => Type::F64,
+        }
+    }
+}
+
+// With every type decided, rustc checks each literal against its type. A literal that doesn't
+// fit is an error (the overflowing_literals lint), and so is negating an unsigned value. A literal
+// straight after a minus is checked as a negative number, which is why -128i8 is allowed.
+fn check_literals(expr: &Expr, types: &[Type], negated: bool) -> Result<(), CalcError> {
+    let ty = types[expr.id];
+    match &expr.kind {
+        ExprKind::Int(value, _) => {
+            let value = if negated { -*value } else { *value };
+            match ty {
+                Type::Int(kind) if !kind.fits(value) => {
+                    let shown = if negated { "-" } else { "" };
+                    Err(CalcError::error(
+                        None,
+                        format!("literal out of range for `{}`", kind.name),
+                        expr.span,
+                    )
+                    .with_label(format!(
+                        "the literal `{shown}{}` does not fit into the type `{}` whose range is `{}..={}`",
+                        value.magnitude(),
+                        kind.name,
+                        kind.min(),
+                        kind.max()
+                    )))
+                }
+                _ => Ok(()),
+            }
+        }
+        ExprKind::Float(text, _) => {
+            let infinite = match ty {
+                Type::F32 => text.parse::<f32>().is_ok_and(f32::is_infinite),
+                _ => text.parse::<f64>().is_ok_and(f64::is_infinite),
+            };
+            if infinite {
+                return Err(CalcError::error(
+                    None,
+                    format!("literal out of range for `{ty}`"),
+                    expr.span,
+                )
+                .with_label(format!("the literal does not fit into the type `{ty}` and will be converted to `{ty}::INFINITY`")));
+            }
+            Ok(())
+        }
+        ExprKind::Var(_) => Ok(()),
+        ExprKind::Neg(operand) => {
+            if let Type::Int(kind) = ty {
+                if !kind.signed {
+                    return Err(CalcError::error(
+                        Some("E0600"),
+                        format!("cannot apply unary operator `-` to type `{}`", kind.name),
+                        expr.span,
+                    )
+                    .with_label("unsigned values cannot be negated"));
+                }
+            }
+            check_literals(operand, types, true)
+        }
+        ExprKind::Binary(_, left, right) => {
+            check_literals(left, types, false)?;
+            check_literals(right, types, false)
+        }
+        ExprKind::Cast(inner, _) => check_literals(inner, types, false),
+    }
+}
+
+/* Values */
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Value {
+    Int(IntKind, Wide),
+    F32(f32),
+    F64(f64),
+}
+
+impl Value {
+    pub fn ty(self) -> Type {
+        match self {
+            Value::Int(kind, _) => Type::Int(kind),
+            Value::F32(_) => Type::F32,
+            Value::F64(_) => Type::F64,
+        }
+    }
+
+    // what 'self as target' gives
+    fn cast(self, target: Type) -> Value {
+        match (self, target) {
+            // keep the low bits, then read them as the new type
+            (Value::Int(_, value), Type::Int(kind)) => {
+                Value::Int(kind, kind.from_bits(value.twos_complement()))
+            }
+            (Value::Int(_, value), Type::F32) => {
+                let magnitude = value.magnitude() as f32;
+                Value::F32(if value.is_negative() {
+                    -magnitude
+                } else {
+                    magnitude
+                })
+            }
+            (Value::Int(_, value), Type::F64) => {
+                let magnitude = value.magnitude() as f64;
+                Value::F64(if value.is_negative() {
+                    -magnitude
+                } else {
+                    magnitude
+                })
+            }
+            (Value::F32(x), _) => Value::F64(x as f64).cast(target),
+            (Value::F64(x), Type::F32) => Value::F32(x as f32),
+            (Value::F64(x), Type::F64) => Value::F64(x),
+            // float to int rounds towards zero and saturates at the type's limits; NaN becomes 0
+            (Value::F64(x), Type::Int(kind)) => {
+                let value = if x.is_nan() {
+                    Wide::new(false, 0)
+                } else if x < 0.0 {
+                    Wide::new(true, (-x) as u128)
+                } else {
+                    Wide::new(false, x as u128)
+                };
+                let clamped = if kind.fits(value) {
+                    value
+                } else if value.is_negative() {
+                    kind.min()
+                } else {
+                    kind.max()
+                };
+                Value::Int(kind, clamped)
+            }
+        }
+    }
+}
+
+// Prints the value the way println!("{}") would.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Int(_, value) => write!(f, "{value}"),
+            Value::F32(x) => write!(f, "{x}"),
+            Value::F64(x) => write!(f, "{x}"),
+        }
+    }
+}
+
+/* Evaluation */
+fn eval(expr: &Expr, types: &[Type], calc: &Calculator) -> Result<Value, CalcError> {
+    let ty = types[expr.id];
+    match &expr.kind {
+        ExprKind::Int(value, _) => Ok(match ty {
+            Type::Int(kind) => Value::Int(kind, *value),
+            _ => unreachable!("integer literals only get integer types"),
+        }),
+        ExprKind::Float(text, _) => Ok(match ty {
+            // parse straight into the target type, so an f32 literal is rounded only once
+            Type::F32 => Value::F32(text.parse().expect("the lexer checked the digits")),
+            _ => Value::F64(text.parse().expect("the lexer checked the digits")),
+        }),
+        ExprKind::Var(name) => Ok(calc.variables[name].1),
+        ExprKind::Neg(operand) => {
+            let negated = match eval(operand, types, calc)? {
+                Value::Int(kind, value) => {
+                    // -MIN doesn't fit: there is one more negative number than positive ones
+                    if !kind.fits(-value) && calc.mode == Mode::Debug {
+                        return Err(CalcError::panic(
+                            "attempt to negate with overflow",
+                            expr.span,
+                        ));
+                    }
+                    Value::Int(kind, kind.from_bits((-value).twos_complement()))
+                }
+                Value::F32(x) => Value::F32(-x),
+                Value::F64(x) => Value::F64(-x),
+            };
+            Ok(negated)
+        }
+        ExprKind::Binary(op, left, right) => {
+            let a = eval(left, types, calc)?;
+            let b = eval(right, types, calc)?;
+            match (a, b) {
+                (Value::Int(kind, a), Value::Int(_, b)) => {
+                    int_op(*op, kind, a, b, calc.mode, expr.span).map(|v| Value::Int(kind, v))
+                }
+                (Value::F32(a), Value::F32(b)) => Ok(Value::F32(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a % b,
+                })),
+                (Value::F64(a), Value::F64(b)) => Ok(Value::F64(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                    Op::Rem => a % b,
+                })),
+                _ => unreachable!("the type checker makes both sides the same type"),
+            }
+        }
+        ExprKind::Cast(inner, target) => Ok(eval(inner, types, calc)?.cast(*target)),
+    }
+}
+
+// Integer arithmetic, done exactly and then checked against the type. In release mode an
+// overflowing +, - or * keeps the low bits, which is what two's complement hardware does anyway.
+fn int_op(
+    op: Op,
+    kind: IntKind,
+    a: Wide,
+    b: Wide,
+    mode: Mode,
+    span: Span,
+) -> Result<Wide, CalcError> {
+    let overflow = CalcError::panic(format!("attempt to {} with overflow", op.verb()), span);
+    match op {
+        Op::Div | Op::Rem if b.is_zero() => {
+            let message = match op {
+                Op::Div => "attempt to divide by zero",
+                _ => "attempt to calculate the remainder with a divisor of zero",
+            };
+            Err(CalcError::panic(message, span))
+        }
+        // MIN / -1 would be MAX + 1. This one panics in release builds too, and so does
+        // MIN % -1 even though the answer (0) would fit.
+        Op::Div | Op::Rem if kind.signed && a == kind.min() && b == Wide::from_i128(-1) => {
+            Err(overflow)
+        }
+        Op::Div => Ok(a.checked_div(b).expect("divisor isn't zero")),
+        Op::Rem => Ok(a.checked_rem(b).expect("divisor isn't zero")),
+        Op::Add | Op::Sub | Op::Mul => {
+            let exact = match op {
+                Op::Add => a.checked_add(b),
+                Op::Sub => a.checked_sub(b),
+                _ => a.checked_mul(b),
+            };
+            match exact {
+                Some(value) if kind.fits(value) => Ok(value),
+                _ if mode == Mode::Debug => Err(overflow),
+                _ => {
+                    let (x, y) = (a.twos_complement(), b.twos_complement());
+                    let bits = match op {
+                        Op::Add => x.wrapping_add(y),
+                        Op::Sub => x.wrapping_sub(y),
+                        _ => x.wrapping_mul(y),
+                    };
+                    Ok(kind.from_bits(bits))
+                }
+            }
+        }
+    }
+}
+
+/* The calculator */
+pub struct Calculator {
+    pub mode: Mode,
+    variables: HashMap<String, (Type, Value)>,
+}
+
+// What a line produced: a value, or a new variable.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Value(Value),
+    Bound(String, Value),
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Outcome::Value(value) => write!(f, "{value}: {}", value.ty()),
+            Outcome::Bound(name, value) => write!(f, "let {name}: {} = {value}", value.ty()),
+        }
+    }
+}
+
+impl Default for Calculator {
+    fn default() -> Self {
+        Calculator::new(Mode::Debug)
+    }
+}
+
+impl Calculator {
+    pub fn new(mode: Mode) -> Self {
+        Calculator {
+            mode,
+            variables: HashMap::new(),
+        }
+    }
+
+    // Checks and evaluates one line: an expression like "5u8 + 10", or a binding like
+    // "let x: f32 = 10.0 / 3.0". Each line is type checked on its own, so in "let x = 5;" x
+    // becomes an i32 straight away. (rustc would look at the rest of the function first.)
+    pub fn eval_line(&mut self, input: &str) -> Result<Outcome, CalcError> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser {
+            chars: input.chars().collect(),
+            tokens,
+            pos: 0,
+            next_id: 0,
+            end: input.chars().count(),
+            nesting: 0,
+        };
+        let line = parser.line()?;
+        let mut checker = Checker {
+            slots: Vec::new(),
+            node_slots: Vec::new(),
+            variables: &self.variables,
+        };
+        let (name, expr) = match line {
+            Line::Let(name, annotation, expr) => {
+                let slot = checker.check(&expr)?;
+                if let Some(ty) = annotation {
+                    let expected = checker.new_slot(Slot::Known(ty));
+                    if checker.unify(expected, slot).is_err() {
+                        return Err(
+                            CalcError::error(Some("E0308"), "mismatched types", expr.span)
+                                .with_label(format!(
+                                    "expected `{ty}`, found {}",
+                                    checker.describe(slot)
+                                )),
+                        );
+                    }
+                }
+                (Some(name), expr)
+            }
+            Line::Expr(expr) => {
+                checker.check(&expr)?;
+                (None, expr)
+            }
+        };
+        let types: Vec<Type> = checker
+            .node_slots
+            .iter()
+            .map(|&slot| checker.resolve(slot))
+            .collect();
+        check_literals(&expr, &types, false)?;
+        let value = eval(&expr, &types, self)?;
+        match name {
+            Some(name) => {
+                self.variables.insert(name.clone(), (value.ty(), value));
+                Ok(Outcome::Bound(name, value))
+            }
+            None => Ok(Outcome::Value(value)),
+        }
+    }
+
+    // name: type = value for each variable, sorted by name
+    pub fn variables(&self) -> Vec<String> {
+        let mut lines: Vec<String> = self
+            .variables
+            .iter()
+            .map(|(name, (ty, value))| format!("{name}: {ty} = {value}"))
+            .collect();
+        lines.sort();
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(mode: Mode, line: &str) -> Result<String, CalcError> {
+        Calculator::new(mode)
+            .eval_line(line)
+            .map(|outcome| outcome.to_string())
+    }
+
+    // The error code (or "panic"), the headline and the span, for comparing errors in one go.
+    fn failure(mode: Mode, line: &str) -> (&'static str, String, Span) {
+        let err = eval(mode, line).unwrap_err();
+        let (code, message) = match err.problem {
+            Problem::Error(code, message) => (code.unwrap_or("lint"), message),
+            Problem::Panic(message) => ("panic", message),
+        };
+        (code, message, (err.start, err.end))
+    }
+
+    #[test]
+    fn literals_take_their_type_from_the_other_side() {
+        assert_eq!(eval(Mode::Debug, "5 / 3").unwrap(), "1: i32");
+        assert_eq!(eval(Mode::Debug, "5u8 + 10").unwrap(), "15: u8");
+        assert_eq!(eval(Mode::Debug, "10 + 5u8").unwrap(), "15: u8");
+        assert_eq!(eval(Mode::Debug, "2.5f32 + 1.0").unwrap(), "3.5: f32");
+        assert_eq!(eval(Mode::Debug, "2.5 + 1.0").unwrap(), "3.5: f64");
+        assert_eq!(eval(Mode::Debug, "0xff_u8 as i8").unwrap(), "-1: i8");
+        assert_eq!(eval(Mode::Debug, "-128i8").unwrap(), "-128: i8");
+    }
+
+    #[test]
+    fn variables_keep_their_type() {
+        let mut calculator = Calculator::default();
+        assert_eq!(
+            calculator.eval_line("let x: u8 = 200").unwrap().to_string(),
+            "let x: u8 = 200"
+        );
+        assert_eq!(
+            calculator.eval_line("x + 55").unwrap().to_string(),
+            "255: u8"
+        );
+        assert_eq!(calculator.variables(), ["x: u8 = 200"]);
+        let err = calculator.eval_line("x + 1.0").unwrap_err();
+        assert_eq!(
+            err.problem,
+            Problem::Error(Some("E0308"), "mismatched types".into())
+        );
+        assert_eq!(err.label, "expected `u8`, found floating-point number");
+    }
+
+    #[test]
+    fn overflow_panics_in_debug_and_wraps_in_release() {
+        assert_eq!(
+            failure(Mode::Debug, "250u8 + 10"),
+            ("panic", "attempt to add with overflow".into(), (0, 10))
+        );
+        assert_eq!(eval(Mode::Release, "250u8 + 10").unwrap(), "4: u8");
+        assert_eq!(
+            failure(Mode::Debug, "-(-128i8)"),
+            ("panic", "attempt to negate with overflow".into(), (0, 9))
+        );
+        assert_eq!(eval(Mode::Release, "-(-128i8)").unwrap(), "-128: i8");
+        // dividing by zero panics in release builds too
+        assert_eq!(
+            failure(Mode::Release, "5 / (3 - 3)"),
+            ("panic", "attempt to divide by zero".into(), (0, 11))
+        );
+    }
+
+    #[test]
+    fn compile_errors() {
+        assert_eq!(
+            failure(Mode::Debug, "2.5f32 + 1.0f64"),
+            ("E0308", "mismatched types".into(), (9, 15))
+        );
+        assert_eq!(
+            failure(Mode::Debug, "1 + 2.0"),
+            (
+                "E0277",
+                "cannot add `{float}` to `{integer}`".into(),
+                (4, 7)
+            )
+        );
+        assert_eq!(
+            failure(Mode::Debug, "-1 as u32"),
+            (
+                "E0600",
+                "cannot apply unary operator `-` to type `u32`".into(),
+                (0, 2)
+            )
+        );
+        assert_eq!(
+            failure(Mode::Debug, "let x: u8 = 300"),
+            ("lint", "literal out of range for `u8`".into(), (12, 15))
+        );
+        // the cast decides the literal's type, so it's out of range before it's cast
+        assert_eq!(
+            failure(Mode::Debug, "300 as u8"),
+            ("lint", "literal out of range for `u8`".into(), (0, 3))
+        );
+        assert_eq!(
+            failure(Mode::Debug, "y * 2"),
+            (
+                "E0425",
+                "cannot find value `y` in this scope".into(),
+                (0, 1)
+            )
+        );
+        assert_eq!(
+            failure(Mode::Debug, "3 as u9"),
+            (
+                "E0412",
+                "cannot find type `u9` in this scope".into(),
+                (5, 7)
+            )
+        );
+    }
+
+    #[test]
+    fn errors_render_with_an_underline() {
+        let err = eval(Mode::Debug, "2.5f32 + 1.0f64").unwrap_err();
+        assert_eq!(
+            err.render("2.5f32 + 1.0f64"),
+            "error[E0308]: mismatched types\n  |\n  | 2.5f32 + 1.0f64\n  |          ^^^^^^ expected `f32`, found `f64`\n"
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let too_deep = "expression is nested too deeply";
+        for line in [
+            format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000)),
+            format!("{}1", "-".repeat(20_000)),
+            vec!["1"; 20_000].join(" + "),
+        ] {
+            assert_eq!(failure(Mode::Release, &line).1, too_deep);
+        }
+        // right up to the limit is fine
+        let deepest = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(eval(Mode::Debug, &deepest).unwrap(), "1: i32");
+        let longest = vec!["1"; MAX_DEPTH].join(" + ");
+        assert_eq!(eval(Mode::Debug, &longest).unwrap(), "64: i32");
+    }
+}
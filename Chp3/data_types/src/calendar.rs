@@ -0,0 +1,633 @@
+/* Calendar */
+// main.rs has an array of month names and nothing else. This module turns that into a small
+// Gregorian calendar: month lengths and leap years, the day of the week, adding days to a date and
+// counting the days between two dates, ISO 8601 parsing and formatting, and a 'cal'-style
+// renderer for a month or a whole year. Nothing here needs an external crate.
+use std::fmt;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const MONTHS: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Weekday {
+    Sunday,
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+}
+
+impl Weekday {
+    pub const ALL: [Weekday; 7] = [
+        Weekday::Sunday,
+        Weekday::Monday,
+        Weekday::Tuesday,
+        Weekday::Wednesday,
+        Weekday::Thursday,
+        Weekday::Friday,
+        Weekday::Saturday,
+    ];
+
+    // 0 for Sunday through 6 for Saturday, the numbering Sakamoto's method produces
+    pub fn from_index(index: u32) -> Weekday {
+        Weekday::ALL[(index % 7) as usize]
+    }
+
+    pub fn index(self) -> u32 {
+        self as u32
+    }
+
+    // ISO 8601 numbers the days 1 (Monday) to 7 (Sunday)
+    pub fn iso_number(self) -> u32 {
+        match self {
+            Weekday::Sunday => 7,
+            other => other.index(),
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Weekday::Sunday => "Sunday",
+            Weekday::Monday => "Monday",
+            Weekday::Tuesday => "Tuesday",
+            Weekday::Wednesday => "Wednesday",
+            Weekday::Thursday => "Thursday",
+            Weekday::Friday => "Friday",
+            Weekday::Saturday => "Saturday",
+        }
+    }
+}
+
+// Every year divisible by 4 is a leap year, except those divisible by 100, unless they're also
+// divisible by 400. So 2024 and 2000 are leap years, but 1900 isn't.
+pub fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+// month is 1-based, like on a calendar
+pub fn days_in_month(year: i32, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+pub fn days_in_year(year: i32) -> u32 {
+    if is_leap_year(year) {
+        366
+    } else {
+        365
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateError {
+    MonthOutOfRange(u32),
+    DayOutOfRange { year: i32, month: u32, day: u32 },
+    DayOfYearOutOfRange { year: i32, day: u32 },
+    WeekOutOfRange { year: i32, week: u32 },
+    InvalidFormat(String),
+    OutOfRange,
+}
+
+impl fmt::Display for DateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DateError::MonthOutOfRange(month) => write!(f, "there is no month {month}"),
+            DateError::DayOutOfRange { year, month, day } => write!(
+                f,
+                "{} {year} only has {} days, so there is no day {day}",
+                MONTHS[(*month - 1) as usize],
+                days_in_month(*year, *month)
+            ),
+            DateError::DayOfYearOutOfRange { year, day } => write!(
+                f,
+                "{year} only has {} days, so there is no day {day}",
+                days_in_year(*year)
+            ),
+            DateError::WeekOutOfRange { year, week } => write!(
+                f,
+                "{year} has {} ISO weeks, so there is no week {week}",
+                iso_weeks_in_year(*year)
+            ),
+            DateError::InvalidFormat(text) => write!(
+                f,
+                "'{text}' is not an ISO 8601 date (try YYYY-MM-DD, YYYYMMDD, YYYY-DDD or YYYY-Www-D)"
+            ),
+            DateError::OutOfRange => write!(
+                f,
+                "the date is outside the supported range, years {MIN_YEAR} to {MAX_YEAR}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DateError {}
+
+// The years a Date can be in. That's far more than anyone needs, and it keeps every calculation
+// below well inside i64, and the ISO week-numbering year of any date inside i32.
+pub const MIN_YEAR: i32 = -999_999_999;
+pub const MAX_YEAR: i32 = 999_999_999;
+
+// A date in the proleptic Gregorian calendar, which is the Gregorian calendar extended backwards
+// before it was introduced in 1582. That's also what ISO 8601 uses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Date {
+    year: i32,
+    month: u32,
+    day: u32,
+}
+
+impl Date {
+    pub fn new(year: i32, month: u32, day: u32) -> Result<Date, DateError> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(DateError::OutOfRange);
+        }
+        if !(1..=12).contains(&month) {
+            return Err(DateError::MonthOutOfRange(month));
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(DateError::DayOutOfRange { year, month, day });
+        }
+        Ok(Date { year, month, day })
+    }
+
+    // day_of_year is 1-based: 1 is January 1st
+    pub fn from_ordinal(year: i32, day_of_year: u32) -> Result<Date, DateError> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(DateError::OutOfRange);
+        }
+        if day_of_year == 0 || day_of_year > days_in_year(year) {
+            return Err(DateError::DayOfYearOutOfRange {
+                year,
+                day: day_of_year,
+            });
+        }
+        let first = Date::new(year, 1, 1)?;
+        first.add_days(day_of_year as i64 - 1)
+    }
+
+    // An ISO week date: week 1 is the week (Monday to Sunday) that contains the year's first
+    // Thursday, so the first few days of January can belong to the previous year's last week.
+    pub fn from_iso_week(year: i32, week: u32, weekday: Weekday) -> Result<Date, DateError> {
+        if !(MIN_YEAR..=MAX_YEAR).contains(&year) {
+            return Err(DateError::OutOfRange);
+        }
+        if week == 0 || week > iso_weeks_in_year(year) {
+            return Err(DateError::WeekOutOfRange { year, week });
+        }
+        // January 4th is always in week 1
+        let january_4th = Date::new(year, 1, 4)?;
+        let week_1_monday = january_4th.add_days(1 - january_4th.weekday().iso_number() as i64)?;
+        week_1_monday.add_days((week as i64 - 1) * 7 + weekday.iso_number() as i64 - 1)
+    }
+
+    // Today's date in UTC, from the system clock.
+    pub fn today() -> Date {
+        let seconds = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_secs() as i64,
+            // a clock set before 1970
+            Err(err) => -(err.duration().as_secs() as i64),
+        };
+        Date::from_days_since_epoch(seconds.div_euclid(86_400))
+    }
+
+    pub fn year(self) -> i32 {
+        self.year
+    }
+
+    pub fn month(self) -> u32 {
+        self.month
+    }
+
+    pub fn day(self) -> u32 {
+        self.day
+    }
+
+    pub fn month_name(self) -> &'static str {
+        MONTHS[(self.month - 1) as usize]
+    }
+
+    /* Sakamoto's method */
+    // The table holds, for each month, how many weekdays that month's first day is shifted from
+    // January's in a common year. January and February are treated as months 13 and 14 of the
+    // previous year, which moves the leap day to the end of the "year" so the year terms
+    // (y + y/4 - y/100 + y/400) count it correctly.
+    pub fn weekday(self) -> Weekday {
+        const OFFSETS: [i64; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+        // in i64: y + y/4 would overflow an i32 for the biggest years
+        let year = if self.month < 3 {
+            self.year as i64 - 1
+        } else {
+            self.year as i64
+        };
+        let index = year + year.div_euclid(4) - year.div_euclid(100)
+            + year.div_euclid(400)
+            + OFFSETS[(self.month - 1) as usize]
+            + self.day as i64;
+        Weekday::from_index(index.rem_euclid(7) as u32)
+    }
+
+    pub fn day_of_year(self) -> u32 {
+        (1..self.month)
+            .map(|month| days_in_month(self.year, month))
+            .sum::<u32>()
+            + self.day
+    }
+
+    // The ISO week-numbering year and week this date falls in.
+    pub fn iso_week(self) -> (i32, u32) {
+        // the week belongs to whichever year its Thursday is in. Straight from the day number
+        // rather than add_days, which would refuse a Thursday just past MAX_YEAR or MIN_YEAR.
+        let thursday = Date::from_days_since_epoch(
+            self.days_since_epoch() + 4 - self.weekday().iso_number() as i64,
+        );
+        (thursday.year, (thursday.day_of_year() - 1) / 7 + 1)
+    }
+
+    /* Day numbers */
+    // Counting days from 1970-01-01 turns date arithmetic into integer arithmetic. This is
+    // Howard Hinnant's days_from_civil algorithm: it works in 400-year "eras" (which always have
+    // exactly 146097 days) and, like Sakamoto's method, starts each year in March.
+    pub fn days_since_epoch(self) -> i64 {
+        let year = if self.month <= 2 {
+            self.year as i64 - 1
+        } else {
+            self.year as i64
+        };
+        let era = year.div_euclid(400);
+        let year_of_era = year - era * 400; // 0..=399
+        let month_from_march = (self.month as i64 + 9) % 12; // March is 0
+        let day_of_year = (153 * month_from_march + 2) / 5 + self.day as i64 - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        era * 146_097 + day_of_era - 719_468
+    }
+
+    pub fn from_days_since_epoch(days: i64) -> Date {
+        let days = days + 719_468;
+        let era = days.div_euclid(146_097);
+        let day_of_era = days - era * 146_097; // 0..=146096
+        let year_of_era =
+            (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+        let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+        let month_from_march = (5 * day_of_year + 2) / 153;
+        let day = (day_of_year - (153 * month_from_march + 2) / 5 + 1) as u32;
+        let month = if month_from_march < 10 {
+            month_from_march + 3
+        } else {
+            month_from_march - 9
+        } as u32;
+        let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+        Date {
+            year: year as i32,
+            month,
+            day,
+        }
+    }
+
+    pub fn add_days(self, days: i64) -> Result<Date, DateError> {
+        let total = self
+            .days_since_epoch()
+            .checked_add(days)
+            .ok_or(DateError::OutOfRange)?;
+        let first = Date {
+            year: MIN_YEAR,
+            month: 1,
+            day: 1,
+        };
+        let last = Date {
+            year: MAX_YEAR,
+            month: 12,
+            day: 31,
+        };
+        if !(first.days_since_epoch()..=last.days_since_epoch()).contains(&total) {
+            return Err(DateError::OutOfRange);
+        }
+        Ok(Date::from_days_since_epoch(total))
+    }
+
+    // other - self in days, negative if other is earlier
+    pub fn days_until(self, other: Date) -> i64 {
+        other.days_since_epoch() - self.days_since_epoch()
+    }
+
+    /* ISO 8601 */
+    // Reads the common ISO 8601 date forms:
+    //   2024-02-29   extended calendar date
+    //   20240229     basic calendar date
+    //   2024-060     ordinal date (day of the year)
+    //   2024-W09-4   week date (4 is Thursday)
+    // Years outside 0000..=9999 need an explicit sign, like +10000-01-01 or -0044-03-15, and
+    // years past MIN_YEAR or MAX_YEAR are OutOfRange.
+    pub fn parse_iso(text: &str) -> Result<Date, DateError> {
+        let invalid = || DateError::InvalidFormat(text.to_string());
+        let (sign, rest): (i64, &str) = match text.as_bytes().first() {
+            Some(b'+') => (1, &text[1..]),
+            Some(b'-') => (-1, &text[1..]),
+            _ => (1, text),
+        };
+        let number = |part: &str| -> Result<u32, DateError> {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            part.parse::<u32>().map_err(|_| invalid())
+        };
+        // the digits are checked by number, so a year that doesn't parse is just too long
+        let year = |part: &str| -> Result<i32, DateError> {
+            if part.is_empty() || !part.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            let year = part.parse::<i64>().map_err(|_| DateError::OutOfRange)? * sign;
+            match i32::try_from(year) {
+                Ok(year) if (MIN_YEAR..=MAX_YEAR).contains(&year) => Ok(year),
+                _ => Err(DateError::OutOfRange),
+            }
+        };
+        // the basic format has no separators, so the parts are found by length
+        if !rest.contains('-') && rest.len() >= 8 && !rest.contains('W') {
+            let year_len = rest.len() - 4;
+            let year = year(&rest[..year_len])?;
+            return Date::new(
+                year,
+                number(&rest[year_len..year_len + 2])?,
+                number(&rest[year_len + 2..])?,
+            );
+        }
+        let parts: Vec<&str> = rest.split('-').collect();
+        let year_text = parts.first().ok_or_else(invalid)?;
+        if year_text.len() < 4 || (year_text.len() > 4 && sign == 1 && !text.starts_with('+')) {
+            return Err(invalid());
+        }
+        let year = year(year_text)?;
+        match parts.as_slice() {
+            [_, week, weekday] if week.starts_with('W') => {
+                let weekday = number(weekday)?;
+                if !(1..=7).contains(&weekday) {
+                    return Err(invalid());
+                }
+                Date::from_iso_week(year, number(&week[1..])?, Weekday::from_index(weekday))
+            }
+            [_, ordinal] if ordinal.len() == 3 => Date::from_ordinal(year, number(ordinal)?),
+            [_, month, day] if month.len() == 2 && day.len() == 2 => {
+                Date::new(year, number(month)?, number(day)?)
+            }
+            _ => Err(invalid()),
+        }
+    }
+
+    fn iso_year(year: i32) -> String {
+        if (0..=9999).contains(&year) {
+            format!("{year:04}")
+        } else if year < 0 {
+            format!("-{:04}", year.unsigned_abs())
+        } else {
+            format!("+{year:04}")
+        }
+    }
+
+    pub fn to_iso_ordinal(self) -> String {
+        format!("{}-{:03}", Date::iso_year(self.year), self.day_of_year())
+    }
+
+    pub fn to_iso_week(self) -> String {
+        let (year, week) = self.iso_week();
+        format!(
+            "{}-W{week:02}-{}",
+            Date::iso_year(year),
+            self.weekday().iso_number()
+        )
+    }
+}
+
+// Display prints the extended ISO 8601 form, e.g. 2024-02-29
+impl fmt::Display for Date {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{:02}-{:02}",
+            Date::iso_year(self.year),
+            self.month,
+            self.day
+        )
+    }
+}
+
+pub fn iso_weeks_in_year(year: i32) -> u32 {
+    // a year has 53 ISO weeks when it starts on a Thursday, or is a leap year starting on a
+    // Wednesday; December 28th is always in the last week
+    let december_28th = Date {
+        year,
+        month: 12,
+        day: 28,
+    };
+    december_28th.iso_week().1
+}
+
+/* 'cal'-style rendering */
+// The same highlight the chapter binaries use for headings, applied to today's date.
+const HIGHLIGHT: &str = "\x1b[1;4;33m";
+const RESET: &str = "\x1b[0m";
+
+// Each month is a block of lines exactly 20 characters wide (not counting escape codes), so that
+// three of them can be placed side by side for the year view.
+const MONTH_WIDTH: usize = 20;
+
+fn month_lines(year: i32, month: u32, today: Option<Date>, monday_first: bool) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    let title = format!("{} {year}", MONTHS[(month - 1) as usize]);
+    lines.push(format!("{title:^MONTH_WIDTH$}"));
+    lines.push(if monday_first {
+        "Mo Tu We Th Fr Sa Su".to_string()
+    } else {
+        "Su Mo Tu We Th Fr Sa".to_string()
+    });
+
+    let first = Date {
+        year,
+        month,
+        day: 1,
+    };
+    let offset = if monday_first {
+        first.weekday().iso_number() - 1
+    } else {
+        first.weekday().index()
+    };
+    let mut cells: Vec<String> = vec!["  ".to_string(); offset as usize];
+    for day in 1..=days_in_month(year, month) {
+        let cell = format!("{day:>2}");
+        if today == Some(Date { year, month, day }) {
+            cells.push(format!("{HIGHLIGHT}{cell}{RESET}"));
+        } else {
+            cells.push(cell);
+        }
+    }
+    for week in cells.chunks(7) {
+        let mut line = week.join(" ");
+        // pad the last week out to the full width; escape codes take no space on screen
+        let missing = 7 - week.len();
+        line.push_str(&" ".repeat(missing * 3));
+        lines.push(line);
+    }
+    // always six week rows, so months line up in the year view
+    while lines.len() < 8 {
+        lines.push(" ".repeat(MONTH_WIDTH));
+    }
+    lines
+}
+
+pub fn render_month(year: i32, month: u32, today: Option<Date>, monday_first: bool) -> String {
+    let mut out = String::new();
+    for line in month_lines(year, month, today, monday_first) {
+        out.push_str(line.trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+pub fn render_year(year: i32, today: Option<Date>, monday_first: bool) -> String {
+    let mut out = format!("{:^64}", year).trim_end().to_string() + "\n\n";
+    for row in 0..4 {
+        let months: Vec<Vec<String>> = (1..=3)
+            .map(|column| month_lines(year, row * 3 + column, today, monday_first))
+            .collect();
+        for line in 0..months[0].len() {
+            let joined: Vec<&str> = months.iter().map(|lines| lines[line].as_str()).collect();
+            out.push_str(joined.join("  ").trim_end());
+            out.push('\n');
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(text: &str) -> Date {
+        Date::parse_iso(text).unwrap()
+    }
+
+    #[test]
+    fn leap_years() {
+        for year in [2024, 2000, 1600, 4, 0, -4, -400] {
+            assert!(is_leap_year(year), "{year}");
+            assert_eq!(days_in_year(year), 366);
+        }
+        for year in [2023, 1900, 2100, 1, -1, -100, MIN_YEAR, MAX_YEAR] {
+            assert!(!is_leap_year(year), "{year}");
+            assert_eq!(days_in_year(year), 365);
+        }
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(1900, 2), 28);
+        assert_eq!(
+            Date::new(2023, 2, 29),
+            Err(DateError::DayOutOfRange {
+                year: 2023,
+                month: 2,
+                day: 29
+            })
+        );
+        assert_eq!(date("2024-02-29").add_days(365), Ok(date("2025-02-28")));
+    }
+
+    #[test]
+    fn weekdays() {
+        for (text, weekday) in [
+            ("1970-01-01", Weekday::Thursday),
+            ("2000-01-01", Weekday::Saturday),
+            ("2024-02-29", Weekday::Thursday),
+            ("2021-01-03", Weekday::Sunday),
+            // the first day of the Gregorian calendar, and the proleptic day before it
+            ("1582-10-15", Weekday::Friday),
+            ("1582-10-14", Weekday::Thursday),
+            ("0001-01-01", Weekday::Monday),
+            ("-0001-12-31", Weekday::Friday),
+            ("+999999999-12-31", Weekday::Friday),
+            ("-999999999-01-01", Weekday::Monday),
+        ] {
+            assert_eq!(date(text).weekday(), weekday, "{text}");
+        }
+    }
+
+    #[test]
+    fn iso_week_dates() {
+        for (text, week_date) in [
+            ("2021-01-03", "2020-W53-7"),
+            ("2021-01-04", "2021-W01-1"),
+            ("2008-12-29", "2009-W01-1"),
+            ("2010-01-03", "2009-W53-7"),
+            ("2024-12-30", "2025-W01-1"),
+            ("2024-02-29", "2024-W09-4"),
+            ("+999999999-12-31", "+999999999-W52-5"),
+            ("-999999999-01-01", "-999999999-W01-1"),
+        ] {
+            assert_eq!(date(text).to_iso_week(), week_date, "{text}");
+            // and back again
+            assert_eq!(date(week_date), date(text), "{week_date}");
+        }
+        assert_eq!(iso_weeks_in_year(2020), 53);
+        assert_eq!(iso_weeks_in_year(2021), 52);
+        assert_eq!(
+            Date::parse_iso("2021-W53-1"),
+            Err(DateError::WeekOutOfRange {
+                year: 2021,
+                week: 53
+            })
+        );
+    }
+
+    #[test]
+    fn ordinal_dates() {
+        assert_eq!(date("2024-366"), date("2024-12-31"));
+        assert_eq!(date("2024-12-31").to_iso_ordinal(), "2024-366");
+        assert_eq!(date("20240229"), date("2024-02-29"));
+        assert_eq!(
+            Date::parse_iso("2023-366"),
+            Err(DateError::DayOfYearOutOfRange {
+                year: 2023,
+                day: 366
+            })
+        );
+    }
+
+    #[test]
+    fn arithmetic_at_the_limits() {
+        let first = Date::new(MIN_YEAR, 1, 1).unwrap();
+        let last = Date::new(MAX_YEAR, 12, 31).unwrap();
+        assert_eq!(first.days_until(last), 730_484_999_633);
+        assert_eq!(last.days_until(first), -730_484_999_633);
+        assert_eq!(first.add_days(730_484_999_633), Ok(last));
+        assert_eq!(last.add_days(-730_484_999_633), Ok(first));
+        assert_eq!(first.add_days(0), Ok(first));
+        assert_eq!(first.add_days(-1), Err(DateError::OutOfRange));
+        assert_eq!(last.add_days(1), Err(DateError::OutOfRange));
+        // too far for the i64 day count itself
+        assert_eq!(last.add_days(i64::MAX), Err(DateError::OutOfRange));
+        assert_eq!(first.add_days(i64::MIN), Err(DateError::OutOfRange));
+        assert_eq!(
+            Date::parse_iso("+1000000000-01-01"),
+            Err(DateError::OutOfRange)
+        );
+        assert_eq!(Date::new(MAX_YEAR + 1, 1, 1), Err(DateError::OutOfRange));
+        assert_eq!(date("2024-01-01").days_until(date("2024-12-31")), 365);
+        assert_eq!(date("1970-01-01").days_since_epoch(), 0);
+        assert_eq!(Date::from_days_since_epoch(-1), date("1969-12-31"));
+    }
+}
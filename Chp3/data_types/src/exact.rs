@@ -0,0 +1,383 @@
+/* Exact arithmetic */
+// Floats can't show their own rounding errors, because every calculation they do is rounded.
+// To see what a float really stores, or how far 95.5 - 2.24 is from 93.26, we need numbers that
+// never round. BigUint is an unsigned integer of any size, and Rational is a fraction of two of
+// them with a sign. They are deliberately simple (schoolbook multiplication, no gcd reduction)
+// because the numbers involved here only go through a handful of operations.
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::{Add, Mul, Neg, Sub};
+
+// Little endian limbs in base 2^32, with no trailing zero limbs, so zero is an empty Vec.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigUint {
+    limbs: Vec<u32>,
+}
+
+impl BigUint {
+    pub fn zero() -> Self {
+        BigUint { limbs: Vec::new() }
+    }
+
+    pub fn from_u128(mut n: u128) -> Self {
+        let mut limbs: Vec<u32> = Vec::new();
+        while n > 0 {
+            limbs.push(n as u32);
+            n >>= 32;
+        }
+        BigUint { limbs }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.limbs.is_empty()
+    }
+
+    fn trim(mut self) -> Self {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+        self
+    }
+
+    pub fn mul_small(&self, factor: u32) -> BigUint {
+        self * &BigUint::from_u128(factor as u128)
+    }
+
+    // Divides by a small number, returning the quotient and the remainder.
+    pub fn div_small(&self, divisor: u32) -> (BigUint, u32) {
+        assert!(divisor != 0, "BigUint division by zero");
+        let mut limbs: Vec<u32> = vec![0; self.limbs.len()];
+        let mut remainder: u64 = 0;
+        for i in (0..self.limbs.len()).rev() {
+            let current = (remainder << 32) | self.limbs[i] as u64;
+            limbs[i] = (current / divisor as u64) as u32;
+            remainder = current % divisor as u64;
+        }
+        (BigUint { limbs }.trim(), remainder as u32)
+    }
+
+    // self * 2^bits
+    pub fn shifted_left(&self, bits: u32) -> BigUint {
+        let mut result = self.clone();
+        for _ in 0..bits {
+            result = &result + &result;
+        }
+        result
+    }
+
+    pub fn pow(base: u32, exponent: u32) -> BigUint {
+        let mut result = BigUint::from_u128(1);
+        for _ in 0..exponent {
+            result = result.mul_small(base);
+        }
+        result
+    }
+}
+
+impl Add for &BigUint {
+    type Output = BigUint;
+
+    fn add(self, other: &BigUint) -> BigUint {
+        let mut limbs: Vec<u32> = Vec::with_capacity(self.limbs.len().max(other.limbs.len()) + 1);
+        let mut carry: u64 = 0;
+        for i in 0..self.limbs.len().max(other.limbs.len()) {
+            let sum = *self.limbs.get(i).unwrap_or(&0) as u64
+                + *other.limbs.get(i).unwrap_or(&0) as u64
+                + carry;
+            limbs.push(sum as u32);
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            limbs.push(carry as u32);
+        }
+        BigUint { limbs }.trim()
+    }
+}
+
+// self - other; panics if other is bigger, like u32 subtraction would in a debug build
+impl Sub for &BigUint {
+    type Output = BigUint;
+
+    fn sub(self, other: &BigUint) -> BigUint {
+        assert!(*self >= *other, "BigUint subtraction would go below zero");
+        let mut limbs: Vec<u32> = Vec::with_capacity(self.limbs.len());
+        let mut borrow: i64 = 0;
+        for i in 0..self.limbs.len() {
+            let mut diff = self.limbs[i] as i64 - *other.limbs.get(i).unwrap_or(&0) as i64 - borrow;
+            if diff < 0 {
+                diff += 1 << 32;
+                borrow = 1;
+            } else {
+                borrow = 0;
+            }
+            limbs.push(diff as u32);
+        }
+        BigUint { limbs }.trim()
+    }
+}
+
+impl Mul for &BigUint {
+    type Output = BigUint;
+
+    fn mul(self, other: &BigUint) -> BigUint {
+        if self.is_zero() || other.is_zero() {
+            return BigUint::zero();
+        }
+        let mut limbs: Vec<u32> = vec![0; self.limbs.len() + other.limbs.len()];
+        for (i, &a) in self.limbs.iter().enumerate() {
+            let mut carry: u64 = 0;
+            for (j, &b) in other.limbs.iter().enumerate() {
+                let current = limbs[i + j] as u64 + a as u64 * b as u64 + carry;
+                limbs[i + j] = current as u32;
+                carry = current >> 32;
+            }
+            limbs[i + other.limbs.len()] = carry as u32;
+        }
+        BigUint { limbs }.trim()
+    }
+}
+
+impl PartialOrd for BigUint {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigUint {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+impl fmt::Display for BigUint {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        // peel off nine decimal digits at a time
+        let mut chunks: Vec<u32> = Vec::new();
+        let mut rest = self.clone();
+        while !rest.is_zero() {
+            let (quotient, remainder) = rest.div_small(1_000_000_000);
+            chunks.push(remainder);
+            rest = quotient;
+        }
+        write!(f, "{}", chunks.pop().unwrap_or(0))?;
+        for chunk in chunks.iter().rev() {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+// A signed fraction num / den. den is never zero.
+#[derive(Debug, Clone)]
+pub struct Rational {
+    negative: bool,
+    num: BigUint,
+    den: BigUint,
+}
+
+impl Rational {
+    pub fn new(negative: bool, num: BigUint, den: BigUint) -> Self {
+        assert!(!den.is_zero(), "Rational with a zero denominator");
+        Rational {
+            negative: negative && !num.is_zero(),
+            num,
+            den,
+        }
+    }
+
+    pub fn zero() -> Self {
+        Rational::new(false, BigUint::zero(), BigUint::from_u128(1))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.negative
+    }
+
+    // The exact value of a finite float given as sign * mantissa * 2^exponent.
+    pub fn from_parts(negative: bool, mantissa: u64, exponent: i32) -> Self {
+        let mantissa = BigUint::from_u128(mantissa as u128);
+        if exponent >= 0 {
+            Rational::new(
+                negative,
+                mantissa.shifted_left(exponent as u32),
+                BigUint::from_u128(1),
+            )
+        } else {
+            let den = BigUint::from_u128(1).shifted_left(exponent.unsigned_abs());
+            Rational::new(negative, mantissa, den)
+        }
+    }
+
+    // The exact value of a decimal literal such as "95.5", "-2.24", "1e-3" or "6.02E23". Returns
+    // None if the text isn't a plain decimal number.
+    pub fn parse_decimal(text: &str) -> Option<Self> {
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, text.strip_prefix('+').unwrap_or(text)),
+        };
+        let (mantissa, exponent): (&str, i32) = match text.find(['e', 'E']) {
+            Some(index) => (&text[..index], text[index + 1..].parse().ok()?),
+            None => (text, 0),
+        };
+        let (whole, fraction) = match mantissa.split_once('.') {
+            Some((whole, fraction)) => (whole, fraction),
+            None => (mantissa, ""),
+        };
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        let mut num = BigUint::zero();
+        for c in whole.chars().chain(fraction.chars()).filter(|&c| c != '_') {
+            num = &num.mul_small(10) + &BigUint::from_u128(c.to_digit(10)? as u128);
+        }
+        let scale: i32 = exponent - fraction.chars().filter(|&c| c != '_').count() as i32;
+        if scale >= 0 {
+            let num = &num * &BigUint::pow(10, scale as u32);
+            Some(Rational::new(negative, num, BigUint::from_u128(1)))
+        } else {
+            let den = BigUint::pow(10, scale.unsigned_abs());
+            Some(Rational::new(negative, num, den))
+        }
+    }
+
+    pub fn abs(&self) -> Rational {
+        Rational::new(false, self.num.clone(), self.den.clone())
+    }
+
+    // None when dividing by zero
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        if other.is_zero() {
+            return None;
+        }
+        Some(Rational::new(
+            self.negative != other.negative,
+            &self.num * &other.den,
+            &self.den * &other.num,
+        ))
+    }
+
+    pub fn compare(&self, other: &Rational) -> Ordering {
+        let difference = self - other;
+        if difference.is_zero() {
+            Ordering::Equal
+        } else if difference.negative {
+            Ordering::Less
+        } else {
+            Ordering::Greater
+        }
+    }
+
+    // Writes the number out in decimal. Fractions that don't terminate (like 10/3) are cut off
+    // after max_fraction_digits, and the bool in the result says whether that happened.
+    pub fn to_decimal(&self, max_fraction_digits: usize) -> (String, bool) {
+        let mut out = String::new();
+        if self.negative {
+            out.push('-');
+        }
+        // integer part, one digit at a time from the top: find the biggest power of ten times
+        // den that still fits, then count how many times each power fits
+        let mut remainder = self.num.clone();
+        let mut powers: Vec<BigUint> = vec![self.den.clone()];
+        while *powers.last().expect("starts non-empty") <= remainder {
+            let next = powers.last().expect("starts non-empty").mul_small(10);
+            powers.push(next);
+        }
+        powers.pop();
+        if powers.is_empty() {
+            out.push('0');
+        }
+        for power in powers.iter().rev() {
+            let mut digit: u8 = 0;
+            while remainder >= *power {
+                remainder = &remainder - power;
+                digit += 1;
+            }
+            out.push((b'0' + digit) as char);
+        }
+        // fraction part
+        if !remainder.is_zero() {
+            out.push('.');
+        }
+        let mut written: usize = 0;
+        while !remainder.is_zero() && written < max_fraction_digits {
+            remainder = remainder.mul_small(10);
+            let mut digit: u8 = 0;
+            while remainder >= self.den {
+                remainder = &remainder - &self.den;
+                digit += 1;
+            }
+            out.push((b'0' + digit) as char);
+            written += 1;
+        }
+        (out, !remainder.is_zero())
+    }
+}
+
+impl Neg for &Rational {
+    type Output = Rational;
+
+    fn neg(self) -> Rational {
+        Rational::new(!self.negative, self.num.clone(), self.den.clone())
+    }
+}
+
+impl Add for &Rational {
+    type Output = Rational;
+
+    fn add(self, other: &Rational) -> Rational {
+        let left = &self.num * &other.den;
+        let right = &other.num * &self.den;
+        let den = &self.den * &other.den;
+        if self.negative == other.negative {
+            return Rational::new(self.negative, &left + &right, den);
+        }
+        match left.cmp(&right) {
+            Ordering::Less => Rational::new(other.negative, &right - &left, den),
+            _ => Rational::new(self.negative, &left - &right, den),
+        }
+    }
+}
+
+impl Sub for &Rational {
+    type Output = Rational;
+
+    fn sub(self, other: &Rational) -> Rational {
+        self + &(-other)
+    }
+}
+
+impl Mul for &Rational {
+    type Output = Rational;
+
+    fn mul(self, other: &Rational) -> Rational {
+        Rational::new(
+            self.negative != other.negative,
+            &self.num * &other.num,
+            &self.den * &other.den,
+        )
+    }
+}
+
+impl fmt::Display for Rational {
+    // the exact decimal if it terminates within 1100 digits (every f64 does), otherwise the
+    // first 60 fraction digits followed by "..."
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let (text, cut_off) = self.to_decimal(1100);
+        if cut_off {
+            let (short, _) = self.to_decimal(60);
+            write!(f, "{short}...")
+        } else {
+            write!(f, "{text}")
+        }
+    }
+}
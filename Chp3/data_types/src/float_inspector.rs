@@ -0,0 +1,793 @@
+/* Float Inspector */
+// main.rs prints 95.5 - 2.24 and 10.0 / 3.0, and the answers come out slightly off. That's because
+// f32 and f64 are IEEE-754 binary floating point numbers: every value is stored as
+//     (-1)^sign * 1.mantissa * 2^(exponent - bias)
+// and most decimal fractions, like 2.24, have no exact binary representation, so they get rounded
+// to the nearest value that does. Every arithmetic operation rounds its result again.
+// This module pulls a float apart into its bit fields, shows the exact decimal value the bits
+// stand for (using the exact arithmetic in exact.rs), and traces an expression step by step to
+// show how much error each literal and each operation introduced.
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::exact::Rational;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Precision {
+    F32,
+    F64,
+}
+
+impl Precision {
+    pub fn name(self) -> &'static str {
+        match self {
+            Precision::F32 => "f32",
+            Precision::F64 => "f64",
+        }
+    }
+
+    pub fn exponent_bits(self) -> u32 {
+        match self {
+            Precision::F32 => 8,
+            Precision::F64 => 11,
+        }
+    }
+
+    // the stored fraction bits; the leading 1 of normal numbers is implicit and not stored
+    pub fn mantissa_bits(self) -> u32 {
+        match self {
+            Precision::F32 => 23,
+            Precision::F64 => 52,
+        }
+    }
+
+    pub fn bias(self) -> i32 {
+        (1 << (self.exponent_bits() - 1)) - 1
+    }
+
+    pub fn total_bits(self) -> u32 {
+        1 + self.exponent_bits() + self.mantissa_bits()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Class {
+    Zero,
+    Subnormal,
+    Normal,
+    Infinite,
+    // the highest mantissa bit tells a quiet NaN from a signalling one; the remaining mantissa
+    // bits are the payload, which programs can use to smuggle information through a calculation
+    Nan { quiet: bool, payload: u64 },
+}
+
+impl fmt::Display for Class {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Class::Zero => write!(f, "zero"),
+            Class::Subnormal => write!(f, "subnormal (no implicit leading 1, reduced precision)"),
+            Class::Normal => write!(f, "normal"),
+            Class::Infinite => write!(f, "infinite"),
+            Class::Nan { quiet, payload } => {
+                let kind = if *quiet { "quiet" } else { "signalling" };
+                write!(f, "{kind} NaN, payload {payload:#x}")
+            }
+        }
+    }
+}
+
+// A float of either precision, kept as its raw bits so that NaN payloads and the sign of zero
+// survive exactly as they were given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Float {
+    pub precision: Precision,
+    pub bits: u64,
+}
+
+impl Float {
+    pub fn from_f32(value: f32) -> Self {
+        Float {
+            precision: Precision::F32,
+            bits: value.to_bits() as u64,
+        }
+    }
+
+    pub fn from_f64(value: f64) -> Self {
+        Float {
+            precision: Precision::F64,
+            bits: value.to_bits(),
+        }
+    }
+
+    // Accepts a decimal literal ("95.5", "1e-3", "2.24f32"), "inf", "-inf", "nan", or raw bits
+    // written in hex ("0x7fc00001") for building subnormals and NaNs by hand.
+    pub fn parse(text: &str, precision: Precision) -> Option<Self> {
+        let text = text.trim();
+        if let Some(hex) = text.strip_prefix("0x") {
+            let bits = u64::from_str_radix(&hex.replace('_', ""), 16).ok()?;
+            if precision == Precision::F32 && bits > u32::MAX as u64 {
+                return None;
+            }
+            return Some(Float { precision, bits });
+        }
+        let text = text
+            .strip_suffix(precision.name())
+            .unwrap_or(text)
+            .replace('_', "");
+        match precision {
+            Precision::F32 => text.parse::<f32>().ok().map(Float::from_f32),
+            Precision::F64 => text.parse::<f64>().ok().map(Float::from_f64),
+        }
+    }
+
+    pub fn sign(self) -> bool {
+        (self.bits >> (self.precision.total_bits() - 1)) & 1 == 1
+    }
+
+    pub fn biased_exponent(self) -> u64 {
+        let mask = (1u64 << self.precision.exponent_bits()) - 1;
+        (self.bits >> self.precision.mantissa_bits()) & mask
+    }
+
+    pub fn mantissa(self) -> u64 {
+        self.bits & ((1u64 << self.precision.mantissa_bits()) - 1)
+    }
+
+    pub fn class(self) -> Class {
+        let max_exponent = (1u64 << self.precision.exponent_bits()) - 1;
+        let mantissa_bits = self.precision.mantissa_bits();
+        match (self.biased_exponent(), self.mantissa()) {
+            (0, 0) => Class::Zero,
+            (0, _) => Class::Subnormal,
+            (e, 0) if e == max_exponent => Class::Infinite,
+            (e, m) if e == max_exponent => Class::Nan {
+                quiet: (m >> (mantissa_bits - 1)) & 1 == 1,
+                payload: m & ((1u64 << (mantissa_bits - 1)) - 1),
+            },
+            _ => Class::Normal,
+        }
+    }
+
+    pub fn is_finite(self) -> bool {
+        !matches!(self.class(), Class::Infinite | Class::Nan { .. })
+    }
+
+    // The exact value the bits stand for, or None for infinities and NaN.
+    pub fn exact(self) -> Option<Rational> {
+        let mantissa_bits = self.precision.mantissa_bits() as i32;
+        let bias = self.precision.bias();
+        match self.class() {
+            Class::Infinite | Class::Nan { .. } => None,
+            // subnormals use the smallest exponent, but without the implicit leading 1
+            Class::Zero | Class::Subnormal => Some(Rational::from_parts(
+                self.sign(),
+                self.mantissa(),
+                1 - bias - mantissa_bits,
+            )),
+            Class::Normal => Some(Rational::from_parts(
+                self.sign(),
+                self.mantissa() | (1u64 << mantissa_bits),
+                self.biased_exponent() as i32 - bias - mantissa_bits,
+            )),
+        }
+    }
+
+    fn with_bits(self, bits: u64) -> Float {
+        Float {
+            precision: self.precision,
+            bits,
+        }
+    }
+
+    fn negated(self) -> Float {
+        self.with_bits(self.bits ^ (1u64 << (self.precision.total_bits() - 1)))
+    }
+
+    // The next representable value towards +infinity. Because the exponent sits above the
+    // mantissa in the bit layout, for positive floats that's simply bits + 1.
+    pub fn next_up(self) -> Float {
+        match self.class() {
+            Class::Nan { .. } => self,
+            Class::Infinite if !self.sign() => self,
+            Class::Zero => self.with_bits(1),
+            _ if self.sign() => self.with_bits(self.bits - 1),
+            _ => self.with_bits(self.bits + 1),
+        }
+    }
+
+    pub fn next_down(self) -> Float {
+        self.negated().next_up().negated()
+    }
+
+    // The gap to the next float away from zero: one "unit in the last place".
+    pub fn ulp(self) -> Option<Rational> {
+        let magnitude = if self.sign() { self.negated() } else { self };
+        let next = magnitude.next_up();
+        Some(&next.exact()? - &magnitude.exact()?)
+    }
+
+    // Applies an operation in the float's own precision, so f32 maths really rounds to f32.
+    pub fn apply(self, op: Op, other: Float) -> Float {
+        match self.precision {
+            Precision::F32 => {
+                let (a, b) = (
+                    f32::from_bits(self.bits as u32),
+                    f32::from_bits(other.bits as u32),
+                );
+                Float::from_f32(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                })
+            }
+            Precision::F64 => {
+                let (a, b) = (f64::from_bits(self.bits), f64::from_bits(other.bits));
+                Float::from_f64(match op {
+                    Op::Add => a + b,
+                    Op::Sub => a - b,
+                    Op::Mul => a * b,
+                    Op::Div => a / b,
+                })
+            }
+        }
+    }
+
+    // How Rust's Display would print the value: the shortest decimal that reads back as the
+    // same float, which is why 2.24 prints as "2.24" even though that's not what's stored.
+    pub fn display(self) -> String {
+        match self.precision {
+            Precision::F32 => f32::from_bits(self.bits as u32).to_string(),
+            Precision::F64 => f64::from_bits(self.bits).to_string(),
+        }
+    }
+
+    // The bits split into their three fields: "sign exponent mantissa".
+    pub fn bit_fields(self) -> String {
+        let all = format!(
+            "{:0width$b}",
+            self.bits,
+            width = self.precision.total_bits() as usize
+        );
+        let exponent_end = 1 + self.precision.exponent_bits() as usize;
+        format!(
+            "{} {} {}",
+            &all[..1],
+            &all[1..exponent_end],
+            &all[exponent_end..]
+        )
+    }
+}
+
+// Rounds an exact value to something readable, like 2.1316282072803e-16, for the short notes
+// next to an exact value.
+pub fn approximate(value: &Rational) -> String {
+    let (text, _) = value.to_decimal(1100);
+    match text.parse::<f64>() {
+        Ok(approx) => format!("{approx:.4e}"),
+        Err(_) => text,
+    }
+}
+
+/* Inspecting a single value */
+pub fn inspect(float: Float) -> String {
+    let precision = float.precision;
+    let mut out = String::new();
+    out.push_str(&format!(
+        "    {} {}  (bits {:#0width$x})\n",
+        precision.name(),
+        float.display(),
+        float.bits,
+        width = precision.total_bits() as usize / 4 + 2
+    ));
+    out.push_str(&format!("    bits:      {}\n", float.bit_fields()));
+    out.push_str(&format!(
+        "    sign:      {} ({})\n",
+        float.sign() as u8,
+        if float.sign() { "-" } else { "+" }
+    ));
+    let biased = float.biased_exponent();
+    let exponent_note = match float.class() {
+        Class::Normal => format!(
+            "{biased} - {} = {}",
+            precision.bias(),
+            biased as i32 - precision.bias()
+        ),
+        Class::Zero | Class::Subnormal => {
+            format!("0, treated as {} for subnormals", 1 - precision.bias())
+        }
+        _ => format!("{biased} (all ones: infinity or NaN)"),
+    };
+    out.push_str(&format!("    exponent:  {exponent_note}\n"));
+    out.push_str(&format!(
+        "    mantissa:  {:#x}{}\n",
+        float.mantissa(),
+        match float.class() {
+            Class::Normal => " (with the implicit leading 1 in front)",
+            _ => "",
+        }
+    ));
+    out.push_str(&format!("    class:     {}\n", float.class()));
+    if let Some(exact) = float.exact() {
+        out.push_str(&format!("    exact:     {exact}\n"));
+        let previous = float.next_down();
+        let next = float.next_up();
+        for (name, neighbour) in [("previous", previous), ("next", next)] {
+            match neighbour.exact() {
+                Some(value) => out.push_str(&format!("    {:<10} {value}\n", format!("{name}:"))),
+                None => out.push_str(&format!(
+                    "    {:<10} {}\n",
+                    format!("{name}:"),
+                    neighbour.display()
+                )),
+            }
+        }
+        if let Some(ulp) = float.ulp() {
+            out.push_str(&format!("    ulp:       {ulp} (~{})\n", approximate(&ulp)));
+        }
+    }
+    out
+}
+
+/* Expressions */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(String),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Literal(text) => write!(f, "{text}"),
+            Expr::Neg(inner) => write!(f, "-{inner}"),
+            Expr::Binary(op, left, right) => write!(f, "({left} {} {right})", op.symbol()),
+        }
+    }
+}
+
+// Parses float expressions like "95.5 - 2.24" or "(0.1 + 0.2) * 3". Same grammar as the integer
+// explorer, but literals are decimals, inf or nan.
+pub fn parse(input: &str) -> Result<Expr, String> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut pos: usize = 0;
+    let (expr, _) = parse_additive(&chars, &mut pos, 0)?;
+    skip_spaces(&chars, &mut pos);
+    if pos < chars.len() {
+        return Err(format!(
+            "unexpected '{}' at character {}",
+            chars[pos],
+            pos + 1
+        ));
+    }
+    Ok(expr)
+}
+
+// Like the integer explorer, a limit on how deep the tree goes, so that thousands of brackets or
+// a sum of thousands of terms are an error rather than a stack overflow.
+pub const MAX_DEPTH: usize = 256;
+
+// Each parse function returns the expression and how deep its tree is. They also get passed how
+// many brackets and minus signs they are inside, since those recurse before there's a tree.
+type Parsed = Result<(Expr, usize), String>;
+
+fn too_deep(pos: usize) -> String {
+    format!(
+        "the expression is nested more than {MAX_DEPTH} levels deep at character {}",
+        pos + 1
+    )
+}
+
+fn deeper(depth: usize, pos: usize) -> Result<usize, String> {
+    if depth >= MAX_DEPTH {
+        return Err(too_deep(pos));
+    }
+    Ok(depth + 1)
+}
+
+fn skip_spaces(chars: &[char], pos: &mut usize) {
+    while *pos < chars.len() && chars[*pos].is_whitespace() {
+        *pos += 1;
+    }
+}
+
+fn parse_additive(chars: &[char], pos: &mut usize, nesting: usize) -> Parsed {
+    let (mut left, mut depth) = parse_multiplicative(chars, pos, nesting)?;
+    loop {
+        skip_spaces(chars, pos);
+        let op = match chars.get(*pos) {
+            Some('+') => Op::Add,
+            Some('-') => Op::Sub,
+            _ => return Ok((left, depth)),
+        };
+        *pos += 1;
+        let (right, right_depth) = parse_multiplicative(chars, pos, nesting)?;
+        depth = deeper(depth.max(right_depth), *pos)?;
+        left = Expr::Binary(op, Box::new(left), Box::new(right));
+    }
+}
+
+fn parse_multiplicative(chars: &[char], pos: &mut usize, nesting: usize) -> Parsed {
+    let (mut left, mut depth) = parse_unary(chars, pos, nesting)?;
+    loop {
+        skip_spaces(chars, pos);
+        let op = match chars.get(*pos) {
+            Some('*') => Op::Mul,
+            Some('/') => Op::Div,
+            _ => return Ok((left, depth)),
+        };
+        *pos += 1;
+        let (right, right_depth) = parse_unary(chars, pos, nesting)?;
+        depth = deeper(depth.max(right_depth), *pos)?;
+        left = Expr::Binary(op, Box::new(left), Box::new(right));
+    }
+}
+
+fn parse_unary(chars: &[char], pos: &mut usize, nesting: usize) -> Parsed {
+    skip_spaces(chars, pos);
+    if matches!(chars.get(*pos), Some('-' | '(')) && nesting >= MAX_DEPTH {
+        return Err(too_deep(*pos));
+    }
+    match chars.get(*pos) {
+        Some('-') => {
+            *pos += 1;
+            // a minus in front of a literal belongs to the literal, like -2.24 or -inf
+            match parse_unary(chars, pos, nesting + 1)? {
+                (Expr::Literal(text), depth) if !text.starts_with("0x") => {
+                    Ok((Expr::Literal(format!("-{text}")), depth))
+                }
+                (other, depth) => Ok((Expr::Neg(Box::new(other)), deeper(depth, *pos)?)),
+            }
+        }
+        Some('(') => {
+            *pos += 1;
+            let inner = parse_additive(chars, pos, nesting + 1)?;
+            skip_spaces(chars, pos);
+            if chars.get(*pos) != Some(&')') {
+                return Err(format!("expected ')' at character {}", *pos + 1));
+            }
+            *pos += 1;
+            Ok(inner)
+        }
+        Some(c) if c.is_ascii_alphanumeric() || *c == '.' => {
+            let start = *pos;
+            while *pos < chars.len() {
+                let c = chars[*pos];
+                // the 'e' of a decimal exponent may be followed by its own sign, as in 1e-3
+                let exponent_sign = (c == '-' || c == '+')
+                    && chars[start].is_ascii_digit()
+                    && !chars[start..*pos].contains(&'x')
+                    && matches!(chars[*pos - 1], 'e' | 'E');
+                if c.is_ascii_alphanumeric() || c == '.' || c == '_' || exponent_sign {
+                    *pos += 1;
+                } else {
+                    break;
+                }
+            }
+            Ok((Expr::Literal(chars[start..*pos].iter().collect()), 1))
+        }
+        Some(c) => Err(format!("unexpected '{c}' at character {}", *pos + 1)),
+        None => Err("expected a number".to_string()),
+    }
+}
+
+/* Tracing an evaluation */
+// One line of the explanation: what was computed, the exact answer, and what the float holds.
+pub struct Step {
+    pub literal: bool,
+    pub description: String,
+    pub exact: Option<Rational>,
+    pub stored: Float,
+}
+
+impl Step {
+    // stored - exact, when both are finite
+    pub fn error(&self) -> Option<Rational> {
+        Some(&self.stored.exact()? - self.exact.as_ref()?)
+    }
+}
+
+// Evaluates the expression in the given precision, recording a Step for every literal and every
+// operation. The exact value of an operation is computed from the rounded operands, so each
+// step's error is the error that step alone introduced.
+pub fn evaluate(expr: &Expr, precision: Precision, steps: &mut Vec<Step>) -> Result<Float, String> {
+    match expr {
+        Expr::Literal(text) => {
+            let stored = Float::parse(text, precision)
+                .ok_or_else(|| format!("'{text}' is not a valid {} literal", precision.name()))?;
+            let cleaned = text.strip_suffix(precision.name()).unwrap_or(text);
+            // raw hex bits are exactly what gets stored, so they have no rounding error
+            let exact = if text.starts_with("0x") {
+                stored.exact()
+            } else {
+                Rational::parse_decimal(cleaned)
+            };
+            steps.push(Step {
+                literal: true,
+                description: format!("literal {text}"),
+                exact,
+                stored,
+            });
+            Ok(stored)
+        }
+        Expr::Neg(inner) => {
+            // negation only flips the sign bit, so it never rounds
+            Ok(evaluate(inner, precision, steps)?.negated())
+        }
+        Expr::Binary(op, left, right) => {
+            let a = evaluate(left, precision, steps)?;
+            let b = evaluate(right, precision, steps)?;
+            let stored = a.apply(*op, b);
+            let exact = match (a.exact(), b.exact()) {
+                (Some(x), Some(y)) => match op {
+                    Op::Add => Some(&x + &y),
+                    Op::Sub => Some(&x - &y),
+                    Op::Mul => Some(&x * &y),
+                    Op::Div => x.checked_div(&y),
+                },
+                _ => None,
+            };
+            steps.push(Step {
+                literal: false,
+                description: format!("{} {} {}", a.display(), op.symbol(), b.display()),
+                exact,
+                stored,
+            });
+            Ok(stored)
+        }
+    }
+}
+
+fn describe_step(step: &Step) -> String {
+    let mut out = format!("    {}\n", step.description);
+    match (&step.exact, step.stored.exact()) {
+        (Some(exact), Some(stored)) => {
+            if exact.compare(&stored) == Ordering::Equal {
+                out.push_str(&format!("        exact, no rounding: {stored}\n"));
+            } else {
+                let error = &stored - exact;
+                if step.literal {
+                    out.push_str(&format!("        written as:   {exact}\n"));
+                } else {
+                    out.push_str(&format!("        exact result: {exact}\n"));
+                }
+                out.push_str(&format!("        stored as:    {stored}\n"));
+                let ulps = step
+                    .stored
+                    .ulp()
+                    .and_then(|ulp| error.abs().checked_div(&ulp))
+                    .and_then(|ulps| ulps.to_decimal(3).0.parse::<f64>().ok())
+                    .map(|ulps| format!(", {ulps} ulp"))
+                    .unwrap_or_default();
+                out.push_str(&format!(
+                    "        error:        {error} (~{}{ulps})\n",
+                    approximate(&error)
+                ));
+            }
+        }
+        _ => out.push_str(&format!(
+            "        result: {} ({})\n",
+            step.stored.display(),
+            step.stored.class()
+        )),
+    }
+    out
+}
+
+// The full step-by-step explanation of one expression, and the float it produced.
+pub fn explain(input: &str, precision: Precision) -> Result<(String, Float), String> {
+    let expr = parse(input)?;
+    let mut steps: Vec<Step> = Vec::new();
+    let result = evaluate(&expr, precision, &mut steps)?;
+    let mut out = format!(
+        "\x1b[1;33m{input} in {}\x1b[0m = {}\n",
+        precision.name(),
+        result.display()
+    );
+    for step in &steps {
+        out.push_str(&describe_step(step));
+    }
+    Ok((out, result))
+}
+
+// Explains two expressions and then how far apart their results are. This is the "why is
+// 95.5 - 2.24 not 93.26" view.
+pub fn compare(left: &str, right: &str, precision: Precision) -> Result<String, String> {
+    let (left_text, a) = explain(left, precision)?;
+    let (right_text, b) = explain(right, precision)?;
+    let mut out = format!("{left_text}\n{right_text}\n");
+    if a.bits == b.bits {
+        out.push_str(&format!(
+            "    both give exactly the same {}: {}\n",
+            precision.name(),
+            a.display()
+        ));
+        return Ok(out);
+    }
+    out.push_str(&format!("    {} != {}\n", a.display(), b.display()));
+    if let (Some(x), Some(y)) = (a.exact(), b.exact()) {
+        let difference = &x - &y;
+        out.push_str(&format!(
+            "    they differ by {difference} (~{})",
+            approximate(&difference)
+        ));
+        // two finite floats of the same sign are n representable values apart when their bit
+        // patterns are n apart
+        if a.sign() == b.sign() {
+            out.push_str(&format!(", which is {} ulp", a.bits.abs_diff(b.bits)));
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exact(float: Float) -> String {
+        float.exact().unwrap().to_string()
+    }
+
+    fn f32_bits(bits: u32) -> Float {
+        Float {
+            precision: Precision::F32,
+            bits: bits as u64,
+        }
+    }
+
+    #[test]
+    fn exact_decimal_expansions() {
+        assert_eq!(
+            exact(Float::from_f64(0.1)),
+            "0.1000000000000000055511151231257827021181583404541015625"
+        );
+        assert_eq!(exact(Float::from_f32(0.1)), "0.100000001490116119384765625");
+        assert_eq!(
+            exact(Float::from_f64(-2.24)),
+            "-2.2400000000000002131628207280300557613372802734375"
+        );
+        assert_eq!(
+            exact(Float::from_f32(f32::MAX)),
+            "340282346638528859811704183484516925440"
+        );
+        assert_eq!(exact(Float::from_f64(95.5)), "95.5");
+        // the smallest subnormal is 2^-1074: 1074 digits after the point, the last one a 5
+        let smallest = exact(Float::from_f64(f64::from_bits(1)));
+        assert_eq!(smallest.len(), "0.".len() + 1074);
+        assert!(smallest.starts_with("0.000") && smallest.ends_with("625"));
+        assert!(Float::from_f64(f64::INFINITY).exact().is_none());
+        assert!(Float::from_f64(f64::NAN).exact().is_none());
+    }
+
+    #[test]
+    fn ulp_neighbours() {
+        let one = Float::from_f32(1.0);
+        assert_eq!(one.next_up(), Float::from_f32(1.0 + f32::EPSILON));
+        assert_eq!(one.next_down(), Float::from_f32(1.0 - f32::EPSILON / 2.0));
+        assert_eq!(one.ulp().unwrap().to_string(), "0.00000011920928955078125");
+        assert_eq!(
+            Float::from_f64(-1.0).next_up(),
+            Float::from_f64(-1.0 + f64::EPSILON / 2.0)
+        );
+        // the ulp is measured away from zero, so it's the same for -1 as for 1
+        assert_eq!(
+            Float::from_f64(-1.0).ulp().unwrap().to_string(),
+            exact(Float::from_f64(f64::EPSILON))
+        );
+        // either zero steps to the smallest subnormal, and back down to zero
+        let tiny = Float::from_f64(f64::from_bits(1));
+        assert_eq!(Float::from_f64(0.0).next_up(), tiny);
+        assert_eq!(Float::from_f64(-0.0).next_up(), tiny);
+        assert_eq!(
+            Float::from_f64(0.0).next_down(),
+            Float::from_f64(-f64::from_bits(1))
+        );
+        assert_eq!(tiny.next_down(), Float::from_f64(0.0));
+        // past the largest finite value is infinity, which stays put
+        let max = Float::from_f64(f64::MAX);
+        assert_eq!(max.next_up(), Float::from_f64(f64::INFINITY));
+        assert_eq!(max.next_up().next_up(), Float::from_f64(f64::INFINITY));
+        assert_eq!(Float::from_f64(f64::INFINITY).next_down(), max);
+        assert!(Float::from_f64(f64::INFINITY).ulp().is_none());
+        // the gap between subnormals is the smallest subnormal itself
+        assert_eq!(tiny.ulp().unwrap().to_string(), exact(tiny));
+    }
+
+    #[test]
+    fn classes() {
+        assert_eq!(Float::from_f64(0.0).class(), Class::Zero);
+        assert_eq!(Float::from_f64(-0.0).class(), Class::Zero);
+        assert!(Float::from_f64(-0.0).sign());
+        assert_eq!(Float::from_f32(f32::MIN_POSITIVE).class(), Class::Normal);
+        assert_eq!(f32_bits(0x007f_ffff).class(), Class::Subnormal);
+        assert_eq!(f32_bits(1).class(), Class::Subnormal);
+        assert_eq!(Float::from_f64(f64::NEG_INFINITY).class(), Class::Infinite);
+        assert_eq!(
+            f32_bits(0x7fc0_0000).class(),
+            Class::Nan {
+                quiet: true,
+                payload: 0
+            }
+        );
+        assert_eq!(
+            f32_bits(0xffc0_0001).class(),
+            Class::Nan {
+                quiet: true,
+                payload: 1
+            }
+        );
+        assert_eq!(
+            f32_bits(0x7f80_0001).class(),
+            Class::Nan {
+                quiet: false,
+                payload: 1
+            }
+        );
+        assert_eq!(
+            Float::parse("0x7ff4000000000000", Precision::F64)
+                .unwrap()
+                .class(),
+            Class::Nan {
+                quiet: false,
+                payload: 0x4_0000_0000_0000
+            }
+        );
+        // the payload survives negation, since that only touches the sign bit
+        let nan = f32_bits(0x7fc0_1234);
+        assert_eq!(nan.next_up(), nan);
+        assert_eq!(nan.next_down().class(), nan.class());
+        // an f32 is only 32 bits
+        assert_eq!(Float::parse("0x1_0000_0000", Precision::F32), None);
+    }
+
+    #[test]
+    fn explaining_a_sum() {
+        let (_, result) = explain("0.1 + 0.2", Precision::F64).unwrap();
+        assert_eq!(result, Float::from_f64(0.1 + 0.2));
+        let mut steps = Vec::new();
+        evaluate(&parse("0.5 * 4").unwrap(), Precision::F32, &mut steps).unwrap();
+        // every step is exact, so none of them has any error
+        assert_eq!(steps.len(), 3);
+        assert!(steps.iter().all(|step| step.error().unwrap().is_zero()));
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let too_deep = format!("the expression is nested more than {MAX_DEPTH} levels deep");
+        for input in [
+            format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000)),
+            format!("{}(1)", "-".repeat(20_000)),
+            vec!["1"; 20_000].join(" + "),
+        ] {
+            assert!(parse(&input).unwrap_err().starts_with(&too_deep));
+        }
+        let deepest = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(parse(&deepest).unwrap(), Expr::Literal("1".to_string()));
+        let longest = vec!["0.5"; MAX_DEPTH].join(" + ");
+        assert_eq!(
+            explain(&longest, Precision::F64).unwrap().1,
+            Float::from_f64(128.0)
+        );
+    }
+}
@@ -0,0 +1,999 @@
+/* Integer Explorer */
+// main.rs lists every integer type from u8 to i128, but not what each one can hold or what
+// happens when a value doesn't fit. This module takes a number or an arithmetic expression,
+// works out its exact value, and then reports for every integer type:
+//   * whether the value fits, and its two's complement bytes in little and big endian
+//   * what the expression evaluates to when done in that type with each of the overflow
+//     strategies the standard library offers: wrapping_, checked_, saturating_ and overflowing_
+// For division and remainder it also compares Rust's truncating '/' and '%' with div_euclid and
+// rem_euclid, which behave differently once negative numbers are involved.
+use std::fmt;
+
+/* Exact values */
+// The exact result of an expression can be anything from -u128::MAX to u128::MAX, which no single
+// built-in type covers (i128 is too small on the positive side, u128 can't be negative), so
+// values are stored as a sign plus a u128 magnitude.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Wide {
+    negative: bool,
+    magnitude: u128,
+}
+
+impl Wide {
+    pub fn new(negative: bool, magnitude: u128) -> Self {
+        // there is only one zero, and it isn't negative
+        Wide {
+            negative: negative && magnitude != 0,
+            magnitude,
+        }
+    }
+
+    pub fn from_i128(n: i128) -> Self {
+        Wide::new(n < 0, n.unsigned_abs())
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.negative
+    }
+
+    pub fn is_zero(self) -> bool {
+        self.magnitude == 0
+    }
+
+    pub fn magnitude(self) -> u128 {
+        self.magnitude
+    }
+
+    pub fn checked_add(self, other: Wide) -> Option<Wide> {
+        if self.negative == other.negative {
+            let magnitude = self.magnitude.checked_add(other.magnitude)?;
+            Some(Wide::new(self.negative, magnitude))
+        } else if self.magnitude >= other.magnitude {
+            Some(Wide::new(self.negative, self.magnitude - other.magnitude))
+        } else {
+            Some(Wide::new(other.negative, other.magnitude - self.magnitude))
+        }
+    }
+
+    pub fn checked_sub(self, other: Wide) -> Option<Wide> {
+        self.checked_add(-other)
+    }
+
+    pub fn checked_mul(self, other: Wide) -> Option<Wide> {
+        let magnitude = self.magnitude.checked_mul(other.magnitude)?;
+        Some(Wide::new(self.negative != other.negative, magnitude))
+    }
+
+    // Rust's '/' on integers truncates towards zero, so -15 / 4 is -3, not -4
+    pub fn checked_div(self, other: Wide) -> Option<Wide> {
+        let magnitude = self.magnitude.checked_div(other.magnitude)?;
+        Some(Wide::new(self.negative != other.negative, magnitude))
+    }
+
+    // ...and '%' gives the remainder that goes with that, which has the sign of the left side
+    pub fn checked_rem(self, other: Wide) -> Option<Wide> {
+        let magnitude = self.magnitude.checked_rem(other.magnitude)?;
+        Some(Wide::new(self.negative, magnitude))
+    }
+
+    // div_euclid rounds so that the remainder is never negative
+    pub fn checked_div_euclid(self, other: Wide) -> Option<Wide> {
+        let quotient = self.checked_div(other)?;
+        let remainder = self.checked_rem(other)?;
+        if !remainder.negative {
+            return Some(quotient);
+        }
+        let one = Wide::new(false, 1);
+        if other.negative {
+            quotient.checked_add(one)
+        } else {
+            quotient.checked_sub(one)
+        }
+    }
+
+    pub fn checked_rem_euclid(self, other: Wide) -> Option<Wide> {
+        let remainder = self.checked_rem(other)?;
+        if remainder.negative {
+            remainder.checked_add(Wide::new(false, other.magnitude))
+        } else {
+            Some(remainder)
+        }
+    }
+
+    // The value's bit pattern in two's complement, as a u128. For a value that doesn't fit in the
+    // type this is also what 'value as T' would keep once the high bits are cut off.
+    pub fn twos_complement(self) -> u128 {
+        if self.negative {
+            self.magnitude.wrapping_neg()
+        } else {
+            self.magnitude
+        }
+    }
+}
+
+impl std::ops::Neg for Wide {
+    type Output = Wide;
+
+    fn neg(self) -> Wide {
+        Wide::new(!self.negative, self.magnitude)
+    }
+}
+
+impl fmt::Display for Wide {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.negative {
+            write!(f, "-{}", self.magnitude)
+        } else {
+            write!(f, "{}", self.magnitude)
+        }
+    }
+}
+
+/* The integer types */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IntKind {
+    pub name: &'static str,
+    pub bits: u32,
+    pub signed: bool,
+}
+
+// the same order as the examples in main.rs
+pub const INT_KINDS: [IntKind; 12] = [
+    IntKind::new("u8", 8, false),
+    IntKind::new("i8", 8, true),
+    IntKind::new("u16", 16, false),
+    IntKind::new("i16", 16, true),
+    IntKind::new("u32", 32, false),
+    IntKind::new("i32", 32, true),
+    IntKind::new("u64", 64, false),
+    IntKind::new("i64", 64, true),
+    IntKind::new("u128", 128, false),
+    IntKind::new("i128", 128, true),
+    IntKind::new("usize", usize::BITS, false),
+    IntKind::new("isize", isize::BITS, true),
+];
+
+impl IntKind {
+    const fn new(name: &'static str, bits: u32, signed: bool) -> Self {
+        IntKind { name, bits, signed }
+    }
+
+    pub fn by_name(name: &str) -> Option<IntKind> {
+        INT_KINDS.iter().copied().find(|kind| kind.name == name)
+    }
+
+    pub fn min(self) -> Wide {
+        if self.signed {
+            Wide::new(true, 1u128 << (self.bits - 1))
+        } else {
+            Wide::new(false, 0)
+        }
+    }
+
+    pub fn max(self) -> Wide {
+        let magnitude: u128 = match (self.signed, self.bits) {
+            (false, 128) => u128::MAX,
+            (false, bits) => (1u128 << bits) - 1,
+            (true, bits) => (1u128 << (bits - 1)) - 1,
+        };
+        Wide::new(false, magnitude)
+    }
+
+    pub fn fits(self, value: Wide) -> bool {
+        if value.negative {
+            self.signed && value.magnitude <= self.min().magnitude
+        } else {
+            value.magnitude <= self.max().magnitude
+        }
+    }
+
+    // The value a bit pattern stands for in this type, after cutting it down to the type's width.
+    // This is how wrapping arithmetic and 'as' casts between integer types pick their result.
+    pub fn from_bits(self, bits: u128) -> Wide {
+        let bits = if self.bits == 128 {
+            bits
+        } else {
+            bits & ((1u128 << self.bits) - 1)
+        };
+        let sign_bit = 1u128 << (self.bits - 1);
+        if self.signed && bits & sign_bit != 0 {
+            // the pattern is the value plus 2^bits, so the magnitude is 2^bits minus the pattern
+            Wide::new(true, sign_bit - (bits & (sign_bit - 1)))
+        } else {
+            Wide::new(false, bits)
+        }
+    }
+
+    // the value's bytes in little endian order (least significant byte first), truncated to
+    // the width of the type just like an 'as' cast would
+    pub fn le_bytes(self, value: Wide) -> Vec<u8> {
+        let len = (self.bits / 8) as usize;
+        value.twos_complement().to_le_bytes()[..len].to_vec()
+    }
+
+    pub fn be_bytes(self, value: Wide) -> Vec<u8> {
+        let mut bytes = self.le_bytes(value);
+        bytes.reverse();
+        bytes
+    }
+}
+
+/* Expressions */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+impl Op {
+    pub fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => '*',
+            Op::Div => '/',
+            Op::Rem => '%',
+        }
+    }
+
+    // the wording rustc uses in its overflow panic messages
+    pub fn verb(self) -> &'static str {
+        match self {
+            Op::Add => "add",
+            Op::Sub => "subtract",
+            Op::Mul => "multiply",
+            Op::Div => "divide",
+            Op::Rem => "calculate the remainder",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Literal(Wide),
+    Neg(Box<Expr>),
+    Binary(Op, Box<Expr>, Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExprError {
+    pub position: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (at character {})", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for ExprError {}
+
+// Parses things like "300", "-15 / 3", "0xff * 2" or "(1_000 - 24) % 7". Literals can be decimal,
+// or hex, octal and binary with Rust's 0x, 0o and 0b prefixes, and may use '_' separators.
+pub fn parse(input: &str) -> Result<Expr, ExprError> {
+    let mut parser = ExprParser {
+        chars: input.chars().collect(),
+        pos: 0,
+        nesting: 0,
+    };
+    let (expr, _) = parser.additive()?;
+    parser.skip_spaces();
+    if parser.pos < parser.chars.len() {
+        return Err(parser.error(format!("unexpected '{}'", parser.chars[parser.pos])));
+    }
+    Ok(expr)
+}
+
+// Parsing, evaluating and even dropping an Expr all recurse once per level of the tree, so a few
+// thousand nested brackets (or a few thousand terms in a row: 1 + 1 + 1 + ... leans to the left)
+// would run out of stack. Nothing anyone types by hand gets close to this.
+pub const MAX_DEPTH: usize = 256;
+
+struct ExprParser {
+    chars: Vec<char>,
+    pos: usize,
+    // how many brackets and minus signs the parser is inside right now
+    nesting: usize,
+}
+
+// Each step of the parser returns the expression along with how deep its tree is.
+type Parsed = Result<(Expr, usize), ExprError>;
+
+impl ExprParser {
+    fn error(&self, message: impl Into<String>) -> ExprError {
+        ExprError {
+            position: self.pos,
+            message: message.into(),
+        }
+    }
+
+    fn too_deep(&self) -> ExprError {
+        self.error(format!(
+            "the expression is nested more than {MAX_DEPTH} levels deep"
+        ))
+    }
+
+    // The depth of a new node on top of children this deep, as long as it stays under the limit.
+    fn deeper(&self, depth: usize) -> Result<usize, ExprError> {
+        if depth >= MAX_DEPTH {
+            return Err(self.too_deep());
+        }
+        Ok(depth + 1)
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.pos < self.chars.len() && self.chars[self.pos].is_whitespace() {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.chars.get(self.pos).copied()
+    }
+
+    fn additive(&mut self) -> Parsed {
+        let (mut left, mut depth) = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some('+') => Op::Add,
+                Some('-') => Op::Sub,
+                _ => return Ok((left, depth)),
+            };
+            self.pos += 1;
+            let (right, right_depth) = self.multiplicative()?;
+            depth = self.deeper(depth.max(right_depth))?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Parsed {
+        let (mut left, mut depth) = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some('*') => Op::Mul,
+                Some('/') => Op::Div,
+                Some('%') => Op::Rem,
+                _ => return Ok((left, depth)),
+            };
+            self.pos += 1;
+            let (right, right_depth) = self.unary()?;
+            depth = self.deeper(depth.max(right_depth))?;
+            left = Expr::Binary(op, Box::new(left), Box::new(right));
+        }
+    }
+
+    fn unary(&mut self) -> Parsed {
+        match self.peek() {
+            Some(c @ ('-' | '(')) => {
+                // brackets don't make the tree any deeper, but they do make the parser recurse,
+                // so they count too, and before recursing rather than after
+                if self.nesting >= MAX_DEPTH {
+                    return Err(self.too_deep());
+                }
+                self.nesting += 1;
+                self.pos += 1;
+                let parsed = if c == '-' {
+                    self.negation()
+                } else {
+                    self.bracketed()
+                };
+                self.nesting -= 1;
+                parsed
+            }
+            Some(c) if c.is_ascii_digit() => Ok((self.literal()?, 1)),
+            Some(c) => Err(self.error(format!("unexpected '{c}'"))),
+            None => Err(self.error("expected a number")),
+        }
+    }
+
+    fn negation(&mut self) -> Parsed {
+        // a minus directly in front of a literal is part of the literal, so that -128
+        // is a valid i8 the same way it is in Rust
+        match self.unary()? {
+            (Expr::Literal(value), depth) => Ok((Expr::Literal(-value), depth)),
+            (other, depth) => Ok((Expr::Neg(Box::new(other)), self.deeper(depth)?)),
+        }
+    }
+
+    fn bracketed(&mut self) -> Parsed {
+        let inner = self.additive()?;
+        if self.peek() != Some(')') {
+            return Err(self.error("expected ')'"));
+        }
+        self.pos += 1;
+        Ok(inner)
+    }
+
+    fn literal(&mut self) -> Result<Expr, ExprError> {
+        let start = self.pos;
+        while self.pos < self.chars.len()
+            && (self.chars[self.pos].is_ascii_alphanumeric() || self.chars[self.pos] == '_')
+        {
+            self.pos += 1;
+        }
+        let text: String = self.chars[start..self.pos]
+            .iter()
+            .filter(|&&c| c != '_')
+            .collect();
+        let (digits, radix) = match text.get(..2) {
+            Some("0x") => (&text[2..], 16),
+            Some("0o") => (&text[2..], 8),
+            Some("0b") => (&text[2..], 2),
+            _ => (&text[..], 10),
+        };
+        match u128::from_str_radix(digits, radix) {
+            Ok(magnitude) => Ok(Expr::Literal(Wide::new(false, magnitude))),
+            Err(_) => Err(ExprError {
+                position: start,
+                message: format!(
+                    "'{text}' is not a valid integer literal (or is bigger than u128)"
+                ),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Literal(value) => write!(f, "{value}"),
+            Expr::Neg(inner) => write!(f, "-({inner})"),
+            Expr::Binary(op, left, right) => write!(f, "({left} {} {right})", op.symbol()),
+        }
+    }
+}
+
+// The exact mathematical value of the expression, or an explanation of why there isn't one.
+pub fn eval_exact(expr: &Expr) -> Result<Wide, String> {
+    match expr {
+        Expr::Literal(value) => Ok(*value),
+        Expr::Neg(inner) => Ok(-eval_exact(inner)?),
+        Expr::Binary(op, left, right) => {
+            let (a, b) = (eval_exact(left)?, eval_exact(right)?);
+            if matches!(op, Op::Div | Op::Rem) && b.is_zero() {
+                return Err(format!("attempt to {} by zero", op.verb()));
+            }
+            let result = match op {
+                Op::Add => a.checked_add(b),
+                Op::Sub => a.checked_sub(b),
+                Op::Mul => a.checked_mul(b),
+                Op::Div => a.checked_div(b),
+                Op::Rem => a.checked_rem(b),
+            };
+            result.ok_or_else(|| "the result is bigger than any integer type".to_string())
+        }
+    }
+}
+
+/* Evaluating in a specific type */
+// The four overflow strategies, plus what a plain operator does in a debug build (panic).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Debug,
+    Wrapping,
+    Checked,
+    Saturating,
+    Overflowing,
+}
+
+pub const STRATEGIES: [Strategy; 5] = [
+    Strategy::Debug,
+    Strategy::Wrapping,
+    Strategy::Checked,
+    Strategy::Saturating,
+    Strategy::Overflowing,
+];
+
+impl Strategy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Strategy::Debug => "plain (debug)",
+            Strategy::Wrapping => "wrapping_",
+            Strategy::Checked => "checked_",
+            Strategy::Saturating => "saturating_",
+            Strategy::Overflowing => "overflowing_",
+        }
+    }
+}
+
+// A trait over all twelve integer types, so one generic function can evaluate an expression in
+// any of them. Each method returns None where the real std method would panic (dividing by zero).
+trait Int: Copy + fmt::Display {
+    fn from_wide(value: Wide) -> Option<Self>;
+    fn wrapping(op: Op, a: Self, b: Self) -> Option<Self>;
+    fn checked(op: Op, a: Self, b: Self) -> Option<Option<Self>>;
+    fn saturating(op: Op, a: Self, b: Self) -> Option<Self>;
+    fn overflowing(op: Op, a: Self, b: Self) -> Option<(Self, bool)>;
+    fn wrapping_neg(a: Self) -> Self;
+    fn checked_neg(a: Self) -> Option<Self>;
+    fn saturating_neg(a: Self) -> Self;
+    fn overflowing_neg(a: Self) -> (Self, bool);
+}
+
+macro_rules! impl_int {
+    ($($t:ty),*) => {$(
+        impl Int for $t {
+            fn from_wide(value: Wide) -> Option<Self> {
+                if value.is_negative() {
+                    // i128 is the widest signed type, so any negative value that fits anywhere
+                    // fits in it first
+                    let n: i128 = 0i128.checked_sub_unsigned(value.magnitude)?;
+                    <$t>::try_from(n).ok()
+                } else {
+                    <$t>::try_from(value.magnitude).ok()
+                }
+            }
+            fn wrapping(op: Op, a: Self, b: Self) -> Option<Self> {
+                Some(match op {
+                    Op::Add => a.wrapping_add(b),
+                    Op::Sub => a.wrapping_sub(b),
+                    Op::Mul => a.wrapping_mul(b),
+                    // the wrapping_ versions still panic on a zero divisor
+                    Op::Div => (b != 0).then(|| a.wrapping_div(b))?,
+                    Op::Rem => (b != 0).then(|| a.wrapping_rem(b))?,
+                })
+            }
+            fn checked(op: Op, a: Self, b: Self) -> Option<Option<Self>> {
+                // unlike every other strategy, checked_div and checked_rem don't panic on a
+                // zero divisor, they return None
+                Some(match op {
+                    Op::Add => a.checked_add(b),
+                    Op::Sub => a.checked_sub(b),
+                    Op::Mul => a.checked_mul(b),
+                    Op::Div => a.checked_div(b),
+                    Op::Rem => a.checked_rem(b),
+                })
+            }
+            fn saturating(op: Op, a: Self, b: Self) -> Option<Self> {
+                if matches!(op, Op::Div | Op::Rem) && b == 0 {
+                    return None;
+                }
+                Some(match op {
+                    Op::Add => a.saturating_add(b),
+                    Op::Sub => a.saturating_sub(b),
+                    Op::Mul => a.saturating_mul(b),
+                    Op::Div => a.saturating_div(b),
+                    // there is no saturating_rem in std: the only overflowing case, MIN % -1,
+                    // has a mathematically exact answer of 0 anyway
+                    Op::Rem => a.wrapping_rem(b),
+                })
+            }
+            fn overflowing(op: Op, a: Self, b: Self) -> Option<(Self, bool)> {
+                if matches!(op, Op::Div | Op::Rem) && b == 0 {
+                    return None;
+                }
+                Some(match op {
+                    Op::Add => a.overflowing_add(b),
+                    Op::Sub => a.overflowing_sub(b),
+                    Op::Mul => a.overflowing_mul(b),
+                    Op::Div => a.overflowing_div(b),
+                    Op::Rem => a.overflowing_rem(b),
+                })
+            }
+            fn wrapping_neg(a: Self) -> Self {
+                a.wrapping_neg()
+            }
+            fn checked_neg(a: Self) -> Option<Self> {
+                a.checked_neg()
+            }
+            fn saturating_neg(a: Self) -> Self {
+                // unsigned types have no saturating_neg; every negative result clamps to 0
+                <$t>::checked_neg(a).unwrap_or(if a == <$t>::MIN { <$t>::MAX } else { 0 })
+            }
+            fn overflowing_neg(a: Self) -> (Self, bool) {
+                a.overflowing_neg()
+            }
+        }
+    )*};
+}
+
+impl_int!(u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize);
+
+// What evaluating the expression in one type with one strategy produced. Checked arithmetic
+// gives None on overflow; overflowing_ reports whether any step overflowed. Failed holds a panic
+// or a compile error, already worded for display.
+enum Outcome<T> {
+    Value(T),
+    Checked(Option<T>),
+    Overflowing(T, bool),
+    Failed(String),
+}
+
+fn eval_in<T: Int>(expr: &Expr, strategy: Strategy, type_name: &str) -> Outcome<T> {
+    match strategy {
+        Strategy::Checked => match eval_checked::<T>(expr, type_name) {
+            Ok(value) => Outcome::Checked(value),
+            Err(message) => Outcome::Failed(message),
+        },
+        Strategy::Overflowing => match eval_overflowing::<T>(expr, type_name) {
+            Ok((value, overflowed)) => Outcome::Overflowing(value, overflowed),
+            Err(message) => Outcome::Failed(message),
+        },
+        Strategy::Debug => match eval_checked::<T>(expr, type_name) {
+            Ok(Some(value)) => Outcome::Value(value),
+            Ok(None) => Outcome::Failed(first_overflow::<T>(expr, type_name)),
+            Err(message) => Outcome::Failed(message),
+        },
+        Strategy::Wrapping | Strategy::Saturating => {
+            match eval_simple::<T>(expr, strategy, type_name) {
+                Ok(value) => Outcome::Value(value),
+                Err(message) => Outcome::Failed(message),
+            }
+        }
+    }
+}
+
+fn literal<T: Int>(value: Wide, type_name: &str) -> Result<T, String> {
+    // rustc refuses to compile this rather than panicking at runtime
+    T::from_wide(value)
+        .ok_or_else(|| format!("error: literal {value} out of range for {type_name}"))
+}
+
+fn divide_by_zero(op: Op) -> String {
+    format!("panic: attempt to {} by zero", op.verb())
+}
+
+fn eval_simple<T: Int>(expr: &Expr, strategy: Strategy, type_name: &str) -> Result<T, String> {
+    match expr {
+        Expr::Literal(value) => literal(*value, type_name),
+        Expr::Neg(inner) => {
+            let a = eval_simple::<T>(inner, strategy, type_name)?;
+            Ok(match strategy {
+                Strategy::Saturating => T::saturating_neg(a),
+                _ => T::wrapping_neg(a),
+            })
+        }
+        Expr::Binary(op, left, right) => {
+            let a = eval_simple::<T>(left, strategy, type_name)?;
+            let b = eval_simple::<T>(right, strategy, type_name)?;
+            let result = match strategy {
+                Strategy::Saturating => T::saturating(*op, a, b),
+                _ => T::wrapping(*op, a, b),
+            };
+            result.ok_or_else(|| divide_by_zero(*op))
+        }
+    }
+}
+
+fn eval_checked<T: Int>(expr: &Expr, type_name: &str) -> Result<Option<T>, String> {
+    match expr {
+        Expr::Literal(value) => literal(*value, type_name).map(Some),
+        Expr::Neg(inner) => Ok(eval_checked::<T>(inner, type_name)?.and_then(T::checked_neg)),
+        Expr::Binary(op, left, right) => {
+            let a = eval_checked::<T>(left, type_name)?;
+            let b = eval_checked::<T>(right, type_name)?;
+            match (a, b) {
+                (Some(a), Some(b)) => T::checked(*op, a, b).ok_or_else(|| divide_by_zero(*op)),
+                _ => Ok(None),
+            }
+        }
+    }
+}
+
+fn eval_overflowing<T: Int>(expr: &Expr, type_name: &str) -> Result<(T, bool), String> {
+    match expr {
+        Expr::Literal(value) => Ok((literal(*value, type_name)?, false)),
+        Expr::Neg(inner) => {
+            let (a, overflowed) = eval_overflowing::<T>(inner, type_name)?;
+            let (result, now) = T::overflowing_neg(a);
+            Ok((result, overflowed || now))
+        }
+        Expr::Binary(op, left, right) => {
+            let (a, left_overflowed) = eval_overflowing::<T>(left, type_name)?;
+            let (b, right_overflowed) = eval_overflowing::<T>(right, type_name)?;
+            let (result, now) = T::overflowing(*op, a, b).ok_or_else(|| divide_by_zero(*op))?;
+            Ok((result, left_overflowed || right_overflowed || now))
+        }
+    }
+}
+
+// In a debug build the program panics at the first operation that overflows. This finds that
+// operation so the message can say which one it was, like rustc's panic message does.
+fn first_overflow<T: Int>(expr: &Expr, type_name: &str) -> String {
+    match expr {
+        Expr::Literal(_) => unreachable!("literals are range-checked before evaluation"),
+        Expr::Neg(inner) => match eval_checked::<T>(inner, type_name) {
+            Ok(Some(_)) => "panic: attempt to negate with overflow".to_string(),
+            _ => first_overflow::<T>(inner, type_name),
+        },
+        Expr::Binary(op, left, right) => {
+            for side in [left, right] {
+                if let Ok(None) = eval_checked::<T>(side, type_name) {
+                    return first_overflow::<T>(side, type_name);
+                }
+            }
+            if matches!(op, Op::Div | Op::Rem) && eval_exact(right) == Ok(Wide::new(false, 0)) {
+                return divide_by_zero(*op);
+            }
+            format!("panic: attempt to {} with overflow", op.verb())
+        }
+    }
+}
+
+fn describe<T: Int>(outcome: Outcome<T>) -> String {
+    match outcome {
+        Outcome::Value(value) => value.to_string(),
+        Outcome::Checked(Some(value)) => format!("Some({value})"),
+        Outcome::Checked(None) => "None".to_string(),
+        Outcome::Overflowing(value, overflowed) => format!("({value}, {overflowed})"),
+        Outcome::Failed(message) => message,
+    }
+}
+
+fn outcome_for(kind: IntKind, expr: &Expr, strategy: Strategy) -> String {
+    let name = kind.name;
+    match name {
+        "u8" => describe(eval_in::<u8>(expr, strategy, name)),
+        "i8" => describe(eval_in::<i8>(expr, strategy, name)),
+        "u16" => describe(eval_in::<u16>(expr, strategy, name)),
+        "i16" => describe(eval_in::<i16>(expr, strategy, name)),
+        "u32" => describe(eval_in::<u32>(expr, strategy, name)),
+        "i32" => describe(eval_in::<i32>(expr, strategy, name)),
+        "u64" => describe(eval_in::<u64>(expr, strategy, name)),
+        "i64" => describe(eval_in::<i64>(expr, strategy, name)),
+        "u128" => describe(eval_in::<u128>(expr, strategy, name)),
+        "i128" => describe(eval_in::<i128>(expr, strategy, name)),
+        "usize" => describe(eval_in::<usize>(expr, strategy, name)),
+        "isize" => describe(eval_in::<isize>(expr, strategy, name)),
+        _ => unreachable!("INT_KINDS only lists the built-in types"),
+    }
+}
+
+/* The report */
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+// Builds the full text report for an input like "-15 / 3". This is what 'cargo run -- ints'
+// prints.
+pub fn report(input: &str) -> Result<String, ExprError> {
+    let expr = parse(input)?;
+    let mut out = String::new();
+    out.push_str(&format!("\x1b[1;4;33mInteger explorer: {input}\x1b[0m\n\n"));
+
+    let exact = match eval_exact(&expr) {
+        Ok(value) => {
+            out.push_str(&format!("    exact value: {value}\n\n"));
+            Some(value)
+        }
+        Err(message) => {
+            out.push_str(&format!("    no exact value: {message}\n\n"));
+            None
+        }
+    };
+
+    if let Some(value) = exact {
+        out.push_str("\x1b[1;33mRepresentation\x1b[0m\n");
+        out.push_str("    (bytes of a type the value doesn't fit in show what 'as' would keep)\n");
+        for kind in INT_KINDS {
+            let fits = if kind.fits(value) { "fits " } else { "--   " };
+            out.push_str(&format!(
+                "    {:<5} {fits} {:>41} ..= {:<40}\n          le: {}\n          be: {}\n",
+                kind.name,
+                kind.min(),
+                kind.max(),
+                hex_bytes(&kind.le_bytes(value)),
+                hex_bytes(&kind.be_bytes(value)),
+            ));
+        }
+        out.push('\n');
+    }
+
+    if !matches!(expr, Expr::Literal(_)) {
+        out.push_str("\x1b[1;33mEvaluated in each type\x1b[0m\n");
+        for kind in INT_KINDS {
+            out.push_str(&format!("    {}:\n", kind.name));
+            for strategy in STRATEGIES {
+                out.push_str(&format!(
+                    "        {:<14} {}\n",
+                    strategy.name(),
+                    outcome_for(kind, &expr, strategy)
+                ));
+            }
+        }
+        out.push('\n');
+    }
+
+    if let Expr::Binary(Op::Div | Op::Rem, left, right) = &expr {
+        if let (Ok(a), Ok(b)) = (eval_exact(left), eval_exact(right)) {
+            out.push_str("\x1b[1;33mTruncating vs Euclidean division\x1b[0m\n");
+            out.push_str(&division_table(a, b));
+        }
+    }
+    Ok(out)
+}
+
+// Rust's '/' truncates towards zero and '%' takes the sign of the left operand. div_euclid and
+// rem_euclid instead keep the remainder in 0..|b|, which is usually what you want for things like
+// wrapping an index around or working out a day of the week.
+pub fn division_table(a: Wide, b: Wide) -> String {
+    if b.is_zero() {
+        return "    division by zero panics for every one of these\n".to_string();
+    }
+    let show = |value: Option<Wide>| match value {
+        Some(value) => value.to_string(),
+        None => "overflow".to_string(),
+    };
+    // a method call binds tighter than unary minus, so -15.div_euclid(4) would mean
+    // -(15.div_euclid(4)); negative receivers need parentheses
+    let receiver = if a.is_negative() {
+        format!("({a})")
+    } else {
+        a.to_string()
+    };
+    let divisor = b.to_string();
+    let plain_width = a.to_string().len() + divisor.len() + 3;
+    format!(
+        "    {:<plain_width$} = {:<6} {receiver}.div_euclid({divisor}) = {}\n    {:<plain_width$} = {:<6} {receiver}.rem_euclid({divisor}) = {}\n",
+        format!("{a} / {divisor}"),
+        show(a.checked_div(b)),
+        show(a.checked_div_euclid(b)),
+        format!("{a} % {divisor}"),
+        show(a.checked_rem(b)),
+        show(a.checked_rem_euclid(b)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // What each of STRATEGIES gives for the expression in the named type, in order.
+    fn outcomes(input: &str, type_name: &str) -> Vec<String> {
+        let expr = parse(input).unwrap();
+        let kind = INT_KINDS
+            .into_iter()
+            .find(|kind| kind.name == type_name)
+            .unwrap();
+        STRATEGIES
+            .into_iter()
+            .map(|strategy| outcome_for(kind, &expr, strategy))
+            .collect()
+    }
+
+    #[test]
+    fn values_that_fit_are_the_same_every_way() {
+        assert_eq!(
+            outcomes("(1_000 - 24) % 7", "u16"),
+            ["3", "3", "Some(3)", "3", "(3, false)"]
+        );
+        assert_eq!(
+            outcomes("-15 / 4", "i8"),
+            ["-3", "-3", "Some(-3)", "-3", "(-3, false)"]
+        );
+    }
+
+    #[test]
+    fn unsigned_overflow() {
+        assert_eq!(
+            outcomes("250 + 10", "u8"),
+            [
+                "panic: attempt to add with overflow",
+                "4",
+                "None",
+                "255",
+                "(4, true)"
+            ]
+        );
+        assert_eq!(
+            outcomes("3 - 5", "u32"),
+            [
+                "panic: attempt to subtract with overflow",
+                "4294967294",
+                "None",
+                "0",
+                "(4294967294, true)"
+            ]
+        );
+    }
+
+    #[test]
+    fn signed_overflow() {
+        assert_eq!(
+            outcomes("-128 / -1", "i8"),
+            [
+                "panic: attempt to divide with overflow",
+                "-128",
+                "None",
+                "127",
+                "(-128, true)"
+            ]
+        );
+        assert_eq!(
+            outcomes("-(-128 + 0)", "i8"),
+            [
+                "panic: attempt to negate with overflow",
+                "-128",
+                "None",
+                "127",
+                "(-128, true)"
+            ]
+        );
+        assert_eq!(
+            outcomes("100 * -2", "i8"),
+            [
+                "panic: attempt to multiply with overflow",
+                "56",
+                "None",
+                "-128",
+                "(56, true)"
+            ]
+        );
+    }
+
+    #[test]
+    fn overflow_part_way_through() {
+        // the sum overflows even though the final answer would fit, and overflowing_ remembers
+        assert_eq!(
+            outcomes("(200 + 100) - 100", "u8"),
+            [
+                "panic: attempt to add with overflow",
+                "200",
+                "None",
+                "155",
+                "(200, true)"
+            ]
+        );
+    }
+
+    #[test]
+    fn failures_that_are_not_overflow() {
+        let out_of_range = "error: literal 300 out of range for u8";
+        assert_eq!(outcomes("300 - 1", "u8"), [out_of_range; 5]);
+        let by_zero = "panic: attempt to calculate the remainder by zero";
+        // except checked_, which gives None for that too
+        assert_eq!(
+            outcomes("7 % (2 - 2)", "i32"),
+            [by_zero, by_zero, "None", by_zero, by_zero]
+        );
+        assert_eq!(
+            outcomes("-1 + 0", "u64")[1],
+            "error: literal -1 out of range for u64"
+        );
+    }
+
+    #[test]
+    fn division_tables() {
+        let table = |a: i128, b: i128| division_table(Wide::from_i128(a), Wide::from_i128(b));
+        assert_eq!(
+            table(-7, 2),
+            "    -7 / 2 = -3     (-7).div_euclid(2) = -4\n    -7 % 2 = -1     (-7).rem_euclid(2) = 1\n"
+        );
+        assert_eq!(
+            table(7, -2),
+            "    7 / -2 = -3     7.div_euclid(-2) = -3\n    7 % -2 = 1      7.rem_euclid(-2) = 1\n"
+        );
+        assert_eq!(
+            table(-7, -2),
+            "    -7 / -2 = 3      (-7).div_euclid(-2) = 4\n    -7 % -2 = -1     (-7).rem_euclid(-2) = 1\n"
+        );
+        assert_eq!(
+            table(5, 0),
+            "    division by zero panics for every one of these\n"
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_an_error() {
+        let message = format!("the expression is nested more than {MAX_DEPTH} levels deep");
+        let brackets = format!("{}1{}", "(".repeat(20_000), ")".repeat(20_000));
+        assert_eq!(parse(&brackets).unwrap_err().message, message);
+        let minuses = format!("{}(1)", "-".repeat(20_000));
+        assert_eq!(parse(&minuses).unwrap_err().message, message);
+        let sum = vec!["1"; 20_000].join(" + ");
+        assert_eq!(parse(&sum).unwrap_err().message, message);
+        // right up to the limit is fine
+        let deepest = format!("{}1{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(parse(&deepest).is_ok());
+        assert!(report(&vec!["1"; MAX_DEPTH].join(" + ")).is_ok());
+    }
+}
@@ -0,0 +1,8 @@
+// Tools that dig deeper into the types main.rs introduces. main.rs still runs the chapter's
+// examples when started without arguments; with arguments it hands off to one of these.
+pub mod calc;
+pub mod calendar;
+pub mod exact;
+pub mod float_inspector;
+pub mod int_explorer;
+pub mod matrix;
@@ -1,4 +1,17 @@
+use data_types::calc::{self, Calculator};
+use data_types::calendar::{self, Date};
+use data_types::float_inspector::{self, Float, Precision};
+use data_types::int_explorer;
+use data_types::matrix::Matrix;
+
 fn main() {
+    // 'cargo run -- <tool> ...' runs one of the explorers in lib.rs instead of the examples
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        run_tool(&args);
+        return;
+    }
+
     // use 'let' keyword to assign a value to an identifier.
     let string = "string";
     println!("this is a string: {string}");
@@ -81,6 +94,17 @@ fn main() {
         floattt + floattt_2,
         floatsf + floatsf_2
     );
+    // floattt + floatsf wouldn't compile. calc.rs type checks expressions the same way rustc does,
+    // so it can show the error, along with what happens to literals that have no suffix and to
+    // arithmetic that overflows. 'cargo run -- calc' starts it as a REPL.
+    let mut calculator = Calculator::default();
+    for line in ["2.5f32 + 1.0f64", "2.5f32 + 1.0", "5u8 + 10", "250u8 + 10"] {
+        println!(">> {line}");
+        match calculator.eval_line(line) {
+            Ok(outcome) => println!("{outcome}"),
+            Err(err) => print!("{}", err.render(line)),
+        }
+    }
 
     /* Operations */
     println!(
@@ -92,12 +116,44 @@ fn main() {
         5 / 3,
         5 % 3
     );
+    // 95.5 - 2.24 prints 93.26, but neither 2.24 nor 93.26 can be stored exactly in binary.
+    // The float inspector shows the value that actually gets stored for each literal, and how
+    // much error the subtraction adds. Here the errors happen to land on the same f64, but with
+    // 0.1 + 0.2 they don't:
+    for (left, right) in [("95.5 - 2.24", "93.26"), ("0.1 + 0.2", "0.3")] {
+        match float_inspector::compare(left, right, Precision::F64) {
+            Ok(explanation) => print!("{explanation}"),
+            Err(err) => println!("{err}"),
+        }
+    }
     println!("{}", 95.2 / 2.5);
     println!("{}", 10 / 3);
     println!("{}", 10 / 2);
     // if you want exact division, you need to use floats, otherwise it will truncate everything
     // after the decimal
     println!("{}", 10.0 / 3.0);
+    // 10/3 has no finite binary (or decimal) expansion, so the division has to round:
+    if let Ok((explanation, result)) = float_inspector::explain("10.0 / 3.0", Precision::F64) {
+        print!("{explanation}{}", float_inspector::inspect(result));
+    }
+    // 'cargo run -- floats [f32] <value or expression> [vs <expression>]' does the same for any
+    // value, e.g. 'floats f32 0.1 + 0.2 vs 0.3' or 'floats 0x0000000000000001' (the smallest
+    // subnormal f64).
+    // integer division truncates towards zero, and % takes the sign of the left-hand side. For
+    // negative numbers that's different from div_euclid/rem_euclid, which keep the remainder
+    // positive. -15 / 3 divides evenly so they agree, but -15 / 4 doesn't:
+    println!("Truncating vs Euclidean division:");
+    for (a, b) in [(-15i128, 3i128), (5, 3), (-15, 4), (15, -4)] {
+        print!(
+            "{}",
+            int_explorer::division_table(
+                int_explorer::Wide::from_i128(a),
+                int_explorer::Wide::from_i128(b)
+            )
+        );
+    }
+    // 'cargo run -- ints <number or expression>' shows which integer types can hold a value, and
+    // what wrapping, checked, saturating and overflowing arithmetic make of it.
 
     /* Booleans */
     // Booleans can be either true or false, and are one byte in length.
@@ -168,6 +224,19 @@ fn main() {
     for month in months {
         println!("{month}");
     }
+    // calendar.rs builds a whole calendar on top of these names, see 'cargo run -- cal'
+    let today: Date = Date::today();
+    println!(
+        "Today is {}, {} {}, {}",
+        today.weekday().name(),
+        months[(today.month() - 1) as usize],
+        today.day(),
+        today.year()
+    );
+    print!(
+        "{}",
+        calendar::render_month(today.year(), today.month(), Some(today), false)
+    );
 
     // You can also initialize an array to contain the same value for each element by specifying
     // the initial value, followed by a semicolon, and then the length of the array in square
@@ -176,6 +245,24 @@ fn main() {
     for val in threesarray {
         println!("{val}");
     }
+
+    // Arrays of arrays make grids, and with const generics the sizes can be part of a type. See
+    // matrix.rs: a Matrix<f64, 2, 3> is a 2x3 matrix, and the compiler checks the dimensions.
+    let a: Matrix<f64, 2, 3> = Matrix::from_rows([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0]]);
+    let b: Matrix<f64, 3, 2> = a.transpose();
+    let product: Matrix<f64, 2, 2> = a * b; // a * a would not compile: 2x3 times 2x3
+    println!("a =\n{a}a transposed =\n{b}a * a transposed =\n{product}");
+    let square: Matrix<f64, 3, 3> =
+        Matrix::from_rows([[2.0, -1.0, 0.0], [-1.0, 2.0, -1.0], [0.0, -1.0, 2.0]]);
+    println!("determinant of\n{square}is {}", square.determinant());
+    if let Some(inverse) = square.inverse() {
+        // square * inverse is the identity, give or take a rounding error or two
+        println!("its inverse is\n{inverse:.3}");
+    }
+    let (reduced, rank) = a.row_reduce();
+    println!("a row reduced (rank {rank}) =\n{reduced}");
+    let column_sums: Vec<f64> = a.columns().map(|column| column.iter().sum()).collect();
+    println!("column sums of a: {column_sums:?}");
     // If an array is directly accessed through user input, it's reccomended to validate the input
     // before trying to access the array index, as an out of bounds index will panic the program.
     // Note: a similar data type is a vector, which is provided by the standard library. It can
@@ -183,3 +270,222 @@ fn main() {
 
     println!("These are all of Rust's build-in data types!");
 }
+
+// For the tools: says what went wrong and exits with status 1, so scripts can tell.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+fn run_tool(args: &[String]) {
+    let input: String = args[1..].join(" ");
+    match args[0].as_str() {
+        "ints" => match int_explorer::report(&input) {
+            Ok(report) => print!("{report}"),
+            Err(err) => fail(format!("could not read '{input}': {err}")),
+        },
+        "floats" => run_floats(&args[1..]),
+        "cal" => run_cal(&args[1..]),
+        "date" => run_date(&args[1..]),
+        "calc" => run_calc(&args[1..]),
+        other => {
+            eprintln!("unknown tool '{other}'");
+            eprintln!("usage: data_types ints <number or expression>");
+            eprintln!("       data_types floats [f32|f64] <value or expression> [vs <expression>]");
+            eprintln!("       data_types calc [--release] [expression]");
+            eprintln!("       data_types cal [-m] [[month] year]");
+            eprintln!("       data_types date <date> [+days | -days | <other date>]");
+            std::process::exit(1);
+        }
+    }
+}
+
+fn run_floats(args: &[String]) {
+    let (precision, rest) = match args.first().map(String::as_str) {
+        Some("f32") => (Precision::F32, &args[1..]),
+        Some("f64") => (Precision::F64, &args[1..]),
+        _ => (Precision::F64, args),
+    };
+    let input: String = rest.join(" ");
+    let result = match input.split_once(" vs ") {
+        Some((left, right)) => float_inspector::compare(left, right, precision),
+        None => match float_inspector::parse(&input) {
+            // a single value gets the full breakdown of its bits
+            Ok(float_inspector::Expr::Literal(text)) => Float::parse(&text, precision)
+                .map(float_inspector::inspect)
+                .ok_or_else(|| format!("'{text}' is not a valid {} value", precision.name())),
+            Ok(_) => float_inspector::explain(&input, precision).map(|(explanation, result)| {
+                format!("{explanation}{}", float_inspector::inspect(result))
+            }),
+            Err(err) => Err(err),
+        },
+    };
+    match result {
+        Ok(text) => print!("{text}"),
+        Err(err) => fail(err),
+    }
+}
+
+// Like the Unix 'cal': no arguments shows this month, a year shows the whole year, and a month
+// and a year show that month. -m starts the weeks on Monday.
+fn run_cal(args: &[String]) {
+    let monday_first: bool = args.iter().any(|arg| arg == "-m");
+    let numbers: Vec<&String> = args.iter().filter(|arg| *arg != "-m").collect();
+    let today: Date = Date::today();
+    let parse = |text: &str| {
+        text.parse::<i32>()
+            .map_err(|_| format!("'{text}' is not a number"))
+    };
+    let parse_year = |text: &str| {
+        let year = parse(text)?;
+        if !(calendar::MIN_YEAR..=calendar::MAX_YEAR).contains(&year) {
+            return Err(calendar::DateError::OutOfRange.to_string());
+        }
+        Ok(year)
+    };
+    let result: Result<String, String> = match numbers.as_slice() {
+        [] => Ok(calendar::render_month(
+            today.year(),
+            today.month(),
+            Some(today),
+            monday_first,
+        )),
+        [year] => {
+            parse_year(year).map(|year| calendar::render_year(year, Some(today), monday_first))
+        }
+        [month, year] => parse(month).and_then(|month| {
+            let year = parse_year(year)?;
+            if !(1..=12).contains(&month) {
+                return Err(calendar::DateError::MonthOutOfRange(month as u32).to_string());
+            }
+            Ok(calendar::render_month(
+                year,
+                month as u32,
+                Some(today),
+                monday_first,
+            ))
+        }),
+        _ => Err("usage: data_types cal [-m] [[month] year]".to_string()),
+    };
+    match result {
+        Ok(text) => print!("{text}"),
+        Err(err) => fail(err),
+    }
+}
+
+// Describes an ISO 8601 date, then either moves it by a number of days or counts the days to a
+// second date.
+fn run_date(args: &[String]) {
+    let date: Date = match args.first().map(|text| Date::parse_iso(text)) {
+        Some(Ok(date)) => date,
+        Some(Err(err)) => fail(err),
+        None => fail("usage: data_types date <date> [+days | -days | <other date>]"),
+    };
+    println!("{date} is a {}", date.weekday().name());
+    println!("  ordinal date: {}", date.to_iso_ordinal());
+    println!("  week date:    {}", date.to_iso_week());
+    println!(
+        "  {} is {}a leap year",
+        date.year(),
+        if calendar::is_leap_year(date.year()) {
+            ""
+        } else {
+            "not "
+        }
+    );
+    let Some(other) = args.get(1) else {
+        return;
+    };
+    // A sign followed by nothing but digits, like "+30" or "-30", is a number of days, however
+    // many digits there are; anything else has to be a second date. (So a signed date in the
+    // basic format, +20240101, counts as days too: a second date with a sign needs its dashes.)
+    let is_offset = (other.starts_with('+') || other.starts_with('-'))
+        && other.len() > 1
+        && other[1..].bytes().all(|b| b.is_ascii_digit());
+    if is_offset {
+        // too many digits for an i64 is too many days for any date
+        let days = other
+            .parse::<i64>()
+            .map_err(|_| calendar::DateError::OutOfRange);
+        match days.and_then(|days| date.add_days(days)) {
+            Ok(moved) => println!(
+                "{date} {other} days is {moved}, a {}",
+                moved.weekday().name()
+            ),
+            Err(err) => fail(err),
+        }
+    } else {
+        match Date::parse_iso(other) {
+            Ok(second) => println!(
+                "from {date} to {second} is {} days",
+                date.days_until(second)
+            ),
+            Err(err) => fail(err),
+        }
+    }
+}
+
+// With an expression, evaluates it once. Without one, reads lines from stdin until EOF or :quit.
+fn run_calc(args: &[String]) {
+    let release: bool = args.iter().any(|arg| arg == "--release");
+    let mode = if release {
+        calc::Mode::Release
+    } else {
+        calc::Mode::Debug
+    };
+    let mut calculator = Calculator::new(mode);
+    let input: String = args
+        .iter()
+        .filter(|arg| *arg != "--release")
+        .cloned()
+        .collect::<Vec<String>>()
+        .join(" ");
+    if !input.is_empty() {
+        match calculator.eval_line(&input) {
+            Ok(outcome) => println!("{outcome}"),
+            Err(err) => fail(err.render(&input).trim_end()),
+        }
+        return;
+    }
+
+    println!("typed calculator, {mode:?} mode. :help lists the commands");
+    let stdin = std::io::stdin();
+    let mut line = String::new();
+    loop {
+        print!(">> ");
+        let _ = std::io::Write::flush(&mut std::io::stdout());
+        line.clear();
+        match stdin.read_line(&mut line) {
+            Ok(0) | Err(_) => break,
+            Ok(_) => {}
+        }
+        let input: &str = line.trim();
+        match input {
+            "" => continue,
+            ":quit" | ":q" => break,
+            ":debug" => calculator.mode = calc::Mode::Debug,
+            ":release" => calculator.mode = calc::Mode::Release,
+            ":vars" => {
+                for variable in calculator.variables() {
+                    println!("{variable}");
+                }
+            }
+            ":help" => {
+                println!("  5 / 3, 10.0f32 / 3.0, 0xff_u8 as i8, (1_000 - 24) % 7   evaluate an expression");
+                println!(
+                    "  let x: u8 = 200                                         keep a value as x"
+                );
+                println!(
+                    "  :debug / :release   overflow panics / wraps (now: {:?})",
+                    calculator.mode
+                );
+                println!("  :vars               list the variables");
+                println!("  :quit               leave");
+            }
+            _ => match calculator.eval_line(input) {
+                Ok(outcome) => println!("{outcome}"),
+                Err(err) => print!("{}", err.render(input)),
+            },
+        }
+    }
+}
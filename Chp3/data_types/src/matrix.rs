@@ -0,0 +1,556 @@
+/* Fixed-size matrices */
+// main.rs shows arrays like [i32; 5], whose length is part of the type. Const generics let a type
+// carry numbers too, so Matrix<f64, 2, 3> is a 2x3 matrix and the compiler checks the dimensions:
+// multiplying a 2x3 by a 3x4 gives a 2x4, and multiplying a 2x3 by a 2x3 doesn't compile at all.
+// The elements are stored as a plain [[T; C]; R], rows first, so there's no allocation anywhere.
+use std::fmt;
+use std::ops::{Add, Index, IndexMut, Mul, Neg, Sub};
+
+// What a matrix element needs for addition, multiplication, transpose and identity.
+pub trait Scalar:
+    Copy + PartialEq + fmt::Display + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+}
+
+// Determinants, inverses and row reduction divide, so they only make sense for floats.
+pub trait FloatScalar:
+    Scalar + Neg<Output = Self> + std::ops::Div<Output = Self> + PartialOrd
+{
+    // while eliminating, anything this small compared to the size of its row and column (times
+    // the size of the matrix) is treated as zero, to absorb rounding errors. It's relative so
+    // that tiny values, like 1e-13 times the identity or the 0.001 in diag(1000, 0.001), aren't
+    // mistaken for a singular matrix.
+    const EPSILON: Self;
+    fn abs(self) -> Self;
+}
+
+macro_rules! impl_scalar {
+    ($($t:ty),*) => {
+        $(
+            impl Scalar for $t {
+                const ZERO: Self = 0 as $t;
+                const ONE: Self = 1 as $t;
+            }
+        )*
+    };
+}
+
+impl_scalar!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize, f32, f64);
+
+impl FloatScalar for f32 {
+    const EPSILON: Self = 1e-6;
+    fn abs(self) -> Self {
+        f32::abs(self)
+    }
+}
+
+impl FloatScalar for f64 {
+    const EPSILON: Self = 1e-12;
+    fn abs(self) -> Self {
+        f64::abs(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix<T, const R: usize, const C: usize> {
+    rows: [[T; C]; R],
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Matrix<T, R, C> {
+    pub fn from_rows(rows: [[T; C]; R]) -> Self {
+        Matrix { rows }
+    }
+
+    pub fn zero() -> Self {
+        Matrix {
+            rows: [[T::ZERO; C]; R],
+        }
+    }
+
+    // Builds a matrix by calling f(row, column) for every element.
+    pub fn from_fn(mut f: impl FnMut(usize, usize) -> T) -> Self {
+        Matrix {
+            rows: std::array::from_fn(|i| std::array::from_fn(|j| f(i, j))),
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        R
+    }
+
+    pub fn column_count(&self) -> usize {
+        C
+    }
+
+    pub fn into_rows(self) -> [[T; C]; R] {
+        self.rows
+    }
+
+    pub fn row(&self, i: usize) -> &[T; C] {
+        &self.rows[i]
+    }
+
+    pub fn column(&self, j: usize) -> [T; R] {
+        std::array::from_fn(|i| self.rows[i][j])
+    }
+
+    // The rows, top to bottom, each as an array.
+    pub fn rows(&self) -> impl Iterator<Item = &[T; C]> {
+        self.rows.iter()
+    }
+
+    // The columns, left to right, each copied out into an array.
+    pub fn columns(&self) -> impl Iterator<Item = [T; R]> + '_ {
+        (0..C).map(|j| self.column(j))
+    }
+
+    // Every element in row order, the same order they're stored in.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.rows.iter().flatten()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.rows.iter_mut().flatten()
+    }
+
+    // Every element along with its (row, column) position.
+    pub fn indexed(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.rows.iter().enumerate().flat_map(|(i, row)| {
+            row.iter()
+                .enumerate()
+                .map(move |(j, value)| ((i, j), value))
+        })
+    }
+
+    pub fn map<U: Scalar>(&self, mut f: impl FnMut(T) -> U) -> Matrix<U, R, C> {
+        Matrix::from_fn(|i, j| f(self.rows[i][j]))
+    }
+
+    // Flips the matrix over its diagonal, so an R x C matrix becomes C x R.
+    pub fn transpose(&self) -> Matrix<T, C, R> {
+        Matrix::from_fn(|i, j| self.rows[j][i])
+    }
+
+    pub fn scale(&self, factor: T) -> Self {
+        self.map(|value| value * factor)
+    }
+}
+
+impl<T: Scalar, const N: usize> Matrix<T, N, N> {
+    pub fn identity() -> Self {
+        Matrix::from_fn(|i, j| if i == j { T::ONE } else { T::ZERO })
+    }
+
+    pub fn diagonal(&self) -> [T; N] {
+        std::array::from_fn(|i| self.rows[i][i])
+    }
+
+    pub fn trace(&self) -> T {
+        self.diagonal()
+            .into_iter()
+            .fold(T::ZERO, |sum, value| sum + value)
+    }
+}
+
+/* Row reduction */
+// Gaussian elimination does all the float-only work below. Row operations (swapping two rows,
+// scaling a row, adding a multiple of one row to another) don't change which vectors solve the
+// system, and the determinant changes in a predictable way with each of them.
+//
+// Rounding leaves values like 1e-17 where there should be a 0, so a pivot that small has to
+// count as zero. What counts as small depends on where it is: in diag(1000, 0.001) the 0.001 is
+// as real as the 1000. Scales works out how big each row and column is, and a value is only
+// treated as zero if it's tiny next to both.
+impl<T: FloatScalar, const R: usize, const C: usize> Matrix<T, R, C> {
+    // Reduced row echelon form, and the rank (the number of pivots). Each column's pivot is the
+    // remaining value that's biggest next to the rest of its row ("scaled partial pivoting"),
+    // which keeps rounding errors from blowing up when a small pivot would be divided into
+    // everything else.
+    pub fn row_reduce(&self) -> (Self, usize) {
+        let scales = Scales::of(&self.rows);
+        let mut m = self.rows;
+        // which row of self each row of m started as, since the scales go with it
+        let mut order: [usize; R] = std::array::from_fn(|i| i);
+        let mut pivot_columns = [0; R];
+        let mut next_row: usize = 0;
+        for column in 0..C {
+            if next_row == R {
+                break;
+            }
+            let best = scales.pivot_row(&m, &order, next_row, column);
+            if scales.is_zero(m[best][column], order[best], column) {
+                continue;
+            }
+            m.swap(next_row, best);
+            order.swap(next_row, best);
+            let pivot = m[next_row][column];
+            for value in m[next_row].iter_mut() {
+                *value = *value / pivot;
+            }
+            let pivot_values: [T; C] = m[next_row];
+            for (i, row) in m.iter_mut().enumerate() {
+                if i == next_row {
+                    continue;
+                }
+                let factor = row[column];
+                for (value, pivot_value) in row.iter_mut().zip(pivot_values) {
+                    *value = *value - factor * pivot_value;
+                }
+            }
+            pivot_columns[next_row] = column;
+            next_row += 1;
+        }
+        // Tidy up the -0.0s and 1e-17s that elimination leaves behind. The rows without a pivot
+        // are all rounding errors, or they'd have one. In a row with a pivot, the value in
+        // column j says how much of the pivot's column makes up column j, so it's measured
+        // against their scales.
+        for (i, row) in m.iter_mut().enumerate() {
+            for (j, value) in row.iter_mut().enumerate() {
+                let noise = i >= next_row
+                    || value.abs()
+                        <= T::EPSILON * scales.size * scales.columns[j]
+                            / scales.columns[pivot_columns[i]];
+                if noise {
+                    *value = T::ZERO;
+                }
+            }
+        }
+        (Matrix { rows: m }, next_row)
+    }
+
+    pub fn rank(&self) -> usize {
+        self.row_reduce().1
+    }
+}
+
+impl<T: FloatScalar, const N: usize> Matrix<T, N, N> {
+    // Eliminates down to an upper triangular matrix, whose determinant is the product of its
+    // diagonal. Every row swap flips the sign.
+    pub fn determinant(&self) -> T {
+        let scales = Scales::of(&self.rows);
+        let mut m = self.rows;
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        let mut det = T::ONE;
+        for column in 0..N {
+            let best = scales.pivot_row(&m, &order, column, column);
+            if scales.is_zero(m[best][column], order[best], column) {
+                return T::ZERO;
+            }
+            if best != column {
+                m.swap(best, column);
+                order.swap(best, column);
+                det = -det;
+            }
+            let pivot = m[column][column];
+            det = det * pivot;
+            let pivot_values: [T; N] = m[column];
+            for row in m.iter_mut().skip(column + 1) {
+                let factor = row[column] / pivot;
+                for (value, pivot_value) in row.iter_mut().zip(pivot_values).skip(column) {
+                    *value = *value - factor * pivot_value;
+                }
+            }
+        }
+        det
+    }
+
+    // Gauss-Jordan elimination on the matrix with the identity next to it: once the left half
+    // has been reduced to the identity, the right half is the inverse. Returns None for a
+    // singular matrix (determinant zero), which has no inverse.
+    pub fn inverse(&self) -> Option<Self> {
+        let scales = Scales::of(&self.rows);
+        let mut left = self.rows;
+        let mut right = Matrix::<T, N, N>::identity().rows;
+        let mut order: [usize; N] = std::array::from_fn(|i| i);
+        for column in 0..N {
+            let best = scales.pivot_row(&left, &order, column, column);
+            if scales.is_zero(left[best][column], order[best], column) {
+                return None;
+            }
+            left.swap(best, column);
+            right.swap(best, column);
+            order.swap(best, column);
+            let pivot = left[column][column];
+            for j in 0..N {
+                left[column][j] = left[column][j] / pivot;
+                right[column][j] = right[column][j] / pivot;
+            }
+            for i in 0..N {
+                if i == column {
+                    continue;
+                }
+                let factor = left[i][column];
+                for j in 0..N {
+                    left[i][j] = left[i][j] - factor * left[column][j];
+                    right[i][j] = right[i][j] - factor * right[column][j];
+                }
+            }
+        }
+        Some(Matrix { rows: right })
+    }
+}
+
+// How big each row and column of a matrix is. rows[i] is the biggest value in row i, and
+// columns[j] the biggest value in column j once every row has been divided by its own rows[i].
+// Dividing like that makes every row and column at most 1 in size, so EPSILON times the size of
+// the matrix is the point below which a value is rounding error. Scaled back up, that's
+// EPSILON * size * rows[i] * columns[j] for the value in row i and column j.
+struct Scales<T, const R: usize, const C: usize> {
+    rows: [T; R],
+    columns: [T; C],
+    size: T,
+}
+
+impl<T: FloatScalar, const R: usize, const C: usize> Scales<T, R, C> {
+    fn of(m: &[[T; C]; R]) -> Self {
+        let biggest = |values: &mut dyn Iterator<Item = T>| {
+            values.fold(T::ZERO, |biggest, value| {
+                if value.abs() > biggest {
+                    value.abs()
+                } else {
+                    biggest
+                }
+            })
+        };
+        let rows: [T; R] = std::array::from_fn(|i| biggest(&mut m[i].iter().copied()));
+        let columns = std::array::from_fn(|j| {
+            // a row of zeros stays zeros, so it has nothing to say about the columns
+            biggest(
+                &mut (0..R)
+                    .filter(|&i| rows[i] != T::ZERO)
+                    .map(|i| m[i][j] / rows[i]),
+            )
+        });
+        let mut size = T::ZERO;
+        for _ in 0..R.max(C) {
+            size = size + T::ONE;
+        }
+        Scales {
+            rows,
+            columns,
+            size,
+        }
+    }
+
+    // Whether value, which is in the row that started as row `row` and in column `column`, is
+    // too small to be anything but rounding error. A row or column of zeros has a scale of 0, so
+    // only an exact 0 counts as zero there.
+    fn is_zero(&self, value: T, row: usize, column: usize) -> bool {
+        value.abs() <= T::EPSILON * self.size * self.rows[row] * self.columns[column]
+    }
+
+    // The row at or below `from` whose value in `column` is the biggest next to the rest of its
+    // row. order[i] is the row of the original matrix that row i of m started as.
+    fn pivot_row(&self, m: &[[T; C]; R], order: &[usize; R], from: usize, column: usize) -> usize {
+        let weight = |i: usize| {
+            let scale = self.rows[order[i]];
+            if scale == T::ZERO {
+                T::ZERO
+            } else {
+                m[i][column].abs() / scale
+            }
+        };
+        (from..R)
+            .max_by(|&a, &b| {
+                weight(a)
+                    .partial_cmp(&weight(b))
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(from)
+    }
+}
+
+/* Operators */
+// (R x C) * (C x K) = (R x K). The shared C is what makes a mismatched product a compile error.
+impl<T: Scalar, const R: usize, const C: usize, const K: usize> Mul<Matrix<T, C, K>>
+    for Matrix<T, R, C>
+{
+    type Output = Matrix<T, R, K>;
+
+    fn mul(self, other: Matrix<T, C, K>) -> Matrix<T, R, K> {
+        Matrix::from_fn(|i, j| {
+            (0..C).fold(T::ZERO, |sum, k| sum + self.rows[i][k] * other.rows[k][j])
+        })
+    }
+}
+
+// Matrix times a column vector, written as a plain array.
+impl<T: Scalar, const R: usize, const C: usize> Mul<[T; C]> for Matrix<T, R, C> {
+    type Output = [T; R];
+
+    fn mul(self, vector: [T; C]) -> [T; R] {
+        std::array::from_fn(|i| (0..C).fold(T::ZERO, |sum, k| sum + self.rows[i][k] * vector[k]))
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Add for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Matrix::from_fn(|i, j| self.rows[i][j] + other.rows[i][j])
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> Sub for Matrix<T, R, C> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Matrix::from_fn(|i, j| self.rows[i][j] - other.rows[i][j])
+    }
+}
+
+// m[(row, column)], which panics when out of bounds just like array indexing does
+impl<T, const R: usize, const C: usize> Index<(usize, usize)> for Matrix<T, R, C> {
+    type Output = T;
+
+    fn index(&self, (i, j): (usize, usize)) -> &T {
+        &self.rows[i][j]
+    }
+}
+
+impl<T, const R: usize, const C: usize> IndexMut<(usize, usize)> for Matrix<T, R, C> {
+    fn index_mut(&mut self, (i, j): (usize, usize)) -> &mut T {
+        &mut self.rows[i][j]
+    }
+}
+
+impl<T: Scalar, const R: usize, const C: usize> From<[[T; C]; R]> for Matrix<T, R, C> {
+    fn from(rows: [[T; C]; R]) -> Self {
+        Matrix { rows }
+    }
+}
+
+// 'for row in &matrix' goes through the rows
+impl<'a, T, const R: usize, const C: usize> IntoIterator for &'a Matrix<T, R, C> {
+    type Item = &'a [T; C];
+    type IntoIter = std::slice::Iter<'a, [T; C]>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.rows.iter()
+    }
+}
+
+/* Display */
+// Prints the matrix in brackets with every column right-aligned. A precision like {:.2} is
+// passed on to each element.
+impl<T: Scalar, const R: usize, const C: usize> fmt::Display for Matrix<T, R, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cells: Vec<Vec<String>> = self
+            .rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|value| match f.precision() {
+                        Some(precision) => format!("{value:.precision$}"),
+                        None => format!("{value}"),
+                    })
+                    .collect()
+            })
+            .collect();
+        let widths: Vec<usize> = (0..C)
+            .map(|j| cells.iter().map(|row| row[j].len()).max().unwrap_or(0))
+            .collect();
+        for (i, row) in cells.iter().enumerate() {
+            let (open, close) = match (R, i) {
+                (1, _) => ('[', ']'),
+                (_, 0) => ('┌', '┐'),
+                (_, i) if i == R - 1 => ('└', '┘'),
+                _ => ('│', '│'),
+            };
+            let padded: Vec<String> = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:>width$}"))
+                .collect();
+            writeln!(f, "{open} {} {close}", padded.join("  "))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn badly_scaled_matrices_are_invertible() {
+        let m: Matrix<f32, 2, 2> = Matrix::from_rows([[1000.0, 0.0], [0.0, 0.001]]);
+        assert!((m.determinant() - 1.0).abs() < 1e-5);
+        let inverse = m.inverse().expect("diag(1000, 0.001) is invertible");
+        assert!((inverse[(0, 0)] - 0.001).abs() < 1e-9);
+        assert!((inverse[(1, 1)] - 1000.0).abs() < 1e-2);
+        assert_eq!(m.rank(), 2);
+
+        let m: Matrix<f64, 2, 2> = Matrix::from_rows([[1e6, 0.0], [0.0, 1e-7]]);
+        assert!(close(m.determinant(), 0.1));
+        assert!(m.inverse().is_some());
+        assert_eq!(m.rank(), 2);
+
+        // badly scaled rows that aren't diagonal
+        let m: Matrix<f64, 2, 2> = Matrix::from_rows([[1e6, 1e6], [1e-7, 2e-7]]);
+        assert!(close(m.determinant(), 0.1));
+        assert_eq!(m.rank(), 2);
+    }
+
+    #[test]
+    fn tiny_identity_is_invertible() {
+        let m = Matrix::<f64, 3, 3>::identity().scale(1e-13);
+        assert!(close(m.determinant(), 1e-39));
+        let inverse = m.inverse().expect("1e-13 * I is invertible");
+        assert!(close(inverse[(2, 2)], 1e13));
+        assert_eq!(m.rank(), 3);
+    }
+
+    #[test]
+    fn singular_matrices() {
+        // the third row is 2 * the second - the first, but only up to rounding
+        let m: Matrix<f64, 3, 3> =
+            Matrix::from_rows([[1.0, 2.0, 3.0], [4.0, 5.0, 6.0], [7.0, 8.0, 9.0]]);
+        assert_eq!(m.determinant(), 0.0);
+        assert_eq!(m.inverse(), None);
+        assert_eq!(m.rank(), 2);
+        let (reduced, _) = m.row_reduce();
+        let expected = [[1.0, 0.0, -1.0], [0.0, 1.0, 2.0], [0.0, 0.0, 0.0]];
+        for ((i, j), value) in reduced.indexed() {
+            assert!(close(*value, expected[i][j]), "{reduced}");
+        }
+        // the row without a pivot is exactly zero, not -0.0 and 1e-16s
+        assert_eq!(reduced.row(2), &[0.0; 3]);
+
+        let zero = Matrix::<f64, 2, 2>::zero();
+        assert_eq!(zero.determinant(), 0.0);
+        assert_eq!(zero.inverse(), None);
+        assert_eq!(zero.rank(), 0);
+    }
+
+    #[test]
+    fn determinant_and_inverse() {
+        let m: Matrix<f64, 3, 3> =
+            Matrix::from_rows([[2.0, -1.0, 0.0], [-1.0, 2.0, -1.0], [0.0, -1.0, 2.0]]);
+        assert!(close(m.determinant(), 4.0));
+        let product = m * m.inverse().unwrap();
+        for ((i, j), value) in product.indexed() {
+            assert!(close(*value, if i == j { 1.0 } else { 0.0 }), "{product}");
+        }
+        // a row swap flips the sign
+        let swapped: Matrix<f64, 2, 2> = Matrix::from_rows([[0.0, 1.0], [1.0, 0.0]]);
+        assert_eq!(swapped.determinant(), -1.0);
+    }
+
+    #[test]
+    fn row_reduce_a_wide_matrix() {
+        let m: Matrix<f64, 2, 3> = Matrix::from_rows([[1.0, 2.0, 3.0], [2.0, 4.0, 7.0]]);
+        let (reduced, rank) = m.row_reduce();
+        assert_eq!(rank, 2);
+        assert_eq!(
+            reduced,
+            Matrix::from_rows([[1.0, 2.0, 0.0], [0.0, 0.0, 1.0]])
+        );
+    }
+}
@@ -0,0 +1,3 @@
+// Extra material for main.rs. units.rs is a typed version of function_with_parameter's
+// value-and-unit-label pair.
+pub mod units;
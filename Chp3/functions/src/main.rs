@@ -1,8 +1,13 @@
+use functions::units::{Acceleration, Length, Mass, Quantity, Time, Unit, Velocity};
+
 fn main() {
     println!("Hello, world!");
     hello_world();
     function_with_parameter(4, 'h');
     function_with_parameter(3, 'm');
+    typed_measurement(Length::new(4.0));
+    typed_measurement(Time::new(3.0));
+    units_of_measure();
     println!(
         "'five_or_fifteen' function with input '8': {}",
         five_or_fifteen(8i32)
@@ -33,6 +38,36 @@ fn hello_world() {
 fn function_with_parameter(value: i32, unit_label: char) {
     println!("The measurement is: {value}{unit_label}");
 }
+// The same thing with the unit in the type (see units.rs). The function is generic over the unit,
+// so it accepts any Quantity, and the value can't be separated from its unit by accident.
+fn typed_measurement<U: Unit>(quantity: Quantity<U>) {
+    println!("The measurement is: {quantity}");
+}
+
+fn units_of_measure() {
+    let distance: Length = Length::new(100.0);
+    let time: Time = Time::new(9.58);
+    // metres / seconds is a velocity, and the compiler works that out from the types
+    let speed: Velocity = distance / time;
+    println!("{distance} in {time} is {speed:.2}");
+    let laps: Length = [distance, distance, Length::new(200.0)].into_iter().sum();
+    println!("Total distance: {laps}");
+
+    let mass: Mass = Mass::new(70.0);
+    let gravity: Acceleration = Acceleration::new(9.81);
+    let weight = mass * gravity; // a Quantity<Newton>
+    let work = weight * Length::new(3.0); // lifting it 3 metres takes this many joules
+    println!("{mass} weighs {weight:.1}, and lifting it 3 m takes {work:.0}");
+    println!("...which in {time} is {:.1}", work / time);
+    let ratio = Length::new(42.195) / Length::new(21.0975); // same units cancel out
+    println!("A marathon is {ratio} half marathons");
+
+    // None of these compile:
+    //   distance + time                   error[E0308]: mismatched types
+    //   let wrong: Velocity = time / distance;
+    //                                     error[E0277]: `Second: UnitDiv<Metre>` is not satisfied
+    //   mass * time                       error[E0277]: `Kilogram: UnitMul<Second>` is not satisfied
+}
 // You can use 'return' to explicitly exit a function and return a value, but most functions return
 // the last expression implicitly.
 fn five_or_fifteen(num: i32) -> i32 {
@@ -0,0 +1,296 @@
+/* Units of measure */
+// function_with_parameter takes a value and a unit label as a char, so nothing stops a caller
+// from passing 4 and 's' where they meant 4 and 'm'. Here the unit is part of the type instead:
+// a Quantity<Metre> and a Quantity<Second> are different types, so adding them doesn't compile.
+//
+// The unit is a "phantom" type parameter. It's never stored (PhantomData takes up no space), so a
+// Quantity is just an f64 at runtime; all the checking happens in the compiler.
+//
+// Multiplying and dividing give new units, so metres divided by seconds is a Quantity of
+// MetrePerSecond. Which combinations make sense is spelled out with traits (UnitMul and UnitDiv)
+// below, and anything not listed there is a compile error.
+use std::cmp::Ordering;
+use std::fmt;
+use std::iter::Sum;
+use std::marker::PhantomData;
+use std::ops::{Add, AddAssign, Div, Mul, Neg, Sub, SubAssign};
+
+// Every unit is an empty type with a symbol to print.
+pub trait Unit {
+    const SYMBOL: &'static str;
+}
+
+// Self * Rhs gives Output
+pub trait UnitMul<Rhs: Unit>: Unit {
+    type Output: Unit;
+}
+
+// Self / Rhs gives Output
+pub trait UnitDiv<Rhs: Unit>: Unit {
+    type Output: Unit;
+}
+
+macro_rules! units {
+    ($($name:ident => $symbol:expr),* $(,)?) => {$(
+        #[derive(Debug)]
+        pub enum $name {}
+
+        impl Unit for $name {
+            const SYMBOL: &'static str = $symbol;
+        }
+    )*};
+}
+
+units! {
+    // no unit at all, e.g. metres divided by metres
+    Scalar => "",
+    Metre => "m",
+    Second => "s",
+    Kilogram => "kg",
+    SquareMetre => "m²",
+    CubicMetre => "m³",
+    MetrePerSecond => "m/s",
+    MetrePerSecondSquared => "m/s²",
+    Newton => "N",
+    Joule => "J",
+    Watt => "W",
+    Hertz => "Hz",
+}
+
+// 'A * B = C' declares both orders of the multiplication, plus the two divisions that undo it
+// (C / B = A and C / A = B).
+macro_rules! relations {
+    ($($a:ident * $b:ident = $c:ident;)*) => {$(
+        relations!(@mul $a, $b, $c);
+        relations!(@div $c, $b, $a);
+        relations!(@other_order $a, $b, $c);
+    )*};
+    (@mul $a:ident, $b:ident, $c:ident) => {
+        impl UnitMul<$b> for $a {
+            type Output = $c;
+        }
+    };
+    (@div $c:ident, $b:ident, $a:ident) => {
+        impl UnitDiv<$b> for $c {
+            type Output = $a;
+        }
+    };
+    // m * m = m² only needs the one multiplication and the one division
+    (@other_order Metre, Metre, $c:ident) => {};
+    (@other_order $a:ident, $b:ident, $c:ident) => {
+        relations!(@mul $b, $a, $c);
+        relations!(@div $c, $a, $b);
+    };
+}
+
+relations! {
+    Metre * Metre = SquareMetre;
+    SquareMetre * Metre = CubicMetre;
+    MetrePerSecond * Second = Metre;
+    MetrePerSecondSquared * Second = MetrePerSecond;
+    Kilogram * MetrePerSecondSquared = Newton;
+    Newton * Metre = Joule;
+    Watt * Second = Joule;
+    Hertz * Second = Scalar;
+}
+
+// Anything divided by itself has no unit left. (None of the relations above divide a unit by
+// itself, so this doesn't overlap with them.)
+impl<U: Unit> UnitDiv<U> for U {
+    type Output = Scalar;
+}
+
+// An f64 tagged with a unit. repr(transparent) guarantees the same layout as a bare f64.
+//
+// The examples below are doc comments so that 'cargo test' checks they still don't compile. The
+// last one uses the same names and does compile, so the first two can't be failing over a typo.
+// (The error codes are only checked by a nightly rustdoc.)
+/// Only quantities of the same unit can be added:
+/// ```compile_fail,E0308
+/// use functions::units::{Length, Time};
+/// let _ = Length::new(3.0) + Time::new(2.0);
+/// ```
+/// and only units with a relation can be multiplied (there's no kilogram-second):
+/// ```compile_fail,E0277
+/// use functions::units::{Mass, Time};
+/// let _ = Mass::new(3.0) * Time::new(2.0);
+/// ```
+/// while the ones that do have one give the new unit:
+/// ```
+/// use functions::units::{Length, Time, Velocity};
+/// let speed: Velocity = Length::new(3.0) / Time::new(2.0);
+/// assert_eq!(speed.value(), 1.5);
+/// ```
+#[repr(transparent)]
+pub struct Quantity<U: Unit> {
+    value: f64,
+    unit: PhantomData<U>,
+}
+
+pub type Length = Quantity<Metre>;
+pub type Time = Quantity<Second>;
+pub type Mass = Quantity<Kilogram>;
+pub type Area = Quantity<SquareMetre>;
+pub type Volume = Quantity<CubicMetre>;
+pub type Velocity = Quantity<MetrePerSecond>;
+pub type Acceleration = Quantity<MetrePerSecondSquared>;
+pub type Force = Quantity<Newton>;
+pub type Energy = Quantity<Joule>;
+pub type Power = Quantity<Watt>;
+pub type Frequency = Quantity<Hertz>;
+
+impl<U: Unit> Quantity<U> {
+    pub const fn new(value: f64) -> Self {
+        Quantity {
+            value,
+            unit: PhantomData,
+        }
+    }
+
+    // The number without its unit. For a Quantity<Scalar> that's all there is; for anything else
+    // it's where the type checking ends, so use it at the edges (printing, storing).
+    pub const fn value(self) -> f64 {
+        self.value
+    }
+
+    pub fn symbol(self) -> &'static str {
+        U::SYMBOL
+    }
+
+    pub fn abs(self) -> Self {
+        Quantity::new(self.value.abs())
+    }
+
+    pub fn min(self, other: Self) -> Self {
+        Quantity::new(self.value.min(other.value))
+    }
+
+    pub fn max(self, other: Self) -> Self {
+        Quantity::new(self.value.max(other.value))
+    }
+}
+
+// Clone, Copy and the comparisons are written out by hand because #[derive] would also require U
+// to implement them, and the unit types are never even created.
+impl<U: Unit> Clone for Quantity<U> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<U: Unit> Copy for Quantity<U> {}
+
+impl<U: Unit> PartialEq for Quantity<U> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<U: Unit> PartialOrd for Quantity<U> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        self.value.partial_cmp(&other.value)
+    }
+}
+
+impl<U: Unit> fmt::Debug for Quantity<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Quantity({:?} {})", self.value, U::SYMBOL)
+    }
+}
+
+// Prints the value and then the symbol, like "9.81 m/s²". Formatting options such as {:.2} apply
+// to the number.
+impl<U: Unit> fmt::Display for Quantity<U> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.value, f)?;
+        if !U::SYMBOL.is_empty() {
+            write!(f, " {}", U::SYMBOL)?;
+        }
+        Ok(())
+    }
+}
+
+/* Same-unit arithmetic */
+impl<U: Unit> Add for Quantity<U> {
+    type Output = Self;
+
+    fn add(self, other: Self) -> Self {
+        Quantity::new(self.value + other.value)
+    }
+}
+
+impl<U: Unit> Sub for Quantity<U> {
+    type Output = Self;
+
+    fn sub(self, other: Self) -> Self {
+        Quantity::new(self.value - other.value)
+    }
+}
+
+impl<U: Unit> AddAssign for Quantity<U> {
+    fn add_assign(&mut self, other: Self) {
+        self.value += other.value;
+    }
+}
+
+impl<U: Unit> SubAssign for Quantity<U> {
+    fn sub_assign(&mut self, other: Self) {
+        self.value -= other.value;
+    }
+}
+
+impl<U: Unit> Neg for Quantity<U> {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Quantity::new(-self.value)
+    }
+}
+
+impl<U: Unit> Sum for Quantity<U> {
+    fn sum<I: Iterator<Item = Self>>(iter: I) -> Self {
+        iter.fold(Quantity::new(0.0), |total, quantity| total + quantity)
+    }
+}
+
+/* Scaling by a plain number */
+impl<U: Unit> Mul<f64> for Quantity<U> {
+    type Output = Self;
+
+    fn mul(self, factor: f64) -> Self {
+        Quantity::new(self.value * factor)
+    }
+}
+
+impl<U: Unit> Mul<Quantity<U>> for f64 {
+    type Output = Quantity<U>;
+
+    fn mul(self, quantity: Quantity<U>) -> Quantity<U> {
+        Quantity::new(self * quantity.value)
+    }
+}
+
+impl<U: Unit> Div<f64> for Quantity<U> {
+    type Output = Self;
+
+    fn div(self, divisor: f64) -> Self {
+        Quantity::new(self.value / divisor)
+    }
+}
+
+/* Derived units */
+impl<A: UnitMul<B>, B: Unit> Mul<Quantity<B>> for Quantity<A> {
+    type Output = Quantity<A::Output>;
+
+    fn mul(self, other: Quantity<B>) -> Quantity<A::Output> {
+        Quantity::new(self.value * other.value)
+    }
+}
+
+impl<A: UnitDiv<B>, B: Unit> Div<Quantity<B>> for Quantity<A> {
+    type Output = Quantity<A::Output>;
+
+    fn div(self, other: Quantity<B>) -> Quantity<A::Output> {
+        Quantity::new(self.value / other.value)
+    }
+}
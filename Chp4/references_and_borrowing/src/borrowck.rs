@@ -0,0 +1,446 @@
+/* A toy borrow checker */
+// main.rs ends with the rules: at any given time a value can have either ONE mutable reference or
+// any number of immutable references, and references must always point to valid values. rustc
+// enforces them with its borrow checker, and this module is a small version of one, for a tiny
+// Rust-like language with just enough in it to break the rules:
+//   * functions with parameters and a return type: fn change(s: &mut String) -> usize { ... }
+//   * let (with mut and an optional type), assignment, and assignment through a reference (*r = 5)
+//   * &x and &mut x, *r, blocks with a value at the end, and return
+//   * calls, String::from("..."), the methods len, push_str and clone, and println!
+//   * the types i32, usize, String, str, &T and &mut T
+//
+// Checking goes through the same stages as looplang in chapter 3:
+//   source text -> lexer (tokens) -> parser (AST) -> checker (diagnostics)
+// The checker goes through each function in the order it would run, keeping track of which
+// variables are in scope, which have been moved, and which loans (borrows) are out on each one,
+// and reports problems the way rustc does: an error code, the spans involved, and notes.
+//
+// Scopes are lexical: a variable lives until the closing brace of its block. Loans are not: as
+// in rustc since 2018 (non-lexical lifetimes), a loan only lasts until the last time a reference
+// holding it is used. Mode::Lexical turns that off, so a loan lasts until the reference itself
+// goes out of scope, which is how rustc worked before, and rejects more programs.
+pub mod ast;
+pub mod checker;
+pub mod lexer;
+pub mod parser;
+
+use std::fmt;
+
+pub use checker::Mode;
+
+// Byte offsets into the source, start inclusive and end exclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    // From the start of self to the end of other.
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start, other.end.max(self.end))
+    }
+}
+
+// A place in the source to point at. The primary label (^^^) marks where the error happened,
+// secondary labels (---) mark the other things involved, like the first borrow.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+    pub primary: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    // rustc's error code, like "E0499"; None for syntax errors
+    pub code: Option<&'static str>,
+    pub message: String,
+    pub labels: Vec<Label>,
+    // printed after the source lines, each starting with "note:" or "help:"
+    pub notes: Vec<String>,
+}
+
+impl Diagnostic {
+    pub fn new(code: Option<&'static str>, message: impl Into<String>) -> Self {
+        Diagnostic {
+            code,
+            message: message.into(),
+            labels: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    pub fn primary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: true,
+        });
+        self
+    }
+
+    pub fn secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.labels.push(Label {
+            span,
+            message: message.into(),
+            primary: false,
+        });
+        self
+    }
+
+    pub fn note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(format!("note: {}", note.into()));
+        self
+    }
+
+    pub fn help(mut self, help: impl Into<String>) -> Self {
+        self.notes.push(format!("help: {}", help.into()));
+        self
+    }
+
+    // The diagnostic laid out like rustc's:
+    //   error[E0499]: cannot borrow `s` as mutable more than once at a time
+    //    --> programs/double_mut.toy:4:14
+    //     |
+    //   3 |     let r1 = &mut s;
+    //     |              ------ first mutable borrow occurs here
+    //   ...
+    pub fn render(&self, source: &str, path: &str) -> String {
+        let mut out = match self.code {
+            Some(code) => format!("error[{code}]: {}\n", self.message),
+            None => format!("error: {}\n", self.message),
+        };
+        // errors that aren't about any one place, like a missing main, are just the notes
+        if self.labels.is_empty() {
+            for note in &self.notes {
+                out.push_str(&format!("  = {note}\n"));
+            }
+            return out;
+        }
+        let mut labels: Vec<(usize, usize, usize, &Label)> = self
+            .labels
+            .iter()
+            .map(|label| {
+                let (line, col) = line_col(source, label.span.start);
+                // a label only underlines the first line of a span that covers several
+                let end = label.span.end.min(line_end(source, label.span.start));
+                let width = source[label.span.start..end].chars().count().max(1);
+                (line, col, width, label)
+            })
+            .collect();
+        labels.sort_by_key(|&(line, col, _, label)| (line, col, !label.primary));
+        let gutter = labels
+            .last()
+            .map_or(1, |&(line, ..)| line.to_string().len());
+        let pad = " ".repeat(gutter);
+        if let Some(&(line, col, ..)) = labels
+            .iter()
+            .find(|(.., label)| label.primary)
+            .or(labels.first())
+        {
+            out.push_str(&format!("{pad}--> {path}:{line}:{col}\n"));
+        }
+        out.push_str(&format!("{pad} |\n"));
+        let mut previous_line: Option<usize> = None;
+        for (index, &(line, ..)) in labels.iter().enumerate() {
+            if previous_line == Some(line) {
+                continue;
+            }
+            if previous_line.is_some_and(|previous| line > previous + 1) {
+                out.push_str("...\n");
+            }
+            previous_line = Some(line);
+            let text = source.lines().nth(line - 1).unwrap_or("");
+            out.push_str(&format!("{line:<gutter$} | {text}\n"));
+            for &(_, col, width, label) in labels[index..].iter().take_while(|l| l.0 == line) {
+                let mark = if label.primary { "^" } else { "-" };
+                let row = format!(
+                    "{}{} {}",
+                    " ".repeat(col - 1),
+                    mark.repeat(width),
+                    label.message
+                );
+                out.push_str(&format!("{pad} | {}\n", row.trim_end()));
+            }
+        }
+        if !self.notes.is_empty() {
+            out.push_str(&format!("{pad} |\n"));
+            for note in &self.notes {
+                out.push_str(&format!("{pad} = {note}\n"));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.code {
+            Some(code) => write!(f, "error[{code}]: {}", self.message),
+            None => write!(f, "error: {}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+// 1-based line and column (in chars) of a byte offset.
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset.min(source.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn line_end(source: &str, offset: usize) -> usize {
+    source[offset..]
+        .find('\n')
+        .map_or(source.len(), |i| offset + i)
+}
+
+// Parses and checks a program. A syntax error stops everything, like in rustc; borrow errors are
+// all collected.
+pub fn check(source: &str, mode: Mode) -> Vec<Diagnostic> {
+    let program = match lexer::tokenize(source).and_then(parser::parse) {
+        Ok(program) => program,
+        Err(error) => return vec![error],
+    };
+    checker::check_program(&program, mode)
+}
+
+// Every diagnostic rendered, followed by a summary line like rustc's.
+pub fn report(source: &str, path: &str, diagnostics: &[Diagnostic]) -> String {
+    let mut out = String::new();
+    for diagnostic in diagnostics {
+        out.push_str(&diagnostic.render(source, path));
+        out.push('\n');
+    }
+    match diagnostics.len() {
+        0 => out.push_str(&format!("{path}: no errors\n")),
+        1 => out.push_str("error: aborting due to 1 previous error\n"),
+        n => out.push_str(&format!("error: aborting due to {n} previous errors\n")),
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Each diagnostic's code, and each of its labels as line, column, the source text it
+    // underlines, and whether it's the primary one.
+    type Summary = Vec<(Option<&'static str>, Vec<(usize, usize, String, bool)>)>;
+
+    fn summarise(source: &str, mode: Mode) -> Summary {
+        check(source, mode)
+            .into_iter()
+            .map(|diagnostic| {
+                let labels = diagnostic
+                    .labels
+                    .iter()
+                    .map(|label| {
+                        let (line, col) = line_col(source, label.span.start);
+                        let text = source[label.span.start..label.span.end].to_string();
+                        (line, col, text, label.primary)
+                    })
+                    .collect();
+                (diagnostic.code, labels)
+            })
+            .collect()
+    }
+
+    // The same diagnostics in both modes, for programs non-lexical lifetimes make no difference to.
+    // line, column, underlined text, primary
+    type Mark<'a> = (usize, usize, &'a str, bool);
+
+    fn assert_both_modes(source: &str, expected: &[(&'static str, &[Mark])]) {
+        let expected: Summary = expected
+            .iter()
+            .map(|(code, labels)| {
+                let labels = labels
+                    .iter()
+                    .map(|&(line, col, text, primary)| (line, col, text.to_string(), primary))
+                    .collect();
+                (Some(*code), labels)
+            })
+            .collect();
+        assert_eq!(summarise(source, Mode::NonLexical), expected);
+        assert_eq!(summarise(source, Mode::Lexical), expected);
+    }
+
+    #[test]
+    fn valid_programs() {
+        for source in [
+            include_str!("../programs/valid.toy"),
+            include_str!("../programs/nll.toy"),
+        ] {
+            assert_eq!(check(source, Mode::NonLexical), []);
+        }
+        assert_eq!(report("", "empty.toy", &[]), "empty.toy: no errors\n");
+    }
+
+    #[test]
+    fn nll_only_passes_without_lexical_lifetimes() {
+        let source = include_str!("../programs/nll.toy");
+        assert_eq!(
+            summarise(source, Mode::Lexical),
+            [(
+                Some("E0502"),
+                vec![
+                    (7, 14, "&s".to_string(), false),
+                    (11, 14, "&mut s".to_string(), true),
+                    (14, 1, "}".to_string(), false),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn dangle() {
+        assert_both_modes(
+            include_str!("../programs/dangle.toy"),
+            &[
+                ("E0106", &[(10, 16, "&", true)]),
+                ("E0515", &[(23, 13, "&s", false), (24, 5, "r", true)]),
+            ],
+        );
+    }
+
+    #[test]
+    fn dangling_scope() {
+        assert_both_modes(
+            include_str!("../programs/dangling_scope.toy"),
+            &[
+                ("E0597", &[(6, 9, "&x", true), (7, 5, "}", false)]),
+                (
+                    "E0597",
+                    &[
+                        (12, 13, "&y", true),
+                        (13, 5, "}", false),
+                        (14, 26, "p", false),
+                    ],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn double_mut() {
+        assert_both_modes(
+            include_str!("../programs/double_mut.toy"),
+            &[(
+                "E0499",
+                &[
+                    (6, 14, "&mut s", false),
+                    (7, 14, "&mut s", true),
+                    (9, 24, "r1", false),
+                ],
+            )],
+        );
+    }
+
+    #[test]
+    fn immutable_change() {
+        assert_both_modes(
+            include_str!("../programs/immutable_change.toy"),
+            &[(
+                "E0596",
+                &[(9, 5, "some_string", true), (8, 24, "&String", false)],
+            )],
+        );
+    }
+
+    #[test]
+    fn mixed() {
+        assert_both_modes(
+            include_str!("../programs/mixed.toy"),
+            &[(
+                "E0502",
+                &[
+                    (6, 14, "&s", false),
+                    (8, 14, "&mut s", true),
+                    (10, 32, "r1", false),
+                ],
+            )],
+        );
+    }
+
+    #[test]
+    fn moves() {
+        assert_both_modes(
+            include_str!("../programs/moves.toy"),
+            &[
+                (
+                    "E0382",
+                    &[
+                        (4, 9, "s1", false),
+                        (5, 14, "s1", false),
+                        (6, 28, "s1", true),
+                    ],
+                ),
+                (
+                    "E0505",
+                    &[
+                        (9, 13, "&t", false),
+                        (10, 13, "t", true),
+                        (11, 20, "r", false),
+                    ],
+                ),
+                (
+                    "E0503",
+                    &[
+                        (14, 13, "&mut n", false),
+                        (15, 16, "n", true),
+                        (16, 6, "m", false),
+                    ],
+                ),
+            ],
+        );
+    }
+
+    #[test]
+    fn unknown_names() {
+        let source = "fn main() {\n    let a = 1;\n    undefined_fn(a);\n    b;\n}\n";
+        assert_both_modes(
+            source,
+            &[
+                // just the name, not the whole call
+                ("E0425", &[(3, 5, "undefined_fn", true)]),
+                ("E0425", &[(4, 5, "b", true)]),
+            ],
+        );
+    }
+
+    #[test]
+    fn deep_nesting_is_a_diagnostic() {
+        let too_deep = |source: &str| {
+            let diagnostics = check(source, Mode::NonLexical);
+            assert_eq!(diagnostics.len(), 1);
+            assert_eq!(diagnostics[0].message, "this is nested too deeply");
+        };
+        let blocks = 20_000;
+        too_deep(&format!(
+            "fn main() {{ {}{} }}",
+            "{ ".repeat(blocks),
+            "} ".repeat(blocks)
+        ));
+        too_deep(&format!(
+            "fn f(x: i32) -> i32 {{ x }}\nfn main() {{ {}1{}; }}",
+            "f(".repeat(blocks),
+            ")".repeat(blocks)
+        ));
+        too_deep(&format!("fn f(x: {}i32) {{ }}", "&".repeat(blocks)));
+        // right up to the limit is fine
+        let deepest = format!(
+            "fn main() {{ {}{} }}",
+            "{ ".repeat(parser::MAX_NESTING),
+            "} ".repeat(parser::MAX_NESTING)
+        );
+        assert_eq!(check(&deepest, Mode::Lexical), []);
+    }
+}
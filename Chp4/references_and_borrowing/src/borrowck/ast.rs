@@ -0,0 +1,153 @@
+// The syntax tree the parser builds. Every node keeps its span, so the checker can point at it.
+use super::Span;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Int,
+    // only ever behind a reference, as the type of string literals
+    Str,
+    String,
+    Unit,
+    Ref(Box<Type>),
+    RefMut(Box<Type>),
+    // the type of something that already had an error, so it doesn't cause more
+    Unknown,
+}
+
+impl Type {
+    // Copy types are copied when they're used; everything else is moved.
+    pub fn is_copy(&self) -> bool {
+        matches!(self, Type::Int | Type::Unit | Type::Ref(_) | Type::Unknown)
+    }
+
+    pub fn is_reference(&self) -> bool {
+        matches!(self, Type::Ref(_) | Type::RefMut(_))
+    }
+
+    // Whether a value of type found can go where expected is wanted. On top of the types being
+    // equal, &String and &mut T coerce to &str and &T, like in Rust.
+    pub fn accepts(&self, found: &Type) -> bool {
+        match (self, found) {
+            (Type::Unknown, _) | (_, Type::Unknown) => true,
+            (Type::Ref(expected), Type::Ref(found) | Type::RefMut(found)) => {
+                expected.accepts(found) || (**expected == Type::Str && **found == Type::String)
+            }
+            (Type::RefMut(expected), Type::RefMut(found)) => expected.accepts(found),
+            _ => self == found,
+        }
+    }
+}
+
+impl fmt::Display for Type {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Type::Int => write!(f, "i32"),
+            Type::Str => write!(f, "str"),
+            Type::String => write!(f, "String"),
+            Type::Unit => write!(f, "()"),
+            Type::Ref(inner) => write!(f, "&{inner}"),
+            Type::RefMut(inner) => write!(f, "&mut {inner}"),
+            Type::Unknown => write!(f, "_"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Program {
+    pub functions: Vec<Function>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Function {
+    pub name: String,
+    pub name_span: Span,
+    pub params: Vec<Param>,
+    // the return type and its span; None for ()
+    pub ret: Option<(Type, Span)>,
+    pub body: Block,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Param {
+    pub name: String,
+    pub span: Span,
+    pub mutable: bool,
+    pub ty: Type,
+    pub ty_span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Block {
+    pub stmts: Vec<Stmt>,
+    // the expression at the end without a semicolon, which is the block's value
+    pub tail: Option<Box<Expr>>,
+    pub span: Span,
+    // the closing brace, where the block's variables are dropped
+    pub end: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Stmt {
+    Let {
+        name: String,
+        span: Span,
+        mutable: bool,
+        ty: Option<Type>,
+        init: Expr,
+    },
+    // x = value
+    Assign {
+        name: String,
+        span: Span,
+        value: Expr,
+    },
+    // *r = value
+    AssignDeref {
+        name: String,
+        span: Span,
+        value: Expr,
+    },
+    Expr(Expr),
+    Return(Option<Expr>, Span),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Expr {
+    pub kind: ExprKind,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExprKind {
+    Int(i64),
+    Str(String),
+    Var(String),
+    // &x or &mut x
+    Borrow {
+        mutable: bool,
+        name: String,
+        name_span: Span,
+    },
+    // *r
+    Deref(String),
+    // f(args) or String::from(args)
+    Call {
+        name: String,
+        name_span: Span,
+        args: Vec<Expr>,
+    },
+    // x.method(args)
+    Method {
+        receiver: String,
+        receiver_span: Span,
+        method: String,
+        args: Vec<Expr>,
+    },
+    // println!(args)
+    Macro {
+        name: String,
+        args: Vec<Expr>,
+    },
+    Block(Block),
+}
@@ -0,0 +1,1120 @@
+// The checker walks each function's body in the order it would run. Along the way it keeps:
+//   * the variables in scope, with their type, whether they're mut, and whether they've been moved
+//   * the loans: one for every &x or &mut x (including the hidden ones, like the &s that
+//     s.len() takes), with the variable borrowed and where the borrow happened
+//   * for every variable holding a reference, which loans that reference depends on
+// Whenever a variable is borrowed, used, moved, assigned or dropped, the loans on it are checked
+// against the rules.
+//
+// To know whether a loan is still needed, the checker has to know whether the references holding
+// it are used again later. So each function is walked twice: the first walk only writes down
+// where every variable is used, and the second does the checking with that list in hand.
+use super::ast::{Block, Expr, ExprKind, Function, Program, Stmt, Type};
+use super::{Diagnostic, Span};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    // a loan lasts until the last use of a reference holding it (rustc since 2018)
+    #[default]
+    NonLexical,
+    // a loan lasts until every reference holding it has gone out of scope (rustc before 2018)
+    Lexical,
+}
+
+struct Signature {
+    params: Vec<Type>,
+    ret: Type,
+}
+
+pub fn check_program(program: &Program, mode: Mode) -> Vec<Diagnostic> {
+    let mut diagnostics: Vec<Diagnostic> = Vec::new();
+    let mut signatures: HashMap<&str, Signature> = HashMap::new();
+    let mut first_definition: HashMap<&str, Span> = HashMap::new();
+    let mut to_check: Vec<&Function> = Vec::new();
+    for function in &program.functions {
+        if let Some(&previous) = first_definition.get(function.name.as_str()) {
+            diagnostics.push(
+                Diagnostic::new(
+                    Some("E0428"),
+                    format!("the name `{}` is defined multiple times", function.name),
+                )
+                .secondary(
+                    previous,
+                    format!("previous definition of the value `{}` here", function.name),
+                )
+                .primary(
+                    function.name_span,
+                    format!("`{}` redefined here", function.name),
+                ),
+            );
+            continue;
+        }
+        first_definition.insert(&function.name, function.name_span);
+        signatures.insert(
+            &function.name,
+            Signature {
+                params: function.params.iter().map(|p| p.ty.clone()).collect(),
+                ret: function
+                    .ret
+                    .as_ref()
+                    .map_or(Type::Unit, |(ty, _)| ty.clone()),
+            },
+        );
+        match check_signature(function) {
+            Some(error) => diagnostics.push(error),
+            None => to_check.push(function),
+        }
+    }
+    if !signatures.contains_key("main") {
+        diagnostics.push(
+            Diagnostic::new(Some("E0601"), "`main` function not found")
+                .note("consider adding a `main` function: fn main() { ... }"),
+        );
+    }
+    // like rustc, a function whose signature is wrong doesn't get its body checked
+    for function in to_check {
+        diagnostics.extend(FunctionChecker::check(function, &signatures, mode));
+    }
+    diagnostics
+}
+
+// A function that returns a reference has to say what it's borrowed from. Without lifetime
+// parameters (chapter 10), that can only be its one reference parameter.
+fn check_signature(function: &Function) -> Option<Diagnostic> {
+    let (ret, ret_span) = function.ret.as_ref()?;
+    if !ret.is_reference() {
+        return None;
+    }
+    let references: Vec<&str> = function
+        .params
+        .iter()
+        .filter(|p| p.ty.is_reference())
+        .map(|p| p.name.as_str())
+        .collect();
+    let ampersand = Span::new(ret_span.start, ret_span.start + 1);
+    let error = Diagnostic::new(Some("E0106"), "missing lifetime specifier")
+        .primary(ampersand, "expected named lifetime parameter");
+    match references.as_slice() {
+        [_] => None,
+        [] => {
+            let mut owned = ret;
+            while let Type::Ref(inner) | Type::RefMut(inner) = owned {
+                owned = inner;
+            }
+            Some(
+                error
+                    .help(
+                        "this function's return type contains a borrowed value, but there is no \
+                         value for it to be borrowed from",
+                    )
+                    .help(format!(
+                        "instead, you are more likely to want to return an owned value: `{owned}`"
+                    )),
+            )
+        }
+        many => {
+            let names: Vec<String> = many.iter().map(|name| format!("`{name}`")).collect();
+            Some(
+                error
+                    .help(format!(
+                        "this function's return type contains a borrowed value, but the \
+                         signature does not say whether it is borrowed from {}",
+                        names.join(" or ")
+                    ))
+                    .note("this language has no lifetime parameters, so return an owned value"),
+            )
+        }
+    }
+}
+
+/* Checking a function */
+type VarId = usize;
+type LoanId = usize;
+
+struct Var {
+    name: String,
+    mutable: bool,
+    ty: Type,
+    span: Span,
+    // index into FunctionChecker::scopes
+    scope: usize,
+    in_scope: bool,
+    // where it was moved from, if it has been
+    moved: Option<Span>,
+    // the loans the reference in this variable depends on
+    loans: Vec<LoanId>,
+}
+
+struct Loan {
+    place: VarId,
+    mutable: bool,
+    span: Span,
+    // already reported as not living long enough
+    reported: bool,
+}
+
+struct Scope {
+    vars: Vec<VarId>,
+    end: Span,
+}
+
+// The result of an expression: its type, and the loans it depends on if it's a reference.
+struct Value {
+    ty: Type,
+    loans: Vec<LoanId>,
+}
+
+impl Value {
+    fn of(ty: Type) -> Self {
+        Value {
+            ty,
+            loans: Vec::new(),
+        }
+    }
+}
+
+// Why a loan is still needed at the current point, if it is.
+enum Liveness {
+    Dead,
+    // held by a value that's still being worked on, like an argument of a call in progress
+    Temporary,
+    // a reference holding it is used again here
+    UsedLater(Span),
+    // (lexical mode) a reference holding it is still in scope
+    InScope(VarId),
+}
+
+struct FunctionChecker<'a> {
+    signatures: &'a HashMap<&'a str, Signature>,
+    function: &'a Function,
+    mode: Mode,
+    vars: Vec<Var>,
+    scopes: Vec<Scope>,
+    loans: Vec<Loan>,
+    temps: Vec<LoanId>,
+    // counts up with every use and borrow, so that uses can be put in order
+    point: usize,
+    // every use of every variable: the point and the span (from the first walk)
+    uses: Vec<Vec<(usize, Span)>>,
+    recording: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> FunctionChecker<'a> {
+    fn check(
+        function: &'a Function,
+        signatures: &'a HashMap<&'a str, Signature>,
+        mode: Mode,
+    ) -> Vec<Diagnostic> {
+        let mut first = FunctionChecker::new(function, signatures, mode, Vec::new(), true);
+        first.walk();
+        let mut second = FunctionChecker::new(function, signatures, mode, first.uses, false);
+        second.walk();
+        second.diagnostics
+    }
+
+    fn new(
+        function: &'a Function,
+        signatures: &'a HashMap<&'a str, Signature>,
+        mode: Mode,
+        uses: Vec<Vec<(usize, Span)>>,
+        recording: bool,
+    ) -> Self {
+        FunctionChecker {
+            signatures,
+            function,
+            mode,
+            vars: Vec::new(),
+            scopes: Vec::new(),
+            loans: Vec::new(),
+            temps: Vec::new(),
+            point: 0,
+            uses,
+            recording,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn error(&mut self, diagnostic: Diagnostic) {
+        if !self.recording {
+            self.diagnostics.push(diagnostic);
+        }
+    }
+
+    fn walk(&mut self) {
+        let function = self.function;
+        let body = &function.body;
+        // the parameters live in a scope around the body, so they're dropped after its locals
+        self.scopes.push(Scope {
+            vars: Vec::new(),
+            end: body.end,
+        });
+        for param in &function.params {
+            self.declare(
+                &param.name,
+                param.mutable,
+                param.ty.clone(),
+                param.span,
+                Vec::new(),
+            );
+        }
+        self.scopes.push(Scope {
+            vars: Vec::new(),
+            end: body.end,
+        });
+        for stmt in &body.stmts {
+            self.stmt(stmt);
+        }
+        let ends_in_return = matches!(body.stmts.last(), Some(Stmt::Return(..)));
+        match &body.tail {
+            Some(tail) => {
+                let value = self.expr_expecting(tail, &self.return_type());
+                self.check_return(value, tail);
+            }
+            None if self.return_type() != Type::Unit && !ends_in_return => {
+                let ret = self.return_type();
+                let ret_span = function.ret.as_ref().map_or(function.name_span, |r| r.1);
+                self.error(
+                    Diagnostic::new(Some("E0308"), "mismatched types")
+                        .secondary(
+                            function.name_span,
+                            "implicitly returns `()` as its body has no tail or `return` \
+                             expression",
+                        )
+                        .primary(ret_span, format!("expected `{ret}`, found `()`")),
+                );
+            }
+            None => {}
+        }
+        self.pop_scope();
+        self.pop_scope();
+    }
+
+    fn return_type(&self) -> Type {
+        self.signatures[self.function.name.as_str()].ret.clone()
+    }
+
+    /* Variables */
+    fn declare(&mut self, name: &str, mutable: bool, ty: Type, span: Span, loans: Vec<LoanId>) {
+        let id = self.vars.len();
+        self.vars.push(Var {
+            name: name.to_string(),
+            mutable,
+            ty,
+            span,
+            scope: self.scopes.len() - 1,
+            in_scope: true,
+            moved: None,
+            loans,
+        });
+        if self.recording {
+            self.uses.push(Vec::new());
+        }
+        self.scopes
+            .last_mut()
+            .expect("there's always a scope")
+            .vars
+            .push(id);
+    }
+
+    fn lookup(&mut self, name: &str, span: Span) -> Option<VarId> {
+        let found = self
+            .scopes
+            .iter()
+            .rev()
+            .flat_map(|scope| scope.vars.iter().rev())
+            .copied()
+            .find(|&id| self.vars[id].name == name);
+        if found.is_none() {
+            self.error(
+                Diagnostic::new(
+                    Some("E0425"),
+                    format!("cannot find value `{name}` in this scope"),
+                )
+                .primary(span, "not found in this scope"),
+            );
+        }
+        found
+    }
+
+    fn record_use(&mut self, var: VarId, span: Span) {
+        self.point += 1;
+        if self.recording {
+            self.uses[var].push((self.point, span));
+        }
+    }
+
+    fn next_use(&self, var: VarId) -> Option<Span> {
+        self.uses[var]
+            .iter()
+            .find(|&&(point, _)| point > self.point)
+            .map(|&(_, span)| span)
+    }
+
+    fn pop_scope(&mut self) {
+        let scope = self.scopes.pop().expect("pushed by the caller");
+        for &var in &scope.vars {
+            self.vars[var].in_scope = false;
+        }
+        // dropping a variable ends its life, so no loan on it may still be needed
+        for &var in scope.vars.iter().rev() {
+            for loan in 0..self.loans.len() {
+                if self.loans[loan].place != var || self.loans[loan].reported {
+                    continue;
+                }
+                let liveness = self.liveness(loan);
+                if matches!(liveness, Liveness::Dead) {
+                    continue;
+                }
+                self.loans[loan].reported = true;
+                let name = &self.vars[var].name;
+                let error =
+                    Diagnostic::new(Some("E0597"), format!("`{name}` does not live long enough"))
+                        .primary(
+                            self.loans[loan].span,
+                            "borrowed value does not live long enough",
+                        )
+                        .secondary(
+                            scope.end,
+                            format!("`{name}` dropped here while still borrowed"),
+                        );
+                let error = self.explain(error, liveness, "");
+                self.error(error);
+            }
+        }
+    }
+
+    /* Loans */
+    fn liveness(&self, loan: LoanId) -> Liveness {
+        if self.temps.contains(&loan) {
+            return Liveness::Temporary;
+        }
+        let mut in_scope: Option<VarId> = None;
+        for (id, var) in self.vars.iter().enumerate() {
+            if !var.in_scope || !var.loans.contains(&loan) {
+                continue;
+            }
+            if let Some(span) = self.next_use(id) {
+                return Liveness::UsedLater(span);
+            }
+            if self.mode == Mode::Lexical {
+                in_scope = Some(id);
+            }
+        }
+        in_scope.map_or(Liveness::Dead, Liveness::InScope)
+    }
+
+    // A loan on place that's still needed and clashes with a new access: any loan if the access
+    // is exclusive (a mutable borrow, an assignment or a move), only mutable loans otherwise.
+    fn conflict(&self, place: VarId, exclusive: bool) -> Option<(LoanId, Liveness)> {
+        self.loans
+            .iter()
+            .enumerate()
+            .filter(|(_, loan)| loan.place == place && (exclusive || loan.mutable))
+            .map(|(id, _)| (id, self.liveness(id)))
+            .find(|(_, liveness)| !matches!(liveness, Liveness::Dead))
+    }
+
+    // Adds a label saying why the loan is still needed.
+    fn explain(&self, error: Diagnostic, liveness: Liveness, what: &str) -> Diagnostic {
+        match liveness {
+            Liveness::UsedLater(span) => {
+                error.secondary(span, format!("{what}borrow later used here"))
+            }
+            Liveness::InScope(var) => {
+                let var = &self.vars[var];
+                error
+                    .secondary(
+                        self.scopes[var.scope].end,
+                        format!(
+                            "{what}borrow ends here, when `{}` goes out of scope",
+                            var.name
+                        ),
+                    )
+                    .note("with lexical lifetimes, a borrow lasts as long as the reference")
+            }
+            Liveness::Temporary | Liveness::Dead => error,
+        }
+    }
+
+    fn moved_error(&mut self, var: VarId, span: Span, borrow: bool) {
+        let var = &self.vars[var];
+        let Some(moved) = var.moved else { return };
+        let (message, label) = if borrow {
+            ("borrow of moved value", "value borrowed here after move")
+        } else {
+            ("use of moved value", "value used here after move")
+        };
+        let error = Diagnostic::new(Some("E0382"), format!("{message}: `{}`", var.name))
+            .secondary(
+                var.span,
+                format!(
+                    "move occurs because `{}` has type `{}`, which does not implement the `Copy` \
+                     trait",
+                    var.name, var.ty
+                ),
+            )
+            .secondary(moved, "value moved here")
+            .primary(span, label);
+        self.error(error);
+    }
+
+    // &x or &mut x, including the hidden borrows made by method calls and println!.
+    fn borrow(&mut self, var: VarId, span: Span, mutable: bool) -> LoanId {
+        self.record_use(var, span);
+        if self.vars[var].moved.is_some() {
+            self.moved_error(var, span, true);
+        } else if mutable && !self.vars[var].mutable {
+            let name = &self.vars[var].name;
+            let error = Diagnostic::new(
+                Some("E0596"),
+                format!("cannot borrow `{name}` as mutable, as it is not declared as mutable"),
+            )
+            .primary(span, "cannot borrow as mutable")
+            .help(format!(
+                "consider changing this to be mutable: `mut {name}`"
+            ));
+            self.error(error);
+        } else if let Some((loan, liveness)) = self.conflict(var, mutable) {
+            let name = &self.vars[var].name;
+            let old = &self.loans[loan];
+            let error = match (old.mutable, mutable) {
+                (true, true) => Diagnostic::new(
+                    Some("E0499"),
+                    format!("cannot borrow `{name}` as mutable more than once at a time"),
+                )
+                .secondary(old.span, "first mutable borrow occurs here")
+                .primary(span, "second mutable borrow occurs here"),
+                (false, _) => Diagnostic::new(
+                    Some("E0502"),
+                    format!(
+                        "cannot borrow `{name}` as mutable because it is also borrowed as \
+                         immutable"
+                    ),
+                )
+                .secondary(old.span, "immutable borrow occurs here")
+                .primary(span, "mutable borrow occurs here"),
+                (true, false) => Diagnostic::new(
+                    Some("E0502"),
+                    format!(
+                        "cannot borrow `{name}` as immutable because it is also borrowed as \
+                         mutable"
+                    ),
+                )
+                .secondary(old.span, "mutable borrow occurs here")
+                .primary(span, "immutable borrow occurs here"),
+            };
+            let what = match (old.mutable, mutable) {
+                (true, true) => "first ",
+                (false, _) => "immutable ",
+                (true, false) => "mutable ",
+            };
+            let error = self.explain(error, liveness, what);
+            self.error(error);
+        }
+        self.point += 1;
+        self.loans.push(Loan {
+            place: var,
+            mutable,
+            span,
+            reported: false,
+        });
+        self.loans.len() - 1
+    }
+
+    // Using a variable's value: a copy for Copy types, a move for everything else.
+    fn use_var(&mut self, var: VarId, span: Span) -> Value {
+        self.record_use(var, span);
+        let ty = self.vars[var].ty.clone();
+        if self.vars[var].moved.is_some() {
+            self.moved_error(var, span, false);
+        } else if ty.is_copy() {
+            if let Some((loan, liveness)) = self.conflict(var, false) {
+                let name = &self.vars[var].name;
+                let error = Diagnostic::new(
+                    Some("E0503"),
+                    format!("cannot use `{name}` because it was mutably borrowed"),
+                )
+                .secondary(self.loans[loan].span, format!("`{name}` is borrowed here"))
+                .primary(span, format!("use of borrowed `{name}`"));
+                let error = self.explain(error, liveness, "");
+                self.error(error);
+            }
+        } else {
+            if let Some((loan, liveness)) = self.conflict(var, true) {
+                let name = &self.vars[var].name;
+                let error = Diagnostic::new(
+                    Some("E0505"),
+                    format!("cannot move out of `{name}` because it is borrowed"),
+                )
+                .secondary(
+                    self.loans[loan].span,
+                    format!("borrow of `{name}` occurs here"),
+                )
+                .primary(span, format!("move out of `{name}` occurs here"));
+                let error = self.explain(error, liveness, "");
+                self.error(error);
+            }
+            self.vars[var].moved = Some(span);
+        }
+        Value {
+            ty,
+            loans: self.vars[var].loans.clone(),
+        }
+    }
+
+    // Using a &mut reference without giving it away, like passing it to a function: Rust
+    // reborrows it instead of moving it.
+    fn reborrow(&mut self, var: VarId, span: Span) -> Value {
+        self.record_use(var, span);
+        if self.vars[var].moved.is_some() {
+            self.moved_error(var, span, true);
+        }
+        Value {
+            ty: self.vars[var].ty.clone(),
+            loans: self.vars[var].loans.clone(),
+        }
+    }
+
+    /* Statements */
+    fn stmt(&mut self, stmt: &Stmt) {
+        match stmt {
+            Stmt::Let {
+                name,
+                span,
+                mutable,
+                ty,
+                init,
+            } => {
+                let value = match ty {
+                    Some(ty) => self.expr_expecting(init, ty),
+                    None => self.expr(init),
+                };
+                let ty = ty.clone().unwrap_or(value.ty);
+                self.declare(name, *mutable, ty, *span, value.loans);
+            }
+            Stmt::Assign { name, span, value } => {
+                let value = self.expr(value);
+                let Some(var) = self.lookup(name, *span) else {
+                    return;
+                };
+                self.point += 1;
+                if !self.vars[var].mutable {
+                    let error = Diagnostic::new(
+                        Some("E0384"),
+                        format!("cannot assign twice to immutable variable `{name}`"),
+                    )
+                    .secondary(self.vars[var].span, format!("first assignment to `{name}`"))
+                    .primary(*span, "cannot assign twice to immutable variable")
+                    .help(format!(
+                        "consider making this binding mutable: `mut {name}`"
+                    ));
+                    self.error(error);
+                } else if let Some((loan, liveness)) = self.conflict(var, true) {
+                    let error = Diagnostic::new(
+                        Some("E0506"),
+                        format!("cannot assign to `{name}` because it is borrowed"),
+                    )
+                    .secondary(self.loans[loan].span, format!("`{name}` is borrowed here"))
+                    .primary(
+                        *span,
+                        format!("`{name}` is assigned to here but it was already borrowed"),
+                    );
+                    let error = self.explain(error, liveness, "");
+                    self.error(error);
+                }
+                let expected = self.vars[var].ty.clone();
+                self.type_check(&expected, &value.ty, *span, None);
+                // the old value (and whatever it borrowed) is gone; the variable is usable again
+                self.vars[var].loans = value.loans;
+                self.vars[var].moved = None;
+            }
+            Stmt::AssignDeref { name, span, value } => {
+                let value = self.expr(value);
+                let name_span = Span::new(span.start + 1, span.end);
+                let Some(var) = self.lookup(name, name_span) else {
+                    return;
+                };
+                self.reborrow(var, name_span);
+                match self.vars[var].ty.clone() {
+                    Type::RefMut(inner) => self.type_check(&inner, &value.ty, *span, None),
+                    Type::Ref(_) => {
+                        let error = Diagnostic::new(
+                            Some("E0594"),
+                            format!("cannot assign to `*{name}`, which is behind a `&` reference"),
+                        )
+                        .primary(
+                            *span,
+                            format!(
+                                "`{name}` is a `&` reference, so the data it refers to cannot be \
+                                 written"
+                            ),
+                        );
+                        self.error(error);
+                    }
+                    Type::Unknown => {}
+                    other => self.not_a_reference(&other, *span),
+                }
+            }
+            Stmt::Expr(expr) => {
+                self.expr(expr);
+            }
+            Stmt::Return(value, span) => {
+                match value {
+                    Some(expr) => {
+                        let value = self.expr_expecting(expr, &self.return_type());
+                        self.check_return(value, expr);
+                    }
+                    None => {
+                        let ret = self.return_type();
+                        self.type_check(&ret, &Type::Unit, *span, None);
+                    }
+                };
+            }
+        }
+    }
+
+    // A returned reference can't point at anything the function owns: its locals, and its
+    // parameters that aren't references themselves, are all dropped when it returns.
+    fn check_return(&mut self, value: Value, expr: &Expr) {
+        let Some(&loan) = value.loans.first() else {
+            return;
+        };
+        let loan = &self.loans[loan];
+        let name = &self.vars[loan.place].name;
+        let error = if matches!(expr.kind, ExprKind::Borrow { .. }) {
+            Diagnostic::new(
+                Some("E0515"),
+                format!("cannot return reference to local variable `{name}`"),
+            )
+            .primary(
+                expr.span,
+                "returns a reference to data owned by the current function",
+            )
+        } else {
+            Diagnostic::new(
+                Some("E0515"),
+                format!("cannot return value referencing local variable `{name}`"),
+            )
+            .secondary(loan.span, format!("`{name}` is borrowed here"))
+            .primary(
+                expr.span,
+                "returns a value referencing data owned by the current function",
+            )
+        };
+        self.error(error);
+        // reported once here rather than again as "does not live long enough"
+        for loan in value.loans {
+            self.loans[loan].reported = true;
+        }
+    }
+
+    /* Expressions */
+    fn type_check(&mut self, expected: &Type, found: &Type, span: Span, help: Option<String>) {
+        if expected.accepts(found) {
+            return;
+        }
+        let mut error = Diagnostic::new(Some("E0308"), "mismatched types")
+            .primary(span, format!("expected `{expected}`, found `{found}`"));
+        if let Some(help) = help {
+            error = error.help(help);
+        }
+        self.error(error);
+    }
+
+    fn not_a_reference(&mut self, ty: &Type, span: Span) {
+        let error = Diagnostic::new(Some("E0614"), format!("type `{ty}` cannot be dereferenced"))
+            .primary(span, "can't be dereferenced");
+        self.error(error);
+    }
+
+    // An expression whose value goes somewhere with a known type: a parameter, a variable with a
+    // type, or the return value.
+    fn expr_expecting(&mut self, expr: &Expr, expected: &Type) -> Value {
+        if let ExprKind::Var(name) = &expr.kind {
+            if let Some(var) = self.lookup(name, expr.span) {
+                let ty = self.vars[var].ty.clone();
+                if !expected.accepts(&ty) {
+                    // a type error, so don't move the variable as well
+                    let help = match expected {
+                        Type::Ref(inner) if inner.accepts(&ty) => {
+                            Some(format!("consider borrowing here: `&{name}`"))
+                        }
+                        Type::RefMut(inner) if inner.accepts(&ty) => {
+                            Some(format!("consider mutably borrowing here: `&mut {name}`"))
+                        }
+                        _ => None,
+                    };
+                    self.record_use(var, expr.span);
+                    self.type_check(expected, &ty, expr.span, help);
+                    return Value::of(Type::Unknown);
+                }
+                if matches!(ty, Type::RefMut(_)) && expected.is_reference() {
+                    return self.reborrow(var, expr.span);
+                }
+                return self.use_var(var, expr.span);
+            }
+            return Value::of(Type::Unknown);
+        }
+        let value = self.expr(expr);
+        self.type_check(expected, &value.ty, expr.span, None);
+        value
+    }
+
+    fn expr(&mut self, expr: &Expr) -> Value {
+        match &expr.kind {
+            ExprKind::Int(_) => Value::of(Type::Int),
+            ExprKind::Str(_) => Value::of(Type::Ref(Box::new(Type::Str))),
+            ExprKind::Var(name) => match self.lookup(name, expr.span) {
+                Some(var) => self.use_var(var, expr.span),
+                None => Value::of(Type::Unknown),
+            },
+            ExprKind::Borrow {
+                mutable,
+                name,
+                name_span,
+            } => {
+                let Some(var) = self.lookup(name, *name_span) else {
+                    return Value::of(Type::Unknown);
+                };
+                let loan = self.borrow(var, expr.span, *mutable);
+                let inner = Box::new(self.vars[var].ty.clone());
+                let mut loans = vec![loan];
+                // a reference to a reference depends on whatever that one borrowed, too
+                loans.extend(self.vars[var].loans.iter().copied());
+                let ty = if *mutable {
+                    Type::RefMut(inner)
+                } else {
+                    Type::Ref(inner)
+                };
+                Value { ty, loans }
+            }
+            ExprKind::Deref(name) => {
+                let name_span = Span::new(expr.span.start + 1, expr.span.end);
+                let Some(var) = self.lookup(name, name_span) else {
+                    return Value::of(Type::Unknown);
+                };
+                self.reborrow(var, name_span);
+                match self.vars[var].ty.clone() {
+                    Type::Ref(inner) | Type::RefMut(inner) if inner.is_copy() => Value::of(*inner),
+                    Type::Ref(inner) | Type::RefMut(inner) => {
+                        let kind = match self.vars[var].ty {
+                            Type::Ref(_) => "shared",
+                            _ => "mutable",
+                        };
+                        let error = Diagnostic::new(
+                            Some("E0507"),
+                            format!(
+                                "cannot move out of `*{name}` which is behind a {kind} reference"
+                            ),
+                        )
+                        .primary(
+                            expr.span,
+                            format!(
+                                "move occurs because `*{name}` has type `{inner}`, which does not \
+                                 implement the `Copy` trait"
+                            ),
+                        )
+                        .help("clone the value instead, or borrow it again with a reference");
+                        self.error(error);
+                        Value::of(*inner)
+                    }
+                    Type::Unknown => Value::of(Type::Unknown),
+                    other => {
+                        self.not_a_reference(&other, expr.span);
+                        Value::of(Type::Unknown)
+                    }
+                }
+            }
+            ExprKind::Call {
+                name,
+                name_span,
+                args,
+            } => self.call(name, *name_span, args, expr.span),
+            ExprKind::Method {
+                receiver,
+                receiver_span,
+                method,
+                args,
+            } => self.method(receiver, *receiver_span, method, args, expr.span),
+            ExprKind::Macro { name, args } => self.macro_call(name, args, expr.span),
+            ExprKind::Block(block) => self.block(block),
+        }
+    }
+
+    fn block(&mut self, block: &Block) -> Value {
+        self.scopes.push(Scope {
+            vars: Vec::new(),
+            end: block.end,
+        });
+        for stmt in &block.stmts {
+            self.stmt(stmt);
+        }
+        let value = match &block.tail {
+            Some(tail) => self.expr(tail),
+            None => Value::of(Type::Unit),
+        };
+        // the block's value is on its way out, so what it borrows has to outlive the block
+        let held = self.temps.len();
+        self.temps.extend(value.loans.iter().copied());
+        self.pop_scope();
+        self.temps.truncate(held);
+        value
+    }
+
+    fn call(&mut self, name: &str, name_span: Span, args: &[Expr], span: Span) -> Value {
+        let (params, ret) = match (name, self.signatures.get(name)) {
+            ("String::from", _) => (vec![Type::Ref(Box::new(Type::Str))], Type::String),
+            (_, Some(signature)) => (signature.params.clone(), signature.ret.clone()),
+            (_, None) => {
+                let error = Diagnostic::new(
+                    Some("E0425"),
+                    format!("cannot find function `{name}` in this scope"),
+                )
+                .primary(name_span, "not found in this scope");
+                self.error(error);
+                for arg in args {
+                    self.expr(arg);
+                }
+                return Value::of(Type::Unknown);
+            }
+        };
+        if params.len() != args.len() {
+            let plural = |n: usize| if n == 1 { "" } else { "s" };
+            let error = Diagnostic::new(
+                Some("E0061"),
+                format!(
+                    "this function takes {} argument{} but {} argument{} supplied",
+                    params.len(),
+                    plural(params.len()),
+                    args.len(),
+                    if args.len() == 1 { " was" } else { "s were" }
+                ),
+            )
+            .primary(
+                span,
+                format!("expected {} argument{}", params.len(), plural(params.len())),
+            );
+            self.error(error);
+        }
+        // the arguments' loans last until the call is over
+        let held = self.temps.len();
+        let mut borrowed: Vec<LoanId> = Vec::new();
+        for (i, arg) in args.iter().enumerate() {
+            let value = match params.get(i) {
+                Some(param) => self.expr_expecting(arg, param),
+                None => self.expr(arg),
+            };
+            if params.get(i).is_some_and(Type::is_reference) {
+                borrowed.extend(value.loans.iter().copied());
+            }
+            self.temps.extend(value.loans);
+        }
+        self.temps.truncate(held);
+        self.point += 1;
+        // a returned reference is borrowed from the reference arguments (check_signature
+        // makes sure there's exactly one)
+        let loans = if ret.is_reference() {
+            borrowed
+        } else {
+            Vec::new()
+        };
+        Value { ty: ret, loans }
+    }
+
+    fn method(
+        &mut self,
+        receiver: &str,
+        receiver_span: Span,
+        method: &str,
+        args: &[Expr],
+        span: Span,
+    ) -> Value {
+        let Some(var) = self.lookup(receiver, receiver_span) else {
+            for arg in args {
+                self.expr(arg);
+            }
+            return Value::of(Type::Unknown);
+        };
+        // whether the method takes &mut self, its parameters and what it returns
+        let (mutable, params, ret) = match method {
+            "len" => (false, vec![], Type::Int),
+            "clone" => (false, vec![], Type::String),
+            "push_str" => (true, vec![Type::Ref(Box::new(Type::Str))], Type::Unit),
+            _ => (false, vec![], Type::Unknown),
+        };
+        let held = self.temps.len();
+        match self.vars[var].ty.clone() {
+            _ if ret == Type::Unknown => {
+                let ty = self.vars[var].ty.clone();
+                let error = Diagnostic::new(
+                    Some("E0599"),
+                    format!("no method named `{method}` found for `{ty}` in this scope"),
+                )
+                .primary(span, "method not found")
+                .note("the methods are len, clone and push_str, on String");
+                self.error(error);
+                self.record_use(var, receiver_span);
+            }
+            // s.len() borrows s for the length of the call
+            Type::String => {
+                let loan = self.borrow(var, receiver_span, mutable);
+                self.temps.push(loan);
+            }
+            Type::Ref(_) if mutable => {
+                self.reborrow(var, receiver_span);
+                let mut error = Diagnostic::new(
+                    Some("E0596"),
+                    format!(
+                        "cannot borrow `*{receiver}` as mutable, as it is behind a `&` reference"
+                    ),
+                )
+                .primary(
+                    receiver_span,
+                    format!(
+                        "`{receiver}` is a `&` reference, so the data it refers to cannot be \
+                         borrowed as mutable"
+                    ),
+                );
+                let param = self.function.params.iter().find(|p| p.name == receiver);
+                if let Some(param) = param {
+                    error = error.secondary(
+                        param.ty_span,
+                        "help: consider changing this to be a mutable reference: `&mut String`",
+                    );
+                }
+                self.error(error);
+            }
+            // through a reference, the reference is used but nothing new is borrowed
+            Type::Ref(_) | Type::RefMut(_) | Type::Unknown => {
+                self.reborrow(var, receiver_span);
+            }
+            other => {
+                let error = Diagnostic::new(
+                    Some("E0599"),
+                    format!("no method named `{method}` found for `{other}` in this scope"),
+                )
+                .primary(span, "method not found");
+                self.error(error);
+                self.record_use(var, receiver_span);
+            }
+        }
+        for (i, arg) in args.iter().enumerate() {
+            let value = match params.get(i) {
+                Some(param) => self.expr_expecting(arg, param),
+                None => self.expr(arg),
+            };
+            self.temps.extend(value.loans);
+        }
+        if args.len() != params.len() && ret != Type::Unknown {
+            let error = Diagnostic::new(
+                Some("E0061"),
+                format!(
+                    "this method takes {} arguments but {} were supplied",
+                    params.len(),
+                    args.len()
+                ),
+            )
+            .primary(span, format!("expected {} arguments", params.len()));
+            self.error(error);
+        }
+        self.temps.truncate(held);
+        self.point += 1;
+        Value::of(ret)
+    }
+
+    // println!("...", args): every argument, and every {name} in the format string, is borrowed
+    // immutably for the length of the call.
+    fn macro_call(&mut self, name: &str, args: &[Expr], span: Span) -> Value {
+        if name != "println" {
+            let error = Diagnostic::new(None, format!("cannot find macro `{name}` in this scope"))
+                .primary(span, "the only macro is println!");
+            self.error(error);
+            return Value::of(Type::Unit);
+        }
+        let Some(Expr {
+            kind: ExprKind::Str(format),
+            span: format_span,
+        }) = args.first()
+        else {
+            let error = Diagnostic::new(None, "format argument must be a string literal")
+                .primary(span, "expected a string literal first");
+            self.error(error);
+            return Value::of(Type::Unit);
+        };
+        let (positional, captured) = placeholders(format);
+        if positional != args.len() - 1 {
+            let error = Diagnostic::new(
+                None,
+                format!(
+                    "{positional} positional argument{} in format string, but there {}",
+                    if positional == 1 { "" } else { "s" },
+                    match args.len() - 1 {
+                        1 => "is 1 argument".to_string(),
+                        n => format!("are {n} arguments"),
+                    }
+                ),
+            )
+            .primary(*format_span, "");
+            self.error(error);
+        }
+        let held = self.temps.len();
+        let named = captured.iter().map(|name| (name.as_str(), *format_span));
+        let positional = args[1..].iter().map(|arg| match &arg.kind {
+            ExprKind::Var(name) => (name.as_str(), arg.span),
+            _ => ("", arg.span),
+        });
+        let printed: Vec<(&str, Span)> = positional.chain(named).collect();
+        for (i, (name, arg_span)) in printed.into_iter().enumerate() {
+            if name.is_empty() {
+                let value = self.expr(&args[i + 1]);
+                self.temps.extend(value.loans);
+                continue;
+            }
+            let Some(var) = self.lookup(name, arg_span) else {
+                continue;
+            };
+            match self.vars[var].ty {
+                Type::String => {
+                    let loan = self.borrow(var, arg_span, false);
+                    self.temps.push(loan);
+                }
+                Type::RefMut(_) => {
+                    self.reborrow(var, arg_span);
+                }
+                _ => {
+                    self.use_var(var, arg_span);
+                }
+            }
+        }
+        self.temps.truncate(held);
+        self.point += 1;
+        Value::of(Type::Unit)
+    }
+}
+
+// The number of {} placeholders in a format string, and the names in the {name} ones.
+fn placeholders(format: &str) -> (usize, Vec<String>) {
+    let mut positional = 0;
+    let mut named: Vec<String> = Vec::new();
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let inside: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                let name = inside.split(':').next().unwrap_or("");
+                if name.is_empty() {
+                    positional += 1;
+                } else {
+                    named.push(name.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    (positional, named)
+}
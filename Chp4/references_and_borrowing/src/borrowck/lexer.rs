@@ -0,0 +1,159 @@
+// The lexer turns the source text into a flat list of tokens, each with its span.
+use super::{Diagnostic, Span};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenKind {
+    Ident(String),
+    Int(i64),
+    Str(String),
+    // keywords
+    Fn,
+    Let,
+    Mut,
+    Return,
+    // punctuation
+    LParen,
+    RParen,
+    LBrace,
+    RBrace,
+    Comma,
+    Semi,
+    Colon,
+    PathSep,
+    Arrow,
+    Amp,
+    Star,
+    Assign,
+    Dot,
+    Bang,
+    Eof,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub kind: TokenKind,
+    pub span: Span,
+}
+
+pub fn tokenize(source: &str) -> Result<Vec<Token>, Diagnostic> {
+    let chars: Vec<(usize, char)> = source.char_indices().collect();
+    // the byte offset of chars[i], or the end of the source
+    let offset = |i: usize| chars.get(i).map_or(source.len(), |&(at, _)| at);
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut i: usize = 0;
+    while i < chars.len() {
+        let (start, c) = chars[i];
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c == '/' && next == Some('/') {
+            while i < chars.len() && chars[i].1 != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        let kind = if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                i += 1;
+            }
+            let word = &source[start..offset(i)];
+            // back onto the last char of the token, like the other branches
+            i -= 1;
+            match word {
+                "fn" => TokenKind::Fn,
+                "let" => TokenKind::Let,
+                "mut" => TokenKind::Mut,
+                "return" => TokenKind::Return,
+                _ => TokenKind::Ident(word.to_string()),
+            }
+        } else if c.is_ascii_digit() {
+            let mut digits = String::new();
+            while i < chars.len() && (chars[i].1.is_ascii_digit() || chars[i].1 == '_') {
+                if chars[i].1 != '_' {
+                    digits.push(chars[i].1);
+                }
+                i += 1;
+            }
+            i -= 1;
+            let span = Span::new(start, offset(i + 1));
+            match digits.parse() {
+                Ok(value) => TokenKind::Int(value),
+                Err(_) => {
+                    return Err(Diagnostic::new(None, "integer literal is too large")
+                        .primary(span, "doesn't fit in an i64"))
+                }
+            }
+        } else if c == '"' {
+            let mut text = String::new();
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => {
+                        return Err(Diagnostic::new(None, "unterminated double quote string")
+                            .primary(Span::new(start, source.len()), ""))
+                    }
+                    Some(&(_, '"')) => break,
+                    Some(&(_, '\\')) => {
+                        i += 1;
+                        match chars.get(i).map(|&(_, c)| c) {
+                            Some('n') => text.push('\n'),
+                            Some('t') => text.push('\t'),
+                            Some(c @ ('\\' | '"')) => text.push(c),
+                            _ => {
+                                let at = offset(i - 1);
+                                return Err(Diagnostic::new(None, "unknown character escape")
+                                    .primary(Span::new(at, offset(i + 1)), ""));
+                            }
+                        }
+                    }
+                    Some(&(_, c)) => text.push(c),
+                }
+                i += 1;
+            }
+            TokenKind::Str(text)
+        } else {
+            let two = (c, next);
+            match two {
+                (':', Some(':')) => {
+                    i += 1;
+                    TokenKind::PathSep
+                }
+                ('-', Some('>')) => {
+                    i += 1;
+                    TokenKind::Arrow
+                }
+                ('(', _) => TokenKind::LParen,
+                (')', _) => TokenKind::RParen,
+                ('{', _) => TokenKind::LBrace,
+                ('}', _) => TokenKind::RBrace,
+                (',', _) => TokenKind::Comma,
+                (';', _) => TokenKind::Semi,
+                (':', _) => TokenKind::Colon,
+                ('&', _) => TokenKind::Amp,
+                ('*', _) => TokenKind::Star,
+                ('=', _) => TokenKind::Assign,
+                ('.', _) => TokenKind::Dot,
+                ('!', _) => TokenKind::Bang,
+                _ => {
+                    let span = Span::new(start, offset(i + 1));
+                    return Err(
+                        Diagnostic::new(None, format!("unknown start of token: {c}"))
+                            .primary(span, ""),
+                    );
+                }
+            }
+        };
+        i += 1;
+        tokens.push(Token {
+            kind,
+            span: Span::new(start, offset(i)),
+        });
+    }
+    tokens.push(Token {
+        kind: TokenKind::Eof,
+        span: Span::new(source.len(), source.len()),
+    });
+    Ok(tokens)
+}
@@ -0,0 +1,398 @@
+// A recursive descent parser: one method per piece of grammar.
+//   program    = function*
+//   function   = "fn" IDENT "(" (param ("," param)*)? ")" ("->" type)? block
+//   param      = "mut"? IDENT ":" type
+//   type       = "&" "mut"? type | "i32" | "usize" | "String" | "str"
+//   block      = "{" stmt* expr? "}"
+//   stmt       = "let" "mut"? IDENT (":" type)? "=" expr ";"
+//              | IDENT "=" expr ";" | "*" IDENT "=" expr ";"
+//              | "return" expr? ";" | block | expr ";"
+//   expr       = INT | STRING | block | "&" "mut"? IDENT | "*" IDENT
+//              | IDENT | IDENT "(" args ")" | IDENT "::" IDENT "(" args ")"
+//              | IDENT "." IDENT "(" args ")" | IDENT "!" "(" args ")"
+use super::ast::{Block, Expr, ExprKind, Function, Param, Program, Stmt, Type};
+use super::lexer::{Token, TokenKind};
+use super::{Diagnostic, Span};
+
+pub fn parse(tokens: Vec<Token>) -> Result<Program, Diagnostic> {
+    let mut parser = Parser {
+        tokens,
+        pos: 0,
+        depth: 0,
+    };
+    let mut functions: Vec<Function> = Vec::new();
+    while parser.peek() != &TokenKind::Eof {
+        functions.push(parser.function()?);
+    }
+    Ok(Program { functions })
+}
+
+// Blocks inside blocks, calls inside calls and & inside types all make the parser (and later
+// the checker) recurse, so a few thousand of them would overflow the stack. Real programs don't
+// come anywhere near this many.
+pub const MAX_NESTING: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    // how many blocks, argument lists and & types the parser is inside
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> &TokenKind {
+        &self.tokens[self.pos].kind
+    }
+
+    fn peek_at(&self, ahead: usize) -> &TokenKind {
+        let index = (self.pos + ahead).min(self.tokens.len() - 1);
+        &self.tokens[index].kind
+    }
+
+    fn span(&self) -> Span {
+        self.tokens[self.pos].span
+    }
+
+    fn previous_span(&self) -> Span {
+        self.tokens[self.pos.saturating_sub(1)].span
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.tokens[self.pos].clone();
+        if token.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn eat(&mut self, kind: &TokenKind) -> bool {
+        if self.peek() == kind {
+            self.advance();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        let found = match self.peek() {
+            TokenKind::Eof => "end of file".to_string(),
+            TokenKind::Ident(name) => format!("`{name}`"),
+            TokenKind::Int(value) => format!("`{value}`"),
+            TokenKind::Str(text) => format!("{text:?}"),
+            other => format!("`{}`", symbol(other)),
+        };
+        Diagnostic::new(None, format!("expected {expected}, found {found}"))
+            .primary(self.span(), format!("expected {expected}"))
+    }
+
+    fn expect(&mut self, kind: TokenKind) -> Result<Span, Diagnostic> {
+        if self.peek() == &kind {
+            Ok(self.advance().span)
+        } else {
+            Err(self.unexpected(&format!("`{}`", symbol(&kind))))
+        }
+    }
+
+    // Parses one nested piece of grammar with parse, or stops if that would be too deep.
+    fn nested<T>(
+        &mut self,
+        parse: impl FnOnce(&mut Parser) -> Result<T, Diagnostic>,
+    ) -> Result<T, Diagnostic> {
+        if self.depth >= MAX_NESTING {
+            return Err(Diagnostic::new(None, "this is nested too deeply")
+                .primary(self.span(), format!("more than {MAX_NESTING} levels deep"))
+                .note("blocks, calls and reference types count as a level each"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    fn ident(&mut self) -> Result<(String, Span), Diagnostic> {
+        match self.peek().clone() {
+            TokenKind::Ident(name) => Ok((name, self.advance().span)),
+            _ => Err(self.unexpected("an identifier")),
+        }
+    }
+
+    fn function(&mut self) -> Result<Function, Diagnostic> {
+        self.expect(TokenKind::Fn)?;
+        let (name, name_span) = self.ident()?;
+        self.expect(TokenKind::LParen)?;
+        let mut params: Vec<Param> = Vec::new();
+        while self.peek() != &TokenKind::RParen {
+            let mutable = self.eat(&TokenKind::Mut);
+            let (name, span) = self.ident()?;
+            self.expect(TokenKind::Colon)?;
+            let (ty, ty_span) = self.ty()?;
+            params.push(Param {
+                name,
+                span,
+                mutable,
+                ty,
+                ty_span,
+            });
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::RParen)?;
+        let ret = if self.eat(&TokenKind::Arrow) {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        let body = self.block()?;
+        Ok(Function {
+            name,
+            name_span,
+            params,
+            ret,
+            body,
+        })
+    }
+
+    fn ty(&mut self) -> Result<(Type, Span), Diagnostic> {
+        let start = self.span();
+        if self.eat(&TokenKind::Amp) {
+            let mutable = self.eat(&TokenKind::Mut);
+            let (inner, end) = self.nested(Parser::ty)?;
+            let ty = if mutable {
+                Type::RefMut(Box::new(inner))
+            } else {
+                Type::Ref(Box::new(inner))
+            };
+            return Ok((ty, start.to(end)));
+        }
+        let (name, span) = self.ident()?;
+        let ty = match name.as_str() {
+            "i32" | "usize" => Type::Int,
+            "String" => Type::String,
+            "str" => Type::Str,
+            _ => {
+                return Err(Diagnostic::new(
+                    Some("E0412"),
+                    format!("cannot find type `{name}` in this scope"),
+                )
+                .primary(span, "not found in this scope")
+                .note("the types are i32, usize, String, str, &T and &mut T"))
+            }
+        };
+        Ok((ty, span))
+    }
+
+    fn block(&mut self) -> Result<Block, Diagnostic> {
+        let start = self.expect(TokenKind::LBrace)?;
+        let mut stmts: Vec<Stmt> = Vec::new();
+        let mut tail: Option<Box<Expr>> = None;
+        while self.peek() != &TokenKind::RBrace {
+            if self.peek() == &TokenKind::Eof {
+                return Err(self.unexpected("`}`"));
+            }
+            match self.peek() {
+                TokenKind::Let => stmts.push(self.let_stmt()?),
+                TokenKind::Return => {
+                    let span = self.advance().span;
+                    let value = if self.peek() == &TokenKind::Semi {
+                        None
+                    } else {
+                        Some(self.expr()?)
+                    };
+                    self.expect(TokenKind::Semi)?;
+                    stmts.push(Stmt::Return(value, span.to(self.previous_span())));
+                }
+                TokenKind::Ident(_) if self.peek_at(1) == &TokenKind::Assign => {
+                    let (name, span) = self.ident()?;
+                    self.advance();
+                    let value = self.expr()?;
+                    self.expect(TokenKind::Semi)?;
+                    stmts.push(Stmt::Assign { name, span, value });
+                }
+                TokenKind::Star if self.peek_at(2) == &TokenKind::Assign => {
+                    let star = self.advance().span;
+                    let (name, name_span) = self.ident()?;
+                    self.advance();
+                    let value = self.expr()?;
+                    self.expect(TokenKind::Semi)?;
+                    stmts.push(Stmt::AssignDeref {
+                        name,
+                        span: star.to(name_span),
+                        value,
+                    });
+                }
+                _ => {
+                    let expr = self.expr()?;
+                    let is_block = matches!(expr.kind, ExprKind::Block(_));
+                    if self.eat(&TokenKind::Semi) {
+                        stmts.push(Stmt::Expr(expr));
+                    } else if self.peek() == &TokenKind::RBrace {
+                        tail = Some(Box::new(expr));
+                    } else if is_block {
+                        // a block statement doesn't need a semicolon
+                        stmts.push(Stmt::Expr(expr));
+                    } else {
+                        return Err(self.unexpected("`;` or `}`"));
+                    }
+                }
+            }
+        }
+        let end = self.expect(TokenKind::RBrace)?;
+        Ok(Block {
+            stmts,
+            tail,
+            span: start.to(end),
+            end,
+        })
+    }
+
+    fn let_stmt(&mut self) -> Result<Stmt, Diagnostic> {
+        self.expect(TokenKind::Let)?;
+        let mutable = self.eat(&TokenKind::Mut);
+        let (name, span) = self.ident()?;
+        let ty = if self.eat(&TokenKind::Colon) {
+            Some(self.ty()?.0)
+        } else {
+            None
+        };
+        if self.peek() == &TokenKind::Semi {
+            return Err(Diagnostic::new(None, "a let needs a value in this language")
+                .primary(self.span(), "expected `=`")
+                .help("declare the variable where its value is ready, or give it a starting value"));
+        }
+        self.expect(TokenKind::Assign)?;
+        let init = self.expr()?;
+        self.expect(TokenKind::Semi)?;
+        Ok(Stmt::Let {
+            name,
+            span,
+            mutable,
+            ty,
+            init,
+        })
+    }
+
+    fn args(&mut self) -> Result<Vec<Expr>, Diagnostic> {
+        self.expect(TokenKind::LParen)?;
+        let mut args: Vec<Expr> = Vec::new();
+        while self.peek() != &TokenKind::RParen {
+            args.push(self.expr()?);
+            if !self.eat(&TokenKind::Comma) {
+                break;
+            }
+        }
+        self.expect(TokenKind::RParen)?;
+        Ok(args)
+    }
+
+    fn expr(&mut self) -> Result<Expr, Diagnostic> {
+        let start = self.span();
+        let kind = match self.peek().clone() {
+            TokenKind::Int(value) => {
+                self.advance();
+                ExprKind::Int(value)
+            }
+            TokenKind::Str(text) => {
+                self.advance();
+                ExprKind::Str(text)
+            }
+            TokenKind::LBrace => {
+                let block = self.nested(Parser::block)?;
+                let span = block.span;
+                return Ok(Expr {
+                    kind: ExprKind::Block(block),
+                    span,
+                });
+            }
+            TokenKind::Amp => {
+                self.advance();
+                let mutable = self.eat(&TokenKind::Mut);
+                let (name, name_span) = match self.peek() {
+                    TokenKind::Ident(_) => self.ident()?,
+                    _ => {
+                        return Err(self
+                            .unexpected("a variable")
+                            .note("only variables can be borrowed in this language"))
+                    }
+                };
+                ExprKind::Borrow {
+                    mutable,
+                    name,
+                    name_span,
+                }
+            }
+            TokenKind::Star => {
+                self.advance();
+                ExprKind::Deref(self.ident()?.0)
+            }
+            TokenKind::Ident(name) => {
+                let name_span = self.advance().span;
+                match self.peek() {
+                    TokenKind::LParen => ExprKind::Call {
+                        name,
+                        name_span,
+                        args: self.nested(Parser::args)?,
+                    },
+                    TokenKind::PathSep => {
+                        self.advance();
+                        let (item, item_span) = self.ident()?;
+                        ExprKind::Call {
+                            name: format!("{name}::{item}"),
+                            name_span: name_span.to(item_span),
+                            args: self.nested(Parser::args)?,
+                        }
+                    }
+                    TokenKind::Dot => {
+                        self.advance();
+                        let (method, _) = self.ident()?;
+                        ExprKind::Method {
+                            receiver: name,
+                            receiver_span: name_span,
+                            method,
+                            args: self.nested(Parser::args)?,
+                        }
+                    }
+                    TokenKind::Bang => {
+                        self.advance();
+                        ExprKind::Macro {
+                            name,
+                            args: self.nested(Parser::args)?,
+                        }
+                    }
+                    _ => ExprKind::Var(name),
+                }
+            }
+            _ => return Err(self.unexpected("an expression")),
+        };
+        Ok(Expr {
+            kind,
+            span: start.to(self.previous_span()),
+        })
+    }
+}
+
+fn symbol(kind: &TokenKind) -> &'static str {
+    match kind {
+        TokenKind::Fn => "fn",
+        TokenKind::Let => "let",
+        TokenKind::Mut => "mut",
+        TokenKind::Return => "return",
+        TokenKind::LParen => "(",
+        TokenKind::RParen => ")",
+        TokenKind::LBrace => "{",
+        TokenKind::RBrace => "}",
+        TokenKind::Comma => ",",
+        TokenKind::Semi => ";",
+        TokenKind::Colon => ":",
+        TokenKind::PathSep => "::",
+        TokenKind::Arrow => "->",
+        TokenKind::Amp => "&",
+        TokenKind::Star => "*",
+        TokenKind::Assign => "=",
+        TokenKind::Dot => ".",
+        TokenKind::Bang => "!",
+        TokenKind::Eof => "end of file",
+        TokenKind::Ident(_) | TokenKind::Int(_) | TokenKind::Str(_) => "token",
+    }
+}
@@ -0,0 +1,3 @@
+// The toy borrow checker lives in the library, so main.rs can run it on the example programs in
+// programs/ and on any other file given on the command line.
+pub mod borrowck;
@@ -1,4 +1,28 @@
+use references_and_borrowing::borrowck::{self, Mode};
+use what_is_ownership::alloc_counter::{self, Report};
+
+// cargo run --features count-allocations prints what each section cost in heap allocations
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
 fn main() {
+    // 'cargo run -- borrowck [--lexical] <file>...' checks toy programs instead of the demos below
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match args[0].as_str() {
+            "borrowck" => run_borrowck(&args[1..]),
+            other => fail(format!(
+                "unknown command '{other}' (try: borrowck [--lexical] <file>...)"
+            )),
+        }
+        return;
+    }
+    // get stdout's buffer allocated before counting starts
+    if alloc_counter::is_installed() {
+        println!("counting allocations");
+    }
+    let mut reports: Vec<Report> = Vec::new();
     /* The problem with using tuples is you have to manually return all of the values you want to
      * use outside of the function's scope back from the function, which can be become overwhelming
      * if you have a lot of values you want to return ownership of. Instead, you can provide a
@@ -10,19 +34,31 @@ fn main() {
      * an object as a parameter instead of taking ownership of the value: */
     let s1 = String::from("hello, fooman!");
     println!("{s1}");
+    let section = alloc_counter::region("calculate_length(&s1)");
     let len = calculate_length(&s1);
+    reports.push(section.finish());
     println!("{s1}(is {len} characters long!)");
+    // for comparison, handing calculate_length its own copy instead of a reference
+    let section = alloc_counter::region("calculate_length(&s1.clone())");
+    calculate_length(&s1.clone());
+    reports.push(section.finish());
     /* Note:The opposite of referencing by using & is 'dereferencing', which is accomplished with
      * the dereference operator, '*'.*/
     let mut s = String::from("Hello, fooman!");
+    let section = alloc_counter::region("mutable_ref(&mut s)");
     mutable_ref(&mut s);
+    reports.push(section.finish());
     println!("{s}");
+    /* Borrowing never allocates. The clone allocates a copy of s1 and frees it again right after
+     * the call, and mutable_ref only allocates because push_str makes s outgrow its capacity. */
+    alloc_counter::print_reports("cost of each call:", &reports);
     /* Mutable referenes have a big restriction: if you have a metable reference to a value, you
      * can have no other references to that value. */
     /* NOTE: a reference must exist in the same scope as its value. A function cannot return a
      * reference to a value created in the scope of the function, because as soon as the function
      * ends the value will be dropped, and the reference will point to nothing or some other data.
      * Instead, just return the value directly, so ownership is transferred to the parent scope. */
+    borrow_checker_demo();
 }
 fn calculate_length(s: &String) -> usize {
     println!("{s}(from inside the function using a reference!)");
@@ -37,3 +73,83 @@ fn mutable_ref(some_string: &mut String) {
 /* At any given time a value can have either ONE mutable reference, or any number of immutable
 * references.
  * References must always point to valid values. */
+
+/* Breaking the rules */
+// The rules above are checked by rustc's borrow checker, so the programs that break them can't be
+// shown here: they don't compile. src/borrowck.rs is a small borrow checker for a toy language
+// that looks like Rust, and programs/ has the chapter's examples written in it. Here it checks
+// each one and prints what it found, the way rustc would have. More can be checked with
+// 'cargo run -- borrowck programs/nll.toy', and --lexical uses the rules from before Rust 2018.
+fn borrow_checker_demo() {
+    println!("\n\x1b[1;4;33mBreaking the rules\x1b[0m");
+    let programs: [(&str, &str); 8] = [
+        ("programs/valid.toy", include_str!("../programs/valid.toy")),
+        (
+            "programs/immutable_change.toy",
+            include_str!("../programs/immutable_change.toy"),
+        ),
+        (
+            "programs/double_mut.toy",
+            include_str!("../programs/double_mut.toy"),
+        ),
+        ("programs/mixed.toy", include_str!("../programs/mixed.toy")),
+        ("programs/nll.toy", include_str!("../programs/nll.toy")),
+        (
+            "programs/dangle.toy",
+            include_str!("../programs/dangle.toy"),
+        ),
+        (
+            "programs/dangling_scope.toy",
+            include_str!("../programs/dangling_scope.toy"),
+        ),
+        ("programs/moves.toy", include_str!("../programs/moves.toy")),
+    ];
+    for (path, source) in programs {
+        println!("\n\x1b[1;33m{path}\x1b[0m");
+        let diagnostics = borrowck::check(source, Mode::NonLexical);
+        print!("{}", borrowck::report(source, path, &diagnostics));
+    }
+    // nll.toy is fine now, but wasn't before non-lexical lifetimes
+    let source = include_str!("../programs/nll.toy");
+    println!("\n\x1b[1;33mprograms/nll.toy, with lexical lifetimes\x1b[0m");
+    let diagnostics = borrowck::check(source, Mode::Lexical);
+    print!(
+        "{}",
+        borrowck::report(source, "programs/nll.toy", &diagnostics)
+    );
+}
+
+// For the tools: says what went wrong and exits with status 1, so scripts can tell.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+fn run_borrowck(args: &[String]) {
+    let mode = if args.iter().any(|arg| arg == "--lexical") {
+        Mode::Lexical
+    } else {
+        Mode::NonLexical
+    };
+    let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    if paths.is_empty() {
+        fail("usage: references_and_borrowing borrowck [--lexical] <file>...");
+    }
+    let mut failed = false;
+    for path in paths {
+        let source = match std::fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) => {
+                eprintln!("could not read {path}: {err}");
+                failed = true;
+                continue;
+            }
+        };
+        let diagnostics = borrowck::check(&source, mode);
+        print!("{}", borrowck::report(&source, path, &diagnostics));
+        failed |= !diagnostics.is_empty();
+    }
+    if failed {
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,732 @@
+/* Bit containers */
+// print_binary in main.rs shows every byte of a string as its bits: "hello" is
+// 1101000 1100101 1101100 1101100 1101111. This module goes the other way and uses the bits of a
+// byte as storage. A Vec<bool> spends a whole byte on each true or false; a BitVec packs eight of
+// them into one byte, so a million flags take 125 KB instead of 1 MB.
+//
+// The bits are stored in order from the highest bit of the first byte down, which is the order
+// print_binary prints them in. So BitVec::from_bytes(b"hello") formatted with {:b} prints exactly
+// what print_binary does, and as_bytes() hands back b"hello".
+//
+// A BitSet is a set of small numbers kept as a BitVec: bit n says whether n is in the set. Union,
+// intersection and difference work a byte (eight numbers) at a time, and the sieve of
+// Eratosthenes at the bottom uses one to find primes.
+use std::fmt;
+use std::ops::{BitAnd, BitOr, Sub};
+use std::str::FromStr;
+
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    bytes: Vec<u8>,
+    // the number of bits; the bits after it in the last byte are always 0
+    len: usize,
+}
+
+// The byte holding bit i, and the mask for it in that byte.
+fn locate(i: usize) -> (usize, u8) {
+    (i / 8, 0x80 >> (i % 8))
+}
+
+impl BitVec {
+    pub fn new() -> Self {
+        BitVec::default()
+    }
+
+    pub fn with_capacity(bits: usize) -> Self {
+        BitVec {
+            bytes: Vec::with_capacity(bits.div_ceil(8)),
+            len: 0,
+        }
+    }
+
+    // len bits, all set to bit.
+    pub fn repeat(bit: bool, len: usize) -> Self {
+        let mut bits = BitVec {
+            bytes: vec![if bit { 0xff } else { 0 }; len.div_ceil(8)],
+            len,
+        };
+        bits.clear_tail();
+        bits
+    }
+
+    // Every bit of the bytes, highest bit first.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        BitVec {
+            bytes: bytes.to_vec(),
+            len: bytes.len() * 8,
+        }
+    }
+
+    // The bits packed into bytes; the last byte is padded with 0 bits.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, i: usize) -> Option<bool> {
+        if i >= self.len {
+            return None;
+        }
+        let (byte, mask) = locate(i);
+        Some(self.bytes[byte] & mask != 0)
+    }
+
+    fn check(&self, i: usize) -> (usize, u8) {
+        assert!(
+            i < self.len,
+            "bit {i} is out of range for a BitVec of {} bits",
+            self.len
+        );
+        locate(i)
+    }
+
+    // Sets bit i to 1. Panics if i is out of range, like indexing a Vec.
+    pub fn set(&mut self, i: usize) {
+        let (byte, mask) = self.check(i);
+        self.bytes[byte] |= mask;
+    }
+
+    // Sets bit i to 0.
+    pub fn clear(&mut self, i: usize) {
+        let (byte, mask) = self.check(i);
+        self.bytes[byte] &= !mask;
+    }
+
+    pub fn toggle(&mut self, i: usize) {
+        let (byte, mask) = self.check(i);
+        self.bytes[byte] ^= mask;
+    }
+
+    pub fn assign(&mut self, i: usize, bit: bool) {
+        if bit {
+            self.set(i);
+        } else {
+            self.clear(i);
+        }
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(8) {
+            self.bytes.push(0);
+        }
+        self.len += 1;
+        self.assign(self.len - 1, bit);
+    }
+
+    pub fn pop(&mut self) -> Option<bool> {
+        let bit = self.get(self.len.checked_sub(1)?)?;
+        self.truncate(self.len - 1);
+        Some(bit)
+    }
+
+    // Grows (with new bits set to bit) or shrinks to len bits.
+    pub fn resize(&mut self, len: usize, bit: bool) {
+        if len <= self.len {
+            return self.truncate(len);
+        }
+        let old = self.len;
+        self.bytes
+            .resize(len.div_ceil(8), if bit { 0xff } else { 0 });
+        self.len = len;
+        if bit {
+            // the rest of the old last byte
+            for i in old..len.min(old.next_multiple_of(8)) {
+                self.set(i);
+            }
+        }
+        self.clear_tail();
+    }
+
+    pub fn truncate(&mut self, len: usize) {
+        if len < self.len {
+            self.len = len;
+            self.bytes.truncate(len.div_ceil(8));
+            self.clear_tail();
+        }
+    }
+
+    // Keeps the unused bits of the last byte 0, so bytes can be compared and counted whole.
+    fn clear_tail(&mut self) {
+        if !self.len.is_multiple_of(8) {
+            let last = self.bytes.len() - 1;
+            self.bytes[last] &= 0xff << (8 - self.len % 8);
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.bytes.iter().map(|b| b.count_ones() as usize).sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = bool> + '_ {
+        (0..self.len).map(|i| self.bytes[i / 8] & (0x80 >> (i % 8)) != 0)
+    }
+
+    // The positions of the 1 bits, in order. Bytes that are all 0 are skipped whole.
+    pub fn ones(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bytes
+            .iter()
+            .enumerate()
+            .filter(|(_, &byte)| byte != 0)
+            .flat_map(|(i, &byte)| {
+                (0..8)
+                    .filter(move |bit| byte & (0x80 >> bit) != 0)
+                    .map(move |bit| i * 8 + bit)
+            })
+    }
+
+    // Saved as the number of bits (8 bytes, little-endian) followed by the packed bits, so
+    // from_serialized can give back exactly the same BitVec, odd lengths included.
+    pub fn serialize(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + self.bytes.len());
+        out.extend_from_slice(&(self.len as u64).to_le_bytes());
+        out.extend_from_slice(&self.bytes);
+        out
+    }
+
+    pub fn from_serialized(data: &[u8]) -> Result<Self, BitsError> {
+        let (header, bytes) = data.split_first_chunk::<8>().ok_or(BitsError::TooShort)?;
+        let len = usize::try_from(u64::from_le_bytes(*header)).map_err(|_| BitsError::TooShort)?;
+        if bytes.len() != len.div_ceil(8) {
+            return Err(BitsError::WrongLength {
+                bits: len,
+                bytes: bytes.len(),
+            });
+        }
+        let bits = BitVec {
+            bytes: bytes.to_vec(),
+            len,
+        };
+        let mut cleared = bits.clone();
+        cleared.clear_tail();
+        if cleared != bits {
+            return Err(BitsError::NonZeroPadding);
+        }
+        Ok(bits)
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let mut bits = BitVec::new();
+        bits.extend(iter);
+        bits
+    }
+}
+
+impl Extend<bool> for BitVec {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+// {:b} prints each byte like print_binary does: with {:b}, so without its leading zeros, and a
+// space between bytes. {:#b} keeps the leading zeros, so every bit is there and in place. A last
+// byte that isn't full only shows the bits that are in use.
+impl fmt::Binary for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, &byte) in self.bytes.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            let used = (self.len - i * 8).min(8);
+            if used < 8 {
+                let bits = byte >> (8 - used);
+                write!(f, "{bits:0used$b}")?;
+            } else if f.alternate() {
+                write!(f, "{byte:08b}")?;
+            } else {
+                write!(f, "{byte:b}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BitVec({} bits: {self:#b})", self.len)
+    }
+}
+
+// Reads print_binary's output back: groups of up to eight 0s and 1s separated by whitespace,
+// each group one byte, like "1101000 1100101". The result is always whole bytes.
+impl FromStr for BitVec {
+    type Err = BitsError;
+
+    fn from_str(text: &str) -> Result<Self, BitsError> {
+        let bytes = text
+            .split_whitespace()
+            .map(|group| {
+                if group.len() > 8 {
+                    return Err(BitsError::GroupTooLong(group.to_string()));
+                }
+                u8::from_str_radix(group, 2).map_err(|_| BitsError::NotBinary(group.to_string()))
+            })
+            .collect::<Result<Vec<u8>, BitsError>>()?;
+        Ok(BitVec::from_bytes(&bytes))
+    }
+}
+
+/* Errors */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BitsError {
+    // a group in a binary string with something other than 0 and 1 in it
+    NotBinary(String),
+    // a group in a binary string with more than 8 bits, so it isn't a byte
+    GroupTooLong(String),
+    // serialized data without the 8 byte length at the start
+    TooShort,
+    // serialized data that doesn't have the number of bytes its length needs
+    WrongLength { bits: usize, bytes: usize },
+    // serialized data with 1s in the padding after the last bit
+    NonZeroPadding,
+}
+
+impl fmt::Display for BitsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BitsError::NotBinary(group) => write!(f, "'{group}' isn't a binary number"),
+            BitsError::GroupTooLong(group) => write!(f, "'{group}' has more than 8 bits"),
+            BitsError::TooShort => write!(f, "the data is too short to hold a length"),
+            BitsError::WrongLength { bits, bytes } => {
+                write!(
+                    f,
+                    "{bits} bits need {} bytes, not {bytes}",
+                    bits.div_ceil(8)
+                )
+            }
+            BitsError::NonZeroPadding => write!(f, "the padding after the last bit isn't zero"),
+        }
+    }
+}
+
+impl std::error::Error for BitsError {}
+
+/* BitSet */
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitSet {
+    // trailing zero bytes are trimmed off, so equal sets have equal bits
+    bits: BitVec,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet::default()
+    }
+
+    // Room for the numbers below capacity without growing.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BitSet {
+            bits: BitVec::with_capacity(capacity),
+        }
+    }
+
+    // Returns whether n was newly added.
+    pub fn insert(&mut self, n: usize) -> bool {
+        if n >= self.bits.len() {
+            // grow a whole byte at a time
+            self.bits.resize((n + 1).next_multiple_of(8), false);
+        }
+        let added = self.bits.get(n) == Some(false);
+        self.bits.set(n);
+        added
+    }
+
+    // Returns whether n was in the set.
+    pub fn remove(&mut self, n: usize) -> bool {
+        if self.bits.get(n) != Some(true) {
+            return false;
+        }
+        self.bits.clear(n);
+        self.trim();
+        true
+    }
+
+    pub fn contains(&self, n: usize) -> bool {
+        self.bits.get(n) == Some(true)
+    }
+
+    fn trim(&mut self) {
+        let used = self
+            .bits
+            .bytes
+            .iter()
+            .rposition(|&b| b != 0)
+            .map_or(0, |i| i + 1);
+        self.bits.truncate(used * 8);
+    }
+
+    // How many numbers are in the set (the population count).
+    pub fn len(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.bits = BitVec::new();
+    }
+
+    pub fn min(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    pub fn max(&self) -> Option<usize> {
+        let byte = self.bits.bytes.len().checked_sub(1)?;
+        let last = self.bits.bytes[byte];
+        Some(byte * 8 + 7 - last.trailing_zeros() as usize)
+    }
+
+    // The numbers in the set, smallest first.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.ones()
+    }
+
+    // The set as bits, bit n for the number n.
+    pub fn as_bitvec(&self) -> &BitVec {
+        &self.bits
+    }
+
+    // Combines the bytes of two sets with op. Bytes past the end of the shorter set count as 0.
+    fn combine(&self, other: &BitSet, op: impl Fn(u8, u8) -> u8) -> BitSet {
+        let (a, b) = (&self.bits.bytes, &other.bits.bytes);
+        let bytes: Vec<u8> = (0..a.len().max(b.len()))
+            .map(|i| {
+                op(
+                    a.get(i).copied().unwrap_or(0),
+                    b.get(i).copied().unwrap_or(0),
+                )
+            })
+            .collect();
+        let mut set = BitSet {
+            bits: BitVec::from_bytes(&bytes),
+        };
+        set.trim();
+        set
+    }
+
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a | b)
+    }
+
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a & b)
+    }
+
+    // The numbers in self that aren't in other.
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a & !b)
+    }
+
+    pub fn symmetric_difference(&self, other: &BitSet) -> BitSet {
+        self.combine(other, |a, b| a ^ b)
+    }
+
+    pub fn is_subset(&self, other: &BitSet) -> bool {
+        self.difference(other).is_empty()
+    }
+
+    pub fn is_disjoint(&self, other: &BitSet) -> bool {
+        self.intersection(other).is_empty()
+    }
+
+    pub fn serialize(&self) -> Vec<u8> {
+        self.bits.serialize()
+    }
+
+    pub fn from_serialized(data: &[u8]) -> Result<Self, BitsError> {
+        let mut set = BitSet {
+            bits: BitVec::from_serialized(data)?,
+        };
+        set.trim();
+        Ok(set)
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for n in iter {
+            self.insert(n);
+        }
+    }
+}
+
+impl BitOr for &BitSet {
+    type Output = BitSet;
+
+    fn bitor(self, other: &BitSet) -> BitSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for &BitSet {
+    type Output = BitSet;
+
+    fn bitand(self, other: &BitSet) -> BitSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for &BitSet {
+    type Output = BitSet;
+
+    fn sub(self, other: &BitSet) -> BitSet {
+        self.difference(other)
+    }
+}
+
+impl fmt::Binary for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Binary::fmt(&self.bits, f)
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/* Sieve of Eratosthenes */
+// The primes below limit. Start with every number from 2 up as a candidate, and for each prime
+// found, cross out its multiples (starting at its square, since the smaller ones were already
+// crossed out by smaller primes). Only odd numbers are kept in the BitVec, which halves it again:
+// bit i stands for 2i + 1.
+pub fn primes_below(limit: usize) -> BitSet {
+    let mut primes = BitSet::with_capacity(limit);
+    if limit <= 2 {
+        return primes;
+    }
+    let mut odd = BitVec::repeat(true, limit / 2);
+    odd.clear(0); // 1 isn't prime
+    let mut i = 1;
+    while (2 * i + 1) * (2 * i + 1) < limit {
+        if odd.get(i) == Some(true) {
+            let p = 2 * i + 1;
+            // p*p, p*p + 2p, ... are the odd multiples; bit (m - 1) / 2 for each m
+            let mut m = p * p;
+            while m < limit {
+                odd.clear(m / 2);
+                m += 2 * p;
+            }
+        }
+        i += 1;
+    }
+    primes.insert(2);
+    primes.extend(odd.ones().map(|i| 2 * i + 1));
+    primes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks a BitVec against the Vec<bool> it should be equal to, padding included.
+    fn assert_same(bits: &BitVec, model: &[bool]) {
+        assert_eq!(bits.len(), model.len());
+        assert_eq!(bits.iter().collect::<Vec<bool>>(), model);
+        assert_eq!(bits, &model.iter().copied().collect::<BitVec>());
+        assert_eq!(bits.as_bytes().len(), model.len().div_ceil(8));
+        assert_eq!(bits.count_ones(), model.iter().filter(|&&bit| bit).count());
+        assert_eq!(bits.get(model.len()), None);
+    }
+
+    #[test]
+    fn push_pop_resize_truncate() {
+        let mut bits = BitVec::new();
+        let mut model: Vec<bool> = Vec::new();
+        for i in 0..21 {
+            bits.push(i % 3 == 0);
+            model.push(i % 3 == 0);
+            assert_same(&bits, &model);
+        }
+        assert_eq!(bits.pop(), model.pop());
+        assert_same(&bits, &model);
+
+        // shrinking clears the bits that are cut off, so they don't come back when it regrows
+        bits.truncate(9);
+        model.truncate(9);
+        assert_same(&bits, &model);
+        bits.resize(20, false);
+        model.resize(20, false);
+        assert_same(&bits, &model);
+        bits.resize(3, true);
+        model.resize(3, true);
+        assert_same(&bits, &model);
+        bits.resize(30, true);
+        model.resize(30, true);
+        assert_same(&bits, &model);
+        // truncating to a longer length does nothing, and resizing to the same length neither
+        bits.truncate(100);
+        bits.resize(30, false);
+        assert_same(&bits, &model);
+
+        bits.toggle(0);
+        bits.clear(29);
+        bits.assign(5, false);
+        model[0] = !model[0];
+        model[29] = false;
+        model[5] = false;
+        assert_same(&bits, &model);
+
+        bits.truncate(0);
+        assert!(bits.is_empty());
+        assert_eq!(bits.pop(), None);
+        assert_eq!(BitVec::repeat(true, 13), BitVec::from_iter([true; 13]));
+    }
+
+    #[test]
+    #[should_panic(expected = "bit 8 is out of range for a BitVec of 8 bits")]
+    fn set_past_the_end_panics() {
+        BitVec::repeat(false, 8).set(8);
+    }
+
+    #[test]
+    fn binary_format_matches_print_binary() {
+        let hello = BitVec::from_bytes(b"hello");
+        assert_eq!(
+            format!("{hello:b}"),
+            "1101000 1100101 1101100 1101100 1101111"
+        );
+        assert_eq!(
+            format!("{hello:#b}"),
+            "01101000 01100101 01101100 01101100 01101111"
+        );
+        let parsed: BitVec = "1101000 1100101 1101100 1101100 1101111".parse().unwrap();
+        assert_eq!(parsed, hello);
+        // a last byte that isn't full only shows its bits in use
+        let odd: BitVec = [true, false, true].into_iter().collect();
+        assert_eq!(format!("{odd:b}"), "101");
+        assert_eq!(
+            "10 2".parse::<BitVec>(),
+            Err(BitsError::NotBinary("2".to_string()))
+        );
+        assert_eq!(
+            "100000000".parse::<BitVec>(),
+            Err(BitsError::GroupTooLong("100000000".to_string()))
+        );
+    }
+
+    #[test]
+    fn serialize_round_trips() {
+        for len in [0usize, 1, 7, 8, 9, 64, 100] {
+            let bits: BitVec = (0..len).map(|i| i % 5 < 2).collect();
+            let data = bits.serialize();
+            assert_eq!(data.len(), 8 + len.div_ceil(8));
+            assert_eq!(BitVec::from_serialized(&data), Ok(bits));
+        }
+        let set: BitSet = [0, 7, 8, 1000].into_iter().collect();
+        assert_eq!(BitSet::from_serialized(&set.serialize()), Ok(set.clone()));
+        // a BitVec with trailing zeros reads back as the same set
+        let mut padded = set.as_bitvec().clone();
+        padded.resize(2000, false);
+        assert_eq!(BitSet::from_serialized(&padded.serialize()), Ok(set));
+    }
+
+    #[test]
+    fn from_serialized_rejects_bad_data() {
+        assert_eq!(
+            BitVec::from_serialized(&[1, 0, 0]),
+            Err(BitsError::TooShort)
+        );
+        let mut data = 10u64.to_le_bytes().to_vec();
+        data.push(0xff);
+        assert_eq!(
+            BitVec::from_serialized(&data),
+            Err(BitsError::WrongLength { bits: 10, bytes: 1 })
+        );
+        // 10 bits in two bytes, but with 1s after the tenth bit
+        data.push(0xff);
+        assert_eq!(
+            BitVec::from_serialized(&data),
+            Err(BitsError::NonZeroPadding)
+        );
+        data[9] = 0xc0;
+        assert_eq!(BitVec::from_serialized(&data), Ok(BitVec::repeat(true, 10)));
+    }
+
+    #[test]
+    fn set_operations() {
+        let readers: BitSet = [0, 1, 2, 3, 5, 8].into_iter().collect();
+        let writers: BitSet = [1, 3, 8, 13].into_iter().collect();
+        let list = |set: BitSet| set.iter().collect::<Vec<usize>>();
+        assert_eq!(list(&readers & &writers), [1, 3, 8]);
+        assert_eq!(list(&readers | &writers), [0, 1, 2, 3, 5, 8, 13]);
+        assert_eq!(list(&readers - &writers), [0, 2, 5]);
+        assert_eq!(list(&writers - &readers), [13]);
+        assert_eq!(list(readers.symmetric_difference(&writers)), [0, 2, 5, 13]);
+        assert!((&readers & &writers).is_subset(&readers));
+        assert!(!writers.is_subset(&readers));
+        assert!((&readers - &writers).is_disjoint(&writers));
+        assert_eq!(readers.min(), Some(0));
+        assert_eq!(writers.max(), Some(13));
+        assert_eq!(writers.len(), 4);
+        assert_eq!(format!("{writers:?}"), "{1, 3, 8, 13}");
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut set = BitSet::new();
+        assert!(set.is_empty());
+        assert_eq!((set.min(), set.max()), (None, None));
+        assert!(set.insert(100));
+        assert!(!set.insert(100));
+        assert!(set.insert(3));
+        assert!(set.contains(100) && set.contains(3) && !set.contains(4));
+        assert!(set.remove(100));
+        assert!(!set.remove(100));
+        assert!(!set.remove(5000));
+        assert_eq!(set.max(), Some(3));
+        // removing the largest number trims the bits, so equal sets compare equal
+        assert_eq!(set, [3].into_iter().collect());
+        assert!(set.remove(3));
+        assert!(set.is_empty());
+        assert_eq!(set, BitSet::new());
+    }
+
+    #[test]
+    fn sieve() {
+        let primes = |limit: usize| primes_below(limit).iter().collect::<Vec<usize>>();
+        assert_eq!(primes(0), []);
+        assert_eq!(primes(2), []);
+        assert_eq!(primes(3), [2]);
+        assert_eq!(primes(10), [2, 3, 5, 7]);
+        // the limit itself isn't included, even when it's prime or a square
+        assert_eq!(primes(11), [2, 3, 5, 7]);
+        assert_eq!(primes(26), [2, 3, 5, 7, 11, 13, 17, 19, 23]);
+        assert_eq!(primes_below(100).len(), 25);
+        assert_eq!(primes_below(1_000_000).len(), 78_498);
+        // against trial division
+        let is_prime = |n: usize| {
+            n >= 2
+                && (2..n)
+                    .take_while(|d| d * d <= n)
+                    .all(|d| !n.is_multiple_of(d))
+        };
+        let expected: Vec<usize> = (0..2000).filter(|&n| is_prime(n)).collect();
+        assert_eq!(primes(2000), expected);
+    }
+}
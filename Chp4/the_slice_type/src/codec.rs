@@ -0,0 +1,616 @@
+/* Base64, Base32 and hex */
+// print_binary shows that text is just bytes and bytes are just bits. These encodings go the
+// other way: they turn arbitrary bytes into text that survives being pasted into an email or a
+// URL, by cutting the bits into small groups and giving each group a printable character.
+//   hex (base16)  4 bits per character, 1 byte  -> 2 characters
+//   base32        5 bits per character, 5 bytes -> 8 characters
+//   base64        6 bits per character, 3 bytes -> 4 characters
+// For example "Man" is 01001101 01100001 01101110, which base64 reads as
+// 010011 010110 000101 101110 = 19 22 5 46 = "TWFu". When the input doesn't fill the last group,
+// the leftover bits are padded with zeros and the group is filled up with '=' (unless padding is
+// turned off). All of this is specified in RFC 4648, whose test vectors are in RFC_4648_VECTORS.
+//
+// Encoding and decoding are done as streams: Encoder wraps any Write and Decoder wraps any Read,
+// so a file of any size can go through without being loaded into memory. encode() and decode()
+// are shortcuts for when the data is already in memory.
+use std::fmt;
+use std::io::{self, Read, Write};
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+const BASE64_URL: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+const BASE32: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+const BASE32_HEX: &[u8; 32] = b"0123456789ABCDEFGHIJKLMNOPQRSTUV";
+const HEX_LOWER: &[u8; 16] = b"0123456789abcdef";
+const HEX_UPPER: &[u8; 16] = b"0123456789ABCDEF";
+const INVALID: u8 = 0xff;
+
+#[derive(Debug, Clone)]
+pub struct Codec {
+    name: &'static str,
+    alphabet: &'static [u8],
+    // the character value of every byte, or INVALID
+    decode_table: [u8; 256],
+    bits: u32,
+    padded: bool,
+}
+
+impl Codec {
+    fn new(name: &'static str, alphabet: &'static [u8], padded: bool) -> Self {
+        let mut decode_table = [INVALID; 256];
+        let case_insensitive = !alphabet.iter().any(u8::is_ascii_lowercase);
+        for (value, &c) in alphabet.iter().enumerate() {
+            decode_table[c as usize] = value as u8;
+            // base32 and hex only use one case, so accept the other one too
+            if case_insensitive {
+                decode_table[c.to_ascii_lowercase() as usize] = value as u8;
+            }
+        }
+        Codec {
+            name,
+            alphabet,
+            decode_table,
+            bits: alphabet.len().trailing_zeros(),
+            padded,
+        }
+    }
+
+    pub fn base64() -> Self {
+        Codec::new("base64", BASE64, true)
+    }
+
+    // '-' and '_' instead of '+' and '/', which have a meaning in URLs and file names
+    pub fn base64_url() -> Self {
+        Codec::new("base64url", BASE64_URL, true)
+    }
+
+    pub fn base32() -> Self {
+        Codec::new("base32", BASE32, true)
+    }
+
+    // base32 with an alphabet that keeps the sort order of the encoded data
+    pub fn base32_hex() -> Self {
+        Codec::new("base32hex", BASE32_HEX, true)
+    }
+
+    // Hex never needs padding: every byte is exactly two characters.
+    pub fn hex() -> Self {
+        Codec::new("hex", HEX_LOWER, false)
+    }
+
+    pub fn hex_upper() -> Self {
+        Codec::new("HEX", HEX_UPPER, false)
+    }
+
+    pub fn unpadded(mut self) -> Self {
+        self.padded = false;
+        self
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    // bytes per full group, and characters per full group
+    fn block(&self) -> (usize, usize) {
+        // the smallest number of bits that is a whole number of both bytes and characters
+        let mut bits = self.bits;
+        while !bits.is_multiple_of(8) {
+            bits += self.bits;
+        }
+        ((bits / 8) as usize, (bits / self.bits) as usize)
+    }
+
+    pub fn encoder<W: Write>(&self, inner: W) -> Encoder<'_, W> {
+        Encoder {
+            codec: self,
+            inner: Some(inner),
+            pending: Vec::new(),
+            wrap: None,
+            column: 0,
+        }
+    }
+
+    pub fn decoder<R: Read>(&self, inner: R) -> Decoder<'_, R> {
+        Decoder {
+            codec: self,
+            inner,
+            state: DecodeState::default(),
+            output: Vec::new(),
+            read_pos: 0,
+            done: false,
+        }
+    }
+
+    pub fn encode(&self, bytes: &[u8]) -> String {
+        let mut out: Vec<u8> = Vec::new();
+        let mut encoder = self.encoder(&mut out);
+        encoder
+            .write_all(bytes)
+            .expect("writing to a Vec can't fail");
+        encoder.finish().expect("writing to a Vec can't fail");
+        String::from_utf8(out).expect("every alphabet is ASCII")
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>, DecodeError> {
+        let mut state = DecodeState::default();
+        let mut out: Vec<u8> = Vec::new();
+        for &c in text.as_bytes() {
+            state.push(self, c, &mut out)?;
+        }
+        state.finish(self, &mut out)?;
+        Ok(out)
+    }
+
+    // Encodes one group of up to block().0 bytes. A short group gets as many characters as it
+    // needs, then '=' up to the full group size if padding is on.
+    fn encode_group(&self, group: &[u8], out: &mut Vec<u8>) {
+        let (_, chars_per_block) = self.block();
+        let mut acc: u64 = 0;
+        for &byte in group {
+            acc = (acc << 8) | byte as u64;
+        }
+        let bits = group.len() as u32 * 8;
+        let chars = bits.div_ceil(self.bits);
+        // shift left so the data is followed by zero bits up to a whole number of characters
+        acc <<= chars * self.bits - bits;
+        let mask = (1u64 << self.bits) - 1;
+        for i in (0..chars).rev() {
+            out.push(self.alphabet[((acc >> (i * self.bits)) & mask) as usize]);
+        }
+        if self.padded {
+            out.resize(out.len() + chars_per_block - chars as usize, b'=');
+        }
+    }
+}
+
+/* Errors */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodeErrorKind {
+    // a byte that isn't in the alphabet (and isn't whitespace or padding)
+    InvalidCharacter(u8),
+    // '=' where it doesn't belong: at the start of a group, or when padding is turned off
+    UnexpectedPadding,
+    // more data after the padding that should have ended it
+    DataAfterPadding,
+    // the last group has the wrong number of characters to make whole bytes
+    InvalidLength,
+    // padding is on but the last group isn't padded to full size
+    MissingPadding,
+    // the bits left over after the last byte aren't zero, so this isn't what any encoder would
+    // have produced
+    NonZeroTrailingBits,
+}
+
+// Where the problem is, as an offset in bytes from the start of the input (counting any
+// whitespace that was skipped).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub kind: DecodeErrorKind,
+    pub offset: usize,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::InvalidCharacter(c) if c.is_ascii_graphic() => {
+                write!(f, "invalid character '{}'", c as char)?
+            }
+            DecodeErrorKind::InvalidCharacter(c) => write!(f, "invalid byte 0x{c:02x}")?,
+            DecodeErrorKind::UnexpectedPadding => write!(f, "unexpected '=' padding")?,
+            DecodeErrorKind::DataAfterPadding => write!(f, "data after the '=' padding")?,
+            DecodeErrorKind::InvalidLength => {
+                write!(f, "the last group has the wrong number of characters")?
+            }
+            DecodeErrorKind::MissingPadding => write!(f, "missing '=' padding")?,
+            DecodeErrorKind::NonZeroTrailingBits => {
+                write!(f, "the unused bits of the last character aren't zero")?
+            }
+        }
+        write!(f, " at offset {}", self.offset)
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+// Decoders report errors through io::Error, so the DecodeError is wrapped in one. This gets it
+// back out again.
+pub fn decode_error(err: &io::Error) -> Option<&DecodeError> {
+    err.get_ref()?.downcast_ref::<DecodeError>()
+}
+
+/* Encoding */
+pub struct Encoder<'c, W: Write> {
+    codec: &'c Codec,
+    // None once finish() has taken it back
+    inner: Option<W>,
+    // bytes waiting for the rest of their group
+    pending: Vec<u8>,
+    wrap: Option<usize>,
+    column: usize,
+}
+
+impl<'c, W: Write> Encoder<'c, W> {
+    // Starts a new line every `width` characters, like 'base64 -w 76'.
+    pub fn wrap(mut self, width: usize) -> Self {
+        self.wrap = (width > 0).then_some(width);
+        self
+    }
+
+    fn emit(&mut self, chars: &[u8]) -> io::Result<()> {
+        let inner = self
+            .inner
+            .as_mut()
+            .expect("the encoder was already finished");
+        let Some(width) = self.wrap else {
+            return inner.write_all(chars);
+        };
+        for &c in chars {
+            if self.column == width {
+                inner.write_all(b"\n")?;
+                self.column = 0;
+            }
+            inner.write_all(&[c])?;
+            self.column += 1;
+        }
+        Ok(())
+    }
+
+    // Encodes whatever is left over (with padding), and hands back the inner writer. Without
+    // this the last partial group would never be written.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.flush_tail()?;
+        Ok(self.inner.take().expect("finish() is only called once"))
+    }
+
+    fn flush_tail(&mut self) -> io::Result<()> {
+        let mut chars: Vec<u8> = Vec::new();
+        if !self.pending.is_empty() {
+            self.codec.encode_group(&self.pending, &mut chars);
+            self.pending.clear();
+        }
+        self.emit(&chars)?;
+        if self.wrap.is_some() && self.column > 0 {
+            self.emit_newline()?;
+        }
+        self.inner.as_mut().expect("not finished yet").flush()
+    }
+
+    fn emit_newline(&mut self) -> io::Result<()> {
+        self.column = 0;
+        self.inner
+            .as_mut()
+            .expect("not finished yet")
+            .write_all(b"\n")
+    }
+}
+
+impl<W: Write> Write for Encoder<'_, W> {
+    fn write(&mut self, bytes: &[u8]) -> io::Result<usize> {
+        let (bytes_per_block, _) = self.codec.block();
+        let mut chars: Vec<u8> = Vec::new();
+        let mut rest: &[u8] = bytes;
+        // top up a group left over from the last write first
+        if !self.pending.is_empty() {
+            let needed = (bytes_per_block - self.pending.len()).min(rest.len());
+            self.pending.extend_from_slice(&rest[..needed]);
+            rest = &rest[needed..];
+            if self.pending.len() < bytes_per_block {
+                return Ok(bytes.len());
+            }
+            self.codec.encode_group(&self.pending, &mut chars);
+            self.pending.clear();
+        }
+        let mut groups = rest.chunks_exact(bytes_per_block);
+        for group in groups.by_ref() {
+            self.codec.encode_group(group, &mut chars);
+        }
+        self.pending.extend_from_slice(groups.remainder());
+        self.emit(&chars)?;
+        Ok(bytes.len())
+    }
+
+    // Only flushes the inner writer; a partial group stays pending until finish().
+    fn flush(&mut self) -> io::Result<()> {
+        match self.inner.as_mut() {
+            Some(inner) => inner.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+// Dropping an encoder without calling finish() still writes the last group, but any error is
+// lost, so call finish() when the result matters.
+impl<W: Write> Drop for Encoder<'_, W> {
+    fn drop(&mut self) {
+        if self.inner.is_some() {
+            let _ = self.flush_tail();
+        }
+    }
+}
+
+/* Decoding */
+// Everything the decoder needs to remember between bytes. Decoder feeds it from a Read, and
+// Codec::decode feeds it from a &str.
+#[derive(Debug, Default)]
+struct DecodeState {
+    // how many input bytes have been looked at
+    offset: usize,
+    // character values of the current group
+    group: Vec<u8>,
+    // where the current group's last character was, for errors about it
+    last_char_offset: usize,
+    // '=' characters seen in the current group; no data may follow them
+    padding: usize,
+    // set once a padded (or short) group has ended the data
+    ended: bool,
+}
+
+impl DecodeState {
+    fn error(&self, kind: DecodeErrorKind, offset: usize) -> DecodeError {
+        DecodeError { kind, offset }
+    }
+
+    fn push(&mut self, codec: &Codec, c: u8, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let offset = self.offset;
+        self.offset += 1;
+        // line breaks and spaces are allowed anywhere (base64 is often wrapped at 76 columns)
+        if c.is_ascii_whitespace() {
+            return Ok(());
+        }
+        let (_, chars_per_block) = codec.block();
+        if c == b'=' {
+            if !codec.padded || self.group.is_empty() || self.ended {
+                return Err(self.error(DecodeErrorKind::UnexpectedPadding, offset));
+            }
+            // the data characters in front of the padding have to make whole bytes
+            if self.padding == 0 && !valid_group_len(codec, self.group.len()) {
+                return Err(self.error(DecodeErrorKind::InvalidLength, offset));
+            }
+            self.padding += 1;
+            if self.group.len() + self.padding == chars_per_block {
+                self.flush_group(codec, out)?;
+                self.ended = true;
+            }
+            return Ok(());
+        }
+        if self.padding > 0 || self.ended {
+            // data after padding: either a character after '=' in the same group, or a whole
+            // group after the padded one
+            return Err(self.error(DecodeErrorKind::DataAfterPadding, offset));
+        }
+        let value = codec.decode_table[c as usize];
+        if value == INVALID {
+            return Err(self.error(DecodeErrorKind::InvalidCharacter(c), offset));
+        }
+        self.group.push(value);
+        self.last_char_offset = offset;
+        if self.group.len() == chars_per_block {
+            self.flush_group(codec, out)?;
+        }
+        Ok(())
+    }
+
+    // Turns the characters of the current group into bytes.
+    fn flush_group(&mut self, codec: &Codec, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        let mut acc: u64 = 0;
+        for &value in &self.group {
+            acc = (acc << codec.bits) | value as u64;
+        }
+        let bits = self.group.len() as u32 * codec.bits;
+        let extra = bits % 8;
+        if acc & ((1u64 << extra) - 1) != 0 {
+            return Err(self.error(DecodeErrorKind::NonZeroTrailingBits, self.last_char_offset));
+        }
+        acc >>= extra;
+        for i in (0..bits / 8).rev() {
+            out.push((acc >> (i * 8)) as u8);
+        }
+        self.group.clear();
+        self.padding = 0;
+        Ok(())
+    }
+
+    fn finish(&mut self, codec: &Codec, out: &mut Vec<u8>) -> Result<(), DecodeError> {
+        if self.group.is_empty() {
+            return Ok(());
+        }
+        if self.padding > 0 || codec.padded {
+            // either not enough '=', or none at all
+            return Err(self.error(DecodeErrorKind::MissingPadding, self.offset));
+        }
+        if !valid_group_len(codec, self.group.len()) {
+            return Err(self.error(DecodeErrorKind::InvalidLength, self.last_char_offset));
+        }
+        self.flush_group(codec, out)?;
+        self.ended = true;
+        Ok(())
+    }
+}
+
+// Whether n characters can be the end of the data: they have to hold at least one byte, and
+// fewer leftover bits than one character has (otherwise that character would be pointless).
+fn valid_group_len(codec: &Codec, n: usize) -> bool {
+    let bits = n as u32 * codec.bits;
+    bits >= 8 && bits % 8 < codec.bits
+}
+
+pub struct Decoder<'c, R: Read> {
+    codec: &'c Codec,
+    inner: R,
+    state: DecodeState,
+    // decoded bytes not handed out yet, from read_pos on
+    output: Vec<u8>,
+    read_pos: usize,
+    done: bool,
+}
+
+impl<R: Read> Read for Decoder<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let mut chunk = [0u8; 4096];
+        while self.read_pos == self.output.len() && !self.done {
+            self.output.clear();
+            self.read_pos = 0;
+            let n = self.inner.read(&mut chunk)?;
+            let result = if n == 0 {
+                self.done = true;
+                self.state.finish(self.codec, &mut self.output)
+            } else {
+                chunk[..n]
+                    .iter()
+                    .try_for_each(|&c| self.state.push(self.codec, c, &mut self.output))
+            };
+            if let Err(err) = result {
+                self.done = true;
+                return Err(io::Error::new(io::ErrorKind::InvalidData, err));
+            }
+        }
+        let n = buf.len().min(self.output.len() - self.read_pos);
+        buf[..n].copy_from_slice(&self.output[self.read_pos..self.read_pos + n]);
+        self.read_pos += n;
+        Ok(n)
+    }
+}
+
+/* RFC 4648 */
+// The test vectors from section 10 of RFC 4648: the input, then its base64, base32, base32hex
+// and base16 encodings.
+pub const RFC_4648_VECTORS: [(&str, &str, &str, &str, &str); 7] = [
+    ("", "", "", "", ""),
+    ("f", "Zg==", "MY======", "CO======", "66"),
+    ("fo", "Zm8=", "MZXQ====", "CPNG====", "666F"),
+    ("foo", "Zm9v", "MZXW6===", "CPNMU===", "666F6F"),
+    ("foob", "Zm9vYg==", "MZXW6YQ=", "CPNMUOG=", "666F6F62"),
+    ("fooba", "Zm9vYmE=", "MZXW6YTB", "CPNMUOJ1", "666F6F6261"),
+    (
+        "foobar",
+        "Zm9vYmFy",
+        "MZXW6YTBOI======",
+        "CPNMUOJ1E8======",
+        "666F6F626172",
+    ),
+];
+
+// Encodes and decodes every vector with every codec, returning one line per check that failed.
+pub fn check_rfc_4648_vectors() -> Vec<String> {
+    let codecs = [
+        Codec::base64(),
+        Codec::base32(),
+        Codec::base32_hex(),
+        Codec::hex_upper(),
+    ];
+    let mut failures: Vec<String> = Vec::new();
+    for (input, base64, base32, base32_hex, base16) in RFC_4648_VECTORS {
+        for (codec, expected) in codecs.iter().zip([base64, base32, base32_hex, base16]) {
+            let encoded = codec.encode(input.as_bytes());
+            if encoded != expected {
+                failures.push(format!(
+                    "{}: encoding {input:?} gave {encoded:?}, expected {expected:?}",
+                    codec.name()
+                ));
+            }
+            match codec.decode(expected) {
+                Ok(decoded) if decoded == input.as_bytes() => {}
+                Ok(decoded) => failures.push(format!(
+                    "{}: decoding {expected:?} gave {:?}, expected {input:?}",
+                    codec.name(),
+                    String::from_utf8_lossy(&decoded)
+                )),
+                Err(err) => failures.push(format!(
+                    "{}: decoding {expected:?} failed: {err}",
+                    codec.name()
+                )),
+            }
+        }
+    }
+    failures
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 4648 section 10, one test per encoding so a failure says which one
+    // column is 0 for base64, 1 for base32, 2 for base32hex and 3 for base16
+    fn check(codec: Codec, column: usize) {
+        for (input, base64, base32, base32_hex, base16) in RFC_4648_VECTORS {
+            let expected = [base64, base32, base32_hex, base16][column];
+            assert_eq!(
+                codec.encode(input.as_bytes()),
+                expected,
+                "encoding {input:?}"
+            );
+            assert_eq!(
+                codec.decode(expected).unwrap(),
+                input.as_bytes(),
+                "decoding {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn base64_vectors() {
+        check(Codec::base64(), 0);
+    }
+
+    #[test]
+    fn base32_vectors() {
+        check(Codec::base32(), 1);
+    }
+
+    #[test]
+    fn base32_hex_vectors() {
+        check(Codec::base32_hex(), 2);
+    }
+
+    #[test]
+    fn base16_vectors() {
+        check(Codec::hex_upper(), 3);
+        // lowercase hex is what hex() writes, and both cases decode
+        assert_eq!(Codec::hex().encode(b"foobar"), "666f6f626172");
+        assert_eq!(
+            Codec::hex_upper().decode("666f6F626172").unwrap(),
+            b"foobar"
+        );
+    }
+
+    #[test]
+    fn selftest_finds_no_failures() {
+        assert_eq!(check_rfc_4648_vectors(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn unpadded_and_url_safe() {
+        let codec = Codec::base64().unpadded();
+        assert_eq!(codec.encode(b"fo"), "Zm8");
+        assert_eq!(codec.decode("Zm8").unwrap(), b"fo");
+        assert_eq!(Codec::base64_url().encode(&[0xfb, 0xff]), "-_8=");
+        assert_eq!(Codec::base64().encode(&[0xfb, 0xff]), "+/8=");
+    }
+
+    #[test]
+    fn bad_input_is_an_error() {
+        assert!(Codec::base64().decode("Zm9v!").is_err());
+        assert!(Codec::base32().decode("MY=====").is_err());
+        assert!(Codec::hex().decode("666").is_err());
+    }
+
+    #[test]
+    fn streams_match_the_one_shot_functions() {
+        let data: Vec<u8> = (0..=255).cycle().take(1000).collect();
+        for codec in [Codec::base64(), Codec::base32(), Codec::hex()] {
+            let mut encoder = codec.encoder(Vec::new()).wrap(0);
+            // a few bytes at a time, so groups are split across writes
+            for chunk in data.chunks(7) {
+                encoder.write_all(chunk).unwrap();
+            }
+            let encoded = String::from_utf8(encoder.finish().unwrap()).unwrap();
+            assert_eq!(encoded, codec.encode(&data), "{}", codec.name());
+            let mut decoded = Vec::new();
+            codec
+                .decoder(encoded.as_bytes())
+                .read_to_end(&mut decoded)
+                .unwrap();
+            assert_eq!(decoded, data, "{}", codec.name());
+        }
+    }
+}
@@ -0,0 +1,585 @@
+/* Hex dump */
+// print_binary in main.rs prints every byte of a string in binary, next to whatever
+// char::from_u32 makes of that single byte. For ASCII that's the right character, but a
+// character like 'é' is two bytes in UTF-8, and neither byte on its own is an 'é'.
+//
+// This is the same idea grown into an xxd-style dump:
+//   00000000: 48 c3 a9 6c 6c 6f  Hé llo
+// Each line starts with the offset of its first byte, then the bytes in binary, octal, hex or
+// decimal, then a gutter showing the text those bytes make up. The gutter decodes UTF-8, so the
+// 'é' appears under its first byte (c3) and the second byte (a9) gets an empty column. With
+// colour on, bytes that start a multi-byte character, bytes that continue one, and bytes that
+// aren't valid UTF-8 at all each get their own colour.
+//
+// reverse() reads a dump back into bytes, so a dump can be edited by hand and turned back into
+// a file.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    Binary,
+    Octal,
+    Hex,
+    Decimal,
+}
+
+impl Mode {
+    // characters per byte
+    fn cell_width(self) -> usize {
+        match self {
+            Mode::Binary => 8,
+            Mode::Octal | Mode::Decimal => 3,
+            Mode::Hex => 2,
+        }
+    }
+
+    fn radix(self) -> u32 {
+        match self {
+            Mode::Binary => 2,
+            Mode::Octal => 8,
+            Mode::Hex => 16,
+            Mode::Decimal => 10,
+        }
+    }
+
+    // xxd's defaults: 16 bytes per line, or 6 in binary so the line stays readable
+    pub fn default_per_line(self) -> usize {
+        match self {
+            Mode::Binary => 6,
+            Mode::Octal | Mode::Decimal => 12,
+            Mode::Hex => 16,
+        }
+    }
+
+    fn format(self, byte: u8) -> String {
+        match self {
+            Mode::Binary => format!("{byte:08b}"),
+            Mode::Octal => format!("{byte:03o}"),
+            Mode::Hex => format!("{byte:02x}"),
+            // zero padded rather than space padded, so a dump never has two spaces in a row
+            // before the gutter (reverse() relies on that)
+            Mode::Decimal => format!("{byte:03}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Gutter {
+    None,
+    // printable ASCII, '.' for everything else, like xxd
+    Ascii,
+    // decoded UTF-8 characters
+    Utf8,
+}
+
+// More bytes per line than anyone can read; per_line is clamped to this, so working out a line's
+// width can't overflow.
+pub const MAX_PER_LINE: usize = 4096;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DumpOptions {
+    pub mode: Mode,
+    // bytes per line (1 to MAX_PER_LINE); None uses the mode's default
+    pub per_line: Option<usize>,
+    // the offset of the first byte to show
+    pub seek: usize,
+    // how many bytes to show; None goes to the end
+    pub length: Option<usize>,
+    pub gutter: Gutter,
+    pub color: bool,
+}
+
+impl Default for DumpOptions {
+    fn default() -> Self {
+        DumpOptions {
+            mode: Mode::Hex,
+            per_line: None,
+            seek: 0,
+            length: None,
+            gutter: Gutter::Utf8,
+            color: false,
+        }
+    }
+}
+
+/* Classifying bytes */
+// What a byte is doing in UTF-8. Continuation bytes are only called that when they belong to a
+// valid character; a stray one is Invalid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ByteClass {
+    Ascii,
+    // the first byte of a multi-byte character: the character, and how many bytes it takes
+    Lead(char, usize),
+    Continuation,
+    Invalid,
+}
+
+fn classify(bytes: &[u8]) -> Vec<ByteClass> {
+    let mut classes: Vec<ByteClass> = Vec::with_capacity(bytes.len());
+    let mut i: usize = 0;
+    while i < bytes.len() {
+        let byte = bytes[i];
+        if byte.is_ascii() {
+            classes.push(ByteClass::Ascii);
+            i += 1;
+            continue;
+        }
+        // the number of leading 1 bits says how long the sequence is: 110xxxxx starts a
+        // two-byte character, 1110xxxx three bytes and 11110xxx four
+        let len: usize = match byte.leading_ones() {
+            2..=4 => byte.leading_ones() as usize,
+            _ => 0,
+        };
+        let decoded = bytes
+            .get(i..i + len)
+            .and_then(|sequence| std::str::from_utf8(sequence).ok())
+            .and_then(|text| text.chars().next());
+        match decoded {
+            Some(c) => {
+                classes.push(ByteClass::Lead(c, len));
+                classes.extend(std::iter::repeat_n(ByteClass::Continuation, len - 1));
+                i += len;
+            }
+            None => {
+                classes.push(ByteClass::Invalid);
+                i += 1;
+            }
+        }
+    }
+    classes
+}
+
+// Roughly how many terminal columns a character takes: 2 for the wide East Asian scripts and
+// most emoji, 0 for combining marks and invisible characters, 1 for everything else. Terminals
+// use a big table for this; these ranges cover the common cases.
+fn display_width(c: char) -> usize {
+    match c {
+        '\u{300}'..='\u{36F}'
+        | '\u{200B}'..='\u{200F}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}' => 0,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{303E}'
+        | '\u{3041}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{1F300}'..='\u{1F64F}'
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{20000}'..='\u{3FFFD}' => 2,
+        _ => 1,
+    }
+}
+
+/* Colours */
+const LEAD: &str = "\x1b[1;33m";
+const CONTINUATION: &str = "\x1b[33m";
+const INVALID: &str = "\x1b[1;31m";
+const RESET: &str = "\x1b[0m";
+
+fn paint(text: &str, class: ByteClass, color: bool) -> String {
+    let code = match class {
+        _ if !color => return text.to_string(),
+        ByteClass::Ascii => return text.to_string(),
+        ByteClass::Lead(..) => LEAD,
+        ByteClass::Continuation => CONTINUATION,
+        ByteClass::Invalid => INVALID,
+    };
+    format!("{code}{text}{RESET}")
+}
+
+/* Dumping */
+pub fn dump(bytes: &[u8], options: &DumpOptions) -> String {
+    let mode = options.mode;
+    let per_line = options
+        .per_line
+        .unwrap_or(mode.default_per_line())
+        .clamp(1, MAX_PER_LINE);
+    // classify everything, so a window that starts or ends in the middle of a character still
+    // colours its bytes correctly
+    let classes = classify(bytes);
+    let start = options.seek.min(bytes.len());
+    let end = match options.length {
+        Some(length) => start.saturating_add(length).min(bytes.len()),
+        None => bytes.len(),
+    };
+
+    let mut out = String::new();
+    let mut offset = start;
+    while offset < end {
+        let line_end = offset.saturating_add(per_line).min(end);
+        out.push_str(&format!("{offset:08x}:"));
+        for i in offset..line_end {
+            out.push(' ');
+            out.push_str(&paint(&mode.format(bytes[i]), classes[i], options.color));
+        }
+        if options.gutter != Gutter::None {
+            // pad a short last line so its gutter lines up with the others
+            let missing = per_line - (line_end - offset);
+            out.push_str(&" ".repeat(missing * (mode.cell_width() + 1)));
+            out.push_str("  ");
+            out.push_str(&gutter(bytes, &classes, offset, line_end, options));
+        }
+        out.push('\n');
+        offset = line_end;
+    }
+    out
+}
+
+// The text column for bytes[start..end]. Every byte gets (at least) one column, so a character
+// of n bytes is printed under its first byte and followed by enough spaces to fill n columns.
+fn gutter(
+    bytes: &[u8],
+    classes: &[ByteClass],
+    start: usize,
+    end: usize,
+    options: &DumpOptions,
+) -> String {
+    let mut out = String::new();
+    // bytes left over from a character that started on the previous line each get a column
+    let mut columns_owed: usize = classes[start..end]
+        .iter()
+        .take_while(|&&class| class == ByteClass::Continuation)
+        .count();
+    for i in start..end {
+        let shown: String = match classes[i] {
+            ByteClass::Ascii if (0x20..0x7f).contains(&bytes[i]) => (bytes[i] as char).to_string(),
+            ByteClass::Lead(c, len) if options.gutter == Gutter::Utf8 => {
+                // the bytes of the character on this line; the rest start the next one, which
+                // gives them a column each
+                let here = len.min(end - i);
+                let width = display_width(c);
+                if width == 0 || c.is_control() || width > here {
+                    // a combining mark would land on the previous column, a control character
+                    // would mess up the terminal, and a wide character with only its first byte
+                    // on the line would stick out past the end of it
+                    columns_owed = here - 1;
+                    "·".to_string()
+                } else {
+                    // a wide character's second column uses up one of the spare bytes
+                    columns_owed = here - width;
+                    c.to_string()
+                }
+            }
+            ByteClass::Continuation if options.gutter == Gutter::Utf8 => {
+                if columns_owed == 0 {
+                    continue;
+                }
+                columns_owed -= 1;
+                " ".to_string()
+            }
+            _ => ".".to_string(),
+        };
+        out.push_str(&paint(&shown, classes[i], options.color));
+    }
+    out
+}
+
+/* Reversing */
+// The biggest gap between lines that reverse fills with zeros. A dump that jumps further than
+// this is more likely a mistake (or a trick) than a file with 16 MiB of zeros in the middle, and
+// filling it in would mean allocating all of it.
+pub const MAX_GAP: usize = 16 << 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReverseError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ReverseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ReverseError {}
+
+// Turns a dump back into bytes. Like xxd -r, each line's bytes go to the offset at the start of
+// the line, and any gap between lines is filled with zeros; unlike xxd -r, offsets count from
+// the first line, so a dump of a --seek window turns back into just that window. Colour codes
+// are skipped, and so is the gutter (everything after the first double space), so the gutter
+// doesn't need to match if the bytes were edited. An offset more than MAX_GAP past the bytes so far
+// is an error.
+pub fn reverse(dump: &str, mode: Mode) -> Result<Vec<u8>, ReverseError> {
+    let mut out: Vec<u8> = Vec::new();
+    let mut first_offset: Option<usize> = None;
+    for (index, raw_line) in dump.lines().enumerate() {
+        let error = |message: String| ReverseError {
+            line: index + 1,
+            message,
+        };
+        let line = strip_ansi(raw_line);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (offset_text, rest) = line
+            .split_once(':')
+            .ok_or_else(|| error("expected an offset followed by ':'".to_string()))?;
+        let offset = usize::from_str_radix(offset_text.trim(), 16)
+            .map_err(|_| error(format!("'{}' is not a hex offset", offset_text.trim())))?;
+        let base = *first_offset.get_or_insert(offset);
+        let position = offset
+            .checked_sub(base)
+            .ok_or_else(|| error(format!("offset {offset:x} comes before the first line's")))?;
+        if position - out.len().min(position) > MAX_GAP {
+            return Err(error(format!(
+                "offset {offset:x} leaves a gap of more than {} MiB",
+                MAX_GAP >> 20
+            )));
+        }
+        if out.len() < position {
+            out.resize(position, 0);
+        }
+        let cells = rest.strip_prefix(' ').unwrap_or(rest);
+        let cells = cells.split("  ").next().unwrap_or("");
+        for (i, cell) in cells.split_whitespace().enumerate() {
+            if cell.len() != mode.cell_width() {
+                return Err(error(format!(
+                    "'{cell}' should be {} digits long",
+                    mode.cell_width()
+                )));
+            }
+            let byte = u8::from_str_radix(cell, mode.radix())
+                .map_err(|_| error(format!("'{cell}' is not a byte in base {}", mode.radix())))?;
+            if position + i < out.len() {
+                out[position + i] = byte;
+            } else {
+                out.push(byte);
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            // skip "[...m"
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MODES: [Mode; 4] = [Mode::Binary, Mode::Octal, Mode::Hex, Mode::Decimal];
+
+    // Some of everything: ASCII, two, three and four byte characters, wide ones, a combining mark,
+    // control characters, and bytes that aren't UTF-8.
+    fn sample() -> Vec<u8> {
+        let mut bytes = "Hé llo, 日本語 😀 e\u{301} \t\n\x7f ~".as_bytes().to_vec();
+        bytes.extend([0xff, 0xc3, 0x28, 0xa9, 0xe6, 0x97, 0x00, 0xf0, 0x9f]);
+        bytes.extend((0..=255).rev());
+        bytes
+    }
+
+    #[test]
+    fn reverse_undoes_dump() {
+        let bytes = sample();
+        for mode in MODES {
+            for gutter in [Gutter::None, Gutter::Ascii, Gutter::Utf8] {
+                for color in [false, true] {
+                    for per_line in [None, Some(1), Some(3), Some(7), Some(16), Some(33)] {
+                        let options = DumpOptions {
+                            mode,
+                            per_line,
+                            gutter,
+                            color,
+                            ..DumpOptions::default()
+                        };
+                        let dumped = dump(&bytes, &options);
+                        assert_eq!(reverse(&dumped, mode), Ok(bytes.clone()), "{options:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn reverse_undoes_a_window() {
+        let bytes = sample();
+        for mode in MODES {
+            for (seek, length) in [
+                (0, Some(0)),
+                (1, Some(5)),
+                (2, None),
+                (17, Some(40)),
+                (300, None),
+            ] {
+                let options = DumpOptions {
+                    mode,
+                    seek,
+                    length,
+                    color: true,
+                    ..DumpOptions::default()
+                };
+                let start = seek.min(bytes.len());
+                let end = length.map_or(bytes.len(), |length| (start + length).min(bytes.len()));
+                let dumped = dump(&bytes, &options);
+                assert_eq!(reverse(&dumped, mode), Ok(bytes[start..end].to_vec()));
+            }
+        }
+    }
+
+    #[test]
+    fn dumps_look_like_xxd() {
+        let options = DumpOptions {
+            per_line: Some(4),
+            ..DumpOptions::default()
+        };
+        assert_eq!(
+            dump("Hé llo".as_bytes(), &options),
+            "00000000: 48 c3 a9 20  Hé  \n00000004: 6c 6c 6f     llo\n"
+        );
+        let options = DumpOptions {
+            mode: Mode::Binary,
+            gutter: Gutter::Ascii,
+            ..options
+        };
+        assert_eq!(
+            dump("é!".as_bytes(), &options),
+            "00000000: 11000011 10101001 00100001           ..!\n"
+        );
+        assert_eq!(dump(b"", &DumpOptions::default()), "");
+    }
+
+    // How many terminal columns the gutter of each line takes, worked out from the bytes the line
+    // shows.
+    fn gutter_widths(dumped: &str, mode: Mode, per_line: usize) -> Vec<(usize, usize)> {
+        let gutter_at = "00000000:".len() + per_line * (mode.cell_width() + 1) + 2;
+        dumped
+            .lines()
+            .map(|line| {
+                let line = strip_ansi(line);
+                let cells = line[..gutter_at].split_whitespace().count() - 1;
+                let width = line[gutter_at..].chars().map(display_width).sum();
+                (cells, width)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn the_utf8_gutter_has_a_column_per_byte() {
+        let bytes = sample();
+        for per_line in 1..=12 {
+            for color in [false, true] {
+                let options = DumpOptions {
+                    per_line: Some(per_line),
+                    color,
+                    ..DumpOptions::default()
+                };
+                let dumped = dump(&bytes, &options);
+                for (line, (cells, width)) in gutter_widths(&dumped, Mode::Hex, per_line)
+                    .into_iter()
+                    .enumerate()
+                {
+                    assert_eq!(width, cells, "line {line} of\n{dumped}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn characters_split_across_lines() {
+        let options = |per_line| DumpOptions {
+            per_line: Some(per_line),
+            ..DumpOptions::default()
+        };
+        // é's second byte starts the next line, and gets an empty column there
+        assert_eq!(
+            dump("aé".as_bytes(), &options(2)),
+            "00000000: 61 c3  aé\n00000002: a9      \n"
+        );
+        // 日 takes two columns, so whichever line its third byte is on has a space for it
+        assert_eq!(
+            dump("日".as_bytes(), &options(3)),
+            "00000000: e6 97 a5  日 \n"
+        );
+        assert_eq!(
+            dump("a日".as_bytes(), &options(3)),
+            "00000000: 61 e6 97  a日\n00000003: a5         \n"
+        );
+        // with only its first byte on the line there's no room for it
+        assert_eq!(
+            dump("ab日".as_bytes(), &options(3)),
+            "00000000: 61 62 e6  ab·\n00000003: 97 a5       \n"
+        );
+    }
+
+    #[test]
+    fn bad_dumps() {
+        let error = |dump: &str| reverse(dump, Mode::Hex).unwrap_err().to_string();
+        assert_eq!(error("48 65"), "line 1: expected an offset followed by ':'");
+        assert_eq!(error("\n\nxyz: 48"), "line 3: 'xyz' is not a hex offset");
+        assert_eq!(error("00000000: 4"), "line 1: '4' should be 2 digits long");
+        assert_eq!(
+            error("00000000: 4g"),
+            "line 1: '4g' is not a byte in base 16"
+        );
+        assert_eq!(
+            reverse("00000000: 256", Mode::Decimal)
+                .unwrap_err()
+                .to_string(),
+            "line 1: '256' is not a byte in base 10"
+        );
+    }
+
+    #[test]
+    fn offsets_going_backwards() {
+        // before the first line is an error
+        assert_eq!(
+            reverse("00000010: 41\n00000000: 42", Mode::Hex),
+            Err(ReverseError {
+                line: 2,
+                message: "offset 0 comes before the first line's".to_string(),
+            })
+        );
+        // anywhere after it overwrites what's there, like xxd -r
+        assert_eq!(
+            reverse("00000010: 41 41 41\n00000020: 43\n00000011: 42", Mode::Hex),
+            Ok([&[0x41, 0x42, 0x41][..], &[0; 13], &[0x43]].concat())
+        );
+    }
+
+    #[test]
+    fn gaps_are_filled_up_to_max_gap() {
+        // one byte, then a line that leaves exactly MAX_GAP zeros after it
+        let dumped = format!("00000000: 41\n{:08x}: 42", 1 + MAX_GAP);
+        let bytes = reverse(&dumped, Mode::Hex).unwrap();
+        assert_eq!(bytes.len(), MAX_GAP + 2);
+        assert_eq!((bytes[0], bytes[MAX_GAP + 1]), (0x41, 0x42));
+        assert!(bytes[1..=MAX_GAP].iter().all(|&byte| byte == 0));
+
+        let dumped = format!("00000000: 41\n{:08x}: 42", 2 + MAX_GAP);
+        assert_eq!(
+            reverse(&dumped, Mode::Hex),
+            Err(ReverseError {
+                line: 2,
+                message: format!("offset {:x} leaves a gap of more than 16 MiB", 2 + MAX_GAP),
+            })
+        );
+        // where the first line is doesn't count, since offsets are from there
+        assert_eq!(
+            reverse("ffffffffff: 41 42", Mode::Hex),
+            Ok(vec![0x41, 0x42])
+        );
+        assert_eq!(
+            reverse(&format!("{:x}: 41", usize::MAX), Mode::Hex),
+            Ok(vec![0x41])
+        );
+    }
+}
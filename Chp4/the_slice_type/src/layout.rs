@@ -0,0 +1,719 @@
+/* Text layout */
+// The chapter binaries print their notes as big string literals, wrapped by hand at around 100
+// columns. On a narrower terminal every one of those lines wraps again wherever the terminal runs
+// out of room, in the middle of a word, and the indentation is lost.
+//
+// Layout re-flows text to a given width instead. The text is split into blocks:
+//   * paragraphs: lines with the same indentation, which are joined and wrapped again with the
+//     words from tokenizer.rs (slices of the text, so the words themselves aren't copied)
+//   * list items starting with "- ", "* " or "1. ", wrapped with a hanging indent so the lines
+//     after the first line up under the text instead of under the marker
+//   * code, left exactly as it is: anything shown in bright white (\x1b[97m, which is how these
+//     notes print code), lines styled from start to end (the headers), shell lines starting with
+//     "$ ", and lines indented further than the paragraph around them
+//   * blank lines, which separate the others
+// Escape sequences take up no room on screen, so they're left out of every width, and they're
+// never split from the word they're attached to. When a paragraph wraps while a style is on,
+// the style is turned off at the end of the line and back on after the indentation, so the
+// indentation isn't underlined.
+//
+// On top of that a layout can justify paragraphs (spread every line but the last out to the full
+// width) and hyphenate words that don't fit at the end of a line.
+use crate::tokenizer;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+// Wider than this and lines get too long to read comfortably.
+pub const MAX_WIDTH: usize = 100;
+// The width when the terminal can't be asked, the usual size of a new terminal window.
+pub const DEFAULT_WIDTH: usize = 80;
+const MIN_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    width: usize,
+    justify: bool,
+    hyphenate: bool,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Layout::new(80)
+    }
+}
+
+impl Layout {
+    pub fn new(width: usize) -> Self {
+        Layout {
+            width: width.max(MIN_WIDTH),
+            justify: false,
+            hyphenate: false,
+        }
+    }
+
+    // As wide as the terminal, up to MAX_WIDTH, or DEFAULT_WIDTH when the output isn't going to
+    // one.
+    pub fn for_terminal() -> Self {
+        Layout::if_terminal().unwrap_or_else(|| Layout::new(DEFAULT_WIDTH))
+    }
+
+    // The same, but None when the output isn't going to a terminal (and $COLUMNS isn't set), so
+    // text that's going into a file or another program can be left as it was written.
+    pub fn if_terminal() -> Option<Self> {
+        terminal_width().map(|width| Layout::new(width.min(MAX_WIDTH)))
+    }
+
+    pub fn justify(mut self, justify: bool) -> Self {
+        self.justify = justify;
+        self
+    }
+
+    pub fn hyphenate(mut self, hyphenate: bool) -> Self {
+        self.hyphenate = hyphenate;
+        self
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    // The whole text re-flowed. Line breaks between blocks are kept, so the result ends with a
+    // newline exactly when the text does.
+    pub fn reflow(&self, text: &str) -> String {
+        let mut out: Vec<String> = Vec::new();
+        let mut style = Style::default();
+        let mut paragraph: Option<Paragraph> = None;
+        for line in text.split('\n') {
+            let style_before = style.clone();
+            style.update(line);
+            let (indent, rest) = split_indent(line);
+            if rest.trim().is_empty() {
+                self.flush(&mut paragraph, &mut out);
+                out.push(String::new());
+                continue;
+            }
+            if style_before.is_code() || is_heading(rest) || rest.starts_with("$ ") {
+                self.flush(&mut paragraph, &mut out);
+                out.push(line.to_string());
+                continue;
+            }
+            let marker = list_marker(rest);
+            if let Some(current) = paragraph.as_mut() {
+                if marker.is_none() {
+                    match current.continues(indent) {
+                        Continues::Yes => {
+                            current.lines.push(rest);
+                            if style.is_code() {
+                                self.flush(&mut paragraph, &mut out);
+                            }
+                            continue;
+                        }
+                        Continues::Preformatted => {
+                            self.flush(&mut paragraph, &mut out);
+                            out.push(line.to_string());
+                            continue;
+                        }
+                        Continues::No => {}
+                    }
+                }
+                self.flush(&mut paragraph, &mut out);
+            }
+            paragraph = Some(Paragraph {
+                indent,
+                marker,
+                continuation: None,
+                lines: vec![rest],
+                style: style_before,
+            });
+            // code starting at the end of the line: the lines after it aren't part of this
+            if style.is_code() {
+                self.flush(&mut paragraph, &mut out);
+            }
+        }
+        self.flush(&mut paragraph, &mut out);
+        out.join("\n")
+    }
+
+    fn flush(&self, paragraph: &mut Option<Paragraph>, out: &mut Vec<String>) {
+        if let Some(paragraph) = paragraph.take() {
+            let mut text = paragraph.lines.join(" ");
+            let mut first = paragraph.indent.to_string();
+            let rest = match (paragraph.marker, paragraph.continuation) {
+                (Some(marker), _) => {
+                    // the marker goes with the indentation, so justifying doesn't stretch it
+                    first.push_str(&text[..marker]);
+                    text.drain(..marker);
+                    " ".repeat(display_width(&first))
+                }
+                (None, Some(continuation)) => continuation.to_string(),
+                (None, None) => first.clone(),
+            };
+            out.extend(self.fill(&text, &first, &rest, paragraph.style));
+        }
+    }
+
+    // One paragraph wrapped: the first line starts with first_indent, the others with indent.
+    // style is whatever was switched on before the paragraph started.
+    pub fn fill(&self, text: &str, first_indent: &str, indent: &str, style: Style) -> Vec<String> {
+        let mut lines: Vec<Vec<Cow<str>>> = vec![Vec::new()];
+        let mut used = display_width(first_indent);
+        let mut queue: VecDeque<Cow<str>> = tokenizer::words(text).map(Cow::Borrowed).collect();
+        while let Some(word) = queue.pop_front() {
+            let line = lines.last_mut().expect("there's always a line");
+            let gap = usize::from(!line.is_empty());
+            let width = display_width(&word);
+            if used + gap + width <= self.width {
+                line.push(word);
+                used += gap + width;
+                continue;
+            }
+            if self.hyphenate {
+                let room = self.width.saturating_sub(used + gap);
+                if let Some((head, tail)) = split_word(&word, room, line.is_empty()) {
+                    line.push(Cow::Owned(head));
+                    queue.push_front(Cow::Owned(tail));
+                    lines.push(Vec::new());
+                    used = display_width(indent);
+                    continue;
+                }
+            }
+            if line.is_empty() {
+                // longer than a whole line, so it sticks out
+                line.push(word);
+                used += width;
+                continue;
+            }
+            lines.push(Vec::new());
+            used = display_width(indent);
+            queue.push_front(word);
+        }
+
+        let mut style = style;
+        let last = lines.len() - 1;
+        let mut out: Vec<String> = Vec::with_capacity(lines.len());
+        for (i, words) in lines.iter().enumerate() {
+            let mut line = String::new();
+            line.push_str(if i == 0 { first_indent } else { indent });
+            if i > 0 {
+                line.push_str(&style.active);
+            }
+            let gaps = self.gaps(words, display_width(&line), i == last);
+            for (j, word) in words.iter().enumerate() {
+                if j > 0 {
+                    line.push_str(&" ".repeat(gaps[j - 1]));
+                }
+                line.push_str(word);
+                style.update(word);
+            }
+            if i < last && style.is_on() {
+                line.push_str(RESET);
+            }
+            out.push(line);
+        }
+        out
+    }
+
+    // The spaces between the words of a line: one each, or spread out to fill the width when
+    // justifying (except on a paragraph's last line).
+    fn gaps(&self, words: &[Cow<str>], indent: usize, last: bool) -> Vec<usize> {
+        let count = words.len().saturating_sub(1);
+        if !self.justify || last || count == 0 {
+            return vec![1; count];
+        }
+        let used: usize = indent + words.iter().map(|w| display_width(w)).sum::<usize>();
+        let spaces = self.width.saturating_sub(used).max(count);
+        (0..count)
+            .map(|i| spaces / count + usize::from(i < spaces % count))
+            .collect()
+    }
+}
+
+struct Paragraph<'a> {
+    indent: &'a str,
+    // the width of the list marker, for a list item
+    marker: Option<usize>,
+    // the indentation of the paragraph's second line, once there is one
+    continuation: Option<&'a str>,
+    lines: Vec<&'a str>,
+    style: Style,
+}
+
+enum Continues {
+    Yes,
+    No,
+    // indented well past the paragraph, like a code example
+    Preformatted,
+}
+
+impl<'a> Paragraph<'a> {
+    fn continues(&mut self, indent: &'a str) -> Continues {
+        if let Some(continuation) = self.continuation {
+            return if indent == continuation {
+                Continues::Yes
+            } else if display_width(indent) >= display_width(continuation) + 4 {
+                Continues::Preformatted
+            } else {
+                Continues::No
+            };
+        }
+        let first = display_width(self.indent);
+        let width = display_width(indent);
+        let continues = match self.marker {
+            // the lines of a list item are indented past its marker
+            Some(_) => width > first,
+            // anything less than 4 more is a first line indent or a hanging indent
+            None if width >= first + 4 => return Continues::Preformatted,
+            None => true,
+        };
+        if continues {
+            self.continuation = Some(indent);
+            Continues::Yes
+        } else {
+            Continues::No
+        }
+    }
+}
+
+/* Styles */
+const RESET: &str = "\x1b[0m";
+
+// The SGR escape sequences (\x1b[...m) that are switched on at some point in the text.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Style {
+    active: String,
+}
+
+impl Style {
+    pub fn update(&mut self, text: &str) {
+        for sequence in escapes(text) {
+            if !sequence.ends_with('m') {
+                continue;
+            }
+            let params = &sequence[2..sequence.len() - 1];
+            if params.is_empty() || params == "0" {
+                self.active.clear();
+            } else {
+                self.active.push_str(sequence);
+            }
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    // Bright white, which is what the notes print code in.
+    pub fn is_code(&self) -> bool {
+        escapes(&self.active).any(|sequence| {
+            sequence[2..sequence.len() - 1]
+                .split(';')
+                .any(|param| param == "97")
+        })
+    }
+}
+
+// The CSI escape sequences in text (ESC, '[', parameters, then a final byte from '@' to '~').
+fn escapes(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        let start = rest.find("\x1b[")?;
+        let end = rest[start + 2..]
+            .find(|c: char| ('@'..='~').contains(&c))
+            .map_or(rest.len(), |i| start + 2 + i + 1);
+        let sequence = &rest[start..end];
+        rest = &rest[end..];
+        Some(sequence)
+    })
+}
+
+/* Measuring */
+// How many columns text takes up in a terminal: escape sequences take none, combining marks
+// (like the accents in "नमस्ते") take none, and wide characters (CJK, most emoji) take two.
+pub fn display_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            if chars.next() == Some('[') {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        }
+        width += char_width(c);
+    }
+    width
+}
+
+fn char_width(c: char) -> usize {
+    match c as u32 {
+        0x0300..=0x036F | 0x0900..=0x0903 | 0x093A..=0x094F | 0x200B..=0x200F | 0xFE00..=0xFE0F => {
+            0
+        }
+        0x1100..=0x115F
+        | 0x2E80..=0xA4CF
+        | 0xAC00..=0xD7A3
+        | 0xF900..=0xFAFF
+        | 0xFE30..=0xFE4F
+        | 0xFF00..=0xFF60
+        | 0xFFE0..=0xFFE6
+        | 0x1F300..=0x1F64F
+        | 0x1F900..=0x1F9FF
+        | 0x20000..=0x3FFFD => 2,
+        _ if c.is_control() => 0,
+        _ => 1,
+    }
+}
+
+// The width of the terminal stdout is going to: $COLUMNS if it's set, or else the terminal's own
+// idea of its width (DEFAULT_WIDTH if it won't say). None when stdout is a pipe or a file and
+// there's no $COLUMNS, since then there's no screen for the text to fit. Looked up once, since
+// prose! asks for it for every paragraph.
+pub fn terminal_width() -> Option<usize> {
+    static WIDTH: OnceLock<Option<usize>> = OnceLock::new();
+    *WIDTH.get_or_init(|| {
+        let columns = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.trim().parse().ok())
+            .filter(|&columns| columns > 0);
+        if columns.is_some() {
+            return columns;
+        }
+        std::io::stdout()
+            .is_terminal()
+            .then(|| window_columns().unwrap_or(DEFAULT_WIDTH))
+    })
+}
+
+// Asks the terminal on stdout how big its window is with the TIOCGWINSZ ioctl, the way stty size
+// does it, only without starting another process. TIOCGWINSZ has one number on Linux and another
+// on macOS and the BSDs (and on the few Linux architectures that copied theirs), so this only
+// asks on those systems.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+))]
+fn window_columns() -> Option<usize> {
+    use std::ffi::c_int;
+    use std::os::fd::AsRawFd;
+
+    // The declaration has to match the C library's: glibc, macOS and the BSDs take the request as
+    // an unsigned long, but musl and Android's bionic take an int.
+    #[cfg(any(target_env = "musl", target_os = "android"))]
+    type Request = c_int;
+    #[cfg(not(any(target_env = "musl", target_os = "android")))]
+    type Request = std::ffi::c_ulong;
+
+    // struct winsize from <sys/ioctl.h>
+    #[repr(C)]
+    #[derive(Default)]
+    struct WinSize {
+        rows: u16,
+        columns: u16,
+        x_pixels: u16,
+        y_pixels: u16,
+    }
+    const TIOCGWINSZ: Request = if cfg!(any(target_os = "linux", target_os = "android"))
+        && !cfg!(any(
+            target_arch = "mips",
+            target_arch = "mips64",
+            target_arch = "powerpc",
+            target_arch = "powerpc64",
+            target_arch = "sparc64"
+        )) {
+        0x5413
+    } else {
+        0x4008_7468
+    };
+    extern "C" {
+        fn ioctl(fd: c_int, request: Request, ...) -> c_int;
+    }
+
+    let stdout = std::io::stdout();
+    let mut size = WinSize::default();
+    // SAFETY: stdout stays open for the call, and TIOCGWINSZ only writes a winsize through the
+    // pointer, which points at one
+    let result = unsafe { ioctl(stdout.as_raw_fd(), TIOCGWINSZ, &mut size as *mut WinSize) };
+    (result == 0 && size.columns > 0).then_some(usize::from(size.columns))
+}
+
+#[cfg(not(any(
+    target_os = "linux",
+    target_os = "android",
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd",
+    target_os = "dragonfly"
+)))]
+fn window_columns() -> Option<usize> {
+    None
+}
+
+/* Splitting */
+fn split_indent(line: &str) -> (&str, &str) {
+    let rest = line.trim_start_matches([' ', '\t']);
+    (&line[..line.len() - rest.len()], rest)
+}
+
+// A line that's styled from start to end, like the headers, or that starts a code example. An
+// italic word at the start of a line is still part of its paragraph.
+fn is_heading(text: &str) -> bool {
+    if !text.starts_with('\x1b') {
+        return false;
+    }
+    let mut style = Style::default();
+    style.update(text.split(RESET).next().unwrap_or(""));
+    style.is_code()
+        || text
+            .split_once(RESET)
+            .is_none_or(|(_, after)| after.trim().is_empty())
+}
+
+// The width of a list marker and the space after it: "- ", "* " or a number like "12. ".
+fn list_marker(text: &str) -> Option<usize> {
+    if text.starts_with("- ") || text.starts_with("* ") {
+        return Some(2);
+    }
+    let digits = text.chars().take_while(char::is_ascii_digit).count();
+    (digits > 0 && text[digits..].starts_with(". ")).then_some(digits + 2)
+}
+
+fn is_vowel(c: char) -> bool {
+    matches!(c.to_ascii_lowercase(), 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+// Splits a word so the first part (with its hyphen) fits in room columns, and the rest goes on
+// the next line. Real hyphenation needs a dictionary; this just looks for the spots that are
+// usually safe: after a hyphen that's already there, between two consonants ("num-ber"), or
+// after a vowel followed by a consonant and another vowel ("mo-dule"), leaving at least three
+// letters on each side. A word too long for a line of its own (whole is true) is cut anywhere.
+fn split_word(word: &str, room: usize, whole: bool) -> Option<(String, String)> {
+    if word.contains('\x1b') {
+        return None;
+    }
+    let chars: Vec<char> = word.chars().collect();
+    let letter = |i: usize| chars.get(i).is_some_and(|c| c.is_alphabetic());
+    let safe = |k: usize| {
+        if chars[k - 1] == '-' {
+            return k >= 2 && letter(k);
+        }
+        if k < 3 || chars.len() - k < 3 || !(k - 2..k + 2).all(letter) {
+            return false;
+        }
+        let (before, after) = (chars[k - 1], chars[k]);
+        let pair: String = [before, after].iter().collect::<String>().to_lowercase();
+        match (is_vowel(before), is_vowel(after)) {
+            // but not through a pair that makes one sound
+            (false, false) => {
+                !["ch", "ck", "gh", "ng", "ph", "sh", "th", "wh"].contains(&pair.as_str())
+            }
+            (true, false) => is_vowel(chars[k + 1]),
+            _ => false,
+        }
+    };
+    // the width of the first k chars, with the hyphen that will be added after them
+    let head_width = |k: usize| {
+        let head: String = chars[..k].iter().collect();
+        display_width(&head) + usize::from(chars[k - 1] != '-')
+    };
+    let k = (1..chars.len())
+        .rev()
+        .filter(|&k| head_width(k) <= room)
+        .find(|&k| safe(k))
+        .or_else(|| {
+            whole
+                .then(|| (1..chars.len()).rev().find(|&k| head_width(k) <= room))
+                .flatten()
+        })?;
+    let mut head: String = chars[..k].iter().collect();
+    if chars[k - 1] != '-' {
+        head.push('-');
+    }
+    Some((head, chars[k..].iter().collect()))
+}
+
+// Like println!, but the text is re-flowed to the width of the terminal first. Output that's
+// going into a file or a pipe is printed as it was written, unless $COLUMNS gives a width.
+#[macro_export]
+macro_rules! prose {
+    ($($arg:tt)*) => {{
+        let text = format!($($arg)*);
+        match $crate::layout::Layout::if_terminal() {
+            Some(layout) => println!("{}", layout.reflow(&text)),
+            None => println!("{text}"),
+        }
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reflow(width: usize, text: &str) -> String {
+        Layout::new(width).reflow(text)
+    }
+
+    #[test]
+    fn paragraphs_wrap_and_keep_their_indentation() {
+        assert_eq!(
+            reflow(
+                20,
+                "one two three four five six seven eight nine ten eleven"
+            ),
+            "one two three four\nfive six seven eight\nnine ten eleven"
+        );
+        assert_eq!(
+            reflow(
+                20,
+                "  indented paragraph that is long enough to wrap twice over\n"
+            ),
+            "  indented paragraph\n  that is long\n  enough to wrap\n  twice over\n"
+        );
+        // lines are joined back up again before wrapping, and blank lines stay
+        assert_eq!(
+            reflow(40, "one\ntwo\nthree\n\nfour\n"),
+            "one two three\n\nfour\n"
+        );
+        assert_eq!(reflow(40, ""), "");
+        // never narrower than MIN_WIDTH
+        assert_eq!(Layout::new(5).width(), MIN_WIDTH);
+    }
+
+    #[test]
+    fn hanging_indents() {
+        assert_eq!(
+            reflow(
+                20,
+                "Note: this line has a\n  hanging indent that continues for a while"
+            ),
+            "Note: this line has\n  a hanging indent\n  that continues for\n  a while"
+        );
+        assert_eq!(
+            reflow(
+                20,
+                "- a list item that is long enough to wrap\n  and continues here\n\
+                 12. numbered item that wraps as well"
+            ),
+            "- a list item that\n  is long enough to\n  wrap and continues\n  here\n\
+             12. numbered item\n    that wraps as\n    well"
+        );
+        assert_eq!(
+            Layout::new(20).fill("words that go on for a while", "> ", "  ", Style::default()),
+            ["> words that go on", "  for a while"]
+        );
+    }
+
+    #[test]
+    fn code_and_headers_are_left_alone() {
+        let text =
+            "para one here\n\n\x1b[1;4;33mA heading that is much longer than twenty\x1b[0m\n\
+                    $ cargo run -- reflow --justify file.txt\nafter";
+        assert_eq!(reflow(20, text), text);
+        // bright white is code until it's switched off, however long the lines are
+        assert_eq!(
+            reflow(
+                20,
+                "text before code:\n\x1b[97m    let x = some_function(argument_one, two);\n    \
+                 more code that is long here\x1b[0m\nand after the code"
+            ),
+            "text before code:\n\x1b[97m    let x = some_function(argument_one, two);\n    \
+             more code that is long here\x1b[0m\nand after the code"
+        );
+        // and so is anything indented well past the paragraph
+        let text = "a paragraph with\n        let preformatted = code_that_is_long;\nback again";
+        assert_eq!(reflow(20, text), text);
+    }
+
+    #[test]
+    fn escapes_stay_with_their_words() {
+        // the escapes take no room, and a style that's on at the end of a line is turned off
+        // there and back on at the start of the next
+        assert_eq!(
+            reflow(20, "some \x1b[3mitalic words that wrap\x1b[0m around here and \x1b[1mbold\x1b[0m"),
+            "some \x1b[3mitalic words\x1b[0m\n\x1b[3mthat wrap\x1b[0m around\nhere and \x1b[1mbold\x1b[0m"
+        );
+        // and a word with an escape in it is never hyphenated
+        let hyphenated = Layout::new(20).hyphenate(true);
+        assert_eq!(
+            hyphenated.reflow("this is a \x1b[1mcombination\x1b[0m of"),
+            "this is a\n\x1b[1mcombination\x1b[0m of"
+        );
+    }
+
+    #[test]
+    fn justify() {
+        let justified = Layout::new(20)
+            .justify(true)
+            .reflow("the quick brown fox jumps over the lazy dog and keeps on running");
+        assert_eq!(
+            justified,
+            "the  quick brown fox\njumps  over the lazy\ndog   and  keeps  on\nrunning"
+        );
+        let lines: Vec<&str> = justified.lines().collect();
+        for line in &lines[..lines.len() - 1] {
+            assert_eq!(display_width(line), 20, "{line:?}");
+        }
+        // a line with one word has nowhere to put the spaces
+        assert_eq!(
+            Layout::new(20)
+                .justify(true)
+                .reflow("supercalifragilistic word"),
+            "supercalifragilistic\nword"
+        );
+    }
+
+    #[test]
+    fn hyphenate() {
+        let hyphenated = Layout::new(20).hyphenate(true);
+        // between a vowel and a consonant followed by another vowel
+        assert_eq!(
+            hyphenated.reflow("this is a combination of"),
+            "this is a combina-\ntion of"
+        );
+        // at a hyphen that's already there, without adding another
+        assert_eq!(
+            hyphenated.reflow("so this is well-known stuff"),
+            "so this is well-\nknown stuff"
+        );
+        // a word too long for a line of its own is cut wherever it has to be
+        assert_eq!(
+            hyphenated.reflow("supercalifragilisticexpialidocious"),
+            "supercalifragilisti-\ncexpialidocious"
+        );
+        // without hyphenation it sticks out instead
+        assert_eq!(
+            reflow(20, "supercalifragilisticexpialidocious is long"),
+            "supercalifragilisticexpialidocious\nis long"
+        );
+        assert_eq!(split_word("thing", 4, false), None);
+        assert_eq!(
+            split_word("number", 4, false),
+            Some(("num-".to_string(), "ber".to_string()))
+        );
+        // not through "th", which is one sound
+        assert_eq!(split_word("father", 4, false), None);
+    }
+
+    #[test]
+    fn widths() {
+        assert_eq!(display_width("plain"), 5);
+        assert_eq!(display_width("\x1b[1;4;33mbold\x1b[0m"), 4);
+        assert_eq!(display_width("日本"), 4);
+        assert_eq!(display_width("नमस्ते"), 4);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width("😀!"), 3);
+    }
+}
@@ -0,0 +1,8 @@
+// The word splitting and byte printing from main.rs, done properly. Other chapters can use them
+// through a path dependency on this crate.
+pub mod bits;
+pub mod codec;
+pub mod hexdump;
+pub mod layout;
+pub mod search;
+pub mod tokenizer;
@@ -1,4 +1,30 @@
+use std::io::{IsTerminal, Read, Write};
+use std::time::{Duration, Instant};
+use the_slice_type::bits::{self, BitSet, BitVec};
+use the_slice_type::codec::{self, Codec};
+use the_slice_type::hexdump::{self, DumpOptions, Gutter, Mode};
+use the_slice_type::layout::Layout;
+use the_slice_type::search::{Algorithm, Searcher};
+use the_slice_type::tokenizer::{self, Tokenizer};
+
 fn main() {
+    // 'cargo run -- dump ...' runs the hex dump tool from hexdump.rs instead of the examples
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match args[0].as_str() {
+            "dump" => run_dump(&args[1..]),
+            "codec" => run_codec(&args[1..]),
+            "reflow" => run_reflow(&args[1..]),
+            "search-bench" => run_search_bench(&args[1..]),
+            "primes" => run_primes(&args[1..]),
+            other => println!(
+                "unknown tool '{other}', try 'dump --help', 'codec --help', 'reflow --help', \
+                 'search-bench [megabytes]' or 'primes <limit>'"
+            ),
+        }
+        return;
+    }
+
     // Slices let you reference a contigueous sequence of elements in a collection, rather than the
     // whole collection.
     // A slice is a kind of reference, so it does not have ownership.
@@ -14,11 +40,104 @@ fn main() {
     let s = String::from("reallyreallyreallylongword!!!");
     println!("{}", second_word(&s));
     let s = String::from(" string starting with a space");
-    println!("{}", second_word(&s)); // can't handle specific edge cases, but for the purposes of
-                                     // this educational project there is no reason to spend time enabling
-                                     // it to handle these cases.
+    // can't handle specific edge cases, but for the purposes of this educational project there is
+    // no reason to spend time enabling it to handle these cases.
+    // (The tokenizer handles them now: the leading space used to count as the end of the first
+    // word.)
+    println!("{}", second_word(&s));
+    println!(
+        "'{}'",
+        first_word("  \u{a0}indented with a non-breaking space")
+    );
+    tokenizer_demo();
+    search_demo();
     print_binary("hello");
+    println!();
+    bits_demo();
+    // print_binary guesses a char for each byte on its own, which goes wrong as soon as a
+    // character needs more than one byte. hexdump.rs decodes the UTF-8 properly:
+    let options = DumpOptions {
+        mode: Mode::Binary,
+        per_line: Some(4),
+        ..DumpOptions::default()
+    };
+    print!("{}", hexdump::dump("héllo, 世界".as_bytes(), &options));
+    // Base64 regroups those same bits six at a time, and gives each group one of 64 characters
+    // (see codec.rs). 'cargo run -- codec b64 encode <file>' does this for a whole file.
+    for codec in [Codec::base64(), Codec::base32(), Codec::hex()] {
+        println!("{:>6}: {}", codec.name(), codec.encode("héllo".as_bytes()));
+    }
+    match Codec::base64().decode("aMOp bGxv!") {
+        Ok(bytes) => println!("decoded: {}", String::from_utf8_lossy(&bytes)),
+        Err(err) => println!("aMOp bGxv! isn't valid base64: {err}"),
+    }
+}
+
+fn tokenizer_demo() {
+    // Each token is a slice of the original string plus its byte span. Non-ASCII characters take
+    // more than one byte, so the spans don't line up with character counts.
+    let text: &str = "Grüß Gott, naïve café-goers… don't panic!";
+    println!("{text}");
+    let words = Tokenizer::words_only();
+    for token in words.tokens(text) {
+        println!("  {:?} at bytes {:?}", token.text, token.span);
+    }
+    println!("  third word: {:?}", words.nth_word(text, 2));
+    println!("  last word: {:?}", words.last_word(text));
+    println!("  {} words", words.count(text));
+    // U+3000 IDEOGRAPHIC SPACE counts as whitespace, and 、 and 。 as punctuation
+    let japanese: &str = "東京　大阪、京都。";
+    let places: Vec<&str> = words.words(japanese).collect();
+    println!("{japanese} -> {places:?}");
+    // or split on any set of characters
+    let fields: Vec<&str> = Tokenizer::on_chars(&[';']).words("a;;b; c;").collect();
+    println!("a;;b; c; split on ';' -> {fields:?}");
+    // layout.rs uses the same words to wrap text to a width: here the comment at the top of
+    // main.rs, squeezed into 40 columns, justified and hyphenated. 'cargo run -- reflow' does it
+    // to any file.
+    let notes = "    Slices let you reference a contigueous sequence of elements in a collection, \
+                 rather than the whole collection. A slice is a kind of reference, so it does \
+                 not have ownership.";
+    let layout = Layout::new(40).justify(true).hyphenate(true);
+    println!("{}", "-".repeat(layout.width()));
+    println!("{}", layout.reflow(notes));
 }
+// first_word looks for one byte; search.rs looks for a whole needle, without trying every
+// position the way the naive loop would.
+fn search_demo() {
+    let text: &[u8] =
+        b"Slices let you reference a contiguous sequence of elements in a collection. \
+                        A slice is a kind of reference, so it does not have ownership.";
+    for algorithm in Algorithm::ALL {
+        let searcher = Searcher::new(algorithm, b"a slice").ignore_ascii_case(true);
+        let found: Vec<_> = searcher.find_iter(text).map(|m| m.span).collect();
+        println!("{algorithm:>20}: 'a slice' (any case) at {found:?}");
+    }
+    let overlapping = Searcher::two_way(b"aa").overlapping(true);
+    println!(
+        "'aa' in 'aaaa': {:?}, or {:?} overlapping",
+        Searcher::two_way(b"aa").find_all(b"aaaa"),
+        overlapping.find_all(b"aaaa")
+    );
+}
+
+// first_word and second_word used to walk through s.as_bytes() with .iter().enumerate(), looking
+// for b' ' (and b',' / b'.' for the second word). tokenizer.rs does the same walk over chars,
+// which also copes with leading or repeated spaces and with text that isn't ASCII, so these are
+// now thin wrappers around it. Taking &str instead of &String means they work on string literals
+// and slices of other strings too.
+fn first_word(s: &str) -> &str {
+    tokenizer::WHITESPACE.first_word(s).unwrap_or("")
+}
+fn second_word(s: &str) -> &str {
+    // returns the second word if one exists, otherwise returns the first word
+    let words = Tokenizer::on_chars(&[',', '.']).with_whitespace();
+    words
+        .nth_word(s, 1)
+        .or_else(|| words.first_word(s))
+        .unwrap_or("")
+}
+/* The byte-by-byte versions they replaced:
 fn first_word(s: &String) -> &str {
     // This converts the string into an array of bytes to iterate over
     let bytes = s.as_bytes();
@@ -58,6 +177,7 @@ fn second_word(s: &String) -> &str {
     println!("return option 3 (only one word in string)");
     &s[..]
 }
+*/
 fn print_binary(s: &str) {
     let bytes = s.as_bytes();
     for &item in bytes.iter() {
@@ -70,3 +190,444 @@ fn print_binary(s: &str) {
         print!("{:b} ", item);
     }
 }
+
+// print_binary reads the bits of each byte; bits.rs stores true/false flags in them, eight to a
+// byte, and prints them back the same way.
+fn bits_demo() {
+    let hello = BitVec::from_bytes(b"hello");
+    println!("{hello:b}");
+    println!("{hello:#b}");
+    let parsed: Result<BitVec, _> = "1101000 1100101 1101100 1101100 1101111".parse();
+    if let Ok(parsed) = parsed {
+        println!(
+            "parsed back: {}",
+            String::from_utf8_lossy(parsed.as_bytes())
+        );
+    }
+    let primes = bits::primes_below(100);
+    println!("{} primes below 100: {primes:?}", primes.len());
+    let million = bits::primes_below(1_000_000);
+    println!(
+        "{} primes below a million, sieved in {} bytes of bits instead of {} bytes of Vec<bool>",
+        million.len(),
+        1_000_000 / 16,
+        1_000_000
+    );
+    // a compact flag set: which users have each permission
+    let readers: BitSet = [0, 1, 2, 3, 5, 8].into_iter().collect();
+    let writers: BitSet = [1, 3, 8, 13].into_iter().collect();
+    println!(
+        "readers {readers:?}, writers {writers:?}\n  both {:?}, either {:?}, read only {:?}",
+        &readers & &writers,
+        &readers | &writers,
+        &readers - &writers
+    );
+    println!(
+        "  writers as bits: {writers:#b}, saved as {:?}",
+        writers.serialize()
+    );
+}
+
+fn run_primes(args: &[String]) {
+    let limit = match args.first().map(|a| a.replace('_', "").parse::<usize>()) {
+        Some(Ok(limit)) => limit,
+        _ => fail("usage: the_slice_type primes <limit>"),
+    };
+    let mut primes = BitSet::new();
+    let elapsed = time(|| primes = bits::primes_below(limit));
+    println!("{} primes below {limit} ({elapsed:.2?})", primes.len());
+    let largest: Vec<String> = primes
+        .iter()
+        .skip(primes.len().saturating_sub(10))
+        .map(|p| p.to_string())
+        .collect();
+    println!("the largest: {}", largest.join(" "));
+}
+
+const DUMP_USAGE: &str = "\
+usage: the_slice_type dump [options] [file]
+  reads the file, or stdin if there isn't one
+  -b, -o, -x, -d     binary, octal, hex (the default) or decimal bytes
+  -c <n>             bytes per line (up to 4096)
+  -s, --seek <n>     start at byte n (0x.. for hex)
+  -l, --length <n>   only show n bytes
+  --ascii            plain ASCII gutter instead of UTF-8
+  --no-gutter        no text column at all
+  --color, --no-color
+                     colour UTF-8 lead/continuation/invalid bytes (default: when on a terminal)
+  --string <text>    dump this text instead of a file
+  -r                 reverse: read a dump (in the mode given) and write the bytes it describes";
+
+// Accepts decimal or 0x-prefixed hex, like xxd.
+fn parse_count(text: Option<&String>) -> Result<usize, String> {
+    let text = text.ok_or("a number is missing")?;
+    let parsed = match text.strip_prefix("0x") {
+        Some(hex) => usize::from_str_radix(hex, 16),
+        None => text.parse(),
+    };
+    parsed.map_err(|_| format!("'{text}' is not a number"))
+}
+
+struct DumpArgs {
+    options: DumpOptions,
+    reverse: bool,
+    text: Option<String>,
+    path: Option<String>,
+    help: bool,
+}
+
+fn parse_dump_args(args: &[String]) -> Result<DumpArgs, String> {
+    let mut parsed = DumpArgs {
+        options: DumpOptions {
+            color: std::io::stdout().is_terminal(),
+            ..DumpOptions::default()
+        },
+        reverse: false,
+        text: None,
+        path: None,
+        help: false,
+    };
+    let options = &mut parsed.options;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-b" => options.mode = Mode::Binary,
+            "-o" => options.mode = Mode::Octal,
+            "-x" => options.mode = Mode::Hex,
+            "-d" => options.mode = Mode::Decimal,
+            "-c" => {
+                let per_line = parse_count(rest.next())?;
+                if !(1..=hexdump::MAX_PER_LINE).contains(&per_line) {
+                    return Err(format!(
+                        "-c takes 1 to {} bytes per line",
+                        hexdump::MAX_PER_LINE
+                    ));
+                }
+                options.per_line = Some(per_line);
+            }
+            "-s" | "--seek" => options.seek = parse_count(rest.next())?,
+            "-l" | "--length" => options.length = Some(parse_count(rest.next())?),
+            "--ascii" => options.gutter = Gutter::Ascii,
+            "--no-gutter" => options.gutter = Gutter::None,
+            "--color" => options.color = true,
+            "--no-color" => options.color = false,
+            "-r" => parsed.reverse = true,
+            "--string" => {
+                parsed.text = Some(rest.next().ok_or("--string needs some text")?.clone())
+            }
+            "-h" | "--help" => parsed.help = true,
+            _ if parsed.path.is_none() && !arg.starts_with('-') => parsed.path = Some(arg.clone()),
+            _ => return Err(format!("unexpected argument '{arg}'")),
+        }
+    }
+    Ok(parsed)
+}
+
+fn run_dump(args: &[String]) {
+    let DumpArgs {
+        options,
+        reverse,
+        text,
+        path,
+        help,
+    } = match parse_dump_args(args) {
+        Ok(parsed) => parsed,
+        Err(err) => fail(format!("{err}\n{DUMP_USAGE}")),
+    };
+    if help {
+        return println!("{DUMP_USAGE}");
+    }
+
+    let input: Result<Vec<u8>, std::io::Error> = match (text, path) {
+        (Some(text), _) => Ok(text.into_bytes()),
+        (None, Some(path)) => std::fs::read(path),
+        (None, None) => {
+            let mut bytes: Vec<u8> = Vec::new();
+            std::io::stdin().read_to_end(&mut bytes).map(|_| bytes)
+        }
+    };
+    let input = match input {
+        Ok(input) => input,
+        Err(err) => fail(format!("could not read the input: {err}")),
+    };
+
+    let mut stdout = std::io::stdout().lock();
+    let written = if reverse {
+        let dump = String::from_utf8_lossy(&input);
+        match hexdump::reverse(&dump, options.mode) {
+            Ok(bytes) => stdout.write_all(&bytes),
+            Err(err) => fail(format!("could not read the dump: {err}")),
+        }
+    } else {
+        stdout.write_all(hexdump::dump(&input, &options).as_bytes())
+    };
+    // a closed pipe (like '| head') isn't worth a panic, or an error
+    if let Err(err) = written {
+        if err.kind() != std::io::ErrorKind::BrokenPipe {
+            fail(format!("could not write the output: {err}"));
+        }
+    }
+}
+
+// For the tools: says what went wrong and exits with status 1, so scripts can tell.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
+
+const CODEC_USAGE: &str = "\
+usage: the_slice_type codec <b64|b64url|b32|b32hex|hex> <encode|decode> [options] [file]
+  reads the file, or stdin if there isn't one, and writes to stdout
+  --no-pad    leave out (or don't expect) the '=' padding
+  -w <n>      when encoding, wrap lines at n characters (default 76, 0 for no wrapping)
+       the_slice_type codec selftest
+  checks every codec against the test vectors from RFC 4648";
+
+fn run_codec(args: &[String]) {
+    if args.first().map(String::as_str) == Some("selftest") {
+        let failures = codec::check_rfc_4648_vectors();
+        for failure in &failures {
+            println!("FAILED {failure}");
+        }
+        let checks = codec::RFC_4648_VECTORS.len() * 4 * 2;
+        println!(
+            "{} of {checks} RFC 4648 checks passed",
+            checks - failures.len()
+        );
+        if !failures.is_empty() {
+            std::process::exit(1);
+        }
+        return;
+    }
+    let codec = match args.first().map(String::as_str) {
+        Some("b64") | Some("base64") => Codec::base64(),
+        Some("b64url") | Some("base64url") => Codec::base64_url(),
+        Some("b32") | Some("base32") => Codec::base32(),
+        Some("b32hex") | Some("base32hex") => Codec::base32_hex(),
+        Some("hex") | Some("base16") => Codec::hex(),
+        Some("-h") | Some("--help") => return println!("{CODEC_USAGE}"),
+        _ => fail(CODEC_USAGE),
+    };
+    let encode = match args.get(1).map(String::as_str) {
+        Some("encode") | Some("e") => true,
+        Some("decode") | Some("d") => false,
+        _ => fail(CODEC_USAGE),
+    };
+    let mut codec = codec;
+    let mut wrap: usize = 76;
+    let mut path: Option<&String> = None;
+    let mut rest = args[2..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "--no-pad" => codec = codec.unpadded(),
+            "-w" => match parse_count(rest.next()) {
+                Ok(width) => wrap = width,
+                Err(err) => fail(err),
+            },
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => fail(format!("unexpected argument '{arg}'\n{CODEC_USAGE}")),
+        }
+    }
+
+    let input: Box<dyn Read> = match path {
+        Some(path) => match std::fs::File::open(path) {
+            Ok(file) => Box::new(std::io::BufReader::new(file)),
+            Err(err) => fail(format!("could not open {path}: {err}")),
+        },
+        None => Box::new(std::io::stdin().lock()),
+    };
+    let stdout = std::io::stdout().lock();
+    let result: std::io::Result<()> = if encode {
+        let mut input = input;
+        let mut encoder = codec.encoder(stdout).wrap(wrap);
+        std::io::copy(&mut input, &mut encoder).and_then(|_| encoder.finish().map(|_| ()))
+    } else {
+        let mut decoder = codec.decoder(input);
+        let mut stdout = stdout;
+        std::io::copy(&mut decoder, &mut stdout).map(|_| ())
+    };
+    // decoded bytes are written as they come, so everything before an error is already out
+    if let Err(err) = result {
+        if err.kind() == std::io::ErrorKind::BrokenPipe {
+            return;
+        }
+        match codec::decode_error(&err) {
+            Some(err) => fail(format!("not valid {}: {err}", codec.name())),
+            None => fail(err),
+        }
+    }
+}
+
+const REFLOW_USAGE: &str = "\
+usage: the_slice_type reflow [options] [file]
+  re-flows the file, or stdin if there isn't one, and writes it to stdout
+  -w <n>         wrap at n columns (default: the terminal's width up to 100, 80 without one)
+  --justify      spread the words out so every line but a paragraph's last fills the width
+  --hyphenate    split words that don't fit at the end of a line";
+
+fn run_reflow(args: &[String]) {
+    let mut width: Option<usize> = None;
+    let mut justify = false;
+    let mut hyphenate = false;
+    let mut path: Option<&String> = None;
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "-w" => match parse_count(rest.next()) {
+                Ok(n) => width = Some(n),
+                Err(err) => fail(err),
+            },
+            "--justify" => justify = true,
+            "--hyphenate" => hyphenate = true,
+            "-h" | "--help" => return println!("{REFLOW_USAGE}"),
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => fail(format!("unexpected argument '{arg}'\n{REFLOW_USAGE}")),
+        }
+    }
+    let layout = width
+        .map_or_else(Layout::for_terminal, Layout::new)
+        .justify(justify)
+        .hyphenate(hyphenate);
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path),
+        None => std::io::read_to_string(std::io::stdin()),
+    };
+    match text {
+        Ok(text) => print!("{}", layout.reflow(&text)),
+        Err(err) => fail(format!("could not read the input: {err}")),
+    }
+}
+
+// The chapter notes, repeated until the haystack is big enough to time.
+const NOTES: [&str; 4] = [
+    include_str!("../../../Chp5/chapternotes.txt"),
+    include_str!("../../../Chp6/chapternotes.md"),
+    include_str!("../../../Chp7/chapter_notes.txt"),
+    include_str!("../../../Chp8/chapter_summary.txt"),
+];
+
+fn run_search_bench(args: &[String]) {
+    let megabytes: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(16);
+    if cfg!(debug_assertions) {
+        println!("(a debug build; cargo run --release -- search-bench gives realistic numbers)");
+    }
+    let notes = NOTES.concat();
+    let haystack = notes.repeat((megabytes * 1024 * 1024 / notes.len()).max(1));
+    println!(
+        "searching {:.1} MB of chapter notes:",
+        haystack.len() as f64 / (1024.0 * 1024.0)
+    );
+    // a short common word, a longer phrase, something that isn't there at all, and the worst case
+    // for the naive loop: a needle that almost matches everywhere
+    let almost = "a".repeat(31) + "b";
+    let needles: [&str; 4] = ["the", "crate root", "borrow checker", &almost];
+    let lots_of_a = "a".repeat(haystack.len());
+    for needle in needles {
+        let haystack = if needle == almost {
+            &lots_of_a
+        } else {
+            &haystack
+        };
+        let mut expected = 0;
+        let std_time = time(|| expected = haystack.matches(needle).count());
+        println!("\n  {needle:?}: {expected} matches");
+        println!("    {:<22} {:>10.2?}", "str::matches", std_time);
+        for algorithm in Algorithm::ALL {
+            let searcher = Searcher::new(algorithm, needle.as_bytes());
+            let mut found = 0;
+            let elapsed = time(|| found = searcher.count(haystack.as_bytes()));
+            let check = if found == expected {
+                ""
+            } else {
+                "  (wrong count!)"
+            };
+            println!(
+                "    {:<22} {:>10.2?}{check}",
+                algorithm.to_string(),
+                elapsed
+            );
+        }
+    }
+
+    // str has no case-insensitive search, so the usual way is to lowercase a copy first
+    let needle = "RUST";
+    let mut expected = 0;
+    let std_time = time(|| {
+        expected = haystack
+            .to_ascii_lowercase()
+            .matches(&needle.to_ascii_lowercase())
+            .count()
+    });
+    println!("\n  {needle:?} ignoring case: {expected} matches");
+    println!("    {:<22} {:>10.2?}", "lowercase + matches", std_time);
+    for algorithm in Algorithm::ALL {
+        let searcher = Searcher::new(algorithm, needle.as_bytes()).ignore_ascii_case(true);
+        let mut found = 0;
+        let elapsed = time(|| found = searcher.count(haystack.as_bytes()));
+        let check = if found == expected {
+            ""
+        } else {
+            "  (wrong count!)"
+        };
+        println!(
+            "    {:<22} {:>10.2?}{check}",
+            algorithm.to_string(),
+            elapsed
+        );
+    }
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{first_word, second_word};
+
+    #[test]
+    fn empty_and_blank_strings_have_no_words() {
+        for text in ["", " ", "   \t\n  ", "\u{a0}\u{3000}\u{2003}"] {
+            assert_eq!(first_word(text), "", "{text:?}");
+            assert_eq!(second_word(text), "", "{text:?}");
+        }
+        // second_word also splits on , and . so these have no words either
+        assert_eq!(second_word(" ,. ,"), "");
+    }
+
+    #[test]
+    fn leading_and_repeated_whitespace_is_skipped() {
+        assert_eq!(first_word("  hello   world"), "hello");
+        assert_eq!(second_word("  hello   world  "), "world");
+        assert_eq!(second_word(" string starting with a space"), "starting");
+        assert_eq!(second_word("hello, fooman. this"), "fooman");
+    }
+
+    #[test]
+    fn one_word_is_both_the_first_and_the_second() {
+        assert_eq!(
+            first_word("reallyreallyreallylongword!!!"),
+            "reallyreallyreallylongword!!!"
+        );
+        assert_eq!(
+            second_word("reallyreallyreallylongword!!!"),
+            "reallyreallyreallylongword!!!"
+        );
+        assert_eq!(second_word("word,"), "word");
+    }
+
+    #[test]
+    fn unicode_whitespace_separates_words() {
+        // no-break space, ideographic space, em space, and a line separator
+        assert_eq!(first_word("\u{a0}café\u{3000}naïve"), "café");
+        assert_eq!(second_word("\u{a0}café\u{3000}naïve"), "naïve");
+        assert_eq!(second_word("東京\u{2003}大阪\u{2028}京都"), "大阪");
+        // the slices are of the original string, even when the words are multibyte
+        let text = "Grüß Gott";
+        let word = second_word(text);
+        assert_eq!(word, "Gott");
+        assert_eq!(word.as_ptr(), text[text.find("Gott").unwrap()..].as_ptr());
+    }
+}
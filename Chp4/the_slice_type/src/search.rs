@@ -0,0 +1,560 @@
+/* Substring search */
+// first_word in main.rs walks s.as_bytes() one byte at a time looking for a single byte, b' '.
+// Looking for a longer needle the same way means trying every position and comparing the whole
+// needle there, which takes (haystack length x needle length) steps when the text is repetitive.
+// The three classic ways around that all look at the needle first and work out how far they can
+// safely skip after a mismatch:
+//   * KMP (Knuth-Morris-Pratt) never reads a haystack byte twice. For every prefix of the needle
+//     it knows the longest part of that prefix that also ends it, so after a mismatch it carries
+//     on from there instead of starting over.
+//   * Boyer-Moore-Horspool compares from the end of the needle. When the window doesn't match,
+//     the last byte under it says how far to jump: if that byte isn't in the needle at all, the
+//     whole needle length. On normal text that skips most of the haystack without reading it.
+//   * Two-Way (Crochemore-Perrin) splits the needle at a "critical factorization", matches the
+//     right part forwards and then the left part backwards, and uses the needle's period to
+//     shift. It never goes back in the haystack and needs no table, just two numbers. It's what
+//     the standard library's str::find is built on.
+//
+// A Searcher holds the needle and whatever its algorithm worked out from it, and hands out the
+// matches in a haystack as slices of it, with their byte spans (like tokenizer.rs does with
+// words). Matches can be found ignoring ASCII case, and can overlap ("aa" is in "aaaa" three
+// times when overlapping, twice when not).
+use std::fmt;
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Algorithm {
+    Kmp,
+    Horspool,
+    TwoWay,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 3] = [Algorithm::Kmp, Algorithm::Horspool, Algorithm::TwoWay];
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Algorithm::Kmp => write!(f, "KMP"),
+            Algorithm::Horspool => write!(f, "Boyer-Moore-Horspool"),
+            Algorithm::TwoWay => write!(f, "Two-Way"),
+        }
+    }
+}
+
+// What each algorithm works out from the needle before searching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Table {
+    // prefix[i]: the length of the longest proper prefix of needle[..=i] that also ends it
+    Kmp(Vec<usize>),
+    // shift[b]: how far the window moves when b is the haystack byte under its last position
+    Horspool(Box<[usize; 256]>),
+    // the critical position, the period, and whether the needle is periodic (needle[..critical]
+    // repeats period bytes later), which is when matches can be remembered between shifts
+    TwoWay {
+        critical: usize,
+        period: usize,
+        periodic: bool,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Searcher {
+    algorithm: Algorithm,
+    // lowercased when ignoring case
+    needle: Vec<u8>,
+    ignore_case: bool,
+    overlapping: bool,
+    table: Table,
+}
+
+impl Searcher {
+    pub fn new(algorithm: Algorithm, needle: &[u8]) -> Self {
+        let mut searcher = Searcher {
+            algorithm,
+            needle: needle.to_vec(),
+            ignore_case: false,
+            overlapping: false,
+            table: Table::Kmp(Vec::new()),
+        };
+        searcher.prepare();
+        searcher
+    }
+
+    pub fn kmp(needle: &[u8]) -> Self {
+        Searcher::new(Algorithm::Kmp, needle)
+    }
+
+    pub fn horspool(needle: &[u8]) -> Self {
+        Searcher::new(Algorithm::Horspool, needle)
+    }
+
+    pub fn two_way(needle: &[u8]) -> Self {
+        Searcher::new(Algorithm::TwoWay, needle)
+    }
+
+    // Treats 'A'-'Z' and 'a'-'z' as the same. Bytes outside ASCII have to match exactly.
+    pub fn ignore_ascii_case(mut self, ignore: bool) -> Self {
+        self.ignore_case = ignore;
+        if ignore {
+            self.needle.make_ascii_lowercase();
+            self.prepare();
+        }
+        self
+    }
+
+    // Whether a match can start inside the previous one.
+    pub fn overlapping(mut self, overlapping: bool) -> Self {
+        self.overlapping = overlapping;
+        self
+    }
+
+    pub fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    pub fn needle(&self) -> &[u8] {
+        &self.needle
+    }
+
+    fn prepare(&mut self) {
+        let needle = &self.needle;
+        self.table = match self.algorithm {
+            Algorithm::Kmp => {
+                let mut prefix = vec![0; needle.len()];
+                let mut k = 0;
+                for i in 1..needle.len() {
+                    while k > 0 && needle[i] != needle[k] {
+                        k = prefix[k - 1];
+                    }
+                    if needle[i] == needle[k] {
+                        k += 1;
+                    }
+                    prefix[i] = k;
+                }
+                Table::Kmp(prefix)
+            }
+            Algorithm::Horspool => {
+                let mut shift = Box::new([needle.len().max(1); 256]);
+                for (i, &b) in needle
+                    .iter()
+                    .enumerate()
+                    .take(needle.len().saturating_sub(1))
+                {
+                    shift[b as usize] = needle.len() - 1 - i;
+                    // the lookup uses the haystack byte as it is, so when ignoring case an
+                    // uppercase letter has to shift as far as its lowercase one in the needle
+                    if self.ignore_case {
+                        shift[b.to_ascii_uppercase() as usize] = needle.len() - 1 - i;
+                    }
+                }
+                Table::Horspool(shift)
+            }
+            Algorithm::TwoWay => {
+                // the critical factorization is the later of the two maximal suffixes, one for
+                // each ordering of the bytes
+                let (less, less_period) = maximal_suffix(needle, false);
+                let (greater, greater_period) = maximal_suffix(needle, true);
+                let (critical, period) = if less > greater {
+                    (less, less_period)
+                } else {
+                    (greater, greater_period)
+                };
+                let periodic = period + critical <= needle.len()
+                    && needle[..critical] == needle[period..period + critical];
+                let period = if periodic {
+                    period
+                } else {
+                    // not periodic: the most that's safe to skip after the right part matched
+                    critical.max(needle.len() - critical) + 1
+                };
+                Table::TwoWay {
+                    critical,
+                    period,
+                    periodic,
+                }
+            }
+        };
+    }
+
+    fn fold(&self, b: u8) -> u8 {
+        if self.ignore_case {
+            b.to_ascii_lowercase()
+        } else {
+            b
+        }
+    }
+
+    pub fn find(&self, haystack: &[u8]) -> Option<usize> {
+        self.find_iter(haystack).next().map(|m| m.span.start)
+    }
+
+    pub fn find_iter<'h, 's>(&'s self, haystack: &'h [u8]) -> Matches<'h, 's> {
+        Matches {
+            searcher: self,
+            haystack,
+            position: 0,
+            carry: 0,
+            done: false,
+        }
+    }
+
+    // The byte offsets where each match starts.
+    pub fn find_all(&self, haystack: &[u8]) -> Vec<usize> {
+        self.find_iter(haystack).map(|m| m.span.start).collect()
+    }
+
+    pub fn count(&self, haystack: &[u8]) -> usize {
+        self.find_iter(haystack).count()
+    }
+
+    // The next match starting at or after *position, which (with *carry) is where the previous
+    // search stopped.
+    fn next_match(
+        &self,
+        haystack: &[u8],
+        position: &mut usize,
+        carry: &mut usize,
+    ) -> Option<usize> {
+        let needle = &self.needle;
+        let m = needle.len();
+        match &self.table {
+            // position is the next haystack byte to read, carry how much of the needle matches
+            // up to it
+            Table::Kmp(prefix) => {
+                while *position < haystack.len() {
+                    let b = self.fold(haystack[*position]);
+                    *position += 1;
+                    while *carry > 0 && b != needle[*carry] {
+                        *carry = prefix[*carry - 1];
+                    }
+                    if b == needle[*carry] {
+                        *carry += 1;
+                    }
+                    if *carry == m {
+                        *carry = if self.overlapping { prefix[m - 1] } else { 0 };
+                        return Some(*position - m);
+                    }
+                }
+                None
+            }
+            // position is the start of the window
+            Table::Horspool(shift) => {
+                while *position + m <= haystack.len() {
+                    let window = &haystack[*position..*position + m];
+                    let matched = window
+                        .iter()
+                        .rev()
+                        .zip(needle.iter().rev())
+                        .all(|(&h, &n)| self.fold(h) == n);
+                    let start = *position;
+                    if matched {
+                        *position += if self.overlapping { 1 } else { m };
+                        return Some(start);
+                    }
+                    *position += shift[window[m - 1] as usize];
+                }
+                None
+            }
+            // position is the start of the window, carry how much of the needle's start is
+            // already known to match there (only for periodic needles)
+            &Table::TwoWay {
+                critical,
+                period,
+                periodic,
+            } => {
+                while *position + m <= haystack.len() {
+                    let at = |i: usize| self.fold(haystack[*position + i]);
+                    // the right part, forwards
+                    let mut i = critical.max(*carry);
+                    while i < m && needle[i] == at(i) {
+                        i += 1;
+                    }
+                    if i < m {
+                        *position += i + 1 - critical;
+                        *carry = 0;
+                        continue;
+                    }
+                    // the left part, backwards
+                    let floor = if periodic { *carry } else { 0 };
+                    let mut j = critical;
+                    while j > floor && needle[j - 1] == at(j - 1) {
+                        j -= 1;
+                    }
+                    let start = *position;
+                    if j <= floor {
+                        if self.overlapping {
+                            *position += period;
+                            *carry = if periodic { m - period } else { 0 };
+                        } else {
+                            *position += m;
+                            *carry = 0;
+                        }
+                        return Some(start);
+                    }
+                    *position += period;
+                    *carry = if periodic { m - period } else { 0 };
+                }
+                None
+            }
+        }
+    }
+}
+
+// Where the lexicographically greatest suffix of needle starts (the smallest one when reversed
+// is true), and that suffix's period.
+fn maximal_suffix(needle: &[u8], reversed: bool) -> (usize, usize) {
+    let mut left = 0;
+    let mut right = 1;
+    let mut offset = 0;
+    let mut period = 1;
+    while right + offset < needle.len() {
+        let a = needle[right + offset];
+        let b = needle[left + offset];
+        let smaller = if reversed { a > b } else { a < b };
+        if smaller {
+            // the suffix at right is smaller, so the period is everything since left
+            right += offset + 1;
+            offset = 0;
+            period = right - left;
+        } else if a == b {
+            if offset + 1 == period {
+                right += offset + 1;
+                offset = 0;
+            } else {
+                offset += 1;
+            }
+        } else {
+            // the suffix at right is bigger, so it's the new candidate
+            left = right;
+            right += 1;
+            offset = 0;
+            period = 1;
+        }
+    }
+    (left, period)
+}
+
+// A match and where it is in the haystack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Match<'h> {
+    pub bytes: &'h [u8],
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Matches<'h, 's> {
+    searcher: &'s Searcher,
+    haystack: &'h [u8],
+    position: usize,
+    carry: usize,
+    done: bool,
+}
+
+impl<'h> Iterator for Matches<'h, '_> {
+    type Item = Match<'h>;
+
+    fn next(&mut self) -> Option<Match<'h>> {
+        if self.done {
+            return None;
+        }
+        let m = self.searcher.needle.len();
+        let start = if m == 0 {
+            // an empty needle matches everywhere, like "".find does, including at the very end
+            (self.position <= self.haystack.len()).then(|| {
+                self.position += 1;
+                self.position - 1
+            })
+        } else {
+            self.searcher
+                .next_match(self.haystack, &mut self.position, &mut self.carry)
+        };
+        match start {
+            Some(start) => Some(Match {
+                bytes: &self.haystack[start..start + m],
+                span: start..start + m,
+            }),
+            None => {
+                self.done = true;
+                None
+            }
+        }
+    }
+}
+
+impl FusedIterator for Matches<'_, '_> {}
+
+// The slow way, for checking the others: try every position.
+pub fn naive(haystack: &[u8], needle: &[u8], ignore_case: bool, overlapping: bool) -> Vec<usize> {
+    let same = |a: &[u8], b: &[u8]| {
+        if ignore_case {
+            a.eq_ignore_ascii_case(b)
+        } else {
+            a == b
+        }
+    };
+    let mut found: Vec<usize> = Vec::new();
+    let mut position = 0;
+    while position + needle.len() <= haystack.len() {
+        if same(&haystack[position..position + needle.len()], needle) {
+            found.push(position);
+            position += if overlapping { 1 } else { needle.len().max(1) };
+        } else {
+            position += 1;
+        }
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Checks every algorithm and every combination of options against naive.
+    fn check(haystack: &[u8], needle: &[u8]) {
+        for algorithm in Algorithm::ALL {
+            for ignore_case in [false, true] {
+                for overlapping in [false, true] {
+                    let searcher = Searcher::new(algorithm, needle)
+                        .ignore_ascii_case(ignore_case)
+                        .overlapping(overlapping);
+                    let expected = naive(haystack, needle, ignore_case, overlapping);
+                    let found: Vec<Match> = searcher.find_iter(haystack).collect();
+                    let starts: Vec<usize> = found.iter().map(|m| m.span.start).collect();
+                    assert_eq!(
+                        starts,
+                        expected,
+                        "{algorithm} ignore_case={ignore_case} overlapping={overlapping} \
+                         {:?} in {:?}",
+                        String::from_utf8_lossy(needle),
+                        String::from_utf8_lossy(haystack)
+                    );
+                    for m in &found {
+                        assert_eq!(m.bytes, &haystack[m.span.clone()]);
+                    }
+                    assert_eq!(searcher.find(haystack), expected.first().copied());
+                    assert_eq!(searcher.count(haystack), expected.len());
+                }
+            }
+        }
+    }
+
+    // Every string of up to max bytes from alphabet.
+    fn all_strings(alphabet: &[u8], max: usize) -> Vec<Vec<u8>> {
+        let mut strings = vec![Vec::new()];
+        let mut last = vec![Vec::new()];
+        for _ in 0..max {
+            last = last
+                .iter()
+                .flat_map(|s: &Vec<u8>| {
+                    alphabet.iter().map(move |&b| {
+                        let mut longer = s.clone();
+                        longer.push(b);
+                        longer
+                    })
+                })
+                .collect();
+            strings.extend(last.iter().cloned());
+        }
+        strings
+    }
+
+    // Two letters make the repetitive needles (aabaab, abab) that the shifts are easy to get
+    // wrong on, so every needle up to 5 bytes is tried in every haystack up to 8.
+    #[test]
+    fn every_short_string_over_two_letters() {
+        let needles = all_strings(b"ab", 5);
+        let haystacks = all_strings(b"ab", 8);
+        for needle in &needles {
+            for haystack in &haystacks {
+                check(haystack, needle);
+            }
+        }
+    }
+
+    // A tiny xorshift, so the test needs no crates and fails the same way every time.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        fn string(&mut self, alphabet: &[u8], max: usize) -> Vec<u8> {
+            let len = self.below(max + 1);
+            (0..len)
+                .map(|_| alphabet[self.below(alphabet.len())])
+                .collect()
+        }
+    }
+
+    // Upper and lower case, and bytes outside ASCII that are a case bit away from 'A' and 'a',
+    // which ignore_ascii_case mustn't treat as the same.
+    #[test]
+    fn random_strings_with_mixed_case() {
+        let mut random = Random(0x2545_f491_4f6c_dd1d);
+        for alphabet in [&b"aAbB"[..], b"aAbBc", b"aA\xc1\xe1"] {
+            for _ in 0..1000 {
+                let needle = random.string(alphabet, 6);
+                let haystack = random.string(alphabet, 60);
+                check(&haystack, &needle);
+                // and somewhere it's sure to be
+                let start = random.below(haystack.len() + 1);
+                let mut planted = haystack.clone();
+                planted.splice(start..start, needle.iter().copied());
+                check(&planted, &needle);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_needles_match_everywhere() {
+        for algorithm in Algorithm::ALL {
+            for overlapping in [false, true] {
+                let searcher = Searcher::new(algorithm, b"").overlapping(overlapping);
+                assert_eq!(searcher.find_all(b"abc"), [0, 1, 2, 3]);
+                assert_eq!(searcher.find_all(b""), [0]);
+                assert!(searcher.find_iter(b"ab").all(|m| m.bytes.is_empty()));
+            }
+        }
+    }
+
+    #[test]
+    fn needles_longer_than_the_haystack() {
+        for algorithm in Algorithm::ALL {
+            let searcher = Searcher::new(algorithm, b"abcd").ignore_ascii_case(true);
+            assert_eq!(searcher.find(b"abc"), None);
+            assert_eq!(searcher.find(b""), None);
+            assert_eq!(searcher.find(b"ABCD"), Some(0));
+            let mut matches = searcher.find_iter(b"xABCx");
+            assert_eq!(matches.next(), None);
+            // fused: still nothing
+            assert_eq!(matches.next(), None);
+        }
+    }
+
+    #[test]
+    fn overlapping_or_not() {
+        for algorithm in Algorithm::ALL {
+            let searcher = Searcher::new(algorithm, b"aa");
+            assert_eq!(searcher.find_all(b"aaaa"), [0, 2]);
+            assert_eq!(
+                searcher.clone().overlapping(true).find_all(b"aaaa"),
+                [0, 1, 2]
+            );
+            let searcher = Searcher::new(algorithm, b"ABA").ignore_ascii_case(true);
+            assert_eq!(searcher.needle(), b"aba");
+            let matches: Vec<Match> = searcher.overlapping(true).find_iter(b"xAbAbA").collect();
+            assert_eq!(matches.len(), 2);
+            assert_eq!(
+                (matches[1].bytes, matches[1].span.clone()),
+                (&b"AbA"[..], 3..6)
+            );
+        }
+    }
+}
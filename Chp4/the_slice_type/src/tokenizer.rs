@@ -0,0 +1,274 @@
+/* Word tokenizer */
+// first_word and second_word in main.rs look at the string one byte at a time and stop at
+// b' ', b',' or b'.'. That works for plain English, but a leading space makes the first word
+// empty, two spaces in a row make an empty word, and a non-breaking or ideographic space (or any
+// other character outside ASCII) isn't recognised at all.
+//
+// This module does the same job on chars instead of bytes. A Tokenizer holds a set of delimiters
+// (Unicode whitespace, Unicode punctuation, and/or specific characters), and hands out words as
+// &str slices of the original text, so nothing is copied. Every word also comes with its span,
+// the byte offsets where it starts and ends, which is what you'd slice the text with to get it
+// back: &text[span.start..span.end].
+use std::iter::FusedIterator;
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tokenizer {
+    whitespace: bool,
+    punctuation: bool,
+    chars: Vec<char>,
+    // keep apostrophes and hyphens that sit between two letters, so "don't" and "well-known"
+    // stay one word even when punctuation splits words
+    keep_joiners: bool,
+}
+
+// The default: words are separated by any Unicode whitespace.
+pub static WHITESPACE: Tokenizer = Tokenizer {
+    whitespace: true,
+    punctuation: false,
+    chars: Vec::new(),
+    keep_joiners: true,
+};
+
+impl Default for Tokenizer {
+    fn default() -> Self {
+        WHITESPACE.clone()
+    }
+}
+
+impl Tokenizer {
+    // Splits on whitespace only.
+    pub fn whitespace() -> Self {
+        Tokenizer::default()
+    }
+
+    // Splits on whitespace and punctuation, so "fooman," is just "fooman".
+    pub fn words_only() -> Self {
+        Tokenizer::default().with_punctuation()
+    }
+
+    // Splits on exactly the given characters and nothing else.
+    pub fn on_chars(chars: &[char]) -> Self {
+        Tokenizer {
+            whitespace: false,
+            punctuation: false,
+            chars: chars.to_vec(),
+            keep_joiners: false,
+        }
+    }
+
+    pub fn with_whitespace(mut self) -> Self {
+        self.whitespace = true;
+        self
+    }
+
+    pub fn with_punctuation(mut self) -> Self {
+        self.punctuation = true;
+        self
+    }
+
+    pub fn with_char(mut self, c: char) -> Self {
+        self.chars.push(c);
+        self
+    }
+
+    // Whether an apostrophe or hyphen between two letters stays inside the word. On by default;
+    // it only matters when the apostrophe or hyphen would otherwise be a delimiter.
+    pub fn keep_joiners(mut self, keep: bool) -> Self {
+        self.keep_joiners = keep;
+        self
+    }
+
+    pub fn is_delimiter(&self, c: char) -> bool {
+        (self.whitespace && c.is_whitespace())
+            || (self.punctuation && is_punctuation(c))
+            || self.chars.contains(&c)
+    }
+
+    // The char at byte offset i in text is a delimiter, taking joiners into account.
+    fn splits_at(&self, text: &str, i: usize, c: char) -> bool {
+        if !self.is_delimiter(c) {
+            return false;
+        }
+        if self.keep_joiners && is_joiner(c) {
+            let before = text[..i].chars().next_back();
+            let after = text[i + c.len_utf8()..].chars().next();
+            if before.is_some_and(char::is_alphanumeric) && after.is_some_and(char::is_alphanumeric)
+            {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Every word with its byte span, front to back (or back to front with .rev()).
+    pub fn tokens<'a, 't>(&'t self, text: &'a str) -> Tokens<'a, 't> {
+        Tokens {
+            tokenizer: self,
+            text,
+            front: 0,
+            back: text.len(),
+        }
+    }
+
+    // Just the words.
+    pub fn words<'a, 't>(&'t self, text: &'a str) -> Words<'a, 't> {
+        Words {
+            tokens: self.tokens(text),
+        }
+    }
+
+    // The word at index n (starting from 0), if there are that many.
+    pub fn nth_word<'a>(&self, text: &'a str, n: usize) -> Option<&'a str> {
+        self.words(text).nth(n)
+    }
+
+    pub fn first_word<'a>(&self, text: &'a str) -> Option<&'a str> {
+        self.nth_word(text, 0)
+    }
+
+    // Walks backwards from the end of the text, so it doesn't look at any earlier words.
+    pub fn last_word<'a>(&self, text: &'a str) -> Option<&'a str> {
+        self.words(text).next_back()
+    }
+
+    pub fn count(&self, text: &str) -> usize {
+        self.words(text).count()
+    }
+}
+
+// A word and where it is in the text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub text: &'a str,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tokens<'a, 't> {
+    tokenizer: &'t Tokenizer,
+    text: &'a str,
+    // the part of the text not handed out yet, as byte offsets
+    front: usize,
+    back: usize,
+}
+
+impl<'a> Iterator for Tokens<'a, '_> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let text = self.text;
+        // skip delimiters to find the start of the word...
+        let start = text[self.front..self.back]
+            .char_indices()
+            .map(|(i, c)| (self.front + i, c))
+            .find(|&(i, c)| !self.tokenizer.splits_at(text, i, c))
+            .map(|(i, _)| i)?;
+        // ...then take everything up to the next delimiter
+        let end = text[start..self.back]
+            .char_indices()
+            .map(|(i, c)| (start + i, c))
+            .find(|&(i, c)| self.tokenizer.splits_at(text, i, c))
+            .map_or(self.back, |(i, _)| i);
+        self.front = end;
+        Some(Token {
+            text: &text[start..end],
+            span: start..end,
+        })
+    }
+}
+
+impl<'a> DoubleEndedIterator for Tokens<'a, '_> {
+    fn next_back(&mut self) -> Option<Token<'a>> {
+        let text = self.text;
+        let end = text[self.front..self.back]
+            .char_indices()
+            .rev()
+            .map(|(i, c)| (self.front + i, c))
+            .find(|&(i, c)| !self.tokenizer.splits_at(text, i, c))
+            .map(|(i, c)| i + c.len_utf8())?;
+        let start = text[self.front..end]
+            .char_indices()
+            .rev()
+            .map(|(i, c)| (self.front + i, c))
+            .find(|&(i, c)| self.tokenizer.splits_at(text, i, c))
+            .map_or(self.front, |(i, c)| i + c.len_utf8());
+        self.back = start;
+        Some(Token {
+            text: &text[start..end],
+            span: start..end,
+        })
+    }
+}
+
+impl FusedIterator for Tokens<'_, '_> {}
+
+#[derive(Debug, Clone)]
+pub struct Words<'a, 't> {
+    tokens: Tokens<'a, 't>,
+}
+
+impl<'a> Iterator for Words<'a, '_> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        self.tokens.next().map(|token| token.text)
+    }
+}
+
+impl<'a> DoubleEndedIterator for Words<'a, '_> {
+    fn next_back(&mut self) -> Option<&'a str> {
+        self.tokens.next_back().map(|token| token.text)
+    }
+}
+
+impl FusedIterator for Words<'_, '_> {}
+
+// Whitespace-separated words, the most common case.
+pub fn words(text: &str) -> Words<'_, 'static> {
+    WHITESPACE.words(text)
+}
+
+pub fn tokens(text: &str) -> Tokens<'_, 'static> {
+    WHITESPACE.tokens(text)
+}
+
+/* Character classes */
+// Apostrophes (straight and curly) and hyphens (ASCII and Unicode).
+fn is_joiner(c: char) -> bool {
+    matches!(c, '\'' | '\u{2019}' | '-' | '\u{2010}' | '\u{2011}')
+}
+
+// The standard library only knows ASCII punctuation, and the full list of Unicode punctuation
+// (the P* general categories) is long. This covers ASCII plus the blocks that hold nearly all
+// punctuation you'll meet in practice: Latin-1, General Punctuation, Supplemental Punctuation,
+// CJK, fullwidth forms, and the sentence marks of a few other scripts.
+pub fn is_punctuation(c: char) -> bool {
+    if c.is_ascii() {
+        return c.is_ascii_punctuation();
+    }
+    matches!(c,
+        // Latin-1: ¡ § « ¶ · » ¿
+        '\u{A1}' | '\u{A7}' | '\u{AB}' | '\u{B6}' | '\u{B7}' | '\u{BB}' | '\u{BF}'
+        // Greek question mark and ano teleia
+        | '\u{37E}' | '\u{387}'
+        // Armenian, Hebrew, Arabic
+        | '\u{55A}'..='\u{55F}' | '\u{589}' | '\u{5BE}' | '\u{5C0}' | '\u{5C3}' | '\u{5C6}'
+        | '\u{5F3}' | '\u{5F4}' | '\u{60C}' | '\u{60D}' | '\u{61B}' | '\u{61E}' | '\u{61F}'
+        | '\u{66A}'..='\u{66D}' | '\u{6D4}'
+        // Devanagari danda, Thai, Ethiopic
+        | '\u{964}' | '\u{965}' | '\u{970}' | '\u{E4F}' | '\u{E5A}' | '\u{E5B}'
+        | '\u{1361}'..='\u{1368}'
+        // General Punctuation: dashes, quotes, daggers, ellipsis, per mille and so on (the
+        // spaces before U+2010 and the invisible characters after U+2064 are left out)
+        | '\u{2010}'..='\u{2027}' | '\u{2030}'..='\u{205E}'
+        // Supplemental Punctuation
+        | '\u{2E00}'..='\u{2E7F}'
+        // CJK: 、 。 〃 and the brackets 〈 〉 《 》 「 」 『 』 【 】 and friends
+        | '\u{3001}'..='\u{3003}' | '\u{3008}'..='\u{3011}' | '\u{3014}'..='\u{301F}'
+        | '\u{3030}' | '\u{303D}' | '\u{30FB}'
+        // fullwidth ！ ＂ ＃ ... and halfwidth ｡ ｢ ｣ ､ ･
+        | '\u{FF01}'..='\u{FF0F}' | '\u{FF1A}'..='\u{FF20}' | '\u{FF3B}'..='\u{FF40}'
+        | '\u{FF5B}'..='\u{FF65}'
+    )
+}
@@ -0,0 +1,210 @@
+/* Counting allocations */
+// main.rs says a move only copies the pointer, length and capacity on the stack, while .clone()
+// copies the heap data too. CountingAllocator lets you check that claim by counting. It hands
+// every request on to the system allocator and keeps count of the allocations, frees and
+// reallocations, the bytes involved, and the most heap memory in use at any one time (the peak).
+//
+// It only counts once it's installed as the program's allocator, which a crate does with:
+//     #[global_allocator]
+//     static ALLOCATOR: CountingAllocator = CountingAllocator;
+// The demos put that behind a 'count-allocations' feature, so normal runs use the plain system
+// allocator, and 'cargo run --features count-allocations' prints real numbers.
+//
+// region("label") starts measuring and .finish() returns a Report of what happened in between.
+// The counters are shared by the whole process, so anything another thread allocates at the same
+// time ends up in the report too; the demos here only have one thread.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fmt;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static DEALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static REALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static BYTES_ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static BYTES_FREED: AtomicUsize = AtomicUsize::new(0);
+// bytes in use right now, and the most there have been since the innermost region started
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+pub struct CountingAllocator;
+
+fn grew(bytes: usize) {
+    BYTES_ALLOCATED.fetch_add(bytes, Ordering::Relaxed);
+    let now = CURRENT.fetch_add(bytes, Ordering::Relaxed) + bytes;
+    PEAK.fetch_max(now, Ordering::Relaxed);
+}
+
+fn shrank(bytes: usize) {
+    BYTES_FREED.fetch_add(bytes, Ordering::Relaxed);
+    CURRENT.fetch_sub(bytes, Ordering::Relaxed);
+}
+
+// Only atomics in here: allocating from inside the allocator would call straight back into it.
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc(layout);
+        if !pointer.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            grew(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let pointer = System.alloc_zeroed(layout);
+        if !pointer.is_null() {
+            ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            grew(layout.size());
+        }
+        pointer
+    }
+
+    unsafe fn dealloc(&self, pointer: *mut u8, layout: Layout) {
+        System.dealloc(pointer, layout);
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        shrank(layout.size());
+    }
+
+    // A String or Vec that outgrows its capacity asks for a bigger block. That counts as one
+    // reallocation, which frees the old size and allocates the new one.
+    unsafe fn realloc(&self, pointer: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_pointer = System.realloc(pointer, layout, new_size);
+        if !new_pointer.is_null() {
+            REALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+            shrank(layout.size());
+            grew(new_size);
+        }
+        new_pointer
+    }
+}
+
+// Whether CountingAllocator is the global allocator: if it is, allocating a byte shows up in the
+// counters.
+pub fn is_installed() -> bool {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    drop(std::hint::black_box(Box::new(0u8)));
+    ALLOCATIONS.load(Ordering::Relaxed) > before
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Counts {
+    allocations: usize,
+    deallocations: usize,
+    reallocations: usize,
+    bytes_allocated: usize,
+    bytes_freed: usize,
+    current: usize,
+}
+
+impl Counts {
+    fn now() -> Self {
+        Counts {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            reallocations: REALLOCATIONS.load(Ordering::Relaxed),
+            bytes_allocated: BYTES_ALLOCATED.load(Ordering::Relaxed),
+            bytes_freed: BYTES_FREED.load(Ordering::Relaxed),
+            current: CURRENT.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/* Regions */
+pub struct Region {
+    label: &'static str,
+    start: Counts,
+    // the peak before this region started; put back (if it was higher) when the region ends, so
+    // an outer region still sees the highest point of everything inside it
+    outer_peak: usize,
+}
+
+pub fn region(label: &'static str) -> Region {
+    let start = Counts::now();
+    let outer_peak = PEAK.swap(start.current, Ordering::Relaxed);
+    Region {
+        label,
+        start,
+        outer_peak,
+    }
+}
+
+impl Region {
+    pub fn finish(self) -> Report {
+        let end = Counts::now();
+        Report {
+            label: self.label,
+            allocations: end.allocations - self.start.allocations,
+            deallocations: end.deallocations - self.start.deallocations,
+            reallocations: end.reallocations - self.start.reallocations,
+            bytes_allocated: end.bytes_allocated - self.start.bytes_allocated,
+            bytes_freed: end.bytes_freed - self.start.bytes_freed,
+            peak: PEAK
+                .load(Ordering::Relaxed)
+                .saturating_sub(self.start.current),
+        }
+    }
+}
+
+impl Drop for Region {
+    fn drop(&mut self) {
+        PEAK.fetch_max(self.outer_peak, Ordering::Relaxed);
+    }
+}
+
+// Runs f in a region of its own, returning its result and the report.
+pub fn measure<R>(label: &'static str, f: impl FnOnce() -> R) -> (R, Report) {
+    let region = region(label);
+    let result = f();
+    (result, region.finish())
+}
+
+/* Reports */
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Report {
+    pub label: &'static str,
+    pub allocations: usize,
+    pub deallocations: usize,
+    pub reallocations: usize,
+    pub bytes_allocated: usize,
+    pub bytes_freed: usize,
+    // the most heap memory the region had in use at once, on top of what was in use before it
+    pub peak: usize,
+}
+
+impl Report {
+    // what the region left allocated when it ended (negative if it freed older memory)
+    pub fn net_bytes(&self) -> isize {
+        self.bytes_allocated as isize - self.bytes_freed as isize
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:<36} {:>6} {:>8} {:>6} {:>9} {:>7} {:>7}",
+            self.label,
+            self.allocations,
+            self.reallocations,
+            self.deallocations,
+            self.bytes_allocated,
+            self.peak,
+            self.net_bytes()
+        )
+    }
+}
+
+pub fn print_reports(title: &str, reports: &[Report]) {
+    println!("\x1b[1;33m{title}\x1b[0m");
+    if !is_installed() {
+        println!("  (not counting: run with 'cargo run --features count-allocations')");
+        return;
+    }
+    println!(
+        "  {:<36} {:>6} {:>8} {:>6} {:>9} {:>7} {:>7}",
+        "", "allocs", "reallocs", "frees", "bytes", "peak", "net"
+    );
+    for report in reports {
+        println!("  {report}");
+    }
+}
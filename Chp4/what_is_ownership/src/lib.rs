@@ -0,0 +1,4 @@
+// Tools for watching ownership happen, used by the demos in main.rs. Other chapters use
+// alloc_counter through a path dependency on this crate.
+pub mod alloc_counter;
+pub mod tracker;
@@ -1,4 +1,19 @@
+use what_is_ownership::alloc_counter::{self, Report};
+use what_is_ownership::tracker::{self, Tracked};
+
+// cargo run --features count-allocations counts every allocation (see alloc_counter.rs)
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
 fn main() {
+    // The first println! allocates stdout's buffer. Get that out of the way now, so it doesn't show
+    // up in the first section's count.
+    if alloc_counter::is_installed() {
+        println!("counting allocations");
+    }
+    let mut reports: Vec<Report> = Vec::new();
+    let section = alloc_counter::region("string literal");
     {
         // Each value in Rust has an owner.
         // There can only be one owner at a time.
@@ -6,6 +21,8 @@ fn main() {
         let s: &str = "hello"; // s is valid from this point forward
         println!("{s}");
     } // this scope is over, s is no longer valid
+    reports.push(section.finish());
+    let section = alloc_counter::region("String::from, then push_str");
     {
         let mut s = String::from("hello");
 
@@ -13,6 +30,8 @@ fn main() {
         s.push_str(", world!"); // push_str() appends a literal to a string
         println!("{s}");
     }
+    reports.push(section.finish());
+    let section = alloc_counter::region("move s1 into s2, then push_str");
     {
         let x: u8 = 5;
         let y = x;
@@ -36,6 +55,8 @@ fn main() {
         s2.push_str(", world");
         println!("s2 = {s2}");
     }
+    reports.push(section.finish());
+    let section = alloc_counter::region("clone s1 into s2, then push_str");
     {
         // if we do want to deeply copy the heap data of the String, not just the stack data, we
         // can use a common method called clone:
@@ -55,48 +76,97 @@ fn main() {
          * * The char type,
          * * Tuples, if they only contain types that also implement Copy*/
     }
+    reports.push(section.finish());
+    /* Both of the last two sections start with one allocation for s1. Moving it into s2 adds
+     * nothing, but cloning it allocates a second copy. push_str then reallocates s2 when it
+     * outgrows its capacity, and every allocation is freed by the end of its block. */
+    alloc_counter::print_reports("cost of each section:", &reports);
+    alloc_counter::print_reports(
+        "cost of moving into and out of functions:",
+        &function_costs(),
+    );
+    // The two demos below use Tracked<String> (see tracker.rs) instead of plain Strings, so each
+    // one can print a timeline afterwards: where every String was allocated, moved, cloned and
+    // freed, with the scope it happened in.
     ownership_and_functions();
+    tracker::print_timeline("ownership_and_functions timeline:");
     return_values_and_scope();
+    tracker::print_timeline("return_values_and_scope timeline:");
 }
 fn ownership_and_functions() {
-    let s = String::from("hello, fooman");
-    takes_ownership(s); // s's value is MOVEd to the function
-                        // now, just like when assigning before, s can no longer be used
+    let _scope = tracker::scope("ownership_and_functions");
+    let s = Tracked::new("s", String::from("hello, fooman"));
+    // s's value is MOVEd to the function
+    // now, just like when assigning before, s can no longer be used
+    tracker::call("takes_ownership", || {
+        takes_ownership(s.moved_to("takes_ownership"))
+    });
 
     let x: u8 = 5;
     makes_copy(x); // u8 is Copy, so its value will be copied into the function rather than
                    // moved, without having to explicitly use '.clone()'
     println!("{} is still in scope!", x);
     // now with a string:
-    let s = String::from("hello, fooman");
-    copies_string(s.clone());
+    let s = Tracked::new("s", String::from("hello, fooman"));
+    tracker::call("copies_string", || copies_string(s.clone()));
     println!("{s} is still in scope");
-}
-fn takes_ownership(some_string: String) {
+} // s is dropped here; the first s was already dropped inside takes_ownership
+fn takes_ownership(some_string: Tracked<String>) {
     println!("{some_string} will be out of scope and unusable once the function ends");
 }
 fn makes_copy(some_integer: u8) {
     println!("{some_integer}");
 }
-fn copies_string(some_string: String) {
+fn copies_string(some_string: Tracked<String>) {
     println!("copied \"{}\"", some_string);
 }
 fn return_values_and_scope() {
+    let _scope = tracker::scope("return_values_and_scope");
     // Returning values can also transfer ownership.
-    let s1 = gives_ownership(); // gives_ownership moves its value into s1
+    // gives_ownership moves its value into s1
+    let s1 = tracker::call("gives_ownership", gives_ownership).rename("s1");
     println!("{s1}");
 
-    let s2 = String::from("hello"); // s2 comes into scope
+    let s2 = Tracked::new("s2", String::from("hello")); // s2 comes into scope
 
-    let s3 = takes_and_gives_back(s2); // s2 is moved into takes_and_gives_back, which moves its
-    println!("{s3}"); // return value to s3
+    // s2 is moved into takes_and_gives_back, which moves its return value to s3
+    let s3 = tracker::call("takes_and_gives_back", || {
+        takes_and_gives_back(s2.moved_to("takes_and_gives_back"))
+    })
+    .rename("s3");
+    println!("{s3}");
+} // s3 and s1 are dropped here, in that order; s2 was moved, so nothing happens to it
+
+// The same calls as the two demos above, with plain Strings, since Tracked allocates for its own
+// bookkeeping.
+fn function_costs() -> Vec<Report> {
+    let s = String::from("hello, fooman");
+    let (_, moved) = alloc_counter::measure("takes_ownership(s)", || takes_ownership_untracked(s));
+    let s = String::from("hello, fooman");
+    let (_, cloned) = alloc_counter::measure("takes_ownership(s.clone())", || {
+        takes_ownership_untracked(s.clone())
+    });
+    let (s1, returned) =
+        alloc_counter::measure("let s1 = gives_ownership()", || String::from("yours"));
+    let (s3, round_trip) = alloc_counter::measure("let s3 = takes_and_gives_back(s1)", || {
+        takes_and_gives_back_untracked(s1)
+    });
+    drop(s3);
+    vec![moved, cloned, returned, round_trip]
+}
+fn takes_ownership_untracked(some_string: String) -> usize {
+    some_string.len()
+}
+fn takes_and_gives_back_untracked(a_string: String) -> String {
+    a_string
 }
-fn gives_ownership() -> String {
-    String::from("yours")
+fn gives_ownership() -> Tracked<String> {
+    let some_string = Tracked::new("some_string", String::from("yours"));
+    some_string.moved_to("the caller") // some_string is returned and moves out
 }
-fn takes_and_gives_back(a_string: String) -> String {
+fn takes_and_gives_back(a_string: Tracked<String>) -> Tracked<String> {
     // a_string comes into scope
-    a_string // a_string is returned and moves out to the calling function
+    a_string.moved_to("the caller") // a_string is returned and moves out to the calling function
 }
 /* The ownership of a variable follows the same pattern every time: assigning a value to another
 * variable moves it. When a variable that includes data on the heap goes out of scope, the value
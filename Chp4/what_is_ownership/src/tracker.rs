@@ -0,0 +1,426 @@
+/* Ownership tracker */
+// main.rs explains moves, clones and drops in comments, because normally none of them print
+// anything. Tracked<T> wraps a value and writes down everything that happens to it:
+//   * where it was created, and where it was cloned (with #[track_caller], the source location
+//     is the line that called new() or clone(), not a line in this file)
+//   * where it was moved. Rust moves are just memcpys the compiler emits, so there is nothing to
+//     hook into; instead, .moved_to("...") marks the spot by hand and hands the value back
+//   * where it was dropped, which happens automatically when its owner goes out of scope
+// For a String (or Vec or Box) every event also shows the heap address, so you can see that a
+// move keeps the same allocation, a clone makes a new one, and the drop frees it.
+//
+// Scopes are named with scope() (a guard that lasts until the end of the block) or call() (for
+// a function call). Events are collected per thread and printed with print_timeline().
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+use std::panic::Location;
+
+// What a value can say about its heap memory: the address and capacity of its allocation, if
+// it has one. Values that live entirely on the stack don't have anything to report.
+pub trait Traceable: fmt::Debug {
+    fn heap(&self) -> Option<(usize, usize)> {
+        None
+    }
+}
+
+impl Traceable for String {
+    fn heap(&self) -> Option<(usize, usize)> {
+        // an empty String hasn't allocated anything yet
+        (self.capacity() > 0).then(|| (self.as_ptr() as usize, self.capacity()))
+    }
+}
+
+impl<T: fmt::Debug> Traceable for Vec<T> {
+    fn heap(&self) -> Option<(usize, usize)> {
+        (self.capacity() > 0).then(|| {
+            (
+                self.as_ptr() as usize,
+                self.capacity() * std::mem::size_of::<T>(),
+            )
+        })
+    }
+}
+
+impl<T: fmt::Debug> Traceable for Box<T> {
+    fn heap(&self) -> Option<(usize, usize)> {
+        let size = std::mem::size_of::<T>();
+        (size > 0).then_some((&**self as *const T as usize, size))
+    }
+}
+
+macro_rules! stack_only {
+    ($($t:ty),*) => {$(
+        impl Traceable for $t {}
+    )*};
+}
+
+stack_only!(
+    u8, i8, u16, i16, u32, i32, u64, i64, u128, i128, usize, isize, f32, f64, bool, char, &str
+);
+
+/* Events */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventKind {
+    Created(String),
+    Cloned { from: usize },
+    Moved { to: &'static str },
+    Unwrapped,
+    Dropped,
+    EnterScope(&'static str),
+    LeaveScope(&'static str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub kind: EventKind,
+    // the value's id and variable name (0 and "" for scope events)
+    pub id: usize,
+    pub name: &'static str,
+    pub depth: usize,
+    // where in the source it happened; drops have no call site, so they name their scope
+    pub location: Option<&'static Location<'static>>,
+    pub scope: &'static str,
+    pub heap: Option<(usize, usize)>,
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let indent = "  ".repeat(self.depth);
+        let what = match &self.kind {
+            EventKind::EnterScope(name) => format!("▸ {name}"),
+            EventKind::LeaveScope(name) => format!("◂ end of {name}"),
+            EventKind::Created(value) => format!("#{} {} = {value}", self.id, self.name),
+            EventKind::Cloned { from } => {
+                format!("#{} {} cloned from #{from}", self.id, self.name)
+            }
+            EventKind::Moved { to } => format!("#{} {} moved into {to}", self.id, self.name),
+            EventKind::Unwrapped => {
+                format!("#{} {} unwrapped, no longer tracked", self.id, self.name)
+            }
+            EventKind::Dropped => {
+                format!(
+                    "#{} {} dropped at the end of {}",
+                    self.id, self.name, self.scope
+                )
+            }
+        };
+        let heap = match (&self.kind, self.heap) {
+            (EventKind::Created(_) | EventKind::Cloned { .. }, Some((address, size))) => {
+                format!("allocates {size} bytes at {address:#x}")
+            }
+            (EventKind::Dropped, Some((address, _))) => format!("frees {address:#x}"),
+            (_, Some((address, _))) => format!("heap still at {address:#x}"),
+            (_, None) => String::new(),
+        };
+        let location = match self.location {
+            Some(location) => format!("{}:{}", location.file(), location.line()),
+            None => String::new(),
+        };
+        let line = format!("{:<56} {heap:<36} {location}", format!("{indent}{what}"));
+        write!(f, "{}", line.trim_end())
+    }
+}
+
+thread_local! {
+    static EVENTS: RefCell<Vec<Event>> = const { RefCell::new(Vec::new()) };
+    static SCOPES: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    static NEXT_ID: Cell<usize> = const { Cell::new(1) };
+}
+
+fn record(
+    kind: EventKind,
+    id: usize,
+    name: &'static str,
+    location: Option<&'static Location<'static>>,
+    heap: Option<(usize, usize)>,
+) {
+    let (depth, scope) = SCOPES.with(|scopes| {
+        let scopes = scopes.borrow();
+        (scopes.len(), scopes.last().copied().unwrap_or("main"))
+    });
+    EVENTS.with(|events| {
+        events.borrow_mut().push(Event {
+            kind,
+            id,
+            name,
+            depth,
+            location,
+            scope,
+            heap,
+        })
+    });
+}
+
+// Everything recorded on this thread so far, emptying the log.
+pub fn take_timeline() -> Vec<Event> {
+    EVENTS.with(|events| std::mem::take(&mut *events.borrow_mut()))
+}
+
+pub fn print_timeline(title: &str) {
+    println!("\x1b[1;33m{title}\x1b[0m");
+    for event in take_timeline() {
+        println!("  {event}");
+    }
+}
+
+/* Scopes */
+pub struct Scope {
+    name: &'static str,
+}
+
+// Marks the rest of the current block as a named scope. Keep the guard in a variable declared
+// before the values it should contain: locals are dropped in reverse order, so the guard, being
+// first, goes last, and the values' drops are recorded inside the scope.
+#[track_caller]
+pub fn scope(name: &'static str) -> Scope {
+    record(
+        EventKind::EnterScope(name),
+        0,
+        "",
+        Some(Location::caller()),
+        None,
+    );
+    SCOPES.with(|scopes| scopes.borrow_mut().push(name));
+    Scope { name }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        SCOPES.with(|scopes| scopes.borrow_mut().pop());
+        record(EventKind::LeaveScope(self.name), 0, "", None, None);
+    }
+}
+
+// Runs f inside a scope called name. Handy for function calls: a function's parameters are
+// dropped after everything in its body, so a scope() guard inside the function would already be
+// gone when they drop, but one wrapped around the call still covers them.
+#[track_caller]
+pub fn call<R>(name: &'static str, f: impl FnOnce() -> R) -> R {
+    let _scope = scope(name);
+    f()
+}
+
+/* Tracked values */
+pub struct Tracked<T: Traceable> {
+    id: usize,
+    name: &'static str,
+    // None once into_inner() has taken the value out, so Drop knows not to report it
+    value: Option<T>,
+}
+
+impl<T: Traceable> Tracked<T> {
+    #[track_caller]
+    pub fn new(name: &'static str, value: T) -> Self {
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        record(
+            EventKind::Created(format!("{value:?}")),
+            id,
+            name,
+            Some(Location::caller()),
+            value.heap(),
+        );
+        Tracked {
+            id,
+            name,
+            value: Some(value),
+        }
+    }
+
+    fn value(&self) -> &T {
+        self.value
+            .as_ref()
+            .expect("only into_inner() takes the value")
+    }
+
+    pub fn id(&self) -> usize {
+        self.id
+    }
+
+    // Records a move into `to` (a function, a variable, a return value...) and returns the
+    // value, so it can be used right where the move happens: takes(s.moved_to("takes")).
+    #[track_caller]
+    pub fn moved_to(self, to: &'static str) -> Self {
+        record(
+            EventKind::Moved { to },
+            self.id,
+            self.name,
+            Some(Location::caller()),
+            self.value().heap(),
+        );
+        self
+    }
+
+    // The same value under a new variable name, like 'let s2 = s1;'.
+    #[track_caller]
+    pub fn rename(mut self, name: &'static str) -> Self {
+        record(
+            EventKind::Moved { to: name },
+            self.id,
+            self.name,
+            Some(Location::caller()),
+            self.value().heap(),
+        );
+        self.name = name;
+        self
+    }
+
+    // Stops tracking and hands back the plain value.
+    #[track_caller]
+    pub fn into_inner(mut self) -> T {
+        let value = self
+            .value
+            .take()
+            .expect("only into_inner() takes the value");
+        record(
+            EventKind::Unwrapped,
+            self.id,
+            self.name,
+            Some(Location::caller()),
+            value.heap(),
+        );
+        value
+    }
+}
+
+impl<T: Traceable + Clone> Clone for Tracked<T> {
+    #[track_caller]
+    fn clone(&self) -> Self {
+        let value = self.value().clone();
+        let id = NEXT_ID.with(|next| next.replace(next.get() + 1));
+        record(
+            EventKind::Cloned { from: self.id },
+            id,
+            self.name,
+            Some(Location::caller()),
+            value.heap(),
+        );
+        Tracked {
+            id,
+            name: self.name,
+            value: Some(value),
+        }
+    }
+}
+
+impl<T: Traceable> Drop for Tracked<T> {
+    fn drop(&mut self) {
+        if let Some(value) = &self.value {
+            record(EventKind::Dropped, self.id, self.name, None, value.heap());
+        }
+    }
+}
+
+impl<T: Traceable> Deref for Tracked<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        self.value()
+    }
+}
+
+impl<T: Traceable> DerefMut for Tracked<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        self.value
+            .as_mut()
+            .expect("only into_inner() takes the value")
+    }
+}
+
+impl<T: Traceable + fmt::Display> fmt::Display for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.value(), f)
+    }
+}
+
+impl<T: Traceable> fmt::Debug for Tracked<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Tracked(#{} {}: {:?})", self.id, self.name, self.value())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The timeline without the parts that change from run to run: which scope each event
+    // happened in, and whether it had a heap address.
+    fn kinds(timeline: &[Event]) -> Vec<(EventKind, usize, &'static str, &'static str)> {
+        timeline
+            .iter()
+            .map(|event| (event.kind.clone(), event.id, event.name, event.scope))
+            .collect()
+    }
+
+    #[test]
+    fn move_then_drop() {
+        take_timeline();
+        let id = {
+            let _block = scope("block");
+            let s1 = Tracked::new("s1", String::from("hello"));
+            let id = s1.id();
+            let s2 = s1.rename("s2");
+            call("takes", || drop(s2.moved_to("takes")));
+            id
+        };
+        let timeline = take_timeline();
+        assert_eq!(
+            kinds(&timeline),
+            [
+                (EventKind::EnterScope("block"), 0, "", "main"),
+                (
+                    EventKind::Created("\"hello\"".to_string()),
+                    id,
+                    "s1",
+                    "block"
+                ),
+                (EventKind::Moved { to: "s2" }, id, "s1", "block"),
+                (EventKind::EnterScope("takes"), 0, "", "block"),
+                (EventKind::Moved { to: "takes" }, id, "s2", "takes"),
+                (EventKind::Dropped, id, "s2", "takes"),
+                (EventKind::LeaveScope("takes"), 0, "", "block"),
+                (EventKind::LeaveScope("block"), 0, "", "main"),
+            ]
+        );
+        let depths: Vec<usize> = timeline.iter().map(|event| event.depth).collect();
+        assert_eq!(depths, [0, 1, 1, 1, 2, 2, 1, 0]);
+        // a move keeps the allocation it was created with, right up to the drop that frees it
+        let heap = timeline[1].heap;
+        assert!(heap.is_some_and(|(_, size)| size == 5));
+        for event in timeline.iter().filter(|event| event.id == id) {
+            assert_eq!(event.heap, heap, "{event}");
+        }
+        // moves and creations point at the line that did them; drops have no line
+        assert!(timeline[1].location.unwrap().file().ends_with("tracker.rs"));
+        assert_eq!(timeline[5].location, None);
+    }
+
+    #[test]
+    fn clone_then_drop() {
+        take_timeline();
+        {
+            let _block = scope("block");
+            let s1 = Tracked::new("s1", String::from("hi"));
+            let s2 = s1.clone();
+            assert_ne!(s1.id(), s2.id());
+            // unwrapping stops the tracking, so s1 never reports a drop
+            assert_eq!(s1.into_inner(), "hi");
+        }
+        let timeline = take_timeline();
+        let (s1, s2) = (timeline[1].id, timeline[2].id);
+        assert_eq!(
+            kinds(&timeline),
+            [
+                (EventKind::EnterScope("block"), 0, "", "main"),
+                (EventKind::Created("\"hi\"".to_string()), s1, "s1", "block"),
+                (EventKind::Cloned { from: s1 }, s2, "s1", "block"),
+                (EventKind::Unwrapped, s1, "s1", "block"),
+                (EventKind::Dropped, s2, "s1", "block"),
+                (EventKind::LeaveScope("block"), 0, "", "main"),
+            ]
+        );
+        // a clone has an allocation of its own, which is the one its drop frees
+        assert_ne!(timeline[2].heap, timeline[1].heap);
+        assert_eq!(timeline[4].heap, timeline[2].heap);
+    }
+}
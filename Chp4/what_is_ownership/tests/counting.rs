@@ -0,0 +1,49 @@
+// The allocation counts, with CountingAllocator installed. This is a test binary of its own
+// because the counters are shared by the whole process: tests in src/ run on several threads at
+// once, and their allocations would end up in these counts. So keep it to the one test.
+// Run it with 'cargo test --features count-allocations'.
+#![cfg(feature = "count-allocations")]
+use std::hint::black_box;
+use what_is_ownership::alloc_counter::{self, CountingAllocator};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+#[test]
+fn push_str_counts() {
+    assert!(alloc_counter::is_installed());
+
+    // String::from allocates exactly the 5 bytes of "hello". Pushing 7 more needs 12, which is
+    // more than double 5, so it grows to 12; one more byte doubles that to 24.
+    let ((), report) = alloc_counter::measure("push_str", || {
+        let mut s = String::from(black_box("hello"));
+        s.push_str(black_box(", world"));
+        assert_eq!(s.capacity(), 12);
+        s.push_str(black_box("!"));
+        assert_eq!(s.capacity(), 24);
+        black_box(s);
+    });
+    assert_eq!(
+        (
+            report.allocations,
+            report.reallocations,
+            report.deallocations
+        ),
+        (1, 2, 1)
+    );
+    // a reallocation frees the old size and allocates the new one
+    assert_eq!(report.bytes_allocated, 5 + 12 + 24);
+    assert_eq!(report.bytes_freed, 5 + 12 + 24);
+    assert_eq!(report.peak, 24);
+    assert_eq!(report.net_bytes(), 0);
+
+    // a move allocates nothing, and a clone copies the data into an allocation of its own
+    let s1 = String::from(black_box("hello"));
+    let (s2, moved) = alloc_counter::measure("move", || black_box(s1));
+    let (s3, cloned) = alloc_counter::measure("clone", || black_box(s2.clone()));
+    assert_eq!((moved.allocations, moved.bytes_allocated), (0, 0));
+    assert_eq!((cloned.allocations, cloned.bytes_allocated), (1, 5));
+    assert_eq!(cloned.net_bytes(), 5);
+    let ((), dropped) = alloc_counter::measure("drop", || drop((s2, s3)));
+    assert_eq!((dropped.deallocations, dropped.net_bytes()), (2, -10));
+}
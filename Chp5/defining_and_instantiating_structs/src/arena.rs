@@ -0,0 +1,379 @@
+/* Arenas */
+// The NewUser struct at the bottom of main.rs won't compile because a &str field needs a lifetime:
+// the compiler has to know that whatever the reference points at outlives the struct. The easy
+// way out is to own the data with String, which is what User does, at the cost of a separate heap
+// allocation for every field of every user.
+//
+// An arena is the other way out. It's a big block of memory that hands out pieces of itself, all
+// with the arena's lifetime: arena.alloc(x) moves x into the arena and returns &'arena T. Nothing
+// is freed on its own; everything goes at once when the arena is dropped, which is one free per
+// chunk instead of one per object. Since every reference lives exactly as long as the arena,
+// structs holding them just need one lifetime, and they can point at each other freely (cycles
+// included) without Rc, because nobody has to own anybody.
+//
+// Arena<T> keeps its values in chunks, Vecs that are never allowed to grow past the capacity they
+// started with. Growing would move the values and leave the references already handed out
+// pointing at freed memory, so when a chunk is full it's put aside and a new one twice the size is
+// started. StringArena does the same for text: it copies strings into big Strings and hands back
+// &str slices of them.
+use std::cell::RefCell;
+use std::mem;
+
+const FIRST_CHUNK: usize = 64;
+
+pub struct Arena<T> {
+    chunks: RefCell<Chunks<T>>,
+}
+
+struct Chunks<T> {
+    // where new values go; it never reallocates, see reserve
+    current: Vec<T>,
+    // the full chunks
+    full: Vec<Vec<T>>,
+}
+
+impl<T> Chunks<T> {
+    // Makes sure the current chunk has room for extra more values, starting a new chunk if not.
+    fn reserve(&mut self, extra: usize) {
+        if self.current.capacity() - self.current.len() >= extra {
+            return;
+        }
+        let capacity = (self.current.capacity() * 2).max(extra).max(FIRST_CHUNK);
+        let full = mem::replace(&mut self.current, Vec::with_capacity(capacity));
+        if !full.is_empty() {
+            self.full.push(full);
+        }
+    }
+}
+
+impl<T> Default for Arena<T> {
+    fn default() -> Self {
+        Arena::new()
+    }
+}
+
+impl<T> Arena<T> {
+    pub fn new() -> Self {
+        Arena::with_capacity(0)
+    }
+
+    // Room for capacity values before the first new chunk is needed.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Arena {
+            chunks: RefCell::new(Chunks {
+                current: Vec::with_capacity(capacity),
+                full: Vec::new(),
+            }),
+        }
+    }
+
+    // Moves value into the arena. The reference is good for as long as the arena is, and the
+    // arena only needs to be borrowed shared, so any number of these can be alive at once.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc(&self, value: T) -> &mut T {
+        let mut chunks = self.chunks.borrow_mut();
+        chunks.reserve(1);
+        chunks.current.push(value);
+        let last = chunks.current.len() - 1;
+        // SAFETY: reserve made room, so the push didn't reallocate and nothing that was handed
+        // out before has moved. The chunk's buffer is only freed when the arena is dropped, which
+        // the borrow of self rules out while the reference is alive, and each value is handed out
+        // exactly once, so the &mut is the only reference to it. The pointer comes straight from
+        // as_mut_ptr: &mut chunks.current[last] would go through a &mut [T] of the whole chunk,
+        // which would also cover the values handed out earlier, while they're still in use.
+        unsafe { &mut *chunks.current.as_mut_ptr().add(last) }
+    }
+
+    // Moves every value from the iterator into the arena, next to each other, and returns them as
+    // a slice.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_extend(&self, values: impl IntoIterator<Item = T>) -> &mut [T] {
+        // collected first: the iterator could allocate from this arena itself
+        let values: Vec<T> = values.into_iter().collect();
+        let mut chunks = self.chunks.borrow_mut();
+        chunks.reserve(values.len());
+        let start = chunks.current.len();
+        chunks.current.extend(values);
+        let len = chunks.current.len() - start;
+        // SAFETY: the same as alloc; reserve made room for all of them in the current chunk
+        unsafe { &mut *new_values(&mut chunks.current, start, len) }
+    }
+
+    // Copies a slice into the arena. Unlike alloc_extend this doesn't need a Vec of its own on the
+    // way in, so building lots of small slices from one reused buffer only allocates chunks.
+    // It's only for Copy types, which are copied byte for byte: a Clone impl would run while the
+    // chunks are borrowed, and one that used this arena would panic. For those,
+    // alloc_extend(values.iter().cloned()) clones everything before it borrows anything.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice(&self, values: &[T]) -> &mut [T]
+    where
+        T: Copy,
+    {
+        let mut chunks = self.chunks.borrow_mut();
+        chunks.reserve(values.len());
+        let start = chunks.current.len();
+        // SAFETY: reserve made room for values.len() more after start, the source is a separate
+        // slice (it can't be in the spare capacity, nothing has been handed out from there), and
+        // T is Copy so the copies are values in their own right once the length takes them in.
+        unsafe {
+            let end = chunks.current.as_mut_ptr().add(start);
+            std::ptr::copy_nonoverlapping(values.as_ptr(), end, values.len());
+            chunks.current.set_len(start + values.len());
+        }
+        // SAFETY: the same as alloc_extend
+        unsafe { &mut *new_values(&mut chunks.current, start, values.len()) }
+    }
+
+    // How many values are in the arena.
+    pub fn len(&self) -> usize {
+        let chunks = self.chunks.borrow();
+        chunks.current.len() + chunks.full.iter().map(Vec::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // How many blocks of memory the arena has asked for, which is how many frees dropping it
+    // takes.
+    pub fn chunk_count(&self) -> usize {
+        let chunks = self.chunks.borrow();
+        chunks.full.len() + usize::from(chunks.current.capacity() > 0)
+    }
+
+    // Gives the values back, in the order they went in. This takes the arena by value, so no
+    // references into it can be left.
+    pub fn into_vec(self) -> Vec<T> {
+        let chunks = self.chunks.into_inner();
+        let mut values: Vec<T> = chunks.full.into_iter().flatten().collect();
+        values.extend(chunks.current);
+        values
+    }
+}
+
+// The values from start to start + len of a chunk, as a raw slice made from the buffer pointer, so
+// no reference to the rest of the chunk is made on the way.
+fn new_values<T>(chunk: &mut Vec<T>, start: usize, len: usize) -> *mut [T] {
+    // SAFETY: the callers only ask for values that are in the chunk, so start is in bounds
+    let first = unsafe { chunk.as_mut_ptr().add(start) };
+    std::ptr::slice_from_raw_parts_mut(first, len)
+}
+
+/* Strings */
+pub struct StringArena {
+    chunks: RefCell<StringChunks>,
+}
+
+struct StringChunks {
+    current: String,
+    full: Vec<String>,
+}
+
+impl Default for StringArena {
+    fn default() -> Self {
+        StringArena::new()
+    }
+}
+
+impl StringArena {
+    pub fn new() -> Self {
+        StringArena::with_capacity(0)
+    }
+
+    // Room for capacity bytes of text before the first new chunk is needed.
+    pub fn with_capacity(capacity: usize) -> Self {
+        StringArena {
+            chunks: RefCell::new(StringChunks {
+                current: String::with_capacity(capacity),
+                full: Vec::new(),
+            }),
+        }
+    }
+
+    // Copies text into the arena.
+    pub fn alloc_str(&self, text: &str) -> &str {
+        let mut chunks = self.chunks.borrow_mut();
+        let chunks = &mut *chunks;
+        if chunks.current.capacity() - chunks.current.len() < text.len() {
+            let capacity = (chunks.current.capacity() * 2)
+                .max(text.len())
+                .max(FIRST_CHUNK * 16);
+            let full = mem::replace(&mut chunks.current, String::with_capacity(capacity));
+            if !full.is_empty() {
+                chunks.full.push(full);
+            }
+        }
+        let start = chunks.current.len();
+        chunks.current.push_str(text);
+        // SAFETY: as in Arena::alloc, there was room so push_str didn't move the String's buffer,
+        // and the buffer lives until the arena is dropped. The bytes are never written again, and
+        // they're a copy of a &str, so they're UTF-8.
+        unsafe {
+            let copy = chunks.current.as_ptr().add(start);
+            std::str::from_utf8_unchecked(std::slice::from_raw_parts(copy, text.len()))
+        }
+    }
+
+    // The bytes of text stored.
+    pub fn len(&self) -> usize {
+        let chunks = self.chunks.borrow();
+        chunks.current.len() + chunks.full.iter().map(String::len).sum::<usize>()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn chunk_count(&self) -> usize {
+        let chunks = self.chunks.borrow();
+        chunks.full.len() + usize::from(chunks.current.capacity() > 0)
+    }
+}
+
+// These are worth running under Miri as well as the usual way, since what they check is that the
+// unsafe blocks above are sound: cargo +nightly miri test arena. They're kept small enough for it.
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+
+    #[test]
+    fn references_survive_new_chunks() {
+        let arena = Arena::with_capacity(2);
+        let first = arena.alloc(String::from("first"));
+        let second = arena.alloc_extend([1, 2].map(|n| n.to_string()));
+        // the first chunk is full now, so these start a second and a third
+        let mut later = Vec::new();
+        for n in 0..FIRST_CHUNK + 10 {
+            later.push(arena.alloc(n.to_string()));
+        }
+        assert_eq!(arena.chunk_count(), 3);
+        // everything handed out is still there, and can still be written through
+        first.push_str(" still");
+        second[1].push('!');
+        *later[0] = String::from("zero");
+        assert_eq!(first, "first still");
+        assert_eq!(second, ["1", "2!"]);
+        assert_eq!(later[0], "zero");
+        assert_eq!(*later[FIRST_CHUNK + 9], (FIRST_CHUNK + 9).to_string());
+        assert_eq!(arena.len(), 3 + FIRST_CHUNK + 10);
+    }
+
+    #[test]
+    fn values_can_point_at_each_other() {
+        struct Node<'a> {
+            name: &'a str,
+            next: Cell<Option<&'a Node<'a>>>,
+        }
+        let names = StringArena::new();
+        let nodes = Arena::new();
+        let a = nodes.alloc(Node {
+            name: names.alloc_str("a"),
+            next: Cell::new(None),
+        });
+        let b = nodes.alloc(Node {
+            name: names.alloc_str("b"),
+            next: Cell::new(Some(&*a)),
+        });
+        let (a, b) = (&*a, &*b);
+        a.next.set(Some(b));
+        assert_eq!(a.next.get().unwrap().next.get().unwrap().name, "a");
+    }
+
+    #[test]
+    fn into_vec_keeps_the_order() {
+        let arena = Arena::new();
+        arena.alloc(0);
+        arena.alloc_extend(1..FIRST_CHUNK as u32);
+        // doesn't fit in what's left of the first chunk, so it goes in a new one
+        arena.alloc_slice(&[100, 101]);
+        arena.alloc_extend(std::iter::empty());
+        arena.alloc(102);
+        assert_eq!(arena.chunk_count(), 2);
+        let mut expected: Vec<u32> = (0..FIRST_CHUNK as u32).collect();
+        expected.extend([100, 101, 102]);
+        assert_eq!(arena.into_vec(), expected);
+        assert_eq!(Arena::<u8>::new().into_vec(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn a_slice_bigger_than_a_chunk_gets_one_of_its_own() {
+        let arena = Arena::new();
+        let small = arena.alloc_slice(&[1u8; 3]);
+        let big = arena.alloc_slice(&[2u8; FIRST_CHUNK * 3]);
+        assert_eq!(big.len(), FIRST_CHUNK * 3);
+        assert!(big.iter().all(|&byte| byte == 2));
+        assert_eq!(small, [1, 1, 1]);
+        assert_eq!(arena.chunk_count(), 2);
+    }
+
+    #[test]
+    fn zero_sized_types() {
+        #[derive(Debug, Clone, Copy, PartialEq)]
+        struct Nothing;
+        let arena = Arena::new();
+        let first = arena.alloc(Nothing);
+        for _ in 0..FIRST_CHUNK * 2 {
+            arena.alloc(Nothing);
+        }
+        let units = Arena::new();
+        let slice = units.alloc_slice(&[Nothing; 5]);
+        assert_eq!(slice.len(), 5);
+        assert_eq!(units.alloc_extend([Nothing, Nothing]).len(), 2);
+        assert_eq!(*first, Nothing);
+        assert_eq!(arena.len(), FIRST_CHUNK * 2 + 1);
+        assert_eq!(units.len(), 7);
+        // a Vec of a zero-sized type never needs memory, so there's never a second chunk
+        assert_eq!(units.chunk_count(), 1);
+        assert_eq!(units.into_vec().len(), 7);
+    }
+
+    #[test]
+    fn everything_is_dropped_with_the_arena_exactly_once() {
+        struct Counted<'a>(&'a Cell<usize>);
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+        let drops = Cell::new(0);
+        {
+            let arena = Arena::new();
+            for _ in 0..FIRST_CHUNK * 3 {
+                arena.alloc(Counted(&drops));
+            }
+            arena.alloc_extend((0..5).map(|_| Counted(&drops)));
+            assert_eq!(drops.get(), 0);
+        }
+        assert_eq!(drops.get(), FIRST_CHUNK * 3 + 5);
+
+        let arena = Arena::new();
+        arena.alloc(Counted(&drops));
+        let values = arena.into_vec();
+        assert_eq!(drops.get(), FIRST_CHUNK * 3 + 5);
+        drop(values);
+        assert_eq!(drops.get(), FIRST_CHUNK * 3 + 6);
+    }
+
+    #[test]
+    fn strings_survive_new_chunks() {
+        let arena = StringArena::with_capacity(8);
+        let short = arena.alloc_str("fooman");
+        let empty = arena.alloc_str("");
+        let long = "bücher ".repeat(FIRST_CHUNK * 4);
+        let copy = arena.alloc_str(&long);
+        let mut more = Vec::new();
+        for n in 0..100 {
+            more.push(arena.alloc_str(&format!("user {n}")));
+        }
+        assert_eq!(short, "fooman");
+        assert_eq!(empty, "");
+        assert_eq!(copy, long);
+        assert_eq!(more[99], "user 99");
+        assert!(arena.chunk_count() >= 2);
+        assert_eq!(
+            arena.len(),
+            6 + long.len() + more.iter().map(|s| s.len()).sum::<usize>()
+        );
+    }
+}
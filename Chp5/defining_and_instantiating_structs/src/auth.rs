@@ -0,0 +1,384 @@
+/* Signing in */
+// User has a sign_in_count, and this is where it goes up. A user with a password has a Credential:
+// the PBKDF2 hash of the password (see crypto.rs) with the salt and iteration count used to make
+// it, so the password itself is never stored. sign_in hashes the password it's given the same
+// way and compares the results.
+//
+// A few things keep guessing slow and quiet:
+//   * after max_failures wrong passwords in a row the account is locked for a while, and while
+//     it's locked even the right password is refused;
+//   * the hashes are compared with constant_time_eq, and a username with no user (or no password)
+//     behind it, or a locked account, still costs a full PBKDF2 run, so how long a failure takes
+//     doesn't tell an attacker which part was wrong, or how close the guess was;
+//   * every kind of failure, a lockout included, gives the same WrongCredentials error, so
+//     signing in can't be used to find out which usernames exist.
+//
+// A successful sign-in hands out a session token, a random string that stands in for the
+// password until it expires. The registry only keeps the SHA-256 of each token, so anyone who
+// reads the registry file still can't use the sessions in it.
+use crate::crypto;
+use crate::registry::{RegistryError, UserId, UserRegistry};
+use std::fmt;
+use std::io;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AuthPolicy {
+    // PBKDF2 iterations for new passwords; old ones keep the count they were made with
+    pub iterations: u32,
+    pub max_failures: u32,
+    pub lockout: Duration,
+    pub session_length: Duration,
+}
+
+impl Default for AuthPolicy {
+    fn default() -> Self {
+        AuthPolicy {
+            iterations: 100_000,
+            max_failures: 5,
+            lockout: Duration::from_secs(15 * 60),
+            session_length: Duration::from_secs(24 * 60 * 60),
+        }
+    }
+}
+
+/* Credentials */
+const SALT_LEN: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credential {
+    iterations: u32,
+    salt: [u8; SALT_LEN],
+    hash: [u8; crypto::DIGEST_LEN],
+}
+
+impl Credential {
+    // Hashes password with a new random salt. Fails if the system has no random bytes to give.
+    pub fn new(password: &str, iterations: u32) -> io::Result<Self> {
+        let mut salt = [0; SALT_LEN];
+        crypto::random_bytes(&mut salt)?;
+        Ok(Credential {
+            iterations,
+            salt,
+            hash: derive(password, &salt, iterations),
+        })
+    }
+
+    // Stands in for a missing credential so checking against nothing takes as long as checking
+    // against something. Nothing hashes to all zeros, so it never matches.
+    fn missing(iterations: u32) -> Self {
+        Credential {
+            iterations,
+            salt: [0; SALT_LEN],
+            hash: [0; crypto::DIGEST_LEN],
+        }
+    }
+
+    pub fn verify(&self, password: &str) -> bool {
+        let hash = derive(password, &self.salt, self.iterations);
+        crypto::constant_time_eq(&hash, &self.hash)
+    }
+
+    pub fn iterations(&self) -> u32 {
+        self.iterations
+    }
+
+    // Read back what Display writes.
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut parts = text.split('$');
+        if parts.next()? != "pbkdf2-sha256" {
+            return None;
+        }
+        let iterations = parts.next()?.parse().ok()?;
+        let salt = crypto::from_hex(parts.next()?)?.try_into().ok()?;
+        let hash = crypto::from_hex(parts.next()?)?.try_into().ok()?;
+        if parts.next().is_some() || iterations == 0 {
+            return None;
+        }
+        Some(Credential {
+            iterations,
+            salt,
+            hash,
+        })
+    }
+}
+
+// pbkdf2-sha256$<iterations>$<salt>$<hash>, the salt and hash in hex
+impl fmt::Display for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "pbkdf2-sha256${}${}${}",
+            self.iterations,
+            crypto::to_hex(&self.salt),
+            crypto::to_hex(&self.hash)
+        )
+    }
+}
+
+fn derive(password: &str, salt: &[u8], iterations: u32) -> [u8; crypto::DIGEST_LEN] {
+    let mut hash = [0; crypto::DIGEST_LEN];
+    crypto::pbkdf2_hmac_sha256(password.as_bytes(), salt, iterations, &mut hash);
+    hash
+}
+
+// A user's password and how their recent sign-ins went.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Account {
+    pub credential: Credential,
+    // wrong passwords since the last right one
+    pub failures: u32,
+    // seconds since 1970
+    pub locked_until: Option<u64>,
+}
+
+/* Sessions */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    pub user: UserId,
+    // seconds since 1970
+    pub expires: u64,
+}
+
+// What a signed in user gets instead of keeping their password around: 32 random bytes in hex.
+#[derive(Clone, PartialEq, Eq)]
+pub struct SessionToken(String);
+
+impl SessionToken {
+    fn new() -> io::Result<Self> {
+        let mut bytes = [0; 32];
+        crypto::random_bytes(&mut bytes)?;
+        Ok(SessionToken(crypto::to_hex(&bytes)))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+// Debug leaves the token out, so it doesn't end up in logs.
+impl fmt::Debug for SessionToken {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SessionToken(..)")
+    }
+}
+
+// What the registry stores for a token: its SHA-256, in hex. A HashMap lookup isn't constant-time,
+// but all it could give away is a hash, which doesn't help anyone make a token that matches it.
+pub fn token_key(token: &str) -> String {
+    crypto::to_hex(&crypto::sha256(token.as_bytes()))
+}
+
+fn seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl UserRegistry {
+    // Gives the user a new password. Any sessions they had end, and a lockout is lifted.
+    pub fn set_password(&mut self, id: UserId, password: &str) -> Result<(), RegistryError> {
+        if self.get(id).is_none() {
+            return Err(RegistryError::NoSuchId(id));
+        }
+        let credential = Credential::new(password, self.policy.iterations)?;
+        self.accounts.insert(
+            id,
+            Account {
+                credential,
+                failures: 0,
+                locked_until: None,
+            },
+        );
+        self.sessions.retain(|_, session| session.user != id);
+        Ok(())
+    }
+
+    pub fn has_password(&self, id: UserId) -> bool {
+        self.accounts.contains_key(&id)
+    }
+
+    pub fn sign_in(&mut self, username: &str, password: &str) -> Result<SessionToken, SignInError> {
+        self.sign_in_at(username, password, SystemTime::now())
+    }
+
+    // sign_in as if the time were now, so lockouts and expiry can be tried out without waiting.
+    pub fn sign_in_at(
+        &mut self,
+        username: &str,
+        password: &str,
+        now: SystemTime,
+    ) -> Result<SessionToken, SignInError> {
+        let now = seconds(now);
+        let user = self
+            .find_by_username(username)
+            .map(|(id, user)| (id, user.active));
+        let account = user.and_then(|(id, _)| self.accounts.get(&id));
+        // hashed even when the account is locked, so a lockout takes as long as any other failure
+        let matched = match account {
+            Some(account) => account.credential.verify(password),
+            None => Credential::missing(self.policy.iterations).verify(password),
+        };
+        let locked = account
+            .and_then(|account| account.locked_until)
+            .is_some_and(|until| now < until);
+        // A locked account fails like a wrong password, even with the right one. Only existing
+        // users can be locked, so a different error would say the username is real. Guesses
+        // while it's locked don't count towards another lockout.
+        let (Some((id, active)), true, false) = (user, matched, locked) else {
+            if !locked {
+                self.failed_sign_in(user.map(|(id, _)| id), now);
+            }
+            return Err(SignInError::WrongCredentials);
+        };
+        // only said once the password is right, so it doesn't give away who is inactive
+        if !active {
+            return Err(SignInError::Inactive);
+        }
+        // made first, so if it can't be the sign-in changes nothing
+        let token = SessionToken::new().map_err(SignInError::Io)?;
+        if let Some(account) = self.accounts.get_mut(&id) {
+            account.failures = 0;
+            account.locked_until = None;
+        }
+        let _ = self.update(id, |user| user.sign_in_count += 1);
+        self.sessions.retain(|_, session| session.expires > now);
+        self.sessions.insert(
+            token_key(token.as_str()),
+            Session {
+                user: id,
+                expires: now + self.policy.session_length.as_secs(),
+            },
+        );
+        Ok(token)
+    }
+
+    fn failed_sign_in(&mut self, id: Option<UserId>, now: u64) {
+        let policy = self.policy;
+        let Some(account) = id.and_then(|id| self.accounts.get_mut(&id)) else {
+            return;
+        };
+        account.failures += 1;
+        if account.failures >= policy.max_failures {
+            account.failures = 0;
+            account.locked_until = Some(now + policy.lockout.as_secs());
+        }
+    }
+
+    // Who the token belongs to, if it's still good.
+    pub fn session(&self, token: &str) -> Result<UserId, SessionError> {
+        self.session_at(token, SystemTime::now())
+    }
+
+    pub fn session_at(&self, token: &str, now: SystemTime) -> Result<UserId, SessionError> {
+        let session = self
+            .sessions
+            .get(&token_key(token))
+            .ok_or(SessionError::Unknown)?;
+        if seconds(now) >= session.expires {
+            return Err(SessionError::Expired);
+        }
+        Ok(session.user)
+    }
+
+    // Ends the session; false if there wasn't one.
+    pub fn sign_out(&mut self, token: &str) -> bool {
+        self.sessions.remove(&token_key(token)).is_some()
+    }
+
+    pub fn policy(&self) -> AuthPolicy {
+        self.policy
+    }
+
+    pub fn set_policy(&mut self, policy: AuthPolicy) {
+        self.policy = policy;
+    }
+}
+
+/* Errors */
+#[derive(Debug)]
+pub enum SignInError {
+    // no such user, no password set, the wrong password, or a locked account: which one isn't
+    // said
+    WrongCredentials,
+    Inactive,
+    // the password was right, but there were no random bytes to make a session token from
+    Io(io::Error),
+}
+
+impl fmt::Display for SignInError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SignInError::WrongCredentials => write!(f, "wrong username or password"),
+            SignInError::Inactive => write!(f, "this user has been deactivated"),
+            SignInError::Io(err) => write!(f, "couldn't start a session: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for SignInError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SignInError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionError {
+    Unknown,
+    Expired,
+}
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SessionError::Unknown => write!(f, "not signed in"),
+            SessionError::Expired => write!(f, "the session has expired, sign in again"),
+        }
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::user::User;
+
+    #[test]
+    fn a_locked_account_looks_like_a_wrong_password() {
+        let mut registry = UserRegistry::new();
+        registry.set_policy(AuthPolicy {
+            iterations: 1,
+            ..AuthPolicy::default()
+        });
+        let id = registry
+            .create(User {
+                active: true,
+                username: String::from("fooman"),
+                email: String::from("fooman@gmail.com"),
+                sign_in_count: 0,
+            })
+            .unwrap();
+        registry.set_password(id, "right").unwrap();
+        let now = SystemTime::now();
+        for _ in 0..registry.policy().max_failures {
+            assert!(registry.sign_in_at("fooman", "wrong", now).is_err());
+        }
+        let locked = registry.sign_in_at("fooman", "right", now);
+        let unknown = registry.sign_in_at("nobody", "right", now);
+        assert!(matches!(locked, Err(SignInError::WrongCredentials)));
+        assert!(matches!(unknown, Err(SignInError::WrongCredentials)));
+        // guesses while it's locked don't lock it again, so it opens when the lockout ends
+        let later = now + registry.policy().lockout;
+        assert!(registry.sign_in_at("fooman", "right", later).is_ok());
+    }
+}
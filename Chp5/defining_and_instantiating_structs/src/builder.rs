@@ -0,0 +1,692 @@
+/* Building users */
+// build_user in main.rs fills in active and sign_in_count for you, but takes any two strings, so
+// build_user(String::from("not an email"), String::new()) is a perfectly good User. UserBuilder
+// has the same defaults, and build() checks the username and email before there's a User at all:
+//   * a username is 3 to 32 letters, digits, '.', '_' or '-', starts with a letter, and doesn't
+//     end with (or double up) the punctuation;
+//   * an email is local@domain. The local part can have a +tag ("fooman+rust@gmail.com", which
+//     delivers to fooman), and the domain can be an internationalised one ("bücher.example"),
+//     which is checked in its ASCII form (Punycode, "xn--bcher-kva.example") since that's what
+//     the length limits apply to.
+// Both are lowercased, so "FooMan" and "fooman" are the same user, and the email is kept in that
+// ASCII form with any trailing dot dropped, so each address is only ever written one way.
+//
+// to_builder() goes the other way, from a User to a builder with all its fields filled in. That's
+// the struct update syntax (User { email, ..user1 }) without its catch: ..user1 moves user1's
+// username String into the new user, so user1 can't be used as a whole afterwards. to_builder
+// only borrows, and the new user is checked like any other.
+use crate::user::User;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserBuilder {
+    active: bool,
+    username: Option<String>,
+    email: Option<String>,
+    sign_in_count: u64,
+}
+
+// The same defaults as build_user.
+impl Default for UserBuilder {
+    fn default() -> Self {
+        UserBuilder {
+            active: true,
+            username: None,
+            email: None,
+            sign_in_count: 1,
+        }
+    }
+}
+
+impl User {
+    pub fn builder() -> UserBuilder {
+        UserBuilder::default()
+    }
+
+    pub fn to_builder(&self) -> UserBuilder {
+        UserBuilder {
+            active: self.active,
+            username: Some(self.username.clone()),
+            email: Some(self.email.clone()),
+            sign_in_count: self.sign_in_count,
+        }
+    }
+}
+
+impl UserBuilder {
+    pub fn new() -> Self {
+        UserBuilder::default()
+    }
+
+    pub fn username(mut self, username: impl Into<String>) -> Self {
+        self.username = Some(username.into());
+        self
+    }
+
+    pub fn email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    pub fn active(mut self, active: bool) -> Self {
+        self.active = active;
+        self
+    }
+
+    pub fn sign_in_count(mut self, sign_in_count: u64) -> Self {
+        self.sign_in_count = sign_in_count;
+        self
+    }
+
+    // Checks and normalises the username and email. Takes &self, so one builder can make several
+    // users.
+    pub fn build(&self) -> Result<User, UserError> {
+        let username = self.username.as_deref().ok_or(UserError::MissingUsername)?;
+        let email = self.email.as_deref().ok_or(UserError::MissingEmail)?;
+        Ok(User {
+            active: self.active,
+            username: normalise_username(username)?,
+            email: Email::parse(email)?.ascii(),
+            sign_in_count: self.sign_in_count,
+        })
+    }
+}
+
+/* Usernames */
+pub const USERNAME_LENGTH: std::ops::RangeInclusive<usize> = 3..=32;
+
+pub fn normalise_username(username: &str) -> Result<String, UserError> {
+    let username = username.trim().to_lowercase();
+    let length = username.chars().count();
+    if !USERNAME_LENGTH.contains(&length) {
+        return Err(UserError::UsernameLength(length));
+    }
+    if let Some(c) = username
+        .chars()
+        .find(|&c| !(c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-')))
+    {
+        return Err(UserError::UsernameCharacter(c));
+    }
+    if !username.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return Err(UserError::UsernameStart);
+    }
+    let punctuation = |c: char| matches!(c, '.' | '_' | '-');
+    let doubled = username
+        .as_bytes()
+        .windows(2)
+        .any(|pair| punctuation(pair[0] as char) && punctuation(pair[1] as char));
+    if doubled || username.ends_with(punctuation) {
+        return Err(UserError::UsernamePunctuation);
+    }
+    Ok(username)
+}
+
+/* Emails */
+// An email address taken apart. Display puts it back together, lowercased, with the domain as it
+// was typed (Unicode and all); ascii() gives the form with the domain in Punycode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Email {
+    // the mailbox, without the tag
+    pub local: String,
+    // what came after the '+', if anything
+    pub tag: Option<String>,
+    pub domain: String,
+    pub ascii_domain: String,
+}
+
+// The characters RFC 5322 allows in a local part without quoting (its "atext"), besides letters
+// and digits. Quoted local parts ("john doe"@example.com) are legal but so rare, and so often
+// rejected elsewhere, that they're refused here.
+const LOCAL_SYMBOLS: &str = "!#$%&'*+/=?^_`{|}~-";
+
+impl Email {
+    pub fn parse(email: &str) -> Result<Self, UserError> {
+        let invalid = |reason: &'static str| UserError::InvalidEmail {
+            email: email.to_string(),
+            reason,
+        };
+        let normalised = email.trim().to_lowercase();
+        let (local, domain) = normalised
+            .rsplit_once('@')
+            .ok_or_else(|| invalid("there's no '@'"))?;
+
+        if local.is_empty() {
+            return Err(invalid("there's nothing before the '@'"));
+        }
+        // 64 bytes, from RFC 5321
+        if local.len() > 64 {
+            return Err(invalid("the part before the '@' is over 64 bytes"));
+        }
+        // letters outside ASCII are allowed, as RFC 6531 does for internationalised mail
+        let allowed = |c: char| c.is_alphanumeric() || c == '.' || LOCAL_SYMBOLS.contains(c);
+        if local.chars().any(|c| !allowed(c)) {
+            return Err(invalid(
+                "the part before the '@' has a character that isn't allowed",
+            ));
+        }
+        if local.starts_with('.') || local.ends_with('.') || local.contains("..") {
+            return Err(invalid("the part before the '@' has a misplaced '.'"));
+        }
+        let (mailbox, tag) = match local.split_once('+') {
+            Some((mailbox, tag)) => (mailbox, Some(tag.to_string())),
+            None => (local, None),
+        };
+        if mailbox.is_empty() {
+            return Err(invalid("there's nothing before the '+'"));
+        }
+
+        let ascii_domain = ascii_domain(domain).map_err(invalid)?;
+        Ok(Email {
+            local: mailbox.to_string(),
+            tag,
+            domain: domain.to_string(),
+            ascii_domain,
+        })
+    }
+
+    // The address mail is actually delivered to: no tag, and the domain in ASCII. Two emails with
+    // the same mailbox are the same person, so the registry only lets one user have each.
+    pub fn mailbox(&self) -> String {
+        format!("{}@{}", self.local, self.ascii_domain)
+    }
+
+    // The whole address with the domain in ASCII and without a trailing dot, which is how build()
+    // stores it.
+    pub fn ascii(&self) -> String {
+        let mut ascii = self.to_string();
+        ascii.truncate(ascii.len() - self.domain.len());
+        ascii + &self.ascii_domain
+    }
+}
+
+impl fmt::Display for Email {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.tag {
+            Some(tag) => write!(f, "{}+{tag}@{}", self.local, self.domain),
+            None => write!(f, "{}@{}", self.local, self.domain),
+        }
+    }
+}
+
+// Checks a (lowercased) domain and returns its ASCII form: labels of letters, digits and hyphens,
+// up to 63 bytes each and 253 in all, with at least two labels and a top level that isn't all
+// digits. Labels with letters outside ASCII are Punycode encoded first, which is how they go over
+// DNS.
+fn ascii_domain(domain: &str) -> Result<String, &'static str> {
+    // a trailing dot (the DNS root) is fine, but doesn't count
+    let domain = domain.strip_suffix('.').unwrap_or(domain);
+    if domain.is_empty() {
+        return Err("there's no domain after the '@'");
+    }
+    let mut labels: Vec<String> = Vec::new();
+    for label in domain.split('.') {
+        if label.is_empty() {
+            return Err("the domain has an empty part");
+        }
+        let ascii = if label.is_ascii() {
+            label.to_string()
+        } else {
+            if !label.chars().all(|c| c.is_alphanumeric() || c == '-') {
+                return Err("the domain has a character that isn't allowed");
+            }
+            let chars: Vec<char> = label.chars().collect();
+            format!("xn--{}", punycode(&chars).ok_or("the domain is too long")?)
+        };
+        if ascii.len() > 63 {
+            return Err("part of the domain is over 63 bytes");
+        }
+        if !ascii
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-')
+        {
+            return Err("the domain has a character that isn't allowed");
+        }
+        if ascii.starts_with('-') || ascii.ends_with('-') {
+            return Err("part of the domain starts or ends with '-'");
+        }
+        labels.push(ascii);
+    }
+    if labels.len() < 2 {
+        return Err("the domain needs a dot in it");
+    }
+    if labels
+        .last()
+        .is_some_and(|tld| tld.bytes().all(|b| b.is_ascii_digit()))
+    {
+        return Err("the domain ends in a number");
+    }
+    let ascii = labels.join(".");
+    if ascii.len() > 253 {
+        return Err("the domain is over 253 bytes");
+    }
+    Ok(ascii)
+}
+
+/* Punycode */
+// RFC 3492: the ASCII characters of the label are copied as they are, then each of the others is
+// written as a variable length number saying where it goes and which character it is, in base 36
+// with the letters and digits as the digits. None if the numbers overflow, which only happens
+// for labels far longer than DNS allows.
+pub fn punycode(input: &[char]) -> Option<String> {
+    const BASE: u32 = 36;
+    const T_MIN: u32 = 1;
+    const T_MAX: u32 = 26;
+    let digit = |d: u32| -> char {
+        if d < 26 {
+            (b'a' + d as u8) as char
+        } else {
+            (b'0' + (d - 26) as u8) as char
+        }
+    };
+    let threshold = |k: u32, bias: u32| k.saturating_sub(bias).clamp(T_MIN, T_MAX);
+
+    let mut output: String = input.iter().filter(|c| c.is_ascii()).collect();
+    let basic = output.len() as u32;
+    let mut handled = basic;
+    if basic > 0 {
+        output.push('-');
+    }
+    let mut n: u32 = 0x80;
+    let mut delta: u32 = 0;
+    let mut bias: u32 = 72;
+    while (handled as usize) < input.len() {
+        // the smallest character not written yet
+        let m = input.iter().map(|&c| c as u32).filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+        for &c in input {
+            let c = c as u32;
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push(digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(digit(q));
+                bias = adapt(delta, handled + 1, handled == basic);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta = delta.checked_add(1)?;
+        n += 1;
+    }
+    Some(output)
+}
+
+fn adapt(delta: u32, points: u32, first: bool) -> u32 {
+    const BASE: u32 = 36;
+    let mut delta = if first { delta / 700 } else { delta / 2 };
+    delta += delta / points;
+    let mut k = 0;
+    while delta > ((BASE - 1) * 26) / 2 {
+        delta /= BASE - 1;
+        k += BASE;
+    }
+    k + (BASE * delta) / (delta + 38)
+}
+
+/* Errors */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UserError {
+    MissingUsername,
+    MissingEmail,
+    // in characters
+    UsernameLength(usize),
+    UsernameCharacter(char),
+    UsernameStart,
+    UsernamePunctuation,
+    InvalidEmail { email: String, reason: &'static str },
+}
+
+impl fmt::Display for UserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UserError::MissingUsername => write!(f, "a user needs a username"),
+            UserError::MissingEmail => write!(f, "a user needs an email"),
+            UserError::UsernameLength(length) => write!(
+                f,
+                "a username has {} to {} characters, not {length}",
+                USERNAME_LENGTH.start(),
+                USERNAME_LENGTH.end()
+            ),
+            UserError::UsernameCharacter(c) => write!(
+                f,
+                "{c:?} can't be in a username, only letters, digits, '.', '_' and '-'"
+            ),
+            UserError::UsernameStart => write!(f, "a username starts with a letter"),
+            UserError::UsernamePunctuation => write!(
+                f,
+                "a username can't end with '.', '_' or '-', or have two of them in a row"
+            ),
+            UserError::InvalidEmail { email, reason } => {
+                write!(f, "'{email}' isn't a valid email: {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for UserError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn punycode_of(text: &str) -> Option<String> {
+        punycode(&text.chars().collect::<Vec<char>>())
+    }
+
+    // The sample strings from section 7.1 of RFC 3492, in the order it gives them.
+    #[test]
+    fn rfc_3492_samples() {
+        for (text, encoded) in [
+            ("ليهمابتكلموشعربي؟", "egbpdaj6bu4bxfgehfvwxn"),
+            ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+            ("他們爲什麽不說中文", "ihqwctvzc91f659drss3x8bo0yb"),
+            ("Pročprostěnemluvíčesky", "Proprostnemluvesky-uyb24dma41a"),
+            ("למההםפשוטלאמדבריםעברית", "4dbcagdahymbxekheh6e0a7fei0b"),
+            (
+                "यहलोगहिन्दीक्योंनहींबोलसकतेहैं",
+                "i1baa7eci9glrd9b2ae1bj0hfcgg6iyaf8o0a1dig0cd",
+            ),
+            (
+                "なぜみんな日本語を話してくれないのか",
+                "n8jok5ay5dzabd5bym9f0cm5685rrjetr6pdxa",
+            ),
+            (
+                "세계의모든사람들이한국어를이해한다면얼마나좋을까",
+                "989aomsvi5e83db1d2a355cv1e0vak1dwrv93d5xbh15a0dt30a5jpsd879ccm6fea98c",
+            ),
+            // the RFC capitalises the first letter, which only changes the case of the output
+            (
+                "почемужеонинеговорятпорусски",
+                "b1abfaaepdrnnbgefbadotcwatmq2g4l",
+            ),
+            (
+                "PorquénopuedensimplementehablarenEspañol",
+                "PorqunopuedensimplementehablarenEspaol-fmd56a",
+            ),
+            (
+                "TạisaohọkhôngthểchỉnóitiếngViệt",
+                "TisaohkhngthchnitingVit-kjcr8268qyxafd2f1b9g",
+            ),
+            ("3年B組金八先生", "3B-ww4c5e180e575a65lsy2b"),
+            (
+                "安室奈美恵-with-SUPER-MONKEYS",
+                "-with-SUPER-MONKEYS-pc58ag80a8qai00g7n9n",
+            ),
+            (
+                "Hello-Another-Way-それぞれの場所",
+                "Hello-Another-Way--fc4qua05auwb3674vfr0b",
+            ),
+            ("ひとつ屋根の下2", "2-u9tlzr9756bt3uc0v"),
+            ("MajiでKoiする5秒前", "MajiKoi5-783gue6qz075azm5e"),
+            ("パフィーdeルンバ", "de-jg4avhby1noc0d"),
+            ("そのスピードで", "d9juau41awczczp"),
+            ("-> $1.00 <-", "-> $1.00 <--"),
+        ] {
+            assert_eq!(punycode_of(text).as_deref(), Some(encoded), "{text}");
+        }
+        assert_eq!(punycode_of("bücher").as_deref(), Some("bcher-kva"));
+        assert_eq!(punycode_of("").as_deref(), Some(""));
+    }
+
+    #[test]
+    fn punycode_overflow_is_none() {
+        // the first non-ASCII character is worth (its code point - 0x80) * (ASCII characters + 1)
+        let text = "a".repeat(4000) + "\u{10ffff}";
+        assert_eq!(punycode_of(&text), None);
+    }
+
+    fn built(username: &str, email: &str) -> Result<(String, String), UserError> {
+        let user = User::builder().username(username).email(email).build()?;
+        Ok((user.username, user.email))
+    }
+
+    fn invalid(email: &str, reason: &'static str) -> Result<(String, String), UserError> {
+        Err(UserError::InvalidEmail {
+            email: email.to_string(),
+            reason,
+        })
+    }
+
+    fn stored(username: &str, email: &str) -> Result<(String, String), UserError> {
+        Ok((username.to_string(), email.to_string()))
+    }
+
+    // The attempts builder_demo prints, and a few more.
+    #[test]
+    fn accepted_and_refused() {
+        for (username, email, expected) in [
+            (
+                "FooMan",
+                "FooMan@Gmail.com",
+                stored("fooman", "fooman@gmail.com"),
+            ),
+            (
+                "fooman",
+                "fooman@bücher.example",
+                stored("fooman", "fooman@xn--bcher-kva.example"),
+            ),
+            ("x", "x@example.com", Err(UserError::UsernameLength(1))),
+            ("9lives", "cat@example.com", Err(UserError::UsernameStart)),
+            (
+                "foo__man",
+                "fooman@gmail.com",
+                Err(UserError::UsernamePunctuation),
+            ),
+            (
+                "sock puppet",
+                "sockpuppet@fun.com",
+                Err(UserError::UsernameCharacter(' ')),
+            ),
+            (
+                "sockpuppet",
+                "sockpuppet@fun",
+                invalid("sockpuppet@fun", "the domain needs a dot in it"),
+            ),
+            (
+                "sockpuppet",
+                "sock..puppet@fun.com",
+                invalid(
+                    "sock..puppet@fun.com",
+                    "the part before the '@' has a misplaced '.'",
+                ),
+            ),
+            (
+                "sockpuppet",
+                "sockpuppet.fun.com",
+                invalid("sockpuppet.fun.com", "there's no '@'"),
+            ),
+            // the trailing dot is dropped, and a domain in mixed case is lowercased before it's
+            // encoded, so each address is only ever stored one way
+            (
+                "fooman",
+                "fooman@Example.COM.",
+                stored("fooman", "fooman@example.com"),
+            ),
+            (
+                "fooman",
+                " Foo.Man+Rust@BÜCHER.Example. ",
+                stored("fooman", "foo.man+rust@xn--bcher-kva.example"),
+            ),
+            (
+                "fooman",
+                "fooman@example.com..",
+                invalid("fooman@example.com..", "the domain has an empty part"),
+            ),
+            (
+                "fooman",
+                "fooman@.",
+                invalid("fooman@.", "there's no domain after the '@'"),
+            ),
+            (
+                "fooman",
+                "fooman@-example.com",
+                invalid(
+                    "fooman@-example.com",
+                    "part of the domain starts or ends with '-'",
+                ),
+            ),
+            (
+                "fooman",
+                "fooman@10.0.0.1",
+                invalid("fooman@10.0.0.1", "the domain ends in a number"),
+            ),
+            (
+                "fooman",
+                "fooman@exa_mple.com",
+                invalid(
+                    "fooman@exa_mple.com",
+                    "the domain has a character that isn't allowed",
+                ),
+            ),
+            (
+                "fooman",
+                "+rust@example.com",
+                invalid("+rust@example.com", "there's nothing before the '+'"),
+            ),
+            (
+                "fooman",
+                "@example.com",
+                invalid("@example.com", "there's nothing before the '@'"),
+            ),
+            (
+                "fooman",
+                "foo man@example.com",
+                invalid(
+                    "foo man@example.com",
+                    "the part before the '@' has a character that isn't allowed",
+                ),
+            ),
+        ] {
+            assert_eq!(built(username, email), expected, "{username:?} {email:?}");
+        }
+    }
+
+    #[test]
+    fn domain_length_limits() {
+        let label = "a".repeat(63);
+        let email = format!("fooman@{label}.com");
+        assert_eq!(built("fooman", &email), stored("fooman", &email));
+        let email = format!("fooman@{label}a.com");
+        assert_eq!(
+            built("fooman", &email),
+            invalid(&email, "part of the domain is over 63 bytes")
+        );
+        // 4 labels of 63 and a dot between each is 255
+        let email = format!("fooman@{label}.{label}.{label}.{label}");
+        assert_eq!(
+            built("fooman", &email),
+            invalid(&email, "the domain is over 253 bytes")
+        );
+        // it's the encoded form that's measured, which can be longer or shorter than the UTF-8
+        let korean = "세계의모든사람들이한국어를이해한다면얼마나좋을까";
+        let first = |n: usize| korean.chars().take(n).collect::<String>();
+        // 63 bytes of UTF-8, 67 once encoded
+        let email = format!("fooman@{}.com", first(21));
+        assert_eq!(
+            built("fooman", &email),
+            invalid(&email, "part of the domain is over 63 bytes")
+        );
+        // 60 bytes of UTF-8, 63 once encoded
+        let email = format!("fooman@{}.com", first(20));
+        assert!(built("fooman", &email).is_ok());
+        // 80 bytes of UTF-8, but the same character over and over encodes short
+        let email = format!("fooman@{}.com", "ü".repeat(40));
+        assert_eq!(
+            built("fooman", &email).unwrap().1.len(),
+            "fooman@".len() + 46 + 4
+        );
+        let email = format!("{}@example.com", "a".repeat(65));
+        assert_eq!(
+            built("fooman", &email),
+            invalid(&email, "the part before the '@' is over 64 bytes")
+        );
+    }
+
+    #[test]
+    fn normalising_usernames() {
+        for (username, expected) in [
+            ("  FooMan ", Ok("fooman")),
+            ("abc", Ok("abc")),
+            ("foo.bar-baz_9", Ok("foo.bar-baz_9")),
+            ("ab", Err(UserError::UsernameLength(2))),
+            ("", Err(UserError::UsernameLength(0))),
+            ("   ", Err(UserError::UsernameLength(0))),
+            // the length is in characters, and checked before the characters are
+            ("éé", Err(UserError::UsernameLength(2))),
+            ("Café", Err(UserError::UsernameCharacter('é'))),
+            ("foo@bar", Err(UserError::UsernameCharacter('@'))),
+            ("_foo", Err(UserError::UsernameStart)),
+            ("1foo", Err(UserError::UsernameStart)),
+            ("foo.", Err(UserError::UsernamePunctuation)),
+            ("foo-", Err(UserError::UsernamePunctuation)),
+            ("foo.-bar", Err(UserError::UsernamePunctuation)),
+            ("foo..bar", Err(UserError::UsernamePunctuation)),
+        ] {
+            assert_eq!(
+                normalise_username(username),
+                expected.map(String::from),
+                "{username:?}"
+            );
+        }
+        let longest = "a".repeat(32);
+        assert_eq!(normalise_username(&longest), Ok(longest.clone()));
+        assert_eq!(
+            normalise_username(&(longest + "a")),
+            Err(UserError::UsernameLength(33))
+        );
+    }
+
+    #[test]
+    fn emails_taken_apart() {
+        let email = Email::parse("FooMan+Rust@Bücher.Example.").unwrap();
+        assert_eq!(email.local, "fooman");
+        assert_eq!(email.tag.as_deref(), Some("rust"));
+        assert_eq!(email.to_string(), "fooman+rust@bücher.example.");
+        assert_eq!(email.ascii(), "fooman+rust@xn--bcher-kva.example");
+        assert_eq!(email.mailbox(), "fooman@xn--bcher-kva.example");
+        // the last '@' splits it, so one in the local part is refused as a character
+        assert!(Email::parse("a@b@example.com").is_err());
+    }
+
+    #[test]
+    fn builders() {
+        assert_eq!(
+            User::builder().email("a@example.com").build(),
+            Err(UserError::MissingUsername)
+        );
+        assert_eq!(
+            User::builder().username("fooman").build(),
+            Err(UserError::MissingEmail)
+        );
+        let builder = User::builder().username("fooman").email("fooman@gmail.com");
+        let user = builder.build().unwrap();
+        assert!(user.active);
+        assert_eq!(user.sign_in_count, 1);
+        // build borrows, so the same builder makes the same user again
+        assert_eq!(builder.build().unwrap(), user);
+        assert_eq!(user.to_builder().build().unwrap(), user);
+        let changed = user
+            .to_builder()
+            .active(false)
+            .sign_in_count(7)
+            .email("Another@Example.com")
+            .build()
+            .unwrap();
+        assert_eq!(changed.username, "fooman");
+        assert_eq!(changed.email, "another@example.com");
+        assert!(!changed.active);
+        assert_eq!(changed.sign_in_count, 7);
+    }
+}
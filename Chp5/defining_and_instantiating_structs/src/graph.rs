@@ -0,0 +1,171 @@
+/* A graph of users */
+// UserNode is the NewUser from main.rs with the lifetime it was missing, plus a list of friends.
+// Every field that refers to something else is a reference with the same lifetime 'a, the
+// lifetime of the arenas everything lives in, so users can be friends with each other both ways
+// round. With Box or String fields that's impossible, since a Box can only have one owner; the
+// usual ways around it are Rc<RefCell<..>> everywhere, or storing indices instead of references,
+// which is what BoxedUser below does.
+//
+// The friend list is a Cell because a user has to exist before anyone can point at it: all the
+// users are allocated first, and the lists are filled in afterwards, through shared references.
+use crate::arena::{Arena, StringArena};
+use std::cell::Cell;
+use std::fmt::Write;
+
+pub struct UserNode<'a> {
+    pub id: usize,
+    pub active: bool,
+    pub username: &'a str,
+    pub email: &'a str,
+    pub sign_in_count: u64,
+    pub friends: Cell<&'a [&'a UserNode<'a>]>,
+}
+
+impl<'a> UserNode<'a> {
+    pub fn friends(&self) -> &'a [&'a UserNode<'a>] {
+        self.friends.get()
+    }
+
+    pub fn befriend(&self, friends: &'a [&'a UserNode<'a>]) {
+        self.friends.set(friends);
+    }
+}
+
+// Everything a graph of users is allocated from. Dropping it frees the whole graph, and the
+// borrow checker makes sure no UserNode is still in use when that happens.
+#[derive(Default)]
+pub struct GraphArenas<'a> {
+    pub users: Arena<UserNode<'a>>,
+    pub friend_lists: Arena<&'a UserNode<'a>>,
+    pub text: StringArena,
+}
+
+impl<'a> GraphArenas<'a> {
+    pub fn new() -> Self {
+        GraphArenas::default()
+    }
+
+    pub fn user(&'a self, username: &str, email: &str, sign_in_count: u64) -> &'a UserNode<'a> {
+        let id = self.users.len();
+        self.users.alloc(UserNode {
+            id,
+            active: true,
+            username: self.text.alloc_str(username),
+            email: self.text.alloc_str(email),
+            sign_in_count,
+            friends: Cell::new(&[]),
+        })
+    }
+
+    pub fn friend_list(&'a self, friends: &[&'a UserNode<'a>]) -> &'a [&'a UserNode<'a>] {
+        self.friend_lists.alloc_slice(friends)
+    }
+}
+
+// count users named user0, user1, ..., each with friends_each friends picked pseudo-randomly
+// (the same ones every time, so both kinds of graph come out the same).
+pub fn build_arena_graph<'a>(
+    arenas: &'a GraphArenas<'a>,
+    count: usize,
+    friends_each: usize,
+) -> &'a [UserNode<'a>] {
+    let mut username = String::new();
+    let mut email = String::new();
+    let users = arenas.users.alloc_extend((0..count).map(|id| {
+        username.clear();
+        email.clear();
+        let _ = write!(username, "user{id}");
+        let _ = write!(email, "user{id}@example.com");
+        UserNode {
+            id,
+            active: id % 7 != 0,
+            username: arenas.text.alloc_str(&username),
+            email: arenas.text.alloc_str(&email),
+            sign_in_count: id as u64 % 100,
+            friends: Cell::new(&[]),
+        }
+    }));
+    let users: &'a [UserNode<'a>] = users;
+    let mut random = Random(count as u64 + 1);
+    let mut friends: Vec<&UserNode> = Vec::with_capacity(friends_each);
+    for user in users {
+        friends.clear();
+        friends.extend((0..friends_each).map(|_| &users[random.below(count)]));
+        user.befriend(arenas.friend_list(&friends));
+    }
+    users
+}
+
+// The same user with owned fields, and friends as positions in the Vec of users.
+pub struct BoxedUser {
+    pub id: usize,
+    pub active: bool,
+    pub username: String,
+    pub email: String,
+    pub sign_in_count: u64,
+    pub friends: Vec<usize>,
+}
+
+// The same graph with every user in a Box of its own.
+pub fn build_boxed_graph(count: usize, friends_each: usize) -> Vec<Box<BoxedUser>> {
+    let mut users: Vec<Box<BoxedUser>> = (0..count)
+        .map(|id| {
+            Box::new(BoxedUser {
+                id,
+                active: id % 7 != 0,
+                username: format!("user{id}"),
+                email: format!("user{id}@example.com"),
+                sign_in_count: id as u64 % 100,
+                friends: Vec::new(),
+            })
+        })
+        .collect();
+    let mut random = Random(count as u64 + 1);
+    for user in users.iter_mut() {
+        user.friends = (0..friends_each).map(|_| random.below(count)).collect();
+    }
+    users
+}
+
+// How many sign-ins the active friends of friends of every user add up to: two hops through the
+// graph from every node, following references in one and looking up indices in the other.
+pub fn friends_of_friends_sign_ins(users: &[UserNode]) -> u64 {
+    let mut total = 0;
+    for user in users {
+        for friend in user.friends() {
+            for other in friend.friends() {
+                if other.active && other.id != user.id {
+                    total += other.sign_in_count;
+                }
+            }
+        }
+    }
+    total
+}
+
+pub fn boxed_friends_of_friends_sign_ins(users: &[Box<BoxedUser>]) -> u64 {
+    let mut total = 0;
+    for user in users {
+        for &friend in &user.friends {
+            for &other in &users[friend].friends {
+                let other = &users[other];
+                if other.active && other.id != user.id {
+                    total += other.sign_in_count;
+                }
+            }
+        }
+    }
+    total
+}
+
+// xorshift: good enough to pick friends, and needs no crate.
+struct Random(u64);
+
+impl Random {
+    fn below(&mut self, n: usize) -> usize {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        (self.0 % n as u64) as usize
+    }
+}
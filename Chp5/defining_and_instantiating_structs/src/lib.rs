@@ -0,0 +1,9 @@
+// Structs that hold references instead of owning their data, and where to keep what they refer
+// to. See the NewUser example at the bottom of main.rs.
+pub mod arena;
+pub mod auth;
+pub mod builder;
+pub mod crypto;
+pub mod graph;
+pub mod registry;
+pub mod user;
@@ -3,15 +3,45 @@
 * it's clear what the values mean. Adding these names means that structs are more flexable than
 * tuples; you don't hve to rely on the order of the data to specify or access the values of an
 * instance */
-struct User {
-    // The 'struct' keyword is used to define a struct, like class in python
-    active: bool,
-    username: String,
-    email: String,
-    sign_in_count: u64,
-}
+use defining_and_instantiating_structs::auth::{AuthPolicy, SignInError};
+use defining_and_instantiating_structs::builder::Email;
+use defining_and_instantiating_structs::graph::{self, GraphArenas};
+use defining_and_instantiating_structs::registry::{
+    LockedRegistry, RegistryError, UserId, UserRegistry,
+};
+use defining_and_instantiating_structs::user::User;
+use field_display::FieldDisplay;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use struct_diff::{Diff, Patch};
+use what_is_ownership::alloc_counter::{self, Report};
+
+// cargo run --features count-allocations adds heap allocation counts to 'arena-bench'
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
+// User is defined in user.rs, so the registry in registry.rs can store it:
+//     pub struct User {
+//         pub active: bool,
+//         pub username: String,
+//         pub email: String,
+//         pub sign_in_count: u64,
+//     }
 
 fn main() {
+    // 'cargo run -- arena-bench [users]' times arena and Box allocation instead of the examples
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match args[0].as_str() {
+            "arena-bench" => run_arena_bench(&args[1..]),
+            "users" => run_users(&args[1..]),
+            other => {
+                println!("unknown tool '{other}', try 'arena-bench [users]' or 'users --help'")
+            }
+        }
+        return;
+    }
     // To use a defined struct, we create an instance with 'let' and specify values for it's
     // attributes.
     let user1 = User {
@@ -25,6 +55,9 @@ fn main() {
         "user1: {}, {}, {}, {}",
         user1.active, user1.username, user1.email, user1.sign_in_count
     );
+    // Listing every field like that gets old fast. User derives FieldDisplay (see the field_display
+    // crate), which writes a Display implementation that does it, so the rest just use {}:
+    println!("user1: {user1}");
 
     // To change attributes, the entire instance must be mutable. Rust does not allow us to make
     // only specific fields mutable:
@@ -34,23 +67,14 @@ fn main() {
         email: String::from("barman@gmail.com"),
         sign_in_count: 2u64,
     };
-    println!(
-        "mutauser: {}, {}, {}, {}",
-        mutauser.active, mutauser.username, mutauser.email, mutauser.sign_in_count
-    );
+    println!("mutauser: {mutauser}");
     (mutauser.active, mutauser.username) = (true, String::from("anothername"));
-    println!(
-        "mutauser: {}, {}, {}, {}",
-        mutauser.active, mutauser.username, mutauser.email, mutauser.sign_in_count
-    );
+    println!("mutauser: {mutauser}");
     let newuser = build_user(
         String::from("sockpuppet@fun.com"),
         String::from("sockpuppet"),
     );
-    println!(
-        "newuser: {}, {}, {}, {}",
-        newuser.active, newuser.username, newuser.email, newuser.sign_in_count,
-    );
+    println!("newuser: {newuser:#}");
     // Here's the naive way to create a new struct instance that includes field values from another
     // struct:
     /*
@@ -68,7 +92,85 @@ fn main() {
     // This syntax allows us to only assign the field values that we want to change, and '..user1'
     // will fill in any that have not been explicitly assigned.
     println!("{} {}", user1.email, user2.email);
+    // ..user1 moved user1.username into user2 (email wasn't moved, which is why the line above
+    // works), so user1 itself can't be used any more. builder.rs has a way around that:
+    builder_demo(&user2);
+    diff_demo(&user2);
     using_tuple_structs_without_named_fields_to_create_different_types();
+    users_in_an_arena();
+    registry_demo();
+    sign_in_demo();
+}
+// user.to_builder() is the update syntax with a borrow instead of a move, so the user it starts
+// from is still there afterwards, and build() checks the result (see builder.rs).
+fn builder_demo(user2: &User) {
+    let checked = user2.to_builder().email("Another+Rust@Example.com").build();
+    match checked {
+        Ok(user3) => println!(
+            "user2 is still {} <{}>, user3 is {} <{}>",
+            user2.username, user2.email, user3.username, user3.email
+        ),
+        Err(err) => println!("{err}"),
+    }
+    let attempts = [
+        ("FooMan", "FooMan@Gmail.com"),
+        ("fooman", "fooman@bücher.example"),
+        ("x", "x@example.com"),
+        ("9lives", "cat@example.com"),
+        ("foo__man", "fooman@gmail.com"),
+        ("sock puppet", "sockpuppet@fun.com"),
+        ("sockpuppet", "sockpuppet@fun"),
+        ("sockpuppet", "sock..puppet@fun.com"),
+        ("sockpuppet", "sockpuppet.fun.com"),
+    ];
+    for (username, email) in attempts {
+        match User::builder().username(username).email(email).build() {
+            Ok(user) => println!(
+                "  {username:?} {email:?} -> {} <{}>",
+                user.username, user.email
+            ),
+            Err(err) => println!("  {username:?} {email:?}: {err}"),
+        }
+    }
+    if let Ok(email) = Email::parse("fooman+rust@bücher.example") {
+        println!(
+            "  {email} is stored as {}, and is delivered to {}",
+            email.ascii(),
+            email.mailbox()
+        );
+    }
+}
+// The update syntax doesn't leave any record of what it changed. User implements Diff (from the
+// struct_diff crate), which works it out afterwards: a patch listing the fields that changed, with
+// their old and new values, that can be printed, logged as JSON and applied to another user.
+fn diff_demo(user2: &User) {
+    let Ok(user3) = user2
+        .to_builder()
+        .username("barman")
+        .sign_in_count(3)
+        .build()
+    else {
+        return;
+    };
+    let patch = user2.diff(&user3);
+    println!("user2 -> user3:\n{patch}");
+    let json = patch.to_json();
+    println!("{json}");
+    // what an audit log would do with it later: read it back and replay it on a copy of user2
+    let mut copy = user2.clone();
+    match Patch::from_json(&json).and_then(|patch| copy.apply(&patch)) {
+        Ok(()) => println!(
+            "replayed on a copy of user2, which is now user3: {}",
+            copy == user3
+        ),
+        Err(err) => println!("{err}"),
+    }
+    // applying it twice is refused: the copy's username isn't the one the patch changes any more
+    if let Err(err) = copy.apply(&patch) {
+        println!("again: {err}");
+    }
+    let undone = copy.apply(&patch.reversed());
+    println!("undone: {}", undone.is_ok() && copy == *user2);
 }
 // To set default values for fields, we can use a function that constructs the struct:
 fn build_user(email: String, username: String) -> User {
@@ -87,13 +189,21 @@ fn using_tuple_structs_without_named_fields_to_create_different_types() {
      * tuples. Tuple structs have the added meaning the struct name provides, but don't have names
      * associated with their fields; rather, they just have the types of the fields*/
     // here is how tuple structs are defined:
+    #[derive(FieldDisplay)]
     struct Color(i32, i32, i32);
-    struct Point(i32, i32, i32);
+    // FieldDisplay can give tuple fields names to print, even though they have none in the code
+    #[derive(FieldDisplay)]
+    struct Point(
+        #[display(rename = "x")] i32,
+        #[display(rename = "y")] i32,
+        #[display(rename = "z")] i32,
+    );
 
     let black = Color(0i32, 0i32, 0i32);
     let origin = Point(0i32, 0i32, 0i32);
     println!("black: {} {} {}", black.0, black.1, black.2);
     println!("origin: {} {} {}", origin.0, origin.1, origin.2);
+    println!("{black} {origin}");
 }
 fn _unit_like_structs_without_any_fields() {
     /* You can also define structs that don't have any fields. These are called unit-like structs,
@@ -127,3 +237,350 @@ fn ref_without_lifetime() {
     };
 // This will fail to compile, with the error specifying that lifetime parameters are required.
 }*/
+// NewUser does compile once it has a lifetime, struct NewUser<'a> { username: &'a str, ... }, as
+// long as something else keeps the strings alive for 'a. graph.rs keeps them in an arena, along
+// with the users themselves, so users can refer to each other too:
+fn users_in_an_arena() {
+    let arenas = GraphArenas::new();
+    let fooman = arenas.user("fooman", "fooman@gmail.com", 1);
+    let barman = arenas.user("barman", "barman@gmail.com", 2);
+    let sockpuppet = arenas.user("sockpuppet", "sockpuppet@fun.com", 1);
+    // a cycle: fooman and barman are friends with each other, which Box alone can't do
+    fooman.befriend(arenas.friend_list(&[barman, sockpuppet]));
+    barman.befriend(arenas.friend_list(&[fooman]));
+    for user in [fooman, barman, sockpuppet] {
+        let friends: Vec<&str> = user.friends().iter().map(|f| f.username).collect();
+        println!(
+            "{} <{}> is friends with {friends:?}",
+            user.username, user.email
+        );
+    }
+    println!(
+        "fooman's first friend's first friend is {}",
+        fooman.friends()[0].friends()[0].username
+    );
+    println!(
+        "{} users, {} bytes of text, all freed together when arenas goes out of scope",
+        arenas.users.len(),
+        arenas.text.len()
+    );
+}
+
+fn run_arena_bench(args: &[String]) {
+    let count: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(100_000);
+    let friends_each = 8;
+    if cfg!(debug_assertions) {
+        println!("(a debug build; cargo run --release -- arena-bench gives realistic numbers)");
+    }
+    println!("{count} users with {friends_each} friends each:");
+    let mut reports: Vec<Report> = Vec::new();
+
+    // users borrows arenas for as long as arenas lives, so it can't be dropped by hand; it goes
+    // at the end of the block, after the clock for dropping it has started
+    let (build, walk, total, chunks, free_started, section) = {
+        let section = alloc_counter::region("arena: build");
+        let start = Instant::now();
+        let arenas = GraphArenas::new();
+        let users = graph::build_arena_graph(&arenas, count, friends_each);
+        let build = start.elapsed();
+        reports.push(section.finish());
+        let mut total = 0;
+        let walk = time(|| total = graph::friends_of_friends_sign_ins(users));
+        let chunks = arenas.users.chunk_count()
+            + arenas.friend_lists.chunk_count()
+            + arenas.text.chunk_count();
+        let section = alloc_counter::region("arena: drop");
+        (build, walk, total, chunks, Instant::now(), section)
+    };
+    let free = free_started.elapsed();
+    reports.push(section.finish());
+    println!("\n  arena ({chunks} chunks)");
+    print_times(build, walk, free, total);
+
+    let section = alloc_counter::region("Box per user: build");
+    let mut boxed = Vec::new();
+    let build = time(|| boxed = graph::build_boxed_graph(count, friends_each));
+    reports.push(section.finish());
+    let mut total = 0;
+    let walk = time(|| total = graph::boxed_friends_of_friends_sign_ins(&boxed));
+    let section = alloc_counter::region("Box per user: drop");
+    let free = time(|| drop(boxed));
+    reports.push(section.finish());
+    println!("\n  a Box, two Strings and a Vec per user");
+    print_times(build, walk, free, total);
+    println!();
+    alloc_counter::print_reports("heap allocations:", &reports);
+}
+
+fn print_times(build: Duration, walk: Duration, free: Duration, total: u64) {
+    println!("    {:<28} {:>10.2?}", "build", build);
+    println!("    {:<28} {:>10.2?}", "walk friends of friends", walk);
+    println!("    {:<28} {:>10.2?}", "drop", free);
+    println!("    (friends of friends signed in {total} times)");
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
+
+// The users above only live until main ends. registry.rs keeps them in a UserRegistry, which
+// won't let two users share a username or an email, and can save them to a file; 'cargo run --
+// users --help' shows the command line tool that does that.
+fn registry_demo() {
+    let mut registry = UserRegistry::new();
+    for (username, email, sign_in_count) in [
+        ("fooman", "fooman@gmail.com", 12),
+        ("barman", "barman@gmail.com", 30),
+        ("sockpuppet", "sockpuppet@fun.com", 1),
+        ("FooMan", "another@example.com", 1),
+        ("imposter", "Barman@Gmail.com", 1),
+    ] {
+        let user = User {
+            active: true,
+            username: String::from(username),
+            email: String::from(email),
+            sign_in_count,
+        };
+        match registry.create(user) {
+            Ok(id) => println!("{username} is user {id}"),
+            Err(err) => println!("couldn't add {username}: {err}"),
+        }
+    }
+    if let Ok(id) = registry.id_of("sockpuppet") {
+        let _ = registry.deactivate(id);
+    }
+    let top: Vec<&str> = registry
+        .active_by_sign_in_count()
+        .iter()
+        .map(|(_, user)| user.username.as_str())
+        .collect();
+    println!("active users, most sign-ins first: {top:?}");
+}
+
+const USERS_USAGE: &str = "\
+usage: defining_and_instantiating_structs users [-f file] <command>
+  keeps users in a file (users.txt unless -f says otherwise)
+commands:
+  add <username> <email>       create an active user
+  list                         every user, in the order they were added
+  top [n]                      the n (default 10) active users with the most sign-ins
+  show <username>
+  rename <username> <new>      change a username
+  email <username> <new>       change an email
+  deactivate <username>
+  activate <username>
+  delete <username>
+  passwd <username>            set a password, read from stdin
+  sign-in <username>           check a password from stdin, and print a session token
+  whoami <token>               who a session token belongs to
+  sign-out <token>
+every change is recorded in the file's .log (users.txt.log), which is written first
+several of these can run at once: each waits for the others to finish with the file";
+
+fn run_users(args: &[String]) {
+    let mut path = PathBuf::from("users.txt");
+    let mut args = args;
+    if let [flag, file, rest @ ..] = args {
+        if flag == "-f" {
+            path = PathBuf::from(file);
+            args = rest;
+        }
+    }
+    let Some((command, args)) = args.split_first() else {
+        return println!("{USERS_USAGE}");
+    };
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    if let Err(err) = users_command(&path, command, &args) {
+        eprintln!("error: {err}");
+        std::process::exit(1);
+    }
+}
+
+fn users_command(path: &Path, command: &str, args: &[&str]) -> Result<(), RegistryError> {
+    // reading doesn't need the lock: a save replaces the whole file at once
+    let read = || UserRegistry::load(path);
+    match (command, args) {
+        ("list", []) => print_users(read()?.iter()),
+        ("top", []) => print_users(read()?.active_by_sign_in_count().into_iter().take(10)),
+        ("top", [n]) => match n.parse::<usize>() {
+            Ok(n) => print_users(read()?.active_by_sign_in_count().into_iter().take(n)),
+            Err(_) => println!("{USERS_USAGE}"),
+        },
+        ("show", [username]) => {
+            let registry = read()?;
+            let id = registry.id_of(username)?;
+            print_users(registry.get(id).map(|user| (id, user)));
+        }
+        ("add", [username, email]) => {
+            let user = User::builder()
+                .username(*username)
+                .email(*email)
+                .sign_in_count(0)
+                .build()?;
+            let username = user.username.clone();
+            let mut registry = LockedRegistry::open(path)?;
+            let id = registry.create(user)?;
+            registry.save()?;
+            println!("added {username} as {id}");
+        }
+        ("whoami", [token]) => {
+            let registry = read()?;
+            match registry.session(token) {
+                Ok(id) => print_users(registry.get(id).map(|user| (id, user))),
+                Err(err) => println!("{err}"),
+            }
+        }
+        ("passwd", [username]) => {
+            let password = read_password()?;
+            let mut registry = LockedRegistry::open(path)?;
+            let id = registry.id_of(username)?;
+            registry.set_password(id, &password)?;
+            registry.save()?;
+            println!("set the password for {username}");
+        }
+        ("sign-in", [username]) => {
+            let password = read_password()?;
+            let mut registry = LockedRegistry::open(path)?;
+            let signed_in = registry.sign_in(username, &password);
+            // failed attempts count towards a lockout, so they're saved too
+            registry.save()?;
+            match signed_in {
+                Ok(token) => println!("{token}"),
+                Err(err) => {
+                    eprintln!("{err}");
+                    std::process::exit(1);
+                }
+            }
+        }
+        ("sign-out", [token]) => {
+            let mut registry = LockedRegistry::open(path)?;
+            if registry.sign_out(token) {
+                registry.save()?;
+                println!("signed out");
+            } else {
+                println!("that session doesn't exist");
+            }
+        }
+        ("delete", [username]) => {
+            let mut registry = LockedRegistry::open(path)?;
+            let id = registry.id_of(username)?;
+            let user = registry.delete(id)?;
+            registry.save()?;
+            println!("deleted {} ({id})", user.username);
+        }
+        ("rename" | "email" | "deactivate" | "activate", [username, rest @ ..]) => {
+            let mut registry = LockedRegistry::open(path)?;
+            let id = registry.id_of(username)?;
+            // the changed user goes through the builder, so it's checked like a new one
+            let before = registry.get(id).cloned();
+            let before = before.ok_or(RegistryError::NoSuchId(id))?;
+            let current = before.to_builder();
+            let user = match (command, rest) {
+                ("rename", [new]) => {
+                    let changed = current.username(*new).build()?;
+                    registry.update(id, |user| *user = changed)
+                }
+                ("email", [new]) => {
+                    let changed = current.email(*new).build()?;
+                    registry.update(id, |user| *user = changed)
+                }
+                ("deactivate", []) => registry.deactivate(id),
+                ("activate", []) => registry.activate(id),
+                _ => {
+                    println!("{USERS_USAGE}");
+                    return Ok(());
+                }
+            }?
+            .clone();
+            registry.save()?;
+            print_users([(id, &user)]);
+        }
+        _ => println!("{USERS_USAGE}"),
+    }
+    Ok(())
+}
+
+// The first line of stdin, so the password doesn't show up in the shell history or ps.
+fn read_password() -> Result<String, RegistryError> {
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+fn print_users<'a>(users: impl IntoIterator<Item = (UserId, &'a User)>) {
+    for (id, user) in users {
+        println!(
+            "{:>6}  {:<8} {:>6} sign-ins  {:<16} {}",
+            id.to_string(),
+            if user.active { "active" } else { "inactive" },
+            user.sign_in_count,
+            user.username,
+            user.email
+        );
+    }
+}
+
+// sign_in_count only goes up when someone signs in, which needs a password. auth.rs stores a
+// salted PBKDF2 hash of it (crypto.rs has the SHA-256, HMAC and PBKDF2 that make it), locks the
+// account after too many wrong guesses, and hands out session tokens that expire.
+fn sign_in_demo() {
+    let mut registry = UserRegistry::new();
+    // real passwords get 100,000 iterations; a thousand keeps the example quick
+    registry.set_policy(AuthPolicy {
+        iterations: 1_000,
+        ..AuthPolicy::default()
+    });
+    let fooman = User {
+        active: true,
+        username: String::from("fooman"),
+        email: String::from("fooman@gmail.com"),
+        sign_in_count: 0,
+    };
+    let Ok(id) = registry.create(fooman) else {
+        return;
+    };
+    if let Err(err) = registry.set_password(id, "correct horse battery staple") {
+        return println!("couldn't set fooman's password: {err}");
+    }
+    let now = SystemTime::now();
+    match registry.sign_in_at("fooman", "correct horse battery staple", now) {
+        Ok(token) => println!(
+            "fooman signed in ({} sign-ins), session {}..., still good 23 hours later: {:?}",
+            registry.get(id).map_or(0, |user| user.sign_in_count),
+            &token.as_str()[..8],
+            registry.session_at(token.as_str(), now + Duration::from_secs(60 * 60 * 23))
+        ),
+        Err(err) => println!("fooman couldn't sign in: {err}"),
+    }
+    for guess in [
+        "hunter2",
+        "password",
+        "123456",
+        "letmein",
+        "fooman",
+        "correct horse",
+    ] {
+        if let Err(err) = registry.sign_in_at("fooman", guess, now) {
+            println!("  {guess:?}: {err}");
+        }
+    }
+    let err = registry.sign_in_at("fooman", "correct horse battery staple", now);
+    println!("even the right password is refused now: {}", err.is_err());
+    let later = now + AuthPolicy::default().lockout;
+    let signed_in = registry.sign_in_at("fooman", "correct horse battery staple", later);
+    println!(
+        "15 minutes later: {}",
+        if signed_in.is_ok() {
+            "signed in"
+        } else {
+            "still locked"
+        }
+    );
+    let missing = registry.sign_in_at("nobody", "hunter2", later);
+    println!(
+        "an unknown user gets the same error as a wrong password: {}",
+        matches!(missing, Err(SignInError::WrongCredentials))
+    );
+}
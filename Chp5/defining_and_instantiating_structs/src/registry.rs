@@ -0,0 +1,677 @@
+/* A registry of users */
+// main.rs makes a few Users and forgets them when it ends. UserRegistry keeps them: every user gets
+// a UserId, and two secondary indexes (username -> id and email -> id) make sure no two users share
+// a username or an email. Both are compared ignoring case, so "FooMan" is taken once "fooman" is.
+// Emails are compared by their mailbox (see builder.rs): the address without its +tag, with the
+// domain in ASCII and no trailing dot. So fooman+rust@gmail.com is taken once fooman@gmail.com is.
+// A change that would break that is refused with an error and leaves the registry as it was.
+//
+// The registry can be saved to a text file, one user per line, and loaded back. Saving never
+// writes over the file in place: it writes a temporary file next to it, flushes it to disk and
+// renames it over the old one. A rename within a directory is atomic, so anyone reading the file
+// sees either the old registry or the new one, never half of each, even if the program dies in the
+// middle of saving.
+//
+// That isn't enough when two programs change the registry at the same time: both load it, both
+// add a user, and whoever saves last wins, losing the other's user. LockedRegistry holds a lock on
+// a separate .lock file from loading to saving, so the second program waits for the first to
+// finish and then loads what it saved. (The lock can't be on the registry file itself, because
+// each save replaces that file with a new one.) Saving a LockedRegistry also keeps a record of
+// what was changed in a .log file next to the registry, one change per line.
+use crate::auth::{Account, AuthPolicy, Credential, Session};
+use crate::builder::{Email, UserError};
+use crate::user::User;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use struct_diff::{Diff, FieldValue, Patch, Value};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct UserId(pub u64);
+
+impl fmt::Display for UserId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UserRegistry {
+    users: BTreeMap<UserId, User>,
+    // lowercased username, or the mailbox of an email -> the user with it
+    by_username: HashMap<String, UserId>,
+    by_email: HashMap<String, UserId>,
+    // ids aren't reused after a delete, so an old id can't end up meaning someone else
+    next_id: u64,
+    // passwords and sessions, see auth.rs
+    pub(crate) accounts: HashMap<UserId, Account>,
+    pub(crate) sessions: HashMap<String, Session>,
+    pub(crate) policy: AuthPolicy,
+}
+
+fn key(text: &str) -> String {
+    text.to_lowercase()
+}
+
+// Users made without the builder can have any email, so one that doesn't parse is only lowercased.
+fn email_key(email: &str) -> String {
+    Email::parse(email).map_or_else(|_| key(email), |email| email.mailbox())
+}
+
+impl UserRegistry {
+    pub fn new() -> Self {
+        UserRegistry {
+            next_id: 1,
+            ..UserRegistry::default()
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.users.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+
+    pub fn get(&self, id: UserId) -> Option<&User> {
+        self.users.get(&id)
+    }
+
+    pub fn find_by_username(&self, username: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_username.get(&key(username))?;
+        Some((id, &self.users[&id]))
+    }
+
+    pub fn find_by_email(&self, email: &str) -> Option<(UserId, &User)> {
+        let id = *self.by_email.get(&email_key(email))?;
+        Some((id, &self.users[&id]))
+    }
+
+    // The id of the user with this username, or an error saying there isn't one.
+    pub fn id_of(&self, username: &str) -> Result<UserId, RegistryError> {
+        self.find_by_username(username)
+            .map(|(id, _)| id)
+            .ok_or_else(|| RegistryError::NoSuchUser(username.to_string()))
+    }
+
+    // Every user, in the order they were created.
+    pub fn iter(&self) -> impl Iterator<Item = (UserId, &User)> {
+        self.users.iter().map(|(&id, user)| (id, user))
+    }
+
+    // Checks that user's username and email aren't taken by anyone other than except.
+    fn check_unique(&self, user: &User, except: Option<UserId>) -> Result<(), RegistryError> {
+        let taken = |index: &HashMap<String, UserId>, key: String| {
+            index.get(&key).is_some_and(|&id| Some(id) != except)
+        };
+        if taken(&self.by_username, key(&user.username)) {
+            return Err(RegistryError::UsernameTaken(user.username.clone()));
+        }
+        if taken(&self.by_email, email_key(&user.email)) {
+            return Err(RegistryError::EmailTaken(user.email.clone()));
+        }
+        Ok(())
+    }
+
+    fn insert(&mut self, id: UserId, user: User) {
+        self.by_username.insert(key(&user.username), id);
+        self.by_email.insert(email_key(&user.email), id);
+        self.users.insert(id, user);
+    }
+
+    fn unindex(&mut self, user: &User) {
+        self.by_username.remove(&key(&user.username));
+        self.by_email.remove(&email_key(&user.email));
+    }
+
+    pub fn create(&mut self, user: User) -> Result<UserId, RegistryError> {
+        self.check_unique(&user, None)?;
+        let id = UserId(self.next_id);
+        self.next_id += 1;
+        self.insert(id, user);
+        Ok(id)
+    }
+
+    // Changes a user with f. If the changed user's username or email belongs to someone else, the
+    // change is thrown away and the user stays as it was.
+    pub fn update(
+        &mut self,
+        id: UserId,
+        f: impl FnOnce(&mut User),
+    ) -> Result<&User, RegistryError> {
+        let mut user = self.get(id).ok_or(RegistryError::NoSuchId(id))?.clone();
+        f(&mut user);
+        self.check_unique(&user, Some(id))?;
+        let old = self.users.remove(&id).ok_or(RegistryError::NoSuchId(id))?;
+        self.unindex(&old);
+        self.insert(id, user);
+        Ok(&self.users[&id])
+    }
+
+    // Deactivating a user also signs them out everywhere.
+    pub fn deactivate(&mut self, id: UserId) -> Result<&User, RegistryError> {
+        self.sessions.retain(|_, session| session.user != id);
+        self.update(id, |user| user.active = false)
+    }
+
+    pub fn activate(&mut self, id: UserId) -> Result<&User, RegistryError> {
+        self.update(id, |user| user.active = true)
+    }
+
+    pub fn delete(&mut self, id: UserId) -> Result<User, RegistryError> {
+        let user = self.users.remove(&id).ok_or(RegistryError::NoSuchId(id))?;
+        self.unindex(&user);
+        self.accounts.remove(&id);
+        self.sessions.retain(|_, session| session.user != id);
+        Ok(user)
+    }
+
+    /* Queries */
+    // The users for which keep returns true, in the order they were created.
+    pub fn select(&self, keep: impl Fn(&User) -> bool) -> Vec<(UserId, &User)> {
+        self.iter().filter(|(_, user)| keep(user)).collect()
+    }
+
+    // The active users, the ones who sign in most first (and by username when that's a tie).
+    pub fn active_by_sign_in_count(&self) -> Vec<(UserId, &User)> {
+        let mut users = self.select(|user| user.active);
+        users.sort_by(|(_, a), (_, b)| {
+            b.sign_in_count
+                .cmp(&a.sign_in_count)
+                .then_with(|| a.username.cmp(&b.username))
+        });
+        users
+    }
+
+    pub fn inactive(&self) -> Vec<(UserId, &User)> {
+        self.select(|user| !user.active)
+    }
+
+    /* Saving and loading */
+    // The file format: a header, the next id, then one line per user with its fields separated by
+    // tabs. Tabs, newlines and backslashes in usernames and emails are escaped, so a line is
+    // always one user. After the users come their passwords (with the failed sign-ins and the
+    // end of any lockout) and the hashes of the session tokens. Version 1 files, from before
+    // there were passwords, load as well.
+    //     users v2
+    //     next 4
+    //     1	true	3	fooman	fooman@gmail.com
+    //     password	1	pbkdf2-sha256$100000$<salt>$<hash>	0	-
+    //     session	<token hash>	1	<expiry>
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{HEADER}\nnext {}\n", self.next_id);
+        for (id, user) in self.iter() {
+            text += &format!(
+                "{}\t{}\t{}\t{}\t{}\n",
+                id.0,
+                user.active,
+                user.sign_in_count,
+                escape(&user.username),
+                escape(&user.email)
+            );
+        }
+        // sorted, so saving the same registry twice writes the same file
+        let mut accounts: Vec<_> = self.accounts.iter().collect();
+        accounts.sort_by_key(|(id, _)| **id);
+        for (id, account) in accounts {
+            let locked_until = account
+                .locked_until
+                .map_or(String::from("-"), |until| until.to_string());
+            text += &format!(
+                "password\t{}\t{}\t{}\t{locked_until}\n",
+                id.0, account.credential, account.failures
+            );
+        }
+        let mut sessions: Vec<_> = self.sessions.iter().collect();
+        sessions.sort_by_key(|(key, _)| key.as_str());
+        for (key, session) in sessions {
+            text += &format!("session\t{key}\t{}\t{}\n", session.user.0, session.expires);
+        }
+        text
+    }
+
+    pub fn from_text(text: &str) -> Result<Self, RegistryError> {
+        let corrupt = |line: usize, message: &str| RegistryError::Corrupt {
+            line,
+            message: message.to_string(),
+        };
+        let mut lines = text.lines().enumerate().map(|(i, line)| (i + 1, line));
+        if !matches!(lines.next(), Some((_, HEADER | "users v1"))) {
+            return Err(corrupt(1, "not a users file"));
+        }
+        let next_id = match lines.next() {
+            Some((n, line)) => line
+                .strip_prefix("next ")
+                .and_then(|id| id.parse().ok())
+                .ok_or_else(|| corrupt(n, "expected 'next <id>'"))?,
+            None => return Err(corrupt(2, "expected 'next <id>'")),
+        };
+        let mut registry = UserRegistry::new();
+        registry.next_id = next_id;
+        for (n, line) in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let user_id = |id: &str| match id.parse() {
+                Ok(id) if registry.users.contains_key(&UserId(id)) => Ok(UserId(id)),
+                _ => Err(corrupt(n, "no user has that id")),
+            };
+            match fields[..] {
+                ["password", id, credential, failures, locked_until] => {
+                    let account = Account {
+                        credential: Credential::parse(credential)
+                            .ok_or_else(|| corrupt(n, "bad password hash"))?,
+                        failures: failures
+                            .parse()
+                            .map_err(|_| corrupt(n, "bad failure count"))?,
+                        locked_until: match locked_until {
+                            "-" => None,
+                            until => Some(until.parse().map_err(|_| corrupt(n, "bad lockout"))?),
+                        },
+                    };
+                    registry.accounts.insert(user_id(id)?, account);
+                    continue;
+                }
+                ["session", key, id, expires] => {
+                    let session = Session {
+                        user: user_id(id)?,
+                        expires: expires.parse().map_err(|_| corrupt(n, "bad expiry"))?,
+                    };
+                    registry.sessions.insert(key.to_string(), session);
+                    continue;
+                }
+                _ => {}
+            }
+            let [id, active, sign_in_count, username, email] = fields[..] else {
+                return Err(corrupt(n, "expected 5 fields separated by tabs"));
+            };
+            let id = UserId(id.parse().map_err(|_| corrupt(n, "bad id"))?);
+            if id.0 >= next_id || registry.users.contains_key(&id) {
+                return Err(corrupt(n, "the id is already used"));
+            }
+            let user = User {
+                active: active.parse().map_err(|_| corrupt(n, "bad active flag"))?,
+                username: unescape(username).ok_or_else(|| corrupt(n, "bad username"))?,
+                email: unescape(email).ok_or_else(|| corrupt(n, "bad email"))?,
+                sign_in_count: sign_in_count
+                    .parse()
+                    .map_err(|_| corrupt(n, "bad sign in count"))?,
+            };
+            registry
+                .check_unique(&user, None)
+                .map_err(|err| corrupt(n, &err.to_string()))?;
+            registry.insert(id, user);
+        }
+        Ok(registry)
+    }
+
+    // A file that doesn't exist yet is an empty registry.
+    pub fn load(path: &Path) -> Result<Self, RegistryError> {
+        match fs::read_to_string(path) {
+            Ok(text) => UserRegistry::from_text(&text),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(UserRegistry::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    // Writes the registry to a temporary file and renames it over path.
+    pub fn save(&self, path: &Path) -> Result<(), RegistryError> {
+        let name = path.file_name().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "the path has no file name")
+        })?;
+        let mut temporary = path.to_path_buf();
+        temporary.set_file_name(format!(
+            ".{}.{}.tmp",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        let written = (|| {
+            let mut file = File::create(&temporary)?;
+            file.write_all(self.to_text().as_bytes())?;
+            // on disk before the rename, or a crash could leave the new name on an empty file
+            file.sync_all()?;
+            fs::rename(&temporary, path)
+        })();
+        if let Err(err) = written {
+            let _ = fs::remove_file(&temporary);
+            return Err(err.into());
+        }
+        // and the rename itself on disk too
+        #[cfg(unix)]
+        if let Some(directory) = path.parent() {
+            let directory = if directory.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                directory
+            };
+            File::open(directory)?.sync_all()?;
+        }
+        Ok(())
+    }
+}
+
+const HEADER: &str = "users v2";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn unescape(text: &str) -> Option<String> {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        unescaped.push(match chars.next()? {
+            '\\' => '\\',
+            't' => '\t',
+            'n' => '\n',
+            'r' => '\r',
+            _ => return None,
+        });
+    }
+    Some(unescaped)
+}
+
+/* Locking */
+// A registry loaded from path, with the lock held until it's dropped. Changes are made through
+// it like any UserRegistry, and only reach the file when save is called.
+pub struct LockedRegistry {
+    registry: UserRegistry,
+    // the registry as it is in the file, to find what has changed since
+    saved: UserRegistry,
+    path: PathBuf,
+    // the lock goes when the file is closed
+    _lock: File,
+}
+
+impl LockedRegistry {
+    // Waits for any other program holding the lock to let go, then loads the registry.
+    pub fn open(path: &Path) -> Result<Self, RegistryError> {
+        let mut lock_path = path.as_os_str().to_owned();
+        lock_path.push(".lock");
+        // the lock file is never deleted: deleting it while someone waits on it would let a third
+        // program lock a new file of the same name, and then two programs hold "the" lock
+        let lock = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(lock_path)?;
+        lock.lock()?;
+        let registry = UserRegistry::load(path)?;
+        Ok(LockedRegistry {
+            saved: registry.clone(),
+            registry,
+            path: path.to_path_buf(),
+            _lock: lock,
+        })
+    }
+
+    // Writes what changed since the last save to path.log, then the registry to path. The log is
+    // flushed to disk before the registry is replaced, so every change that's saved is in the
+    // log. (If the program dies in between, the log has a change the registry doesn't, but never
+    // the other way round.) If the log can't be written, nothing is saved.
+    pub fn save(&mut self) -> Result<(), RegistryError> {
+        let changes = self.changes();
+        if !changes.is_empty() {
+            let mut log_path = self.path.as_os_str().to_owned();
+            log_path.push(".log");
+            let mut log = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(log_path)?;
+            log.write_all(changes.as_bytes())?;
+            log.sync_all()?;
+        }
+        // this syncs the directory too, which makes a log file that was only just made stay there
+        self.registry.save(&self.path)?;
+        self.saved = self.registry.clone();
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // The log lines for everything that's different from the saved registry, one per change: the
+    // time in seconds since 1970, the user's id, what happened and a patch (see the struct_diff
+    // crate) as JSON, separated by tabs.
+    //     1760843298	4	added	[{"field":"active","old":null,"new":true},...]
+    //     1760843301	4	changed	[{"field":"email","old":"barman@gmail.com","new":"bar@fun.com"}]
+    //     1760843305	4	password	[{"field":"iterations","old":null,"new":100000}]
+    //     1760843310	4	session started	[{"field":"expires","old":null,"new":1760929710}]
+    //     1760843310	4	account	[{"field":"failures","old":2,"new":0}]
+    // Passwords and session tokens are never written, not even their hashes.
+    fn changes(&self) -> String {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let (old, new) = (&self.saved, &self.registry);
+        let mut ids: Vec<UserId> = old.users.keys().chain(new.users.keys()).copied().collect();
+        ids.sort();
+        ids.dedup();
+        let mut lines = String::new();
+        let mut line = |id: UserId, what: &str, patch: &Patch| {
+            lines += &format!("{now}\t{}\t{what}\t{}\n", id.0, patch.to_json());
+        };
+        for id in ids {
+            match (old.get(id), new.get(id)) {
+                (None, Some(user)) => line(id, "added", &whole(user)),
+                (Some(user), None) => line(id, "deleted", &whole(user).reversed()),
+                (Some(before), Some(after)) if before != after => {
+                    line(id, "changed", &before.diff(after))
+                }
+                _ => {}
+            }
+            // a deleted user's password and sessions go with them, and "deleted" says so
+            if new.get(id).is_none() {
+                continue;
+            }
+            let (before, after) = (old.accounts.get(&id), new.accounts.get(&id));
+            if before.map(|a| &a.credential) != after.map(|a| &a.credential) {
+                let iterations = |account: Option<&Account>| {
+                    account.map_or(Value::Null, |a| a.credential.iterations().to_value())
+                };
+                let mut patch = Patch::new();
+                patch.push("iterations", iterations(before), iterations(after));
+                line(id, "password", &patch);
+            }
+            for (key, session) in &new.sessions {
+                if session.user == id && !old.sessions.contains_key(key) {
+                    let mut patch = Patch::new();
+                    patch.push("expires", Value::Null, session.expires.to_value());
+                    line(id, "session started", &patch);
+                }
+            }
+            for (key, session) in &old.sessions {
+                if session.user == id && !new.sessions.contains_key(key) {
+                    let mut patch = Patch::new();
+                    patch.push("expires", session.expires.to_value(), Value::Null);
+                    line(id, "session ended", &patch);
+                }
+            }
+            let mut patch = Patch::new();
+            let failures = |account: Option<&Account>| account.map_or(0, |a| a.failures);
+            if failures(before) != failures(after) {
+                let (old, new) = (failures(before), failures(after));
+                patch.push("failures", old.to_value(), new.to_value());
+            }
+            let locked = |account: Option<&Account>| account.and_then(|a| a.locked_until);
+            if locked(before) != locked(after) {
+                let (old, new) = (locked(before), locked(after));
+                patch.push("locked_until", old.to_value(), new.to_value());
+            }
+            if !patch.is_empty() {
+                line(id, "account", &patch);
+            }
+        }
+        lines
+    }
+}
+
+// Every field of user, as a patch from nothing.
+fn whole(user: &User) -> Patch {
+    let mut patch = Patch::new();
+    for &name in User::FIELDS {
+        if let Some(value) = user.field(name) {
+            patch.push(name, Value::Null, value);
+        }
+    }
+    patch
+}
+
+impl Deref for LockedRegistry {
+    type Target = UserRegistry;
+
+    fn deref(&self) -> &UserRegistry {
+        &self.registry
+    }
+}
+
+impl DerefMut for LockedRegistry {
+    fn deref_mut(&mut self) -> &mut UserRegistry {
+        &mut self.registry
+    }
+}
+
+/* Errors */
+#[derive(Debug)]
+pub enum RegistryError {
+    UsernameTaken(String),
+    EmailTaken(String),
+    NoSuchUser(String),
+    NoSuchId(UserId),
+    // a username or email that UserBuilder wouldn't accept
+    Invalid(UserError),
+    // a line in a registry file that couldn't be read
+    Corrupt { line: usize, message: String },
+    Io(io::Error),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RegistryError::UsernameTaken(username) => {
+                write!(f, "the username '{username}' is taken")
+            }
+            RegistryError::EmailTaken(email) => write!(f, "the email '{email}' is taken"),
+            RegistryError::NoSuchUser(username) => write!(f, "there is no user '{username}'"),
+            RegistryError::NoSuchId(id) => write!(f, "there is no user {id}"),
+            RegistryError::Invalid(err) => write!(f, "{err}"),
+            RegistryError::Corrupt { line, message } => {
+                write!(f, "the registry file is damaged at line {line}: {message}")
+            }
+            RegistryError::Io(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RegistryError::Invalid(err) => Some(err),
+            RegistryError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for RegistryError {
+    fn from(err: io::Error) -> Self {
+        RegistryError::Io(err)
+    }
+}
+
+impl From<UserError> for RegistryError {
+    fn from(err: UserError) -> Self {
+        RegistryError::Invalid(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user(username: &str, email: &str) -> User {
+        User::builder()
+            .username(username)
+            .email(email)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn the_same_mailbox_is_the_same_email() {
+        for (first, second) in [
+            ("fooman@bücher.example", "fooman@xn--bcher-kva.example"),
+            ("a@b.com", "a@b.com."),
+            ("fooman@gmail.com", "FooMan+x@gmail.com"),
+        ] {
+            let mut registry = UserRegistry::new();
+            registry.create(user("first", first)).unwrap();
+            assert!(
+                matches!(
+                    registry.create(user("second", second)),
+                    Err(RegistryError::EmailTaken(_))
+                ),
+                "{first} and {second}"
+            );
+            assert!(registry.find_by_email(second).is_some());
+        }
+    }
+
+    #[test]
+    fn saving_logs_the_changes_first() {
+        let dir = std::env::temp_dir().join(format!("registry-log-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("users.txt");
+        let log = dir.join("users.txt.log");
+
+        let mut registry = LockedRegistry::open(&path).unwrap();
+        let id = registry.create(user("fooman", "fooman@gmail.com")).unwrap();
+        registry.save().unwrap();
+        // saving again with nothing changed adds nothing
+        registry.save().unwrap();
+        let logged = fs::read_to_string(&log).unwrap();
+        assert_eq!(logged.lines().count(), 1);
+        assert!(logged.contains("\t1\tadded\t"));
+
+        // a log that can't be written means no save
+        fs::remove_file(&log).unwrap();
+        fs::create_dir(&log).unwrap();
+        registry.set_policy(AuthPolicy {
+            iterations: 1,
+            ..AuthPolicy::default()
+        });
+        registry.set_password(id, "hunter2").unwrap();
+        assert!(registry.save().is_err());
+        assert!(!UserRegistry::load(&path).unwrap().has_password(id));
+
+        drop(registry);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn build_stores_the_ascii_form() {
+        assert_eq!(
+            user("fooman", "FooMan+Rust@Bücher.Example.").email,
+            "fooman+rust@xn--bcher-kva.example"
+        );
+    }
+}
@@ -0,0 +1,24 @@
+/* The User struct */
+// This is the User from main.rs, moved into the library so the registry (and anything else that
+// wants to keep users around) can use it too. The fields are pub so main.rs can still build one
+// with a struct literal and read the fields with dot notation. FieldDisplay (from the field_display
+// crate) makes println!("{user}") list the fields, and Diff (from struct_diff) says which fields
+// are different between two users.
+use field_display::FieldDisplay;
+
+#[derive(Debug, Clone, PartialEq, Eq, FieldDisplay)]
+pub struct User {
+    // The 'struct' keyword is used to define a struct, like class in python
+    pub active: bool,
+    pub username: String,
+    pub email: String,
+    #[display(rename = "sign-ins")]
+    pub sign_in_count: u64,
+}
+
+struct_diff::diff_fields!(User {
+    active,
+    username,
+    email,
+    sign_in_count,
+});
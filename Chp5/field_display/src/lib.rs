@@ -0,0 +1,219 @@
+// #[derive(FieldDisplay)] writes a Display implementation that lists a struct's fields, so
+// printing a User is println!("{user1}") instead of naming every field in the format string.
+//
+// The derive is a procedural macro: a function the compiler runs at compile time, which is handed
+// the tokens of the struct it's attached to and hands back the tokens of the impl. Procedural
+// macros have to live in a crate of their own (proc-macro = true in Cargo.toml), which the crates
+// using them depend on like any other. syn parses the tokens into a syntax tree, and quote turns
+// Rust-looking code with #variables in it back into tokens.
+//
+//     #[derive(FieldDisplay)]
+//     struct User {
+//         active: bool,
+//         #[display(rename = "name")]
+//         username: String,
+//         #[display(skip)]
+//         email: String,
+//         sign_in_count: u64,
+//     }
+//
+// prints as User { active: true, name: fooman, sign_in_count: 1 }, and with {:#} as
+//     User {
+//         active: true,
+//         name: fooman,
+//         sign_in_count: 1,
+//     }
+// #[display(layout = "multiline")] on the struct makes the second one the default (and {:#} then
+// gives the first). Tuple structs print as Color(0, 0, 0), or Point(x: 0, y: 0, z: 0) when their
+// fields are renamed. Every field that's shown needs to implement Display itself.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as Tokens;
+use quote::{quote, quote_spanned};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, Index, LitStr};
+
+#[proc_macro_derive(FieldDisplay, attributes(display))]
+pub fn derive_field_display(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// A field that will be printed: how to get at it, and what to call it (None for an unrenamed
+// tuple field, which is printed without a label).
+struct Shown {
+    access: Tokens,
+    label: Option<String>,
+}
+
+fn expand(mut input: DeriveInput) -> syn::Result<Tokens> {
+    let multiline = container_layout(&input)?;
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new(
+            input.ident.span(),
+            "FieldDisplay only works on structs",
+        ));
+    };
+    let named = matches!(data.fields, Fields::Named(_));
+    let mut shown: Vec<Shown> = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let options = field_options(&field.attrs)?;
+        if options.skip {
+            continue;
+        }
+        let span = field.ty.span();
+        let access = match &field.ident {
+            // quote_spanned puts "doesn't implement Display" errors on the field's type
+            Some(ident) => quote_spanned!(span=> self.#ident),
+            None => {
+                let index = Index::from(i);
+                quote_spanned!(span=> self.#index)
+            }
+        };
+        let label = match (options.rename, &field.ident) {
+            (Some(rename), _) => Some(rename),
+            // unraw so a field called r#type is labelled type
+            (None, Some(ident)) => Some(ident.unraw().to_string()),
+            (None, None) => None,
+        };
+        shown.push(Shown { access, label });
+    }
+
+    let name = &input.ident;
+    let title = name.to_string();
+    let (open, close) = if named { (" {", "}") } else { ("(", ")") };
+    let one_line = one_line(&shown, &title, named, open, close);
+    let multi_line = multi_line(&shown, &title, open, close);
+
+    // every type parameter has to be Display too, in case it's the type of a field
+    for parameter in input.generics.type_params_mut() {
+        parameter
+            .bounds
+            .push(syn::parse_quote!(::std::fmt::Display));
+    }
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        #[automatically_derived]
+        impl #impl_generics ::std::fmt::Display for #name #type_generics #where_clause {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                // {:#} picks whichever layout isn't the default
+                if f.alternate() != #multiline {
+                    #multi_line
+                } else {
+                    #one_line
+                }
+                Ok(())
+            }
+        }
+    })
+}
+
+// User { active: true, username: fooman }, or Color(0, 0, 0)
+fn one_line(shown: &[Shown], title: &str, named: bool, open: &str, close: &str) -> Tokens {
+    if shown.is_empty() {
+        return quote!(f.write_str(#title)?;);
+    }
+    let writes = shown.iter().enumerate().map(|(i, field)| {
+        let separator = if i == 0 { "" } else { ", " };
+        let access = &field.access;
+        match &field.label {
+            Some(label) => quote!(::std::write!(f, "{}{}: {}", #separator, #label, #access)?;),
+            None => quote!(::std::write!(f, "{}{}", #separator, #access)?;),
+        }
+    });
+    // named fields get spaces inside the braces, like rustfmt and Debug do
+    let (open, close) = if named {
+        (format!("{open} "), format!(" {close}"))
+    } else {
+        (open.to_string(), close.to_string())
+    };
+    quote! {
+        f.write_str(#title)?;
+        f.write_str(#open)?;
+        #(#writes)*
+        f.write_str(#close)?;
+    }
+}
+
+// One field per line, indented, with a comma after each, like {:#?}. A value that takes more
+// than one line itself (another FieldDisplay struct printed with {:#}, say) is indented to match.
+fn multi_line(shown: &[Shown], title: &str, open: &str, close: &str) -> Tokens {
+    if shown.is_empty() {
+        return quote!(f.write_str(#title)?;);
+    }
+    let writes = shown.iter().map(|field| {
+        let access = &field.access;
+        let label = match &field.label {
+            Some(label) => format!("{label}: "),
+            None => String::new(),
+        };
+        quote! {
+            let value = if f.alternate() {
+                ::std::format!("{:#}", #access)
+            } else {
+                ::std::format!("{}", #access)
+            };
+            ::std::writeln!(f, "    {}{},", #label, value.replace('\n', "\n    "))?;
+        }
+    });
+    quote! {
+        // open is " {" or "(", so only named structs get a space before it
+        ::std::writeln!(f, "{}{}", #title, #open)?;
+        #(#writes)*
+        f.write_str(#close)?;
+    }
+}
+
+// #[display(layout = "line")] or #[display(layout = "multiline")] on the struct.
+fn container_layout(input: &DeriveInput) -> syn::Result<bool> {
+    let mut multiline = false;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("display")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("layout") {
+                let layout: LitStr = meta.value()?.parse()?;
+                multiline = match layout.value().as_str() {
+                    "line" => false,
+                    "multiline" => true,
+                    _ => {
+                        return Err(syn::Error::new(
+                            layout.span(),
+                            "the layout is \"line\" or \"multiline\"",
+                        ))
+                    }
+                };
+                Ok(())
+            } else {
+                Err(meta.error("expected layout = \"line\" or layout = \"multiline\""))
+            }
+        })?;
+    }
+    Ok(multiline)
+}
+
+#[derive(Default)]
+struct FieldOptions {
+    skip: bool,
+    rename: Option<String>,
+}
+
+// #[display(skip)] and #[display(rename = "...")] on a field.
+fn field_options(attrs: &[syn::Attribute]) -> syn::Result<FieldOptions> {
+    let mut options = FieldOptions::default();
+    for attr in attrs.iter().filter(|a| a.path().is_ident("display")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                options.skip = true;
+                Ok(())
+            } else if meta.path.is_ident("rename") {
+                let rename: LitStr = meta.value()?.parse()?;
+                options.rename = Some(rename.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected skip or rename = \"...\""))
+            }
+        })?;
+    }
+    Ok(options)
+}
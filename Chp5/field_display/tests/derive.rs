@@ -0,0 +1,144 @@
+// The derive can only be used from outside the crate that defines it, so its tests live here,
+// where they use it the same way the chapter's crates do.
+use field_display::FieldDisplay;
+
+#[derive(FieldDisplay)]
+struct User {
+    active: bool,
+    #[display(rename = "name")]
+    username: String,
+    #[display(skip)]
+    #[allow(dead_code)]
+    email: String,
+    sign_in_count: u64,
+}
+
+fn user() -> User {
+    User {
+        active: true,
+        username: String::from("fooman"),
+        email: String::from("foo@example.com"),
+        sign_in_count: 1,
+    }
+}
+
+#[test]
+fn one_line_skips_and_renames() {
+    assert_eq!(
+        user().to_string(),
+        "User { active: true, name: fooman, sign_in_count: 1 }"
+    );
+}
+
+#[test]
+fn alternate_is_multi_line() {
+    assert_eq!(
+        format!("{:#}", user()),
+        "User {\n    active: true,\n    name: fooman,\n    sign_in_count: 1,\n}"
+    );
+}
+
+#[derive(FieldDisplay)]
+#[display(layout = "multiline")]
+struct Settings {
+    width: u32,
+    height: u32,
+}
+
+#[test]
+fn multiline_layout_swaps_the_two() {
+    let settings = Settings {
+        width: 80,
+        height: 24,
+    };
+    assert_eq!(
+        settings.to_string(),
+        "Settings {\n    width: 80,\n    height: 24,\n}"
+    );
+    assert_eq!(
+        format!("{settings:#}"),
+        "Settings { width: 80, height: 24 }"
+    );
+}
+
+#[derive(FieldDisplay)]
+struct Color(u8, u8, u8);
+
+#[derive(FieldDisplay)]
+struct Point(
+    #[display(rename = "x")] i32,
+    #[display(rename = "y")] i32,
+    #[display(skip)]
+    #[allow(dead_code)]
+    i32,
+);
+
+#[test]
+fn tuple_structs() {
+    assert_eq!(Color(0, 128, 255).to_string(), "Color(0, 128, 255)");
+    assert_eq!(
+        format!("{:#}", Color(1, 2, 3)),
+        "Color(\n    1,\n    2,\n    3,\n)"
+    );
+    assert_eq!(Point(1, -2, 3).to_string(), "Point(x: 1, y: -2)");
+    assert_eq!(
+        format!("{:#}", Point(1, -2, 3)),
+        "Point(\n    x: 1,\n    y: -2,\n)"
+    );
+}
+
+#[derive(FieldDisplay)]
+struct Token {
+    r#type: &'static str,
+    r#ref: u32,
+}
+
+#[test]
+fn raw_identifiers_are_labelled_without_the_prefix() {
+    let token = Token {
+        r#type: "ident",
+        r#ref: 7,
+    };
+    assert_eq!(token.to_string(), "Token { type: ident, ref: 7 }");
+}
+
+#[derive(FieldDisplay)]
+struct Empty {}
+
+#[derive(FieldDisplay)]
+struct Unit;
+
+#[test]
+fn structs_with_nothing_to_show() {
+    assert_eq!(Empty {}.to_string(), "Empty");
+    assert_eq!(format!("{:#}", Empty {}), "Empty");
+    assert_eq!(Unit.to_string(), "Unit");
+}
+
+#[derive(FieldDisplay)]
+struct Wrapper<T> {
+    inner: T,
+}
+
+#[test]
+fn nested_values_are_indented() {
+    let wrapper = Wrapper {
+        inner: Settings {
+            width: 1,
+            height: 2,
+        },
+    };
+    assert_eq!(
+        wrapper.to_string(),
+        "Wrapper { inner: Settings {\n    width: 1,\n    height: 2,\n} }"
+    );
+    assert_eq!(
+        format!("{wrapper:#}"),
+        "Wrapper {\n    inner: Settings { width: 1, height: 2 },\n}"
+    );
+    let wrapper = Wrapper { inner: user() };
+    assert_eq!(
+        format!("{wrapper:#}"),
+        "Wrapper {\n    inner: User {\n        active: true,\n        name: fooman,\n        sign_in_count: 1,\n    },\n}"
+    );
+}
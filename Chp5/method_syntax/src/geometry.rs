@@ -0,0 +1,519 @@
+/* Rectangles with a position */
+// The Rectangle in main.rs is only a size: it knows how big it is, but not where it is. Rect adds
+// the where. Its origin is its top left corner, with x growing to the right and y growing
+// downwards, like the pixels on a screen.
+//
+// A Rect covers the points with left <= x < right and top <= y < bottom. The right and bottom
+// edges aren't part of it, so two rectangles side by side (one's right edge is the other's left)
+// touch but don't overlap, and every point on the line between them belongs to exactly one of
+// them. A Rect with a width or height of 0 is empty: it covers no points, has no area and
+// overlaps nothing, but it still has a position, so splitting or insetting can give one.
+//
+// Every Rect lies entirely within i32 coordinates, right and bottom edges included, so the edges
+// are i32s and the width and height fit in u32s. Anything that could move a rectangle outside
+// that (making one, translating, scaling, outset) gives None instead. area() is a u64, because
+// u32::MAX * u32::MAX doesn't fit in a u32.
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Point { x, y }
+    }
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "({}, {})", self.x, self.y)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Rect {
+    // private, so nothing can make a Rect that sticks out of i32 coordinates
+    origin: Point,
+    width: u32,
+    height: u32,
+}
+
+impl Rect {
+    // None if the right or bottom edge would be past i32::MAX.
+    pub fn new(x: i32, y: i32, width: u32, height: u32) -> Option<Self> {
+        let (x, y) = (i64::from(x), i64::from(y));
+        Rect::from_edges(x, y, x + i64::from(width), y + i64::from(height))
+    }
+
+    // The rectangle with a and b as opposite corners, whichever two corners they are.
+    pub fn from_corners(a: Point, b: Point) -> Self {
+        Rect {
+            origin: Point::new(a.x.min(b.x), a.y.min(b.y)),
+            width: a.x.abs_diff(b.x),
+            height: a.y.abs_diff(b.y),
+        }
+    }
+
+    // The edges are worked out in i64, where nothing can overflow, and only turned back into a
+    // Rect if they fit.
+    fn from_edges(left: i64, top: i64, right: i64, bottom: i64) -> Option<Self> {
+        if right < left || bottom < top {
+            return None;
+        }
+        let (left, top) = (i32::try_from(left).ok()?, i32::try_from(top).ok()?);
+        i32::try_from(right).ok()?;
+        i32::try_from(bottom).ok()?;
+        Some(Rect {
+            origin: Point::new(left, top),
+            width: (right - i64::from(left)) as u32,
+            height: (bottom - i64::from(top)) as u32,
+        })
+    }
+
+    pub fn origin(&self) -> Point {
+        self.origin
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn left(&self) -> i32 {
+        self.origin.x
+    }
+
+    pub fn top(&self) -> i32 {
+        self.origin.y
+    }
+
+    // Can't overflow: new() and everything else checked this edge fits when the Rect was made.
+    pub fn right(&self) -> i32 {
+        (i64::from(self.origin.x) + i64::from(self.width)) as i32
+    }
+
+    pub fn bottom(&self) -> i32 {
+        (i64::from(self.origin.y) + i64::from(self.height)) as i32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    pub fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
+    }
+
+    /* Points and other rectangles */
+    // Points on the left and top edges are inside, points on the right and bottom edges aren't.
+    pub fn contains_point(&self, point: Point) -> bool {
+        self.left() <= point.x
+            && point.x < self.right()
+            && self.top() <= point.y
+            && point.y < self.bottom()
+    }
+
+    // Whether other lies completely inside self, where it is now. Sharing edges is fine, so every
+    // rectangle contains itself. An empty other only has to be on or inside self's edges. This is
+    // about positions: whether a rectangle the size of other would fit in one the size of self if
+    // it were moved or turned round is Rectangle::can_fit in main.rs.
+    pub fn contains(&self, other: &Rect) -> bool {
+        self.left() <= other.left()
+            && other.right() <= self.right()
+            && self.top() <= other.top()
+            && other.bottom() <= self.bottom()
+    }
+
+    // Whether the two share some area. Rectangles that only touch along an edge or at a corner
+    // don't, and neither does an empty rectangle, even one lying across the other.
+    pub fn intersects(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && self.left() < other.right()
+            && other.left() < self.right()
+            && self.top() < other.bottom()
+            && other.top() < self.bottom()
+    }
+
+    // Whether the two meet along an edge or at a corner without overlapping. Empty rectangles
+    // don't touch anything.
+    pub fn touches(&self, other: &Rect) -> bool {
+        !self.is_empty()
+            && !other.is_empty()
+            && !self.intersects(other)
+            && self.left() <= other.right()
+            && other.left() <= self.right()
+            && self.top() <= other.bottom()
+            && other.top() <= self.bottom()
+    }
+
+    // The area the two share, or None if they don't share any (see intersects).
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        if !self.intersects(other) {
+            return None;
+        }
+        Rect::from_edges(
+            self.left().max(other.left()).into(),
+            self.top().max(other.top()).into(),
+            self.right().min(other.right()).into(),
+            self.bottom().min(other.bottom()).into(),
+        )
+    }
+
+    pub fn overlap_area(&self, other: &Rect) -> u64 {
+        self.intersection(other).map_or(0, |overlap| overlap.area())
+    }
+
+    // The smallest rectangle with both of them inside it. An empty rectangle has nothing in it to
+    // cover, so it's ignored (unless both are empty, when the result is self).
+    pub fn union(&self, other: &Rect) -> Rect {
+        match (self.is_empty(), other.is_empty()) {
+            (_, true) => *self,
+            (true, false) => *other,
+            (false, false) => Rect::from_corners(
+                Point::new(self.left().min(other.left()), self.top().min(other.top())),
+                Point::new(
+                    self.right().max(other.right()),
+                    self.bottom().max(other.bottom()),
+                ),
+            ),
+        }
+    }
+
+    /* Moving and resizing */
+    pub fn translate(&self, dx: i32, dy: i32) -> Option<Rect> {
+        let left = i64::from(self.left()) + i64::from(dx);
+        let top = i64::from(self.top()) + i64::from(dy);
+        Rect::from_edges(
+            left,
+            top,
+            left + i64::from(self.width),
+            top + i64::from(self.height),
+        )
+    }
+
+    // Scales the rectangle by factor, keeping the point about where it is: scaling about the
+    // origin grows the rectangle to the right and down, scaling about the middle grows it in
+    // every direction. The edges are rounded to whole numbers, rather than the size, so
+    // rectangles that touched before still touch afterwards. A factor of 0 leaves an empty
+    // rectangle at about. None for a negative, NaN or infinite factor, or if the result doesn't
+    // fit.
+    pub fn scale(&self, factor: f64, about: Point) -> Option<Rect> {
+        if !(factor.is_finite() && factor >= 0.0) {
+            return None;
+        }
+        // every i32 is exactly an f64, and 'as' turns anything too big into i64::MAX or MIN,
+        // which from_edges then refuses
+        let scale = |edge: i32, about: i32| {
+            let offset = f64::from(edge) - f64::from(about);
+            (f64::from(about) + offset * factor).round() as i64
+        };
+        Rect::from_edges(
+            scale(self.left(), about.x),
+            scale(self.top(), about.y),
+            scale(self.right(), about.x),
+            scale(self.bottom(), about.y),
+        )
+    }
+
+    // Moves every edge in by amount. A rectangle less than twice amount across shrinks to an empty
+    // one in its middle instead of turning inside out.
+    pub fn inset(&self, amount: u32) -> Rect {
+        // how far the left (or top) edge moves, and the new width (or height)
+        let shrink = |size: u32| {
+            if u64::from(amount) * 2 >= u64::from(size) {
+                (size / 2, 0)
+            } else {
+                (amount, size - 2 * amount)
+            }
+        };
+        let (dx, width) = shrink(self.width);
+        let (dy, height) = shrink(self.height);
+        // dx is at most half the width, so the new left edge is still left of the right edge
+        Rect {
+            origin: Point::new(self.left() + dx as i32, self.top() + dy as i32),
+            width,
+            height,
+        }
+    }
+
+    // Moves every edge out by amount, or None if that goes past the edge of i32 coordinates.
+    pub fn outset(&self, amount: u32) -> Option<Rect> {
+        let amount = i64::from(amount);
+        Rect::from_edges(
+            i64::from(self.left()) - amount,
+            i64::from(self.top()) - amount,
+            i64::from(self.right()) + amount,
+            i64::from(self.bottom()) + amount,
+        )
+    }
+
+    /* Splitting */
+    // Cuts the rectangle in two with a vertical line width from the left edge, giving the left
+    // part and the rest. Both together cover exactly what self did. A width of 0 or past the
+    // right edge gives an empty part on that side.
+    pub fn split_left(&self, width: u32) -> (Rect, Rect) {
+        let width = width.min(self.width);
+        let left = Rect { width, ..*self };
+        let rest = Rect {
+            origin: Point::new(left.right(), self.top()),
+            width: self.width - width,
+            height: self.height,
+        };
+        (left, rest)
+    }
+
+    // The same with a horizontal line height from the top, giving the top part and the rest.
+    pub fn split_top(&self, height: u32) -> (Rect, Rect) {
+        let height = height.min(self.height);
+        let top = Rect { height, ..*self };
+        let rest = Rect {
+            origin: Point::new(self.left(), top.bottom()),
+            width: self.width,
+            height: self.height - height,
+        };
+        (top, rest)
+    }
+}
+
+// 30x50 at (10, 20)
+impl fmt::Display for Rect {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}x{} at {}", self.width, self.height, self.origin)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: i32, y: i32, width: u32, height: u32) -> Rect {
+        Rect::new(x, y, width, height).unwrap()
+    }
+
+    // The biggest there is: every i32 coordinate, right and bottom edges at i32::MAX.
+    fn everything() -> Rect {
+        rect(i32::MIN, i32::MIN, u32::MAX, u32::MAX)
+    }
+
+    #[test]
+    fn edges_and_points() {
+        let r = rect(10, 20, 30, 50);
+        assert_eq!((r.left(), r.top(), r.right(), r.bottom()), (10, 20, 40, 70));
+        assert_eq!(r.area(), 1500);
+        assert_eq!(r.to_string(), "30x50 at (10, 20)");
+        assert!(r.contains_point(Point::new(10, 20)));
+        assert!(r.contains_point(Point::new(39, 69)));
+        assert!(!r.contains_point(Point::new(40, 20)));
+        assert!(!r.contains_point(Point::new(10, 70)));
+        assert_eq!(
+            Rect::from_corners(Point::new(40, 20), Point::new(10, 70)),
+            r
+        );
+        assert!(!rect(5, 5, 0, 0).contains_point(Point::new(5, 5)));
+    }
+
+    #[test]
+    fn shared_edges_touch_but_dont_intersect() {
+        let a = rect(0, 0, 10, 10);
+        for (other, touches, intersects) in [
+            // side by side, above and below, and corner to corner
+            (rect(10, 0, 10, 10), true, false),
+            (rect(-10, 0, 10, 10), true, false),
+            (rect(0, 10, 10, 10), true, false),
+            (rect(0, -5, 10, 5), true, false),
+            (rect(10, 10, 5, 5), true, false),
+            (rect(-5, -5, 5, 5), true, false),
+            // along part of an edge
+            (rect(10, 9, 5, 5), true, false),
+            // a gap of one
+            (rect(11, 0, 10, 10), false, false),
+            (rect(11, 11, 1, 1), false, false),
+            // overlapping by one, and inside
+            (rect(9, 9, 5, 5), false, true),
+            (rect(2, 2, 2, 2), false, true),
+            (a, false, true),
+            // empty ones, on the edge or lying right across
+            (rect(10, 0, 0, 10), false, false),
+            (rect(5, -5, 0, 20), false, false),
+        ] {
+            assert_eq!(a.touches(&other), touches, "{other}");
+            assert_eq!(other.touches(&a), touches, "{other}");
+            assert_eq!(a.intersects(&other), intersects, "{other}");
+            assert_eq!(other.intersects(&a), intersects, "{other}");
+            assert_eq!(a.intersection(&other).is_some(), intersects, "{other}");
+        }
+        assert_eq!(a.intersection(&rect(9, 9, 5, 5)), Some(rect(9, 9, 1, 1)));
+        assert_eq!(a.overlap_area(&rect(5, 5, 10, 10)), 25);
+        assert_eq!(a.overlap_area(&rect(10, 0, 10, 10)), 0);
+        assert_eq!(a.union(&rect(10, 10, 5, 5)), rect(0, 0, 15, 15));
+        // empty rectangles are left out of unions, wherever they are
+        assert_eq!(a.union(&rect(100, 100, 0, 5)), a);
+        assert_eq!(rect(100, 100, 0, 5).union(&a), a);
+    }
+
+    #[test]
+    fn containing() {
+        let a = rect(0, 0, 10, 10);
+        assert!(a.contains(&a));
+        assert!(a.contains(&rect(5, 0, 5, 10)));
+        assert!(!a.contains(&rect(5, 0, 6, 10)));
+        assert!(!a.contains(&rect(-1, 0, 5, 5)));
+        // an empty rectangle only has to be on or inside the edges
+        assert!(a.contains(&rect(10, 10, 0, 0)));
+        assert!(!a.contains(&rect(11, 10, 0, 0)));
+        assert!(everything().contains(&a));
+    }
+
+    #[test]
+    fn inset_past_half_the_size_leaves_an_empty_middle() {
+        let r = rect(0, 0, 10, 7);
+        assert_eq!(r.inset(0), r);
+        assert_eq!(r.inset(2), rect(2, 2, 6, 3));
+        // the height runs out first, at 3 of its 7
+        assert_eq!(r.inset(4), rect(4, 3, 2, 0));
+        assert_eq!(r.inset(5), rect(5, 3, 0, 0));
+        assert_eq!(r.inset(1000), rect(5, 3, 0, 0));
+        assert_eq!(r.inset(u32::MAX), rect(5, 3, 0, 0));
+        assert_eq!(everything().inset(u32::MAX), rect(-1, -1, 0, 0));
+        assert_eq!(
+            everything().inset(1),
+            rect(i32::MIN + 1, i32::MIN + 1, u32::MAX - 2, u32::MAX - 2)
+        );
+    }
+
+    #[test]
+    fn making_one_at_the_limits() {
+        let all = everything();
+        assert_eq!((all.right(), all.bottom()), (i32::MAX, i32::MAX));
+        assert_eq!(all.area(), u64::from(u32::MAX) * u64::from(u32::MAX));
+        assert_eq!(
+            Rect::from_corners(
+                Point::new(i32::MAX, i32::MAX),
+                Point::new(i32::MIN, i32::MIN)
+            ),
+            all
+        );
+        assert_eq!(Rect::new(0, 0, u32::MAX, 1), None);
+        assert_eq!(Rect::new(i32::MAX, 0, 1, 1), None);
+        assert_eq!(Rect::new(0, i32::MAX, 1, 1), None);
+        assert!(Rect::new(i32::MAX, i32::MAX, 0, 0).is_some());
+    }
+
+    #[test]
+    fn translating_at_the_limits() {
+        let all = everything();
+        assert_eq!(all.translate(0, 0), Some(all));
+        assert_eq!(all.translate(1, 0), None);
+        assert_eq!(all.translate(0, -1), None);
+        let a = rect(0, 0, 10, 10);
+        assert_eq!(a.translate(i32::MIN, 0), Some(rect(i32::MIN, 0, 10, 10)));
+        assert_eq!(a.translate(i32::MAX, 0), None);
+        assert_eq!(
+            a.translate(i32::MAX - 10, i32::MAX - 10),
+            Some(rect(i32::MAX - 10, i32::MAX - 10, 10, 10))
+        );
+        assert_eq!(rect(-1, 0, 1, 1).translate(i32::MIN, 0), None);
+        assert_eq!(rect(i32::MIN, 0, 1, 1).translate(-1, 0), None);
+    }
+
+    #[test]
+    fn outset_at_the_limits() {
+        let all = everything();
+        assert_eq!(all.outset(0), Some(all));
+        assert_eq!(all.outset(1), None);
+        assert_eq!(rect(0, 0, 1, 1).outset(u32::MAX), None);
+        assert_eq!(rect(0, 0, 10, 10).outset(5), Some(rect(-5, -5, 20, 20)));
+        let grown = rect(-5, -5, 10, 10).outset(i32::MAX as u32 - 5).unwrap();
+        assert_eq!((grown.left(), grown.right()), (i32::MIN + 1, i32::MAX));
+        assert_eq!(rect(-5, -5, 10, 10).outset(i32::MAX as u32 - 4), None);
+    }
+
+    #[test]
+    fn scaling() {
+        let a = rect(10, 10, 10, 10);
+        assert_eq!(a.scale(1.0, Point::new(-7, 3)), Some(a));
+        assert_eq!(a.scale(2.0, Point::new(15, 15)), Some(rect(5, 5, 20, 20)));
+        assert_eq!(a.scale(2.0, Point::new(10, 10)), Some(rect(10, 10, 20, 20)));
+        assert_eq!(a.scale(0.0, Point::new(15, 15)), Some(rect(15, 15, 0, 0)));
+        // the edges are rounded, 1.5 up to 2
+        assert_eq!(
+            rect(0, 0, 3, 3).scale(0.5, Point::default()),
+            Some(rect(0, 0, 2, 2))
+        );
+        for factor in [-1.0, -0.0001, f64::NAN, f64::INFINITY, f64::NEG_INFINITY] {
+            assert_eq!(a.scale(factor, Point::default()), None, "{factor}");
+        }
+        // rounding the edges, not the sizes, keeps neighbours touching
+        let (left, right) = rect(0, 0, 20, 10).split_left(10);
+        let (left, right) = (
+            left.scale(0.33, Point::default()).unwrap(),
+            right.scale(0.33, Point::default()).unwrap(),
+        );
+        assert_eq!(left.right(), right.left());
+        assert!(left.touches(&right));
+    }
+
+    #[test]
+    fn scaling_at_the_limits() {
+        let all = everything();
+        assert_eq!(all.scale(1.0, Point::default()), Some(all));
+        assert_eq!(all.scale(2.0, Point::default()), None);
+        assert_eq!(all.scale(f64::MAX, Point::default()), None);
+        assert_eq!(
+            all.scale(0.5, Point::default()),
+            Some(rect(-(1 << 30), -(1 << 30), 1 << 31, 1 << 31))
+        );
+        let corner = rect(i32::MAX - 1, i32::MAX - 1, 1, 1);
+        assert_eq!(
+            corner.scale(1.0, Point::new(i32::MIN, i32::MIN)),
+            Some(corner)
+        );
+        assert_eq!(corner.scale(2.0, corner.origin()), None);
+        assert_eq!(rect(0, 0, 10, 10).scale(f64::MAX, Point::default()), None);
+        assert_eq!(
+            rect(0, 0, 10, 10).scale(f64::MIN_POSITIVE, Point::default()),
+            Some(rect(0, 0, 0, 0))
+        );
+    }
+
+    #[test]
+    fn splitting_at_zero_and_past_the_edge() {
+        let r = rect(10, 20, 30, 50);
+        assert_eq!(r.split_left(0), (rect(10, 20, 0, 50), r));
+        assert_eq!(
+            r.split_left(12),
+            (rect(10, 20, 12, 50), rect(22, 20, 18, 50))
+        );
+        assert_eq!(r.split_left(30), (r, rect(40, 20, 0, 50)));
+        assert_eq!(r.split_left(31), (r, rect(40, 20, 0, 50)));
+        assert_eq!(r.split_left(u32::MAX), (r, rect(40, 20, 0, 50)));
+        assert_eq!(r.split_top(0), (rect(10, 20, 30, 0), r));
+        assert_eq!(
+            r.split_top(20),
+            (rect(10, 20, 30, 20), rect(10, 40, 30, 30))
+        );
+        assert_eq!(r.split_top(51), (r, rect(10, 70, 30, 0)));
+        for cut in [0, 1, 29, 30, 31] {
+            let (left, rest) = r.split_left(cut);
+            assert_eq!(left.area() + rest.area(), r.area());
+            assert_eq!(left.union(&rest), r);
+            assert!(!left.intersects(&rest));
+        }
+        let all = everything();
+        assert_eq!(
+            all.split_left(u32::MAX),
+            (all, rect(i32::MAX, i32::MIN, 0, u32::MAX))
+        );
+        assert_eq!(
+            all.split_top(u32::MAX - 1).1,
+            rect(i32::MIN, i32::MAX - 1, u32::MAX, 1)
+        );
+    }
+}
@@ -0,0 +1,2 @@
+// Rectangles that know where they are, for main.rs's Rectangle that only knows its size.
+pub mod geometry;
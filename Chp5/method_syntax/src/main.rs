@@ -6,22 +6,36 @@
 */
 /* Here I will change the area function from the last subchapter, and make it into a method
 * defined on the Rectangle struct */
+use field_display::FieldDisplay;
+use method_syntax::geometry::{Point, Rect};
+use struct_diff::{Diff, FieldValue, PatchError, Value};
 
-#[derive(Debug)]
+// FieldDisplay (from ../field_display) gives Rectangle a Display that lists its fields, on one
+// line with {} or one per line with {:#}, next to the {:?} and {:#?} that Debug gives it.
+#[derive(Debug, Clone, PartialEq, FieldDisplay)]
 struct Rectangle {
     width: u32,
     height: u32,
 }
 
 impl Rectangle {
-    fn area(&self) -> u32 {
-        self.width * self.height
+    // u64, because the area of a big enough rectangle doesn't fit in a u32: 65536 * 65536 is
+    // already u32::MAX + 1, which would panic in a debug build and wrap round to 0 in a release one
+    fn area(&self) -> u64 {
+        u64::from(self.width) * u64::from(self.height)
     }
     // Methods can have the same names as fields, and are differentiated by the parentheses
     fn width(&self) -> bool {
         self.width > 0u32
     }
+    // Whether other fits inside self, either way round. It has to be strictly smaller, so a
+    // rectangle doesn't fit in one the same size as it, except that a rectangle with no width or
+    // height takes up no room and fits in anything. (geometry.rs has rectangles with positions;
+    // Rect::contains is whether one is inside another where they are now.)
     fn can_fit(&self, other: &Rectangle) -> bool {
+        if other.width == 0 || other.height == 0 {
+            return true;
+        }
         (self.width > other.width && self.height > other.height)
             || (self.width > other.height && self.height > other.width)
     }
@@ -39,8 +53,42 @@ impl Rectangle {
             height: size,
         }
     }
+    // A Rect this size with its top left corner at x, y (None if it wouldn't fit in i32
+    // coordinates)
+    fn at(&self, x: i32, y: i32) -> Option<Rect> {
+        Rect::new(x, y, self.width, self.height)
+    }
     // to call this type of function we use "::"; 'let sq = Rectangle::square(3);'
 } // each struct is allowed to have multiple impl blocks, you are not limited to just one.
+
+// Diff (from ../struct_diff) lists the fields that are different between two rectangles. User
+// gets it from struct_diff::diff_fields!(User { ... }); here it's written out by hand, which is
+// all that macro does: name the fields, and get and set them by name.
+impl Diff for Rectangle {
+    const FIELDS: &'static [&'static str] = &["width", "height"];
+
+    fn field(&self, name: &str) -> Option<Value> {
+        match name {
+            "width" => Some(self.width.to_value()),
+            "height" => Some(self.height.to_value()),
+            _ => None,
+        }
+    }
+
+    fn set_field(&mut self, name: &str, value: &Value) -> Result<(), PatchError> {
+        let field = match name {
+            "width" => &mut self.width,
+            "height" => &mut self.height,
+            _ => return Err(PatchError::NoSuchField(name.to_string())),
+        };
+        // u32::from_value is None for text, or a number that doesn't fit in a u32
+        *field = u32::from_value(value).ok_or_else(|| PatchError::WrongType {
+            field: name.to_string(),
+            value: value.clone(),
+        })?;
+        Ok(())
+    }
+}
 fn main() {
     let rect1 = Rectangle {
         width: 30,
@@ -72,5 +120,106 @@ fn main() {
     println!("Can rect4 fit in rect1? {}", &rect1.can_fit(&rect4));
     let square1 = Rectangle::square(20u32);
     println!("square1 =\n{:#?}", &square1);
+    println!("square1 = {square1}, or\n{square1:#}");
     println!("can rect1 fit in square1? {}", &square1.can_fit(&rect1));
+    // what it takes to turn square1 into rect1, and doing it to a copy of square1
+    let patch = square1.diff(&rect1);
+    println!("square1 -> rect1:\n{patch}\n{}", patch.to_json());
+    let mut resized = square1.clone();
+    match resized.apply(&patch) {
+        Ok(()) => println!("resized square1 is rect1: {}", resized == rect1),
+        Err(err) => println!("{err}"),
+    }
+    positioned_rectangles(&rect1, &square1);
+}
+
+// Rect (in geometry.rs) is a Rectangle with a position, so two of them can overlap, touch or be
+// inside one another.
+fn positioned_rectangles(rect1: &Rectangle, square1: &Rectangle) {
+    let (Some(a), Some(b)) = (rect1.at(0, 0), square1.at(20, 40)) else {
+        return;
+    };
+    println!("a is {a}, b is {b}");
+    println!(
+        "a contains (29, 49)? {}, (30, 50)? {}",
+        a.contains_point(Point::new(29, 49)),
+        a.contains_point(Point::new(30, 50))
+    );
+    match a.intersection(&b) {
+        Some(overlap) => println!(
+            "they overlap in {overlap}, {} square pixels",
+            overlap.area()
+        ),
+        None => println!("they don't overlap"),
+    }
+    println!("both fit in {}", a.union(&b));
+    // side by side: they touch, but share no area
+    if let Some(c) = a.translate(30, 0) {
+        println!(
+            "{c} touches a? {}, overlaps it? {} ({} square pixels)",
+            c.touches(&a),
+            c.intersects(&a),
+            c.overlap_area(&a)
+        );
+    }
+    let (left, right) = a.split_left(10);
+    let (top, bottom) = right.split_top(20);
+    println!("a split: {left} | {top} / {bottom}");
+    println!("a inset by 5: {}, by 100: {}", a.inset(5), a.inset(100));
+    if let (Some(outset), Some(doubled)) = (a.outset(5), a.scale(2.0, Point::new(15, 25))) {
+        println!("a outset by 5: {outset}, doubled about its middle: {doubled}");
+    }
+    // the edges of the coordinates: these would overflow, so they're refused instead
+    let huge = Rectangle::square(u32::MAX);
+    println!(
+        "{} square pixels; at (0, 0)? {:?}; at (i32::MIN, i32::MIN)? {:?}",
+        huge.area(),
+        huge.at(0, 0),
+        huge.at(i32::MIN, i32::MIN).map(|rect| rect.to_string())
+    );
+    println!("moved right by i32::MAX? {:?}", a.translate(i32::MAX, 0));
+    let dot = Rectangle::square(0);
+    println!(
+        "a zero-size rectangle fits in rect1: {}, but covers no points, not even its origin: {}",
+        rect1.can_fit(&dot),
+        dot.at(5, 5)
+            .is_some_and(|dot| dot.contains_point(Point::new(5, 5)))
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn area_doesnt_overflow() {
+        assert_eq!(Rectangle::square(3).area(), 9);
+        assert_eq!(Rectangle::square(65536).area(), 1 << 32);
+        assert_eq!(
+            Rectangle::square(u32::MAX).area(),
+            18_446_744_065_119_617_025
+        );
+        let wide = Rectangle {
+            width: u32::MAX,
+            height: 2,
+        };
+        assert_eq!(wide.area(), 2 * u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn fitting() {
+        let big = Rectangle {
+            width: 30,
+            height: 50,
+        };
+        let turned = Rectangle {
+            width: 40,
+            height: 10,
+        };
+        assert!(big.can_fit(&turned));
+        assert!(!big.can_fit(&big));
+        assert!(big.can_fit(&Rectangle::square(0)));
+        assert!(Rectangle::square(0).can_fit(&Rectangle::square(0)));
+        assert!(!Rectangle::square(0).can_fit(&Rectangle::square(1)));
+    }
 }
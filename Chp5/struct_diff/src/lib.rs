@@ -0,0 +1,948 @@
+// The struct update syntax, User { email: ..., ..user1 }, makes a copy of a struct with a few
+// fields changed, but afterwards nothing says which ones. The Diff trait does: a.diff(&b) is a
+// Patch, the list of fields that are different with the value each one had in a and has in b.
+// The patch can be applied to another instance, printed as text for people, or written out as
+// JSON for a log of who changed what, and read back in from there.
+//
+//     let user2 = User { email: String::from("another@example.com"), ..user1.clone() };
+//     let patch = user1.diff(&user2);
+//     println!("{patch}");            // email: "fooman@gmail.com" -> "another@example.com"
+//     println!("{}", patch.to_json()); // [{"field":"email","old":"fooman@gmail.com",...}]
+//     user1.apply(&patch)?;           // user1 == user2 now
+//
+// A struct gets Diff from one line naming the fields to compare,
+//     struct_diff::diff_fields!(User { active, username, email, sign_in_count });
+// or by implementing the three required items by hand, which is what the macro writes anyway.
+// Every field listed needs to implement FieldValue, which bool, the integers, String and Option
+// of any of those do.
+use std::fmt;
+
+/* Values */
+// A field's value with its type forgotten, so fields of different types can go in the same
+// Patch. Every integer type fits in an i128, so a u64 and an i32 that are equal compare equal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    Integer(i128),
+    Text(String),
+}
+
+impl Value {
+    fn kind(&self) -> &'static str {
+        match self {
+            Value::Null => "null",
+            Value::Bool(_) => "a bool",
+            Value::Integer(_) => "a number",
+            Value::Text(_) => "text",
+        }
+    }
+}
+
+// Text is quoted, like {:?} does, so "" and a string with spaces in it are both readable.
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "null"),
+            Value::Bool(value) => write!(f, "{value}"),
+            Value::Integer(value) => write!(f, "{value}"),
+            Value::Text(value) => write!(f, "{value:?}"),
+        }
+    }
+}
+
+// The types a field can have. from_value gives None when the value is the wrong kind, or a number
+// that doesn't fit.
+pub trait FieldValue: Sized {
+    fn to_value(&self) -> Value;
+    fn from_value(value: &Value) -> Option<Self>;
+}
+
+impl FieldValue for bool {
+    fn to_value(&self) -> Value {
+        Value::Bool(*self)
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl FieldValue for String {
+    fn to_value(&self) -> Value {
+        Value::Text(self.clone())
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Text(value) => Some(value.clone()),
+            _ => None,
+        }
+    }
+}
+
+// None is null, and Some(x) is whatever x is.
+impl<T: FieldValue> FieldValue for Option<T> {
+    fn to_value(&self) -> Value {
+        match self {
+            Some(value) => value.to_value(),
+            None => Value::Null,
+        }
+    }
+
+    fn from_value(value: &Value) -> Option<Self> {
+        match value {
+            Value::Null => Some(None),
+            value => T::from_value(value).map(Some),
+        }
+    }
+}
+
+macro_rules! integer_field_values {
+    ($($int:ty),*) => {
+        $(
+            impl FieldValue for $int {
+                fn to_value(&self) -> Value {
+                    Value::Integer(*self as i128)
+                }
+
+                fn from_value(value: &Value) -> Option<Self> {
+                    match value {
+                        Value::Integer(value) => <$int>::try_from(*value).ok(),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_field_values!(u8, u16, u32, u64, usize, i8, i16, i32, i64, isize);
+
+/* The trait */
+pub trait Diff {
+    // The names of the fields that are compared, in the order changes are listed.
+    const FIELDS: &'static [&'static str];
+
+    // The value of a field, or None if there isn't a field called name.
+    fn field(&self, name: &str) -> Option<Value>;
+
+    // Sets a field, or says why it can't: there's no such field, or value is the wrong type for it.
+    fn set_field(&mut self, name: &str, value: &Value) -> Result<(), PatchError>;
+
+    // What would have to change to turn self into other.
+    fn diff(&self, other: &Self) -> Patch {
+        let mut patch = Patch::new();
+        for &name in Self::FIELDS {
+            if let (Some(old), Some(new)) = (self.field(name), other.field(name)) {
+                if old != new {
+                    patch.push(name, old, new);
+                }
+            }
+        }
+        patch
+    }
+
+    // Makes the changes in patch, as long as every field it changes still has the old value the
+    // patch expects. If one of them has been changed since, or anything else is wrong, that's
+    // an error and nothing is changed.
+    fn apply(&mut self, patch: &Patch) -> Result<(), PatchError>
+    where
+        Self: Clone,
+    {
+        patch.check_repeats()?;
+        for change in patch.iter() {
+            let found = self
+                .field(&change.field)
+                .ok_or_else(|| PatchError::NoSuchField(change.field.clone()))?;
+            if found != change.old {
+                return Err(PatchError::Conflict {
+                    field: change.field.clone(),
+                    expected: change.old.clone(),
+                    found,
+                });
+            }
+        }
+        self.overwrite(patch)
+    }
+
+    // Sets every field in patch to its new value, whatever the old one was. All or nothing, too.
+    fn overwrite(&mut self, patch: &Patch) -> Result<(), PatchError>
+    where
+        Self: Clone,
+    {
+        patch.check_repeats()?;
+        // changed on a copy, so an error halfway through leaves self as it was
+        let mut patched = self.clone();
+        for change in patch.iter() {
+            patched.set_field(&change.field, &change.new)?;
+        }
+        *self = patched;
+        Ok(())
+    }
+}
+
+// Writes the Diff impl for a struct with named fields, from a list of the fields to compare:
+//     diff_fields!(Rectangle { width, height });
+// Fields that aren't listed are left out of diffs, and patches that mention them are refused.
+#[macro_export]
+macro_rules! diff_fields {
+    ($type:ty { $($field:ident),* $(,)? }) => {
+        impl $crate::Diff for $type {
+            const FIELDS: &'static [&'static str] = &[$(stringify!($field)),*];
+
+            fn field(&self, name: &str) -> Option<$crate::Value> {
+                match name {
+                    $(stringify!($field) => Some($crate::FieldValue::to_value(&self.$field)),)*
+                    _ => None,
+                }
+            }
+
+            fn set_field(
+                &mut self,
+                name: &str,
+                value: &$crate::Value,
+            ) -> Result<(), $crate::PatchError> {
+                match name {
+                    $(stringify!($field) => {
+                        self.$field = $crate::FieldValue::from_value(value).ok_or_else(|| {
+                            $crate::PatchError::WrongType {
+                                field: name.to_string(),
+                                value: value.clone(),
+                            }
+                        })?;
+                        Ok(())
+                    })*
+                    _ => Err($crate::PatchError::NoSuchField(name.to_string())),
+                }
+            }
+        }
+    };
+}
+
+/* Patches */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+// field: old -> new
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} -> {}", self.field, self.old, self.new)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Patch {
+    changes: Vec<Change>,
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Patch::default()
+    }
+
+    pub fn push(&mut self, field: &str, old: Value, new: Value) {
+        self.changes.push(Change {
+            field: field.to_string(),
+            old,
+            new,
+        });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Change> {
+        self.changes.iter()
+    }
+
+    pub fn get(&self, field: &str) -> Option<&Change> {
+        self.changes.iter().find(|change| change.field == field)
+    }
+
+    // A patch that changes the same field twice can't be right: which new value is meant, and
+    // which old one should the field have? Applying one is refused rather than letting the last
+    // change win.
+    fn check_repeats(&self) -> Result<(), PatchError> {
+        for (i, change) in self.changes.iter().enumerate() {
+            if self.changes[..i]
+                .iter()
+                .any(|earlier| earlier.field == change.field)
+            {
+                return Err(PatchError::RepeatedField(change.field.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    // The patch that undoes this one: every change with old and new swapped.
+    pub fn reversed(&self) -> Patch {
+        let changes = self
+            .changes
+            .iter()
+            .map(|change| Change {
+                field: change.field.clone(),
+                old: change.new.clone(),
+                new: change.old.clone(),
+            })
+            .collect();
+        Patch { changes }
+    }
+
+    // [{"field":"email","old":"fooman@gmail.com","new":"another@example.com"}], all on one line, so
+    // a log can keep one patch per line.
+    pub fn to_json(&self) -> String {
+        let mut json = String::from("[");
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push_str("{\"field\":");
+            write_json_string(&mut json, &change.field);
+            json.push_str(",\"old\":");
+            write_json_value(&mut json, &change.old);
+            json.push_str(",\"new\":");
+            write_json_value(&mut json, &change.new);
+            json.push('}');
+        }
+        json.push(']');
+        json
+    }
+
+    // Reads back what to_json writes. Any JSON with the same shape is accepted, spaces and all,
+    // but the numbers have to be whole.
+    pub fn from_json(json: &str) -> Result<Patch, PatchError> {
+        let mut parser = Parser { json, at: 0 };
+        let mut patch = Patch::new();
+        parser.expect(b'[')?;
+        if parser.peek() == Some(b']') {
+            parser.at += 1;
+        } else {
+            loop {
+                parser.peek();
+                let start = parser.at;
+                let change = parser.change()?;
+                if patch.get(&change.field).is_some() {
+                    return parser
+                        .error(start, "the field is already changed earlier in the patch");
+                }
+                patch.changes.push(change);
+                match parser.next() {
+                    Some(b',') => continue,
+                    Some(b']') => break,
+                    _ => return parser.error(parser.at.saturating_sub(1), "expected , or ]"),
+                }
+            }
+        }
+        if parser.peek().is_some() {
+            return parser.error(parser.at, "unexpected text after the patch");
+        }
+        Ok(patch)
+    }
+}
+
+// One change per line, or "no changes".
+impl fmt::Display for Patch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.changes.is_empty() {
+            return write!(f, "no changes");
+        }
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a Patch {
+    type Item = &'a Change;
+    type IntoIter = std::slice::Iter<'a, Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+/* JSON */
+fn write_json_value(json: &mut String, value: &Value) {
+    match value {
+        Value::Null => json.push_str("null"),
+        Value::Bool(value) => json.push_str(if *value { "true" } else { "false" }),
+        Value::Integer(value) => json.push_str(&value.to_string()),
+        Value::Text(value) => write_json_string(json, value),
+    }
+}
+
+fn write_json_string(json: &mut String, text: &str) {
+    json.push('"');
+    for c in text.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if c < ' ' => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+// Just enough of a JSON parser for patches: arrays of objects holding strings, whole numbers,
+// true, false and null. at is the byte position in json it has got to.
+struct Parser<'a> {
+    json: &'a str,
+    at: usize,
+}
+
+impl Parser<'_> {
+    fn error<T>(&self, at: usize, message: &'static str) -> Result<T, PatchError> {
+        Err(PatchError::BadJson { at, message })
+    }
+
+    // The next byte that isn't whitespace, without moving past it.
+    fn peek(&mut self) -> Option<u8> {
+        let bytes = self.json.as_bytes();
+        while self.at < bytes.len() && matches!(bytes[self.at], b' ' | b'\t' | b'\n' | b'\r') {
+            self.at += 1;
+        }
+        bytes.get(self.at).copied()
+    }
+
+    fn next(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.at += 1;
+        Some(byte)
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), PatchError> {
+        let message = match byte {
+            b'[' => "expected [",
+            b'{' => "expected {",
+            b':' => "expected :",
+            _ => "expected \"",
+        };
+        match self.peek() {
+            Some(next) if next == byte => {
+                self.at += 1;
+                Ok(())
+            }
+            _ => self.error(self.at, message),
+        }
+    }
+
+    // {"field": ..., "old": ..., "new": ...}, in any order
+    fn change(&mut self) -> Result<Change, PatchError> {
+        let start = self.at;
+        self.expect(b'{')?;
+        let (mut field, mut old, mut new) = (None, None, None);
+        loop {
+            self.peek();
+            let key_at = self.at;
+            let key = self.string()?;
+            self.expect(b':')?;
+            self.peek();
+            let value_at = self.at;
+            let value = self.value()?;
+            let slot = match key.as_str() {
+                "field" => &mut field,
+                "old" => &mut old,
+                "new" => &mut new,
+                _ => return self.error(key_at, "expected field, old or new"),
+            };
+            if slot.is_some() {
+                return self.error(key_at, "repeated key");
+            }
+            if key == "field" && !matches!(value, Value::Text(_)) {
+                return self.error(value_at, "field should be a string");
+            }
+            *slot = Some(value);
+            match self.next() {
+                Some(b',') => continue,
+                Some(b'}') => break,
+                _ => return self.error(self.at.saturating_sub(1), "expected , or }"),
+            }
+        }
+        match (field, old, new) {
+            (Some(Value::Text(field)), Some(old), Some(new)) => Ok(Change { field, old, new }),
+            _ => self.error(start, "a change needs field, old and new"),
+        }
+    }
+
+    fn value(&mut self) -> Result<Value, PatchError> {
+        let start = self.at;
+        match self.peek() {
+            Some(b'"') => self.string().map(Value::Text),
+            Some(b'-' | b'0'..=b'9') => self.integer(),
+            Some(_) => {
+                let rest = &self.json[self.at..];
+                for (word, value) in [
+                    ("null", Value::Null),
+                    ("true", Value::Bool(true)),
+                    ("false", Value::Bool(false)),
+                ] {
+                    if rest.starts_with(word) {
+                        self.at += word.len();
+                        return Ok(value);
+                    }
+                }
+                self.error(start, "expected a string, number, true, false or null")
+            }
+            None => self.error(start, "expected a value"),
+        }
+    }
+
+    fn integer(&mut self) -> Result<Value, PatchError> {
+        let start = self.at;
+        let bytes = self.json.as_bytes();
+        let mut end = start;
+        if bytes[end] == b'-' {
+            end += 1;
+        }
+        while end < bytes.len() && bytes[end].is_ascii_digit() {
+            end += 1;
+        }
+        if matches!(bytes.get(end), Some(b'.' | b'e' | b'E')) {
+            return self.error(start, "only whole numbers can be fields");
+        }
+        let digits = &self.json[start..end];
+        let unsigned = digits.strip_prefix('-').unwrap_or(digits);
+        // JSON doesn't allow 007
+        if unsigned.is_empty() || unsigned.len() > 1 && unsigned.starts_with('0') {
+            return self.error(start, "bad number");
+        }
+        let number = digits
+            .parse::<i128>()
+            .or_else(|_| self.error(start, "number too big"))?;
+        self.at = end;
+        Ok(Value::Integer(number))
+    }
+
+    fn string(&mut self) -> Result<String, PatchError> {
+        self.expect(b'"')?;
+        let mut text = String::new();
+        let mut chars = self.json[self.at..].char_indices();
+        let start = self.at;
+        while let Some((i, c)) = chars.next() {
+            let at = start + i;
+            match c {
+                '"' => {
+                    self.at = at + 1;
+                    return Ok(text);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let Some(unit) = hex4(&mut chars) else {
+                                return self.error(at, "bad \\u escape");
+                            };
+                            match unit {
+                                // the first half of a character outside the BMP: the second half
+                                // has to come straight after, as another \u escape
+                                0xd800..=0xdbff => {
+                                    let low = match (chars.next(), chars.next()) {
+                                        (Some((_, '\\')), Some((_, 'u'))) => hex4(&mut chars),
+                                        _ => None,
+                                    };
+                                    match low {
+                                        Some(low @ 0xdc00..=0xdfff) => {
+                                            let c =
+                                                0x10000 + ((unit - 0xd800) << 10) + (low - 0xdc00);
+                                            char::from_u32(c).unwrap_or('\u{fffd}')
+                                        }
+                                        _ => return self.error(at, "unpaired surrogate"),
+                                    }
+                                }
+                                0xdc00..=0xdfff => return self.error(at, "unpaired surrogate"),
+                                unit => char::from_u32(unit).unwrap_or('\u{fffd}'),
+                            }
+                        }
+                        _ => return self.error(at, "bad escape"),
+                    };
+                    text.push(escaped);
+                }
+                c if c < ' ' => return self.error(at, "control character in a string"),
+                c => text.push(c),
+            }
+        }
+        self.error(self.json.len(), "unterminated string")
+    }
+}
+
+fn hex4(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let mut unit = 0;
+    for _ in 0..4 {
+        unit = unit * 16 + chars.next()?.1.to_digit(16)?;
+    }
+    Some(unit)
+}
+
+/* Errors */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatchError {
+    NoSuchField(String),
+    // the patch has more than one change to this field
+    RepeatedField(String),
+    WrongType {
+        field: String,
+        value: Value,
+    },
+    // the field doesn't have the value the patch was made from any more
+    Conflict {
+        field: String,
+        expected: Value,
+        found: Value,
+    },
+    // at is a byte position in the JSON
+    BadJson {
+        at: usize,
+        message: &'static str,
+    },
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PatchError::NoSuchField(field) => write!(f, "there's no field called {field}"),
+            PatchError::RepeatedField(field) => {
+                write!(f, "the patch changes {field} more than once")
+            }
+            PatchError::WrongType { field, value } => {
+                write!(f, "{field} can't be set to {}, {value}", value.kind())
+            }
+            PatchError::Conflict {
+                field,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{field} was expected to be {expected}, but it's been changed to {found}"
+            ),
+            PatchError::BadJson { at, message } => {
+                write!(f, "bad patch JSON at byte {at}: {message}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for PatchError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct User {
+        active: bool,
+        username: String,
+        nickname: Option<String>,
+        sign_in_count: u64,
+        // not listed, so not compared
+        notes: String,
+    }
+
+    crate::diff_fields!(User {
+        active,
+        username,
+        nickname,
+        sign_in_count,
+    });
+
+    fn fooman() -> User {
+        User {
+            active: true,
+            username: String::from("fooman"),
+            nickname: None,
+            sign_in_count: 1,
+            notes: String::new(),
+        }
+    }
+
+    fn text(text: &str) -> Value {
+        Value::Text(text.to_string())
+    }
+
+    fn bad_json(json: &str) -> (usize, &'static str) {
+        match Patch::from_json(json) {
+            Err(PatchError::BadJson { at, message }) => (at, message),
+            other => panic!("{json} gave {other:?}"),
+        }
+    }
+
+    #[test]
+    fn diff_lists_the_changed_fields_in_order() {
+        let before = fooman();
+        let after = User {
+            sign_in_count: 2,
+            nickname: Some(String::from("foo")),
+            notes: String::from("unlisted fields don't count"),
+            ..before.clone()
+        };
+        let patch = before.diff(&after);
+        assert_eq!(
+            patch.to_string(),
+            "nickname: null -> \"foo\"\nsign_in_count: 1 -> 2"
+        );
+        assert!(before.diff(&before).is_empty());
+        assert_eq!(Patch::new().to_string(), "no changes");
+
+        let mut patched = before.clone();
+        patched.apply(&patch).unwrap();
+        assert_eq!(patched.diff(&after), Patch::new());
+        patched.apply(&patch.reversed()).unwrap();
+        assert_eq!(patched, before);
+    }
+
+    #[test]
+    fn json_round_trips() {
+        let mut patch = Patch::new();
+        patch.push("null", Value::Null, Value::Bool(false));
+        patch.push("bool", Value::Bool(true), Value::Bool(false));
+        patch.push("min", Value::Integer(i128::MIN), Value::Integer(0));
+        patch.push("max", Value::Integer(i128::MAX), Value::Integer(-1));
+        patch.push("empty", text(""), text(" "));
+        patch.push(
+            "escapes",
+            text("\"quoted\" \\ back\nslash\r\t"),
+            text("\u{0}\u{1}\u{1f}\u{7f}"),
+        );
+        patch.push("unicode", text("bücher"), text("😀 \u{fffd} \u{ffff}"));
+        patch.push("a \"field\"", Value::Null, Value::Null);
+        let json = patch.to_json();
+        assert!(!json.contains('\n'));
+        assert_eq!(Patch::from_json(&json), Ok(patch.clone()));
+        assert_eq!(
+            Patch::from_json(&patch.reversed().to_json()),
+            Ok(patch.reversed())
+        );
+        assert_eq!(Patch::from_json("[]"), Ok(Patch::new()));
+        assert_eq!(Patch::new().to_json(), "[]");
+    }
+
+    #[test]
+    fn json_written_by_something_else() {
+        let patch = Patch::from_json(
+            " [ { \"new\" : -5 ,\n\"field\":\"x\",\t\"old\":0 } ,{\"field\":\"y\",\"old\":null,\"new\":true}]\n",
+        )
+        .unwrap();
+        assert_eq!(patch.to_string(), "x: 0 -> -5\ny: null -> true");
+
+        let strings = Patch::from_json(r#"[{"field":"s","old":"\/\b\fAé€","new":"😀𝄞"}]"#).unwrap();
+        let change = strings.get("s").unwrap();
+        assert_eq!(change.old, text("/\u{8}\u{c}Aé€"));
+        assert_eq!(change.new, text("😀𝄞"));
+    }
+
+    #[test]
+    fn lone_surrogates_are_refused() {
+        for string in [
+            r#""\ud83d""#,
+            r#""\ud83dx""#,
+            r#""\ud83d\n""#,
+            r#""\ud83dA""#,
+            r#""\ud83d\ud83d""#,
+            r#""\ude00""#,
+            r#""\ude00\ud83d""#,
+        ] {
+            let json = format!(r#"[{{"field":"s","old":null,"new":{string}}}]"#);
+            // the error points at the backslash the escape starts with
+            let at = json.find('\\').unwrap();
+            assert_eq!(bad_json(&json), (at, "unpaired surrogate"), "{string}");
+        }
+    }
+
+    #[test]
+    fn bad_json_says_where() {
+        let change = r#"{"field":"a","old":1,"new":2}"#;
+        for (json, at, message) in [
+            ("", 0, "expected ["),
+            ("  {}", 2, "expected ["),
+            ("[", 1, "expected {"),
+            ("[1]", 1, "expected {"),
+            ("[{}]", 2, "expected \""),
+            (r#"[{"field" 1}]"#, 10, "expected :"),
+            (r#"[{"size":1}]"#, 2, "expected field, old or new"),
+            (r#"[{"field":1}]"#, 10, "field should be a string"),
+            (r#"[{"old":1,"old":2}]"#, 10, "repeated key"),
+            (r#"[{"old":1 "new":2}]"#, 10, "expected , or }"),
+            (
+                r#"[{"field":"a","old":1}]"#,
+                1,
+                "a change needs field, old and new",
+            ),
+            (
+                r#"[{"field":"a","old":nul}]"#,
+                20,
+                "expected a string, number, true, false or null",
+            ),
+            (r#"[{"field":"a","old":"#, 20, "expected a value"),
+            (
+                r#"[{"field":"a","old":1.5}]"#,
+                20,
+                "only whole numbers can be fields",
+            ),
+            (
+                r#"[{"field":"a","old":1e3}]"#,
+                20,
+                "only whole numbers can be fields",
+            ),
+            (r#"[{"field":"a","old":007}]"#, 20, "bad number"),
+            (r#"[{"field":"a","old":-}]"#, 20, "bad number"),
+            (r#"[{"field":"a","old":"\x"}]"#, 21, "bad escape"),
+            (r#"[{"field":"a","old":"\u12g4"}]"#, 21, "bad \\u escape"),
+            (
+                "[{\"field\":\"a\",\"old\":\"tab\there\"}]",
+                24,
+                "control character in a string",
+            ),
+            (r#"[{"field":"a","old":"open"#, 25, "unterminated string"),
+        ] {
+            assert_eq!(bad_json(json), (at, message), "{json}");
+        }
+        let too_big = format!(r#"[{{"field":"a","old":{}0,"new":0}}]"#, i128::MAX);
+        assert_eq!(bad_json(&too_big), (20, "number too big"));
+
+        let json = format!("[{change} {change}]");
+        assert_eq!(bad_json(&json), (change.len() + 2, "expected , or ]"));
+        let json = format!("[{change}] x");
+        assert_eq!(
+            bad_json(&json),
+            (change.len() + 3, "unexpected text after the patch")
+        );
+    }
+
+    #[test]
+    fn json_naming_a_field_twice_is_refused() {
+        let first = r#"{"field":"a","old":1,"new":2}"#;
+        let again = r#"{"field":"a","old":2,"new":3}"#;
+        let json = format!("[{first}, {again}]");
+        assert_eq!(
+            bad_json(&json),
+            (
+                first.len() + 3,
+                "the field is already changed earlier in the patch"
+            )
+        );
+    }
+
+    #[test]
+    fn apply_refuses_conflicts_and_changes_nothing() {
+        let before = fooman();
+        let after = User {
+            active: false,
+            sign_in_count: 5,
+            ..before.clone()
+        };
+        let patch = before.diff(&after);
+
+        // someone else signed in since the patch was made
+        let mut current = User {
+            sign_in_count: 2,
+            ..before.clone()
+        };
+        let unchanged = current.clone();
+        assert_eq!(
+            current.apply(&patch),
+            Err(PatchError::Conflict {
+                field: String::from("sign_in_count"),
+                expected: Value::Integer(1),
+                found: Value::Integer(2),
+            })
+        );
+        assert_eq!(current, unchanged);
+        assert_eq!(
+            current.apply(&patch).unwrap_err().to_string(),
+            "sign_in_count was expected to be 1, but it's been changed to 2"
+        );
+
+        // overwrite doesn't care what the old values were
+        current.overwrite(&patch).unwrap();
+        assert_eq!(current, after);
+
+        // a change that's already been made is a conflict too
+        let mut done = after.clone();
+        assert!(matches!(
+            done.apply(&patch),
+            Err(PatchError::Conflict { .. })
+        ));
+        assert_eq!(done, after);
+    }
+
+    #[test]
+    fn apply_refuses_unknown_fields_and_wrong_types() {
+        let mut user = fooman();
+        let unchanged = user.clone();
+
+        let mut patch = Patch::new();
+        patch.push("active", Value::Bool(true), Value::Bool(false));
+        patch.push("notes", text(""), text("not listed"));
+        assert_eq!(
+            user.apply(&patch),
+            Err(PatchError::NoSuchField(String::from("notes")))
+        );
+        assert_eq!(user, unchanged);
+
+        // the first change would work, so this checks that it gets undone
+        let mut patch = Patch::new();
+        patch.push("active", Value::Bool(true), Value::Bool(false));
+        patch.push("sign_in_count", Value::Integer(1), Value::Integer(-1));
+        assert_eq!(
+            user.apply(&patch),
+            Err(PatchError::WrongType {
+                field: String::from("sign_in_count"),
+                value: Value::Integer(-1),
+            })
+        );
+        assert_eq!(user, unchanged);
+
+        let mut patch = Patch::new();
+        patch.push("username", text("fooman"), Value::Integer(7));
+        assert_eq!(
+            user.overwrite(&patch).unwrap_err().to_string(),
+            "username can't be set to a number, 7"
+        );
+        assert_eq!(user, unchanged);
+    }
+
+    #[test]
+    fn patches_changing_a_field_twice_are_refused() {
+        let mut user = fooman();
+        let unchanged = user.clone();
+        let mut patch = Patch::new();
+        patch.push("username", text("fooman"), text("barman"));
+        patch.push("active", Value::Bool(true), Value::Bool(false));
+        patch.push("username", text("fooman"), text("bazman"));
+        let repeated = PatchError::RepeatedField(String::from("username"));
+        assert_eq!(user.apply(&patch), Err(repeated.clone()));
+        assert_eq!(user.overwrite(&patch), Err(repeated.clone()));
+        assert_eq!(user, unchanged);
+        assert_eq!(
+            repeated.to_string(),
+            "the patch changes username more than once"
+        );
+    }
+}
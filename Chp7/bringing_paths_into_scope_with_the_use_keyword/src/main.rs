@@ -1,5 +1,7 @@
+use the_slice_type::prose;
+
 fn main() {
-    println!("\x1b[1;4;33mBringing Paths into Scope with the use Keyword\x1b[0m 
+    prose!("\x1b[1;4;33mBringing Paths into Scope with the use Keyword\x1b[0m 
 
     having to write out the paths to call functions can feel inconvenient and repetative. In the last subchapter,
     whether we chose the absolute or relative path to the add_to_waitlist function, every time we 
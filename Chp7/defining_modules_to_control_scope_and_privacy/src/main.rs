@@ -1,6 +1,8 @@
+use the_slice_type::prose;
+
 fn main() {
     println!("\x1b[1;4;33mDefining Modules to Control Scope and Privacy\x1b[0m");
-    println!(
+    prose!(
         "
     In this section, we'll talk about modules and other parts of the module system, namely paths, which
     allow you to name items; the use keyword that brings a path into scope; and the pub keyword to
@@ -8,7 +10,7 @@ fn main() {
 "
     );
     println!("\x1b[1;4;33mModules Cheat Sheet\x1b[0m");
-    println!(
+    prose!(
         "
     Before we get to the details of modules and paths, here we provide a quick reference on how
     modules, paths, the use keyword, and the pub keyword work in the compiler, and how most
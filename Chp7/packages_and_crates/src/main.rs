@@ -1,3 +1,5 @@
+use the_slice_type::prose;
+
 fn main() {
     let notes1 = r#"The first parts of the module system we'll cover are packages and crates.
     
@@ -51,5 +53,5 @@ library, both with the same name as the package. A package can have multiple bin
 placing files in the src/bin directory: each file will be a separate binary crate.
     "#;
 
-    println!("{}", notes1);
+    prose!("{}", notes1);
 }
@@ -1,5 +1,7 @@
+use the_slice_type::prose;
+
 fn main() {
-    println!("\x1b[1;4;33mPaths for Referring to an Item in the Module Tree\x1b[0m
+    prose!("\x1b[1;4;33mPaths for Referring to an Item in the Module Tree\x1b[0m
 
     To show Rust where to find an item in a module tree, we use a path in the same way we use a path
     when navigating a filesystem. To call a function, we need to know its path.
@@ -1,5 +1,7 @@
+use the_slice_type::prose;
+
 fn main() {
-    println!("\x1b[1;4;33mSeparating Modules into Different Files\x1b[0m 
+    prose!("\x1b[1;4;33mSeparating Modules into Different Files\x1b[0m 
 
     So far, all the examples in htis chapter defined multiple modules in one file. When modules get 
     large, you might want to move their definitions to a separate file to make the code easier to 
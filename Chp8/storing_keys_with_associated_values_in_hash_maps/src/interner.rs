@@ -0,0 +1,341 @@
+/* String interner */
+// The word count in main.rs gets away with HashMap<&str, i32> because the text outlives the map.
+// When it doesn't (lines read one at a time from a file, say), every key has to be an owned String,
+// and a program that keeps the words around in more than one place ends up cloning the same
+// String over and over: "the" once in the map, and once more every time it's stored anywhere else.
+//
+// An interner keeps one copy of each distinct string and hands out a Symbol for it. A Symbol is a
+// u32, so it's Copy, 4 bytes, and as cheap to hash and compare as a number; resolve() turns it back
+// into the &str. A Symbol only means something to the interner that made it.
+//
+// Interner keeps all its text in one String, one string after the other, with the start and end
+// of each Symbol's string in a Vec, so there's no allocation per string. Like HashMap, it hashes
+// with RandomState unless it's given another BuildHasher. SyncInterner is the version for sharing
+// between threads.
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::BuildHasher;
+use std::sync::{Arc, RwLock};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    // Symbols are numbered from 0 in the order their strings were first interned.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+fn symbol(index: usize) -> Symbol {
+    Symbol(u32::try_from(index).expect("more than u32::MAX interned strings"))
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Interner<S = RandomState> {
+    // every interned string, back to back
+    text: String,
+    // where each symbol's string is in text
+    spans: Vec<(u32, u32)>,
+    // The map can't hold &strs into text, since text moves when it grows, so it's keyed by hash
+    // instead. Strings whose hashes collide are chained through `next`.
+    by_hash: HashMap<u64, Symbol>,
+    next: Vec<Option<Symbol>>,
+    hasher: S,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner::default()
+    }
+}
+
+impl<S: BuildHasher> Interner<S> {
+    // An interner that hashes with hasher instead of RandomState.
+    pub fn with_hasher(hasher: S) -> Self {
+        Interner {
+            text: String::new(),
+            spans: Vec::new(),
+            by_hash: HashMap::new(),
+            next: Vec::new(),
+            hasher,
+        }
+    }
+
+    // The Symbol for text, adding it if it's new.
+    pub fn intern(&mut self, text: &str) -> Symbol {
+        let hash = self.hasher.hash_one(text);
+        if let Some(found) = self.find(hash, text) {
+            return found;
+        }
+        // everything is checked before text goes in, so a panic leaves the interner as it was
+        let too_big = "more than 4 GiB of interned text";
+        let start = u32::try_from(self.text.len()).expect(too_big);
+        let end = u32::try_from(self.text.len() + text.len()).expect(too_big);
+        let new = symbol(self.spans.len());
+        self.text.push_str(text);
+        self.spans.push((start, end));
+        // the new symbol goes to the front of its hash's chain
+        self.next.push(self.by_hash.insert(hash, new));
+        new
+    }
+
+    // The Symbol for text, if it has been interned.
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        self.find(self.hasher.hash_one(text), text)
+    }
+
+    fn find(&self, hash: u64, text: &str) -> Option<Symbol> {
+        let mut candidate = self.by_hash.get(&hash).copied();
+        while let Some(symbol) = candidate {
+            if self.resolve(symbol) == text {
+                return Some(symbol);
+            }
+            candidate = self.next[symbol.index()];
+        }
+        None
+    }
+
+    // The string behind a symbol. Panics if the symbol came from a different interner with more
+    // strings than this one.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        let (start, end) = self.spans[symbol.index()];
+        &self.text[start as usize..end as usize]
+    }
+
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    // Every symbol and its string, in the order they were interned.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        (0..self.len()).map(|i| (symbol(i), self.resolve(symbol(i))))
+    }
+
+    // Roughly how much heap memory the interner is using, in bytes: the text buffer, the spans and
+    // chains, and the hash table (which stores a control byte next to every slot).
+    pub fn heap_size(&self) -> usize {
+        self.text.capacity()
+            + self.spans.capacity() * std::mem::size_of::<(u32, u32)>()
+            + self.next.capacity() * std::mem::size_of::<Option<Symbol>>()
+            + self.by_hash.capacity() * (std::mem::size_of::<(u64, Symbol)>() + 1)
+    }
+}
+
+/* Sharing between threads */
+// Behind an RwLock, so looking up strings that are already there only needs a read lock and can
+// happen on several threads at once. The strings are kept as Arc<str>, shared between the map and
+// the list; resolve() hands out another Arc instead of a &str, which would only be valid while
+// the lock was held.
+#[derive(Debug, Default)]
+pub struct SyncInterner {
+    inner: RwLock<SyncInner>,
+}
+
+#[derive(Debug, Default)]
+struct SyncInner {
+    by_text: HashMap<Arc<str>, Symbol>,
+    strings: Vec<Arc<str>>,
+}
+
+impl SyncInterner {
+    pub fn new() -> Self {
+        SyncInterner::default()
+    }
+
+    pub fn intern(&self, text: &str) -> Symbol {
+        if let Some(found) = self.get(text) {
+            return found;
+        }
+        let mut inner = self.inner.write().expect("interner lock poisoned");
+        // another thread may have added it between the two locks
+        if let Some(&found) = inner.by_text.get(text) {
+            return found;
+        }
+        let shared: Arc<str> = Arc::from(text);
+        let new = symbol(inner.strings.len());
+        inner.strings.push(Arc::clone(&shared));
+        inner.by_text.insert(shared, new);
+        new
+    }
+
+    pub fn get(&self, text: &str) -> Option<Symbol> {
+        let inner = self.inner.read().expect("interner lock poisoned");
+        inner.by_text.get(text).copied()
+    }
+
+    pub fn resolve(&self, symbol: Symbol) -> Arc<str> {
+        let inner = self.inner.read().expect("interner lock poisoned");
+        Arc::clone(&inner.strings[symbol.index()])
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner
+            .read()
+            .expect("interner lock poisoned")
+            .strings
+            .len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A single-threaded Interner with the same strings under the same symbols, for when the
+    // threads are done.
+    pub fn to_interner(&self) -> Interner {
+        let inner = self.inner.read().expect("interner lock poisoned");
+        let mut interner = Interner::new();
+        for text in &inner.strings {
+            interner.intern(text);
+        }
+        interner
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::hash::Hasher;
+
+    #[test]
+    fn the_same_string_is_the_same_symbol() {
+        let mut interner = Interner::new();
+        let the = interner.intern("the");
+        let cat = interner.intern("cat");
+        assert_eq!(interner.intern("the"), the);
+        assert_eq!(interner.intern(&String::from("cat")), cat);
+        assert_ne!(the, cat);
+        assert_eq!((the.index(), cat.index()), (0, 1));
+        assert_eq!(the.to_string(), "#0");
+        assert_eq!(interner.len(), 2);
+        assert_eq!(interner.resolve(cat), "cat");
+        // the empty string is a string like any other
+        let empty = interner.intern("");
+        assert_eq!(interner.intern(""), empty);
+        assert_eq!(interner.resolve(empty), "");
+        // and case matters
+        assert_ne!(interner.intern("The"), the);
+        let all: Vec<(Symbol, &str)> = interner.iter().collect();
+        assert_eq!(
+            all,
+            [(the, "the"), (cat, "cat"), (empty, ""), (Symbol(3), "The")]
+        );
+    }
+
+    #[test]
+    fn get_doesnt_add() {
+        let mut interner = Interner::new();
+        assert_eq!(interner.get("the"), None);
+        assert!(interner.is_empty());
+        let the = interner.intern("the");
+        assert_eq!(interner.get("the"), Some(the));
+        // prefixes and extensions of an interned string are different strings
+        assert_eq!(interner.get("th"), None);
+        assert_eq!(interner.get("then"), None);
+        assert_eq!(interner.len(), 1);
+    }
+
+    // Hashes everything to the same number, so every string lands in the same chain.
+    #[derive(Debug, Clone, Default)]
+    struct Collide;
+
+    impl BuildHasher for Collide {
+        type Hasher = Constant;
+
+        fn build_hasher(&self) -> Constant {
+            Constant
+        }
+    }
+
+    struct Constant;
+
+    impl Hasher for Constant {
+        fn finish(&self) -> u64 {
+            42
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    #[test]
+    fn colliding_hashes_are_chained() {
+        let mut interner = Interner::with_hasher(Collide);
+        let words: Vec<String> = (0..200).map(|i| format!("word{i}")).collect();
+        let symbols: Vec<Symbol> = words.iter().map(|word| interner.intern(word)).collect();
+        assert_eq!(interner.by_hash.len(), 1);
+        assert_eq!(interner.len(), words.len());
+        for (word, &symbol) in words.iter().zip(&symbols) {
+            assert_eq!(interner.intern(word), symbol);
+            assert_eq!(interner.get(word), Some(symbol));
+            assert_eq!(interner.resolve(symbol), word);
+        }
+        assert_eq!(interner.get("word200"), None);
+        assert_eq!(interner.get(""), None);
+        assert_eq!(interner.len(), words.len());
+    }
+
+    #[test]
+    fn threads_agree_on_symbols() {
+        let interner = SyncInterner::new();
+        let words: Vec<String> = (0..500).map(|i| format!("w{}", i % 300)).collect();
+        let seen: Vec<Vec<(String, Symbol)>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..8)
+                .map(|thread| {
+                    let (interner, words) = (&interner, &words);
+                    scope.spawn(move || {
+                        // each thread goes through the words from a different place
+                        let start = thread * 61 % words.len();
+                        let order = words[start..].iter().chain(&words[..start]);
+                        order
+                            .map(|word| (word.clone(), interner.intern(word)))
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        assert_eq!(interner.len(), 300);
+        let mut symbols: HashMap<String, Symbol> = HashMap::new();
+        for (word, symbol) in seen.into_iter().flatten() {
+            assert_eq!(*symbols.entry(word.clone()).or_insert(symbol), symbol, "{word}");
+            assert_eq!(&*interner.resolve(symbol), word);
+        }
+        let mut indexes: Vec<usize> = symbols.values().map(|symbol| symbol.index()).collect();
+        indexes.sort_unstable();
+        assert_eq!(indexes, (0..300).collect::<Vec<_>>());
+        assert_eq!(interner.get("w300"), None);
+    }
+
+    #[test]
+    fn to_interner_keeps_the_symbols() {
+        let shared = SyncInterner::new();
+        assert!(shared.is_empty());
+        let symbols: Vec<Symbol> = ["b", "a", "", "b", "c"]
+            .iter()
+            .map(|text| shared.intern(text))
+            .collect();
+        let interner = shared.to_interner();
+        assert_eq!(interner.len(), 4);
+        for (text, symbol) in ["b", "a", "", "b", "c"].iter().zip(symbols) {
+            assert_eq!(interner.get(text), Some(symbol));
+            assert_eq!(interner.resolve(symbol), *text);
+        }
+        let texts: Vec<&str> = interner.iter().map(|(_, text)| text).collect();
+        assert_eq!(texts, ["b", "a", "", "c"]);
+    }
+}
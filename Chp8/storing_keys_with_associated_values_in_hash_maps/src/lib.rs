@@ -0,0 +1,2 @@
+// Data structures that build on the hash maps in main.rs.
+pub mod interner;
@@ -1,5 +1,23 @@
+use std::collections::HashMap;
+use std::io::Read;
+use std::mem::size_of;
+use std::thread;
+
+use storing_keys_with_associated_values_in_hash_maps::interner::{Interner, Symbol, SyncInterner};
+use the_slice_type::prose;
+
 fn main() {
-    println!("\x1b[1;4;33mStoring Keys with Associated Values in Hash Maps\x1b[0m 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match args[0].as_str() {
+            "wordcount" => run_wordcount(&args[1..]),
+            other => {
+                fail(format!("unknown command '{other}'; try: wordcount [--threads n] [file]"))
+            }
+        }
+        return;
+    }
+    prose!("\x1b[1;4;33mStoring Keys with Associated Values in Hash Maps\x1b[0m 
     
     The last of our common collections is the hash map. The type Hashmap<K, V> stores a mapping of 
     keys of type K to values of type V using a hashing function, which determines how it places these 
@@ -33,7 +51,7 @@ fn main() {
 
     scores.insert(String::from("Blue"), 10);
     scores.insert(String::from("Yellow"), 50);
-    println!("
+    prose!("
     Note that we need to first use the HashMap from the collections portion of the standard library. Of 
     our three common collectoins, this one is the least often used, so it's not included in the features 
     brought into scope automatically in the prelude. Hash maps also have less support from the 
@@ -58,7 +76,7 @@ fn main() {
     let team_name = String::from("Blue");
     let score = scores.get(&team_name).copied().unwrap_or(0);
     println!("   The output of this code would be:\n\x1b[97m{}\x1b[0m", score);
-    println!("
+    prose!("
     Here, score will have the value that's associated with the Blue team, and the result will be 10. The 
     get method returns an Option<&V>; if there's no value for that key in the hash map, get will 
     return None. This program handles the Option by calling copied to get an Option<i32> rather 
@@ -82,7 +100,7 @@ fn main() {
     for (key, value) in &scores {
         println!("\x1b[97m{key}: {value}\x1b[0m");
     }
-    println!("
+    prose!("
 \x1b[1;4;33mHash Maps and Ownership\x1b[0m 
 
     For types that implement the Copy trait, like i32, the values are copied into the hash map. for 
@@ -133,10 +151,10 @@ fn main() {
         println!(\"{{scores:?}}\");\x1b[0m");
     scores.insert(String::from("Blue"), 25);
 
-    println!("    this code will have the output:\x1b[97m 
+    prose!("    this code will have the output:\x1b[97m 
 {scores:?}\x1b[0m 
     The original value of 10 has been overwritten.");
-    println!("
+    prose!("
 \x1b[1;4;33mAdding a Key and Value Only If a Key Isn't Present\x1b[0m 
 
     It's common to check whether a particular key already exists in the hash map with a value and then 
@@ -158,9 +176,9 @@ fn main() {
 
         println(\"{{scores:?}}\");\x1b[0m");
     scores.entry(String::from("Blue")).or_insert(50);
-    println!("    The output for this would be:\x1b[97m 
+    prose!("    The output for this would be:\x1b[97m 
 {scores:?}\x1b[0m");
-    println!("
+    prose!("
     The or_insert method on Entry is defined to return a mutable reference to the value for the 
     corresponding Entry key if that key exists, and if not, is inserts the parameter as the new value for 
     this key and returns a mutable reference to the new value. This technique is much cleaner than 
@@ -197,9 +215,9 @@ fn main() {
         let count = map.entry(word).or_insert(0);
         *count += 1;
     }
-    println!("    The output of this code would be:\x1b[97m 
+    prose!("    The output of this code would be:\x1b[97m 
 {map:?}\x1b[0m");
-    println!("
+    prose!("
     You might see the same key-value pairs printed in a different order: recall from the \"Accessing Values 
     in a Hash Map\" section that iterating over a hash map happens in an arbitrary order.
 
@@ -209,7 +227,17 @@ fn main() {
     that value, we must first dereference count using the asterisk (*). The mutable reference goes out 
     of scope at the end of the for loop, so all of these changes are safe and allowed by the borrowing 
     rules.
-    
+
+    \x1b[1;33mInterning Repeated Words\x1b[0m 
+
+    Using &str keys works here because text lives longer than map. When it doesn't, the keys have to 
+    be owned Strings, and anything else that keeps hold of the words (like a list of every word in 
+    order) needs clones of its own. An interner (see src/interner.rs) stores each distinct word 
+    once and hands out a Symbol, a 4-byte Copy number that can be turned back into the word. Here 
+    are both ways of counting the words in this chapter's own source code and keeping every word in 
+    order, with a rough count of the heap memory each one uses:");
+    interning_demo();
+    prose!("
 \x1b[1;4;33mHashing Functions\x1b[0m 
 
     By default, HashMap uses a hashing function called SipHash that can provide resistance to denial-of-
@@ -244,3 +272,144 @@ fn main() {
     We're getting into more complex programs in which operations can fail, so it's a perfect time to 
     discuss error handling. We'll do that next.");
 }
+
+// Heap bytes used by a hash map's table: every slot, plus the control byte that goes with it.
+// (The Strings' own buffers are counted separately.)
+fn table_size<K, V>(map: &HashMap<K, V>) -> usize {
+    map.capacity() * (size_of::<(K, V)>() + 1)
+}
+
+fn interning_demo() {
+    let text = include_str!("main.rs");
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut in_order: Vec<String> = Vec::new();
+    for word in text.split_whitespace() {
+        match counts.get_mut(word) {
+            Some(count) => *count += 1,
+            None => {
+                counts.insert(word.to_string(), 1);
+            }
+        }
+        in_order.push(word.to_string());
+    }
+    let owned_size = table_size(&counts)
+        + counts.keys().map(String::capacity).sum::<usize>()
+        + in_order.capacity() * size_of::<String>()
+        + in_order.iter().map(String::capacity).sum::<usize>();
+
+    let mut interner = Interner::new();
+    let mut symbol_counts: HashMap<Symbol, usize> = HashMap::new();
+    let mut symbols_in_order: Vec<Symbol> = Vec::new();
+    for word in text.split_whitespace() {
+        let symbol = interner.intern(word);
+        *symbol_counts.entry(symbol).or_insert(0) += 1;
+        symbols_in_order.push(symbol);
+    }
+    let interned_size = interner.heap_size()
+        + table_size(&symbol_counts)
+        + symbols_in_order.capacity() * size_of::<Symbol>();
+
+    println!("\x1b[97m");
+    println!(
+        "        {} words, {} different ones",
+        in_order.len(),
+        interner.len()
+    );
+    for (what, size) in [
+        ("HashMap<String, usize> + Vec<String>", owned_size),
+        (
+            "Interner + HashMap<Symbol, usize> + Vec<Symbol>",
+            interned_size,
+        ),
+    ] {
+        println!("        {what:<48} {size:>8} bytes");
+    }
+    let mut top: Vec<(Symbol, usize)> = symbol_counts.into_iter().collect();
+    top.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    let top: Vec<String> = top
+        .iter()
+        .take(5)
+        .map(|&(symbol, count)| format!("{:?}: {count}", interner.resolve(symbol)))
+        .collect();
+    println!("        most common: {}", top.join(", "));
+    // the sequence of symbols still turns back into the words
+    let first: Vec<&str> = symbols_in_order[..6]
+        .iter()
+        .map(|&symbol| interner.resolve(symbol))
+        .collect();
+    println!("        first words: {}\x1b[0m", first.join(" "));
+}
+
+/* wordcount */
+// Counts the words in a file (or stdin) on several threads. Every thread interns into the same
+// SyncInterner, so "the" is the same Symbol on all of them, and adding up each thread's counts is
+// just adding numbers under the same keys.
+fn run_wordcount(args: &[String]) {
+    let mut threads: usize = thread::available_parallelism().map_or(1, |n| n.get());
+    let mut path: Option<&str> = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--threads" => match args.next().and_then(|n| n.parse().ok()) {
+                Some(n) if n > 0 => threads = n,
+                _ => fail("--threads needs a positive number"),
+            },
+            other => path = Some(other),
+        }
+    }
+    let text = match path {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut text = String::new();
+            std::io::stdin().read_to_string(&mut text).map(|_| text)
+        }
+    };
+    let text = match text {
+        Ok(text) => text,
+        Err(error) => fail(format!("couldn't read {}: {error}", path.unwrap_or("stdin"))),
+    };
+
+    let lines: Vec<&str> = text.lines().collect();
+    let per_thread = lines.len().div_ceil(threads).max(1);
+    let interner = SyncInterner::new();
+    let mut counts: HashMap<Symbol, usize> = HashMap::new();
+    thread::scope(|scope| {
+        let workers: Vec<_> = lines
+            .chunks(per_thread)
+            .map(|chunk| {
+                let interner = &interner;
+                scope.spawn(move || {
+                    let mut counts: HashMap<Symbol, usize> = HashMap::new();
+                    for word in chunk.iter().flat_map(|line| line.split_whitespace()) {
+                        *counts.entry(interner.intern(word)).or_insert(0) += 1;
+                    }
+                    counts
+                })
+            })
+            .collect();
+        for worker in workers {
+            for (symbol, count) in worker.join().expect("a counting thread panicked") {
+                *counts.entry(symbol).or_insert(0) += count;
+            }
+        }
+    });
+
+    let interner = interner.to_interner();
+    let total: usize = counts.values().sum();
+    let mut sorted: Vec<(Symbol, usize)> = counts.into_iter().collect();
+    sorted.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+    println!(
+        "{total} words, {} different, counted on {threads} thread(s)",
+        interner.len()
+    );
+    for (symbol, count) in sorted.iter().take(20) {
+        println!("{count:>8} {}", interner.resolve(*symbol));
+    }
+}
+
+// Says what went wrong and exits with status 1, so scripts can tell.
+fn fail(message: impl std::fmt::Display) -> ! {
+    eprintln!("{message}");
+    std::process::exit(1)
+}
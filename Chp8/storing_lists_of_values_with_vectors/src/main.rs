@@ -1,5 +1,10 @@
+use the_slice_type::prose;
+
+// The "Updating a Vector" example pushes one value at a time, the way the book shows it, rather
+// than using vec![5, 6, 7, 8]. Clippy checks that lint for the whole block, so it's allowed here.
+#[allow(clippy::vec_init_then_push)]
 fn main() {
-    println!("\x1b[1;4;33mStoring Lists of Values with Vectors\x1b[0m 
+    prose!("\x1b[1;4;33mStoring Lists of Values with Vectors\x1b[0m 
 
     The first collection type we'll look at is Vec<T>, also known as a vector. Vectors allow you to store
     more than one value in a single data structure that puts all the values next to each other in memory.
@@ -10,11 +15,11 @@ fn main() {
     // To create a new empty vector, we call the Vec::new function, as shown here:
     let v: Vec<i32> = Vec::new();
 
-    println!("
+    prose!("
     To create a new empty vector, we call the vec::new functoin, as shown here:\n
     \x1b[97mlet v: Vec<i32> = Vec::new();\x1b[0m");
     println!("    the variable v contains {:?}", &v);
-    println!("
+    prose!("
     Note that we added a type annotation here. Because we aren't inserting any values into this vector,
     Rust doesn't know what kind of elements we intend to store. This is an important point. Vectors are 
     implemented using generics; we'll cover how to use generics with your own types in Chapter 10. For 
@@ -30,7 +35,7 @@ fn main() {
 
     let v = vec![1, 2, 3];
 
-    println!("
+    prose!("
     \x1b[97mlet v = vec1[1, 2, 3];\x1b[0m 
     the variable v contains {:?}
     
@@ -38,9 +43,9 @@ fn main() {
     annotation isn't necessary. Next, we'll look at how to modify a vector.
         ", &v);
     println!("\x1b[1;4;33mUpdating a Vector\x1b[0m");
-    println!("
+    prose!("
     To create a vector and then add elements to it, we can use the push method, as shown here:");
-    println!("\x1b[97m 
+    prose!("\x1b[97m 
         let mut v: Vec<i32> = Vec::new();
 
         v.push(5);
@@ -54,13 +59,13 @@ fn main() {
     v.push(7);
     v.push(8);
     println!("    the variable v contains {:?}", &v);
-    println!("
+    prose!("
     As with any variable, if we want to be able to change its value, be need to make it mutable using the 
     mut keyword, as discussed in Chapter 3. The numbers we place inside are all of type i32, and Rust 
     nifers this from the data, so we don't need the Vec<i32> annotation (I still included the annotation, 
     because I prefer to explicitly annotate the types of things.)
         ");
-    println!("\x1b[1;4;33mReading Elements of Vectors\x1b[0m 
+    prose!("\x1b[1;4;33mReading Elements of Vectors\x1b[0m 
 
     There are two ways to reference a value stored in a vector: via indexing or by using the get method.
     In the following examples, we've annotated the types of the values that are returned from these 
@@ -69,12 +74,14 @@ fn main() {
         let v = vec![1, 2, 3, 4, 5];
 
         let third: Option<&i32> = &v[2];\x1b[0m");
+    // a vector, not an array, since vectors are what this chapter is about
+    #[allow(clippy::useless_vec)]
     let v = vec![1, 2, 3, 4, 5];
 
     let third: &i32 = &v[2];
     println!("    The third element is {third}");
 
-    println!("\x1b[97m
+    prose!("\x1b[97m
         let third: Option<&i32> = v.get(2);
         match third {{
             Some(third) => println!(\"The third element is {{third}}\"),
@@ -85,7 +92,7 @@ fn main() {
         Some(third) => println!("    The third element is {third}"),
         None => println!("    There is no third element."),
     }
-    println!("
+    prose!("
     Note a few details here. We use the index value of 2 to get the third element because vectors are 
     indexed by number, starting at zero. Using & and [] gives us a reference to the element at the 
     index value. When we use the get method with the index passed as an argument, we get an 
@@ -154,7 +161,7 @@ fn main() {
     borrowing rules prevent programs from ending up in that situation.
     ");
     
-    println!("\x1b[1;4;33mIterating Over the Values in a Vector\x1b[0m 
+    prose!("\x1b[1;4;33mIterating Over the Values in a Vector\x1b[0m 
     
     To access each element in a vector in turn, we would iterate through all of the elements rather than
     use indices to access one at a time. The next example shows how to use a for loop to get immutable 
@@ -169,7 +176,7 @@ fn main() {
     for i in &v {
         println!("    {i}");
     }
-    println!("
+    prose!("
     We can also iterate over mutable references to each element in a mutable vector in order to make 
     changes to all the elements. The next example fill add 50 to each element:\x1b[97m
         let mut v = vec![100, 32, 57];
@@ -182,7 +189,7 @@ fn main() {
         *i += 50;
     }
     println!("    v after changes: {:?}", v);
-    println!("
+    prose!("
     To change the value that the mutable reference refers to, we have to use the * dereference 
     opeator to get to the value in i before we can use the += operator. We'll talk more about the 
     dereference operator in the 'Following the Pointer to the Value with Dereference Operator'
@@ -224,6 +231,7 @@ fn main() {
         Text(String),
     }
 
+    #[allow(clippy::useless_vec)]
     let row = vec![
         SpreadsheetCell::Int(3),
         SpreadsheetCell::Text(String::from("blue")),
@@ -238,7 +246,7 @@ fn main() {
             SpreadsheetCell::Float(val) => print!("{val}"),
         }
     }
-    println!("
+    prose!("
 
     Rust need to know what types will be in the vector at compile time so it knows exactly how much
     memory on the heap will be needed to store each element. We must also be explicit about what 
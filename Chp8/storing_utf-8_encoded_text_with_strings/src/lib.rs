@@ -0,0 +1,2 @@
+// Data structures for the string operations in main.rs.
+pub mod rope;
@@ -1,5 +1,22 @@
+use std::time::{Duration, Instant};
+use storing_utf_8_encoded_text_with_strings::rope::Rope;
+use the_slice_type::prose;
+use what_is_ownership::alloc_counter::{self, Report};
+
+#[cfg(feature = "count-allocations")]
+#[global_allocator]
+static ALLOCATOR: alloc_counter::CountingAllocator = alloc_counter::CountingAllocator;
+
 fn main() {
-    println!("\x1b[1;4;33mStoring UTF-8 Encoded Text with Strings\x1b[0m 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        match args[0].as_str() {
+            "rope-bench" => run_rope_bench(&args[1..]),
+            other => eprintln!("unknown command '{other}'; try: rope-bench [megabytes] [edits]"),
+        }
+        return;
+    }
+    prose!("\x1b[1;4;33mStoring UTF-8 Encoded Text with Strings\x1b[0m 
 
     We talked about strings in Chapter 4, but we'll look at them in more depth now. New Rustaceans 
     commonly get stuck on strings for a combination of three reasons: Rust's propensity for exposing 
@@ -99,7 +116,7 @@ fn main() {
     let mut s1 = String::from("foo");
     let s2 = "bar";
     s1.push_str(s2);
-    println!("\x1b[97m    s1 is {s1}, s2 is {s2}.\x1b[0m
+    prose!("\x1b[97m    s1 is {s1}, s2 is {s2}.\x1b[0m
 
     The push method takes a sungle character as a parameter and adds it to the String. The next example 
     shows how this is done:\x1b[97m 
@@ -108,7 +125,7 @@ fn main() {
     let mut s = String::from("lo");
     s.push('l');
     println!("    s is {s}");
-    println!("
+    prose!("
     As a result, s will contain lol.
 
     \x1b[1;33mConcatenation with the + Operator or the format! Macro\x1b[0m 
@@ -122,7 +139,7 @@ fn main() {
     let s2 = String::from("world!");
     let s3 = s1 + &s2;
     println!("    s3 is \"{s3}\"");
-    println!("
+    prose!("
     The string s3 will contain Hello, world! The reason s1 is no longer valid after the addition, and 
     the reason we used a reference to s2, has to do with the signature of the method that's called when 
     we use the + operator. The + operator uses the add method, whose signature looks something 
@@ -171,12 +188,30 @@ fn main() {
 
     let s = format!("{s1}-{s2}-{s3}");
     println!("    contents of s are: {s}");
-    println!("
+    prose!("
     This code also sets s to tic-tac-toe. The format! macro works like println!, but instead of 
     printing the output to the scheen, it returns a String with the contents. The version of the code 
     using format! is much easier to read, and the code generated by the format! macro uses 
     references so that this call doesn't take ownership of any of its parameters.
 
+    \x1b[1;33mWhat Concatenation Costs\x1b[0m 
+
+    Run with cargo run --features count-allocations to count the heap allocations each way of 
+    building tic-tac-toe makes. + reuses s1's buffer, so it only reallocates when s1 runs out of 
+    capacity; format! allocates a new String, but only guesses its size from the literal parts of 
+    the format string, so it can still have to grow; and a String::with_capacity that's big enough 
+    to start with allocates once and never grows.");
+    concatenation_costs();
+    prose!("
+    \x1b[1;33mEditing in the Middle\x1b[0m 
+
+    All of these add to the end of a String. Inserting into the middle with insert_str, or removing 
+    from it with replace_range, has to move every byte after that point, which adds up in a document 
+    that's megabytes long. A rope (see src/rope.rs) stores the text as a balanced tree of small 
+    chunks instead, so an edit only touches the chunks along one path through the tree:");
+    rope_demo();
+    prose!("
+    Run cargo run --release -- rope-bench to time the two against each other.
 \x1b[1;4;33mIndexing into Strings\x1b[0m
 
     In many other programming languages, acessing indevidual characters in a string by referencing
@@ -299,7 +334,7 @@ fn main() {
     for c in "Зд".chars() {
         println!("{c}");
     }
-    println!("\x1b[0m 
+    prose!("\x1b[0m 
         Alternatively, the bytes method returns each raw byte, which might be appropriate for your
         domain:\x1b[97m 
             for b in \"Зд\".bytes() {{
@@ -310,7 +345,7 @@ fn main() {
     for b in "Зд".bytes() {
         println!("{b}");
     }
-    println!("\x1b[0m 
+    prose!("\x1b[0m 
         But be sure to remember that valid Unicode scalar values may be made up of more than one byte.
 
         Getting grapheme clusters from strings, as with the Devangari script, is complex, so this 
@@ -333,3 +368,138 @@ fn main() {
 
     Let's switch to something a bit less complex: hash maps.");
 }
+
+// Each way of concatenating runs in its own region; the Strings being joined are made beforehand,
+// so only the concatenation itself is counted.
+fn concatenation_costs() {
+    let mut reports: Vec<Report> = Vec::new();
+
+    let (s1, s2) = (String::from("Hello, "), String::from("world!"));
+    let (_s3, report) = alloc_counter::measure("s1 + &s2", || s1 + &s2);
+    reports.push(report);
+
+    let (s1, s2, s3) = (
+        String::from("tic"),
+        String::from("tac"),
+        String::from("toe"),
+    );
+    let (_s, report) = alloc_counter::measure("s1 + \"-\" + &s2 + \"-\" + &s3", || {
+        s1 + "-" + &s2 + "-" + &s3
+    });
+    reports.push(report);
+
+    let s1 = String::from("tic");
+    let (_s, report) =
+        alloc_counter::measure("format!(\"{s1}-{s2}-{s3}\")", || format!("{s1}-{s2}-{s3}"));
+    reports.push(report);
+
+    let (_s, report) = alloc_counter::measure("String::with_capacity + push_str", || {
+        let mut s = String::with_capacity(s1.len() + s2.len() + s3.len() + 2);
+        for (i, part) in [&s1, &s2, &s3].into_iter().enumerate() {
+            if i > 0 {
+                s.push('-');
+            }
+            s.push_str(part);
+        }
+        s
+    });
+    reports.push(report);
+
+    alloc_counter::print_reports("cost of each concatenation:", &reports);
+}
+
+fn rope_demo() {
+    let mut rope = Rope::from("tic-toe\nЗдравствуйте, world\n");
+    rope.insert(4, "tac-");
+    println!(
+        "\x1b[97m        rope after insert(4, \"tac-\"): {:?}",
+        rope.to_string()
+    );
+    // indexes count chars, so this is the 'w' even though 'Здравствуйте' is 24 bytes
+    let w = rope.line_col_to_char(1, 14).unwrap();
+    println!(
+        "        line 1, column 14 is char {w}: {:?}",
+        rope.char_at(w).unwrap()
+    );
+    rope.remove(12..26);
+    println!("        after remove(12..26): {:?}", rope.to_string());
+    println!(
+        "        {} chars, {} bytes, {} lines, line_col(12) = {:?}\x1b[0m",
+        rope.len_chars(),
+        rope.len_bytes(),
+        rope.len_lines(),
+        rope.line_col(12)
+    );
+}
+
+/* rope-bench */
+// Makes the same edits to a String and a Rope holding a document of a few megabytes, and times
+// them. Positions come from a small xorshift generator with a fixed seed, so every run makes the
+// same edits; the text is ASCII, so String's byte indexes and Rope's char indexes agree.
+fn run_rope_bench(args: &[String]) {
+    let megabytes: usize = args.first().and_then(|a| a.parse().ok()).unwrap_or(4);
+    let edits: usize = args.get(1).and_then(|a| a.parse().ok()).unwrap_or(2000);
+    if cfg!(debug_assertions) {
+        println!("(a debug build; cargo run --release -- rope-bench gives realistic numbers)");
+    }
+    let line = "The quick brown fox jumps over the lazy dog, again and again.\n";
+    let document = line.repeat(megabytes * 1024 * 1024 / line.len());
+    println!(
+        "{} edits on a {:.1} MB document:",
+        edits,
+        document.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |below: usize| {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state % below as u64) as usize
+    };
+    // half inserts, half removals, all at random positions
+    let operations: Vec<(bool, usize, usize)> = (0..edits)
+        .map(|i| (i % 2 == 0, next(usize::MAX), next(16) + 1))
+        .collect();
+
+    let mut string = document.clone();
+    let string_time = time(|| {
+        for &(insert, position, size) in &operations {
+            if insert {
+                string.insert_str(position % (string.len() + 1), &"+".repeat(size));
+            } else {
+                let start = position % (string.len() - size);
+                string.replace_range(start..start + size, "");
+            }
+        }
+    });
+    let mut rope = Rope::from(document.as_str());
+    let rope_time = time(|| {
+        for &(insert, position, size) in &operations {
+            if insert {
+                rope.insert(position % (rope.len_chars() + 1), &"+".repeat(size));
+            } else {
+                let start = position % (rope.len_chars() - size);
+                rope.remove(start..start + size);
+            }
+        }
+    });
+    let build_time = time(|| drop(Rope::from(document.as_str())));
+    let convert_time = time(|| drop(String::from(&rope)));
+
+    println!("  String::insert_str/replace_range {:>10.2?}", string_time);
+    println!("  Rope::insert/remove              {:>10.2?}", rope_time);
+    println!("  Rope::from(&str)                 {:>10.2?}", build_time);
+    println!("  String::from(&Rope)              {:>10.2?}", convert_time);
+    println!(
+        "  rope depth {}, same text: {}",
+        rope.depth(),
+        rope == string.as_str()
+    );
+}
+
+fn time(f: impl FnOnce()) -> Duration {
+    let start = Instant::now();
+    f();
+    start.elapsed()
+}
@@ -0,0 +1,859 @@
+/* Rope */
+// A String keeps its text in one contiguous buffer. Appending to the end is cheap (push_str only
+// copies the new part, plus the occasional reallocation), but insert_str or replace_range in the
+// middle has to shift everything after that point along, so each edit in a big document costs
+// time proportional to the document.
+//
+// A rope splits the text into chunks of at most MAX_LEAF bytes and keeps them as the leaves of a
+// balanced binary tree. Every branch remembers how many bytes, chars and newlines are under it,
+// so finding char n (or line n) is a walk down the tree, and inserting or deleting only splits and
+// rejoins the O(log n) branches along that walk instead of moving the whole text. Indexes here
+// are char indexes, not byte indexes like String's, so an index can never land inside a
+// character.
+//
+// The balancing is the one AVL trees use: the heights of a branch's two sides never differ by
+// more than one (well, almost never: see rebalance).
+use std::fmt;
+use std::ops::Range;
+
+// Leaves hold at most this many bytes (more only when a single character is bigger, which can't
+// happen with 4-byte UTF-8 characters and a limit this size).
+const MAX_LEAF: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+struct Info {
+    bytes: usize,
+    chars: usize,
+    newlines: usize,
+}
+
+impl Info {
+    fn of(text: &str) -> Self {
+        Info {
+            bytes: text.len(),
+            chars: text.chars().count(),
+            newlines: text.bytes().filter(|&b| b == b'\n').count(),
+        }
+    }
+
+    fn plus(self, other: Info) -> Self {
+        Info {
+            bytes: self.bytes + other.bytes,
+            chars: self.chars + other.chars,
+            newlines: self.newlines + other.newlines,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Leaf {
+        text: String,
+        info: Info,
+    },
+    Branch {
+        left: Box<Node>,
+        right: Box<Node>,
+        info: Info,
+        height: usize,
+    },
+}
+
+impl Default for Node {
+    fn default() -> Self {
+        leaf(String::new())
+    }
+}
+
+fn leaf(text: String) -> Node {
+    let info = Info::of(&text);
+    Node::Leaf { text, info }
+}
+
+fn branch(left: Node, right: Node) -> Node {
+    Node::Branch {
+        info: left.info().plus(right.info()),
+        height: left.height().max(right.height()) + 1,
+        left: Box::new(left),
+        right: Box::new(right),
+    }
+}
+
+// The byte offset of char n in text, or text.len() if n is the number of chars.
+fn byte_of_char(text: &str, n: usize) -> usize {
+    text.char_indices().nth(n).map_or(text.len(), |(i, _)| i)
+}
+
+impl Node {
+    fn info(&self) -> Info {
+        match self {
+            Node::Leaf { info, .. } | Node::Branch { info, .. } => *info,
+        }
+    }
+
+    fn height(&self) -> usize {
+        match self {
+            Node::Leaf { .. } => 0,
+            Node::Branch { height, .. } => *height,
+        }
+    }
+
+    // A leaf per chunk, paired up level by level into a balanced tree.
+    fn from_str(text: &str) -> Node {
+        let mut level: Vec<Node> = Vec::new();
+        let mut rest = text;
+        while !rest.is_empty() {
+            let mut end = rest.len().min(MAX_LEAF);
+            while !rest.is_char_boundary(end) {
+                end -= 1;
+            }
+            level.push(leaf(rest[..end].to_string()));
+            rest = &rest[end..];
+        }
+        while level.len() > 1 {
+            let mut next: Vec<Node> = Vec::with_capacity(level.len().div_ceil(2));
+            let mut nodes = level.into_iter();
+            while let Some(left) = nodes.next() {
+                next.push(match nodes.next() {
+                    Some(right) => branch(left, right),
+                    None => left,
+                });
+            }
+            level = next;
+        }
+        level.pop().unwrap_or_default()
+    }
+
+    // Splits into the first n chars and the rest.
+    fn split(self, n: usize) -> (Node, Node) {
+        match self {
+            Node::Leaf { mut text, .. } => {
+                let at = byte_of_char(&text, n);
+                let right = text[at..].to_string();
+                text.truncate(at);
+                (leaf(text), leaf(right))
+            }
+            Node::Branch { left, right, .. } => {
+                let left_chars = left.info().chars;
+                if n < left_chars {
+                    let (a, b) = left.split(n);
+                    (a, join(b, *right))
+                } else if n > left_chars {
+                    let (a, b) = right.split(n - left_chars);
+                    (join(*left, a), b)
+                } else {
+                    (*left, *right)
+                }
+            }
+        }
+    }
+
+    // Adds text to the end of the last leaf if it fits there, fixing up the counts on the way
+    // back up. Keeps lots of small edits from leaving behind lots of tiny leaves.
+    fn try_append(&mut self, text: &str) -> bool {
+        match self {
+            Node::Leaf { text: own, info } => {
+                if own.len() + text.len() > MAX_LEAF {
+                    return false;
+                }
+                own.push_str(text);
+                *info = info.plus(Info::of(text));
+                true
+            }
+            Node::Branch { right, info, .. } => {
+                let appended = right.try_append(text);
+                if appended {
+                    *info = info.plus(Info::of(text));
+                }
+                appended
+            }
+        }
+    }
+
+    // The same at the start of the first leaf.
+    fn try_prepend(&mut self, text: &str) -> bool {
+        match self {
+            Node::Leaf { text: own, info } => {
+                if own.len() + text.len() > MAX_LEAF {
+                    return false;
+                }
+                own.insert_str(0, text);
+                *info = info.plus(Info::of(text));
+                true
+            }
+            Node::Branch { left, info, .. } => {
+                let prepended = left.try_prepend(text);
+                if prepended {
+                    *info = info.plus(Info::of(text));
+                }
+                prepended
+            }
+        }
+    }
+
+    fn char_at(&self, n: usize) -> char {
+        match self {
+            Node::Leaf { text, .. } => text.chars().nth(n).expect("index checked by the caller"),
+            Node::Branch { left, right, .. } => {
+                let left_chars = left.info().chars;
+                if n < left_chars {
+                    left.char_at(n)
+                } else {
+                    right.char_at(n - left_chars)
+                }
+            }
+        }
+    }
+
+    // How many newlines come before char n.
+    fn newlines_before(&self, n: usize) -> usize {
+        match self {
+            Node::Leaf { text, .. } => text.chars().take(n).filter(|&c| c == '\n').count(),
+            Node::Branch { left, right, .. } => {
+                let left_info = left.info();
+                if n <= left_info.chars {
+                    left.newlines_before(n)
+                } else {
+                    left_info.newlines + right.newlines_before(n - left_info.chars)
+                }
+            }
+        }
+    }
+
+    // The char index just after newline number n (counting from 1).
+    fn char_after_newline(&self, n: usize) -> usize {
+        match self {
+            Node::Leaf { text, .. } => {
+                let mut seen = 0;
+                for (i, c) in text.chars().enumerate() {
+                    if c == '\n' {
+                        seen += 1;
+                        if seen == n {
+                            return i + 1;
+                        }
+                    }
+                }
+                unreachable!("line checked by the caller")
+            }
+            Node::Branch { left, right, .. } => {
+                let left_info = left.info();
+                if n <= left_info.newlines {
+                    left.char_after_newline(n)
+                } else {
+                    left_info.chars + right.char_after_newline(n - left_info.newlines)
+                }
+            }
+        }
+    }
+
+    // A copy of chars start..end. Whole subtrees inside the range are cloned as they are.
+    fn slice(&self, start: usize, end: usize) -> Node {
+        if start == 0 && end == self.info().chars {
+            return self.clone();
+        }
+        match self {
+            Node::Leaf { text, .. } => {
+                let from = byte_of_char(text, start);
+                let to = from + byte_of_char(&text[from..], end - start);
+                leaf(text[from..to].to_string())
+            }
+            Node::Branch { left, right, .. } => {
+                let left_chars = left.info().chars;
+                let left_part = if start < left_chars {
+                    left.slice(start, end.min(left_chars))
+                } else {
+                    Node::default()
+                };
+                let right_part = if end > left_chars {
+                    right.slice(start.saturating_sub(left_chars), end - left_chars)
+                } else {
+                    Node::default()
+                };
+                join(left_part, right_part)
+            }
+        }
+    }
+}
+
+/* Joining and balancing */
+// Puts left before right. If one tree is much taller, the shorter one is joined onto the
+// matching edge of the taller one, far enough down that the heights match, and the branches on
+// the way back up are rebalanced. The cost is the difference in heights.
+fn join(mut left: Node, mut right: Node) -> Node {
+    if left.info().chars == 0 {
+        return right;
+    }
+    if right.info().chars == 0 {
+        return left;
+    }
+    if let Node::Leaf { text, .. } = &right {
+        if left.try_append(text) {
+            return left;
+        }
+    }
+    if let Node::Leaf { text, .. } = &left {
+        if right.try_prepend(text) {
+            return right;
+        }
+    }
+    let (left_height, right_height) = (left.height(), right.height());
+    if left_height > right_height + 1 {
+        let Node::Branch {
+            left: left_left,
+            right: left_right,
+            ..
+        } = left
+        else {
+            unreachable!("a node taller than another is a branch")
+        };
+        rebalance(*left_left, join(*left_right, right))
+    } else if right_height > left_height + 1 {
+        let Node::Branch {
+            left: right_left,
+            right: right_right,
+            ..
+        } = right
+        else {
+            unreachable!("a node taller than another is a branch")
+        };
+        rebalance(join(left, *right_left), *right_right)
+    } else {
+        branch(left, right)
+    }
+}
+
+// A branch of left and right, rotated if one side is two levels taller. That's all join ever
+// produces, except when merging leaves made a subtree shorter; then the tree is a little less
+// balanced than AVL would allow, which costs a level here and there but nothing more.
+fn rebalance(left: Node, right: Node) -> Node {
+    if left.height() > right.height() + 1 {
+        let Node::Branch {
+            left: outer,
+            right: inner,
+            ..
+        } = left
+        else {
+            unreachable!("a node taller than another is a branch")
+        };
+        if outer.height() >= inner.height() {
+            branch(*outer, branch(*inner, right))
+        } else {
+            let Node::Branch {
+                left: inner_left,
+                right: inner_right,
+                ..
+            } = *inner
+            else {
+                unreachable!("a node taller than another is a branch")
+            };
+            branch(branch(*outer, *inner_left), branch(*inner_right, right))
+        }
+    } else if right.height() > left.height() + 1 {
+        let Node::Branch {
+            left: inner,
+            right: outer,
+            ..
+        } = right
+        else {
+            unreachable!("a node taller than another is a branch")
+        };
+        if outer.height() >= inner.height() {
+            branch(branch(left, *inner), *outer)
+        } else {
+            let Node::Branch {
+                left: inner_left,
+                right: inner_right,
+                ..
+            } = *inner
+            else {
+                unreachable!("a node taller than another is a branch")
+            };
+            branch(branch(left, *inner_left), branch(*inner_right, *outer))
+        }
+    } else {
+        branch(left, right)
+    }
+}
+
+/* The rope */
+#[derive(Debug, Clone, Default)]
+pub struct Rope {
+    root: Node,
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Rope::default()
+    }
+
+    pub fn len_bytes(&self) -> usize {
+        self.root.info().bytes
+    }
+
+    pub fn len_chars(&self) -> usize {
+        self.root.info().chars
+    }
+
+    // One more than the number of newlines, like an editor counts them: "" is one (empty) line
+    // and "a\n" is two.
+    pub fn len_lines(&self) -> usize {
+        self.root.info().newlines + 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len_bytes() == 0
+    }
+
+    // The height of the tree, to see that it stays around log2(len_bytes / MAX_LEAF).
+    pub fn depth(&self) -> usize {
+        self.root.height()
+    }
+
+    // Inserts text before char index, like String::insert_str but counting chars. Panics if
+    // index is past the end.
+    pub fn insert(&mut self, index: usize, text: &str) {
+        let len = self.len_chars();
+        assert!(
+            index <= len,
+            "insert index {index} is past the end ({len} chars)"
+        );
+        if text.is_empty() {
+            return;
+        }
+        let (before, after) = std::mem::take(&mut self.root).split(index);
+        self.root = join(join(before, Node::from_str(text)), after);
+    }
+
+    pub fn insert_char(&mut self, index: usize, c: char) {
+        self.insert(index, c.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.insert(self.len_chars(), text);
+    }
+
+    // Adds another rope to the end without copying its text.
+    pub fn append(&mut self, other: Rope) {
+        self.root = join(std::mem::take(&mut self.root), other.root);
+    }
+
+    // Removes chars range.start..range.end. Panics if the range is backwards or past the end.
+    pub fn remove(&mut self, range: Range<usize>) {
+        let len = self.len_chars();
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "remove range {range:?} is out of bounds ({len} chars)"
+        );
+        let (before, rest) = std::mem::take(&mut self.root).split(range.start);
+        let (_, after) = rest.split(range.end - range.start);
+        self.root = join(before, after);
+    }
+
+    // Splits off chars index.. into a rope of their own, like String::split_off.
+    pub fn split_off(&mut self, index: usize) -> Rope {
+        let len = self.len_chars();
+        assert!(
+            index <= len,
+            "split index {index} is past the end ({len} chars)"
+        );
+        let (before, after) = std::mem::take(&mut self.root).split(index);
+        self.root = before;
+        Rope { root: after }
+    }
+
+    // A new rope with chars range.start..range.end.
+    pub fn slice(&self, range: Range<usize>) -> Rope {
+        let len = self.len_chars();
+        assert!(
+            range.start <= range.end && range.end <= len,
+            "slice range {range:?} is out of bounds ({len} chars)"
+        );
+        if range.is_empty() {
+            return Rope::new();
+        }
+        Rope {
+            root: self.root.slice(range.start, range.end),
+        }
+    }
+
+    pub fn char_at(&self, index: usize) -> Option<char> {
+        (index < self.len_chars()).then(|| self.root.char_at(index))
+    }
+
+    /* Lines and columns */
+    // The line (from 0) that char index is on. The end of the text counts as being on the last
+    // line.
+    pub fn char_to_line(&self, index: usize) -> usize {
+        self.root.newlines_before(index.min(self.len_chars()))
+    }
+
+    // The char index where line starts, or None if there aren't that many lines.
+    pub fn line_to_char(&self, line: usize) -> Option<usize> {
+        match line {
+            0 => Some(0),
+            _ if line < self.len_lines() => Some(self.root.char_after_newline(line)),
+            _ => None,
+        }
+    }
+
+    // The line and column (both from 0, the column in chars) of char index.
+    pub fn line_col(&self, index: usize) -> (usize, usize) {
+        let index = index.min(self.len_chars());
+        let line = self.char_to_line(index);
+        let start = self
+            .line_to_char(line)
+            .expect("char_to_line gives a real line");
+        (line, index - start)
+    }
+
+    // The char index of a line and column, if the line has that many columns. The newline at
+    // the end of a line counts as its last column.
+    pub fn line_col_to_char(&self, line: usize, column: usize) -> Option<usize> {
+        let start = self.line_to_char(line)?;
+        let end = self.line_to_char(line + 1).unwrap_or(self.len_chars() + 1);
+        // a column near usize::MAX would overflow, and is past the end of any line anyway
+        let index = start.checked_add(column)?;
+        (index < end).then_some(index)
+    }
+
+    // Line number `line`, including its newline.
+    pub fn line(&self, line: usize) -> Option<Rope> {
+        let start = self.line_to_char(line)?;
+        let end = self.line_to_char(line + 1).unwrap_or(self.len_chars());
+        Some(self.slice(start..end))
+    }
+
+    /* Iterating */
+    // The text in the pieces it's stored in, front to back.
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            stack: vec![&self.root],
+        }
+    }
+
+    pub fn chars(&self) -> impl Iterator<Item = char> + '_ {
+        self.chunks().flat_map(str::chars)
+    }
+
+    pub fn bytes(&self) -> impl Iterator<Item = u8> + '_ {
+        self.chunks().flat_map(str::bytes)
+    }
+}
+
+pub struct Chunks<'a> {
+    // nodes still to visit, the next one on top
+    stack: Vec<&'a Node>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        while let Some(node) = self.stack.pop() {
+            match node {
+                Node::Leaf { text, .. } if !text.is_empty() => return Some(text),
+                Node::Leaf { .. } => {}
+                Node::Branch { left, right, .. } => {
+                    self.stack.push(right);
+                    self.stack.push(left);
+                }
+            }
+        }
+        None
+    }
+}
+
+/* Conversions */
+impl From<&str> for Rope {
+    fn from(text: &str) -> Self {
+        Rope {
+            root: Node::from_str(text),
+        }
+    }
+}
+
+impl From<String> for Rope {
+    fn from(text: String) -> Self {
+        Rope::from(text.as_str())
+    }
+}
+
+impl From<&Rope> for String {
+    fn from(rope: &Rope) -> Self {
+        let mut text = String::with_capacity(rope.len_bytes());
+        for chunk in rope.chunks() {
+            text.push_str(chunk);
+        }
+        text
+    }
+}
+
+impl From<Rope> for String {
+    fn from(rope: Rope) -> Self {
+        String::from(&rope)
+    }
+}
+
+impl fmt::Display for Rope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for chunk in self.chunks() {
+            f.write_str(chunk)?;
+        }
+        Ok(())
+    }
+}
+
+// Two ropes are equal when their text is, however it happens to be chunked.
+impl PartialEq for Rope {
+    fn eq(&self, other: &Rope) -> bool {
+        self.len_bytes() == other.len_bytes() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl Eq for Rope {}
+
+impl PartialEq<str> for Rope {
+    fn eq(&self, other: &str) -> bool {
+        self.len_bytes() == other.len() && self.bytes().eq(other.bytes())
+    }
+}
+
+impl PartialEq<&str> for Rope {
+    fn eq(&self, other: &&str) -> bool {
+        self == *other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A tiny xorshift, so the tests need no crates and fail the same way every time.
+    struct Random(u64);
+
+    impl Random {
+        fn below(&mut self, n: usize) -> usize {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            (self.0 % n as u64) as usize
+        }
+
+        // Mostly short, sometimes several leaves long, with newlines and 2, 3 and 4 byte chars.
+        fn text(&mut self) -> String {
+            let len = if self.below(10) == 0 {
+                self.below(3000)
+            } else {
+                self.below(40)
+            };
+            let chars = ['a', 'b', ' ', '\n', 'é', '日', '😀'];
+            (0..len).map(|_| chars[self.below(chars.len())]).collect()
+        }
+    }
+
+    // log2(leaves) or so, with room for the few levels the joins can add: a tree that had lost
+    // its balance would be far deeper than this.
+    fn max_depth(bytes: usize) -> usize {
+        2 * (bytes / MAX_LEAF + 1).ilog2() as usize + 2
+    }
+
+    // Checks the counts in every branch, the heights, the leaf sizes, and the depth, and that
+    // the rope holds exactly model.
+    fn check(rope: &Rope, model: &[char]) {
+        fn walk(node: &Node) -> Info {
+            match node {
+                Node::Leaf { text, info } => {
+                    assert!(text.len() <= MAX_LEAF, "a leaf of {} bytes", text.len());
+                    assert_eq!(*info, Info::of(text));
+                    *info
+                }
+                Node::Branch {
+                    left,
+                    right,
+                    info,
+                    height,
+                } => {
+                    assert_eq!(*height, left.height().max(right.height()) + 1);
+                    assert_eq!(walk(left).plus(walk(right)), *info);
+                    *info
+                }
+            }
+        }
+        walk(&rope.root);
+        let text: String = model.iter().collect();
+        assert_eq!(rope.to_string(), text);
+        assert_eq!(rope.len_chars(), model.len());
+        assert_eq!(rope.len_bytes(), text.len());
+        assert_eq!(rope.len_lines(), text.matches('\n').count() + 1);
+        assert!(
+            rope.depth() <= max_depth(rope.len_bytes()),
+            "depth {} for {} bytes",
+            rope.depth(),
+            rope.len_bytes()
+        );
+    }
+
+    // Where each line starts in model, the way the rope should count them.
+    fn line_starts(model: &[char]) -> Vec<usize> {
+        let newlines = model.iter().enumerate().filter(|&(_, &c)| c == '\n');
+        std::iter::once(0)
+            .chain(newlines.map(|(i, _)| i + 1))
+            .collect()
+    }
+
+    fn check_lines(rope: &Rope, model: &[char], random: &mut Random) {
+        let starts = line_starts(model);
+        for _ in 0..5 {
+            let index = random.below(model.len() + 2);
+            let line = model[..index.min(model.len())]
+                .iter()
+                .filter(|&&c| c == '\n')
+                .count();
+            assert_eq!(rope.char_to_line(index), line);
+            let clamped = index.min(model.len());
+            assert_eq!(rope.line_col(index), (line, clamped - starts[line]));
+            assert_eq!(rope.char_at(index), model.get(index).copied());
+
+            let line = random.below(starts.len() + 1);
+            assert_eq!(rope.line_to_char(line), starts.get(line).copied());
+            let column = random.below(40);
+            let expected = starts.get(line).and_then(|&start| {
+                let end = starts.get(line + 1).copied().unwrap_or(model.len() + 1);
+                (start + column < end).then_some(start + column)
+            });
+            assert_eq!(rope.line_col_to_char(line, column), expected);
+            if let Some(start) = starts.get(line) {
+                let end = starts.get(line + 1).copied().unwrap_or(model.len());
+                let text: String = model[*start..end].iter().collect();
+                assert_eq!(rope.line(line).unwrap(), text.as_str());
+            } else {
+                assert!(rope.line(line).is_none());
+            }
+        }
+    }
+
+    #[test]
+    fn random_edits_match_a_string() {
+        let mut random = Random(0x9e37_79b9_7f4a_7c15);
+        let mut rope = Rope::new();
+        let mut model: Vec<char> = Vec::new();
+        for _ in 0..600 {
+            match random.below(10) {
+                0..=3 => {
+                    let index = random.below(model.len() + 1);
+                    let text = random.text();
+                    rope.insert(index, &text);
+                    model.splice(index..index, text.chars());
+                }
+                4..=5 => {
+                    let start = random.below(model.len() + 1);
+                    let end = (start + random.below(2000)).min(model.len());
+                    rope.remove(start..end);
+                    model.drain(start..end);
+                }
+                6 => {
+                    // split in two, check both halves, and put them back
+                    let index = random.below(model.len() + 1);
+                    let back = rope.split_off(index);
+                    check(&rope, &model[..index]);
+                    check(&back, &model[index..]);
+                    rope.append(back);
+                }
+                7 => {
+                    let other = random.text();
+                    model.extend(other.chars());
+                    rope.append(Rope::from(other));
+                }
+                8 => {
+                    let start = random.below(model.len() + 1);
+                    let end = start + random.below(model.len() - start + 1);
+                    check(&rope.slice(start..end), &model[start..end]);
+                }
+                _ => {
+                    let index = random.below(model.len() + 1);
+                    rope.insert_char(index, '\n');
+                    model.insert(index, '\n');
+                }
+            }
+            check(&rope, &model);
+            check_lines(&rope, &model, &mut random);
+        }
+    }
+
+    #[test]
+    fn depth_stays_logarithmic() {
+        // a character at a time at the end, which is the worst case for a tree that doesn't
+        // rebalance
+        let mut rope = Rope::new();
+        for i in 0..60_000 {
+            rope.push_str(if i % 80 == 79 { "\n" } else { "x" });
+        }
+        assert!(
+            rope.depth() <= max_depth(rope.len_bytes()),
+            "depth {}",
+            rope.depth()
+        );
+        // and at the front
+        let mut rope = Rope::new();
+        for _ in 0..2000 {
+            rope.insert(0, &"y".repeat(100));
+        }
+        assert!(
+            rope.depth() <= max_depth(rope.len_bytes()),
+            "depth {}",
+            rope.depth()
+        );
+        // ropes of very different sizes appended either way round
+        let mut rope = Rope::new();
+        for i in 0..200 {
+            let other = Rope::from("z".repeat(i * 97 % 5000));
+            if i % 2 == 0 {
+                rope.append(other);
+            } else {
+                let mut other = other;
+                other.append(rope);
+                rope = other;
+            }
+        }
+        assert!(
+            rope.depth() <= max_depth(rope.len_bytes()),
+            "depth {}",
+            rope.depth()
+        );
+    }
+
+    #[test]
+    fn lines_and_columns() {
+        let rope = Rope::from("one\ntwo\n\nfour");
+        assert_eq!(rope.len_lines(), 4);
+        assert_eq!(rope.line_to_char(3), Some(9));
+        assert_eq!(rope.line_to_char(4), None);
+        assert_eq!(rope.line_col(5), (1, 1));
+        assert_eq!(rope.line_col(100), (3, 4));
+        // the newline is a line's last column, and the end of the text the last line's
+        assert_eq!(rope.line_col_to_char(0, 3), Some(3));
+        assert_eq!(rope.line_col_to_char(0, 4), None);
+        assert_eq!(rope.line_col_to_char(2, 0), Some(8));
+        assert_eq!(rope.line_col_to_char(3, 4), Some(13));
+        assert_eq!(rope.line_col_to_char(3, 5), None);
+        // a column too big to add to the line's start is just past the end, not an overflow
+        assert_eq!(rope.line_col_to_char(1, usize::MAX), None);
+        assert_eq!(rope.line_col_to_char(usize::MAX, 0), None);
+        assert_eq!(rope.line(1).unwrap(), "two\n");
+        assert_eq!(rope.line(3).unwrap(), "four");
+        assert_eq!(Rope::new().line(0).unwrap(), "");
+        assert_eq!(Rope::new().line_col_to_char(0, 0), Some(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end")]
+    fn inserting_past_the_end_panics() {
+        Rope::from("abc").insert(4, "d");
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn removing_past_the_end_panics() {
+        Rope::from("abc").remove(2..4);
+    }
+}